@@ -0,0 +1,102 @@
+//! Compares the IR size and run time of a loop doing FIXED DECIMAL arithmetic.
+//!
+//! `cargo bench --bench fixed_decimal` measures the plick built from this
+//! tree. To compare it with another layout, build plick from that revision
+//! and point PLICK_BASELINE at the binary:
+//!
+//! ```text
+//! git worktree add ../plick-baseline <revision>
+//! cargo build --release --manifest-path ../plick-baseline/Cargo.toml
+//! PLICK_BASELINE=../plick-baseline/target/release/plick cargo bench --bench fixed_decimal
+//! ```
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant},
+};
+
+const PROGRAM: &str = "BENCH: PROCEDURE OPTIONS (MAIN);
+    DCL I FIXED DECIMAL(7);
+    DCL TOTAL FIXED DECIMAL(15,2);
+    TOTAL = 0;
+    DO I = 1 TO 1000000;
+        TOTAL = TOTAL + I * 0.5 - 0.25;
+    END;
+    PUT LIST(TOTAL);
+    END;";
+
+///The program is run this many times, and the fastest run is reported.
+const RUNS: u32 = 5;
+
+struct Measurement {
+    ir_lines: usize,
+    ir_bytes: usize,
+    run_time: Duration,
+    output: String,
+}
+
+fn main() {
+    let mut compilers = vec![("current", PathBuf::from(env!("CARGO_BIN_EXE_plick")))];
+    if let Ok(baseline) = env::var("PLICK_BASELINE") {
+        compilers.push(("baseline", fs::canonicalize(baseline).unwrap()));
+    }
+
+    println!(
+        "{:<10} {:>10} {:>12} {:>14}  output",
+        "compiler", "IR lines", "IR bytes", "best run (ms)"
+    );
+    for (name, compiler) in compilers {
+        let measurement = measure(name, &compiler);
+        println!(
+            "{:<10} {:>10} {:>12} {:>14.1}  {}",
+            name,
+            measurement.ir_lines,
+            measurement.ir_bytes,
+            measurement.run_time.as_secs_f64() * 1000.0,
+            measurement.output
+        );
+    }
+}
+
+fn measure(name: &str, compiler: &Path) -> Measurement {
+    let directory = env::temp_dir().join(format!("plick_bench_{}_{}", name, std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join("bench.pli"), PROGRAM).unwrap();
+
+    run(Command::new(compiler)
+        .arg("--save-as-ir")
+        .arg("bench.pli")
+        .current_dir(&directory));
+    let ir = fs::read_to_string(directory.join("bench.ll")).unwrap();
+
+    run(Command::new(compiler)
+        .arg("bench.pli")
+        .current_dir(&directory));
+    run(Command::new("cc")
+        .args(["bench.o", "-o", "bench", "-lm"])
+        .current_dir(&directory));
+
+    let mut run_time = Duration::MAX;
+    let mut output = String::new();
+    for _run in 0..RUNS {
+        let start = Instant::now();
+        let result = Command::new(directory.join("bench")).output().unwrap();
+        run_time = run_time.min(start.elapsed());
+        output = String::from_utf8_lossy(&result.stdout).trim().to_string();
+    }
+
+    fs::remove_dir_all(&directory).unwrap();
+    Measurement {
+        ir_lines: ir.lines().count(),
+        ir_bytes: ir.len(),
+        run_time,
+        output,
+    }
+}
+
+fn run(command: &mut Command) {
+    let status = command.output().unwrap().status;
+    assert!(status.success(), "{:?} failed", command);
+}
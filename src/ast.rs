@@ -1,12 +1,16 @@
 use std::fmt::Display;
 use std::string;
 
+use crate::codegen::builtins;
+use crate::codegen::c_interface;
 use crate::codegen::codegen::Compiler;
 use crate::codegen::named_value;
 use crate::codegen::named_value_store::NamedValueStore;
 use crate::lexer;
 use crate::types;
-use crate::types::resolve_types;
+use crate::types::defaults::DefaultRule;
+use crate::types::fixed_decimal::{get_constant_type, is_decimal_constant};
+use crate::types::get_binary_type;
 use crate::types::Type;
 
 use crate::codegen::named_value_store::NamedValueHashmapStore;
@@ -16,6 +20,10 @@ use crate::codegen::named_value_store::NamedValueHashmapStore;
 pub enum Expr {
     Assignment {
         variable_name: String,
+        ///Set when the variable is qualified by a locator, as in P->X = 5
+        locator: Option<Box<Expr>>,
+        ///Set when an element of an array is assigned, as in A(I) = 5
+        subscript: Option<Box<Expr>>,
         value: Box<Expr>,
     },
     Binary {
@@ -36,6 +44,10 @@ pub enum Expr {
     NumVal {
         value: f64,
         _type: types::Type,
+        ///The constant as it was written, like -123.45, which FIXED DECIMAL
+        ///constants are built from so that none of their 31 digits are lost.
+        ///None for the constants the compiler makes.
+        digits: Option<String>,
     },
     Char {
         value: String,
@@ -44,13 +56,42 @@ pub enum Expr {
         _type: types::Type,
         name: String,
     },
+    ///A BASED variable reached through a pointer, as in P->X
+    Qualified {
+        locator: Box<Expr>,
+        name: String,
+    },
+    ///An operand converted to the type the conversion rules give it, which
+    ///the type pass puts around the operands of binary expressions.
+    Conversion {
+        operand: Box<Expr>,
+        to: types::Type,
+    },
 }
 
 impl Expr {
     pub fn new_numval(value: f64) -> Expr {
         Expr::NumVal {
             value,
-            _type: Type::FixedDecimal,
+            _type: get_constant_type(&value.to_string()),
+            digits: None,
+        }
+    }
+    ///A constant written like 123.45, which keeps its digits. Ones written
+    ///some other way, like 1E5, are held as their value.
+    pub fn new_written_numval(written: &str, is_negative: bool) -> Expr {
+        let digits = match is_negative {
+            true => format!("-{}", written),
+            false => written.to_string(),
+        };
+        let value: f64 = digits.parse().unwrap_or_default();
+        if !is_decimal_constant(written) {
+            return Expr::new_numval(value);
+        }
+        Expr::NumVal {
+            value,
+            _type: get_constant_type(&digits),
+            digits: Some(digits),
         }
     }
     pub fn get_type<'a, 'ctx>(&self, compiler: &'a Compiler<'a, 'ctx>) -> types::Type {
@@ -64,31 +105,99 @@ impl Expr {
                 if let Some(named_value) = named_values_result {
                     return named_value._type;
                 }
+                //the label of a statement, given to a LABEL variable
+                if compiler.is_label_constant(name) {
+                    return Type::Label;
+                }
+                //a procedure, given to an ENTRY variable or passed as an argument
+                if compiler.get_entry_constant(name).is_some() {
+                    return Type::Entry;
+                }
                 return *_type;
             }
-            Expr::NumVal {
-                ref _type,
-                ref value,
-            } => return *_type,
+            Expr::NumVal { ref _type, .. } => return *_type,
             Expr::Infix {
                 ref operator,
                 ref operand,
-            } => return operand.get_type(compiler),
+            } => return operand.get_type(compiler).get_computational_type(),
             Expr::Call {
                 ref _type,
                 ref args,
                 ref fn_name,
-            } => return *_type,
+            } => {
+                if builtins::is_builtin(fn_name) {
+                    let argument_types: Vec<Type> =
+                        args.iter().map(|arg| arg.get_type(compiler)).collect();
+                    if let Some(builtin_type) = builtins::get_builtin_type(fn_name, &argument_types)
+                    {
+                        return builtin_type;
+                    }
+                }
+                if let Some(c_entry) = compiler.get_c_entry(fn_name) {
+                    return c_interface::get_result_type(&c_entry.entry);
+                }
+                //A(I) is an element of an array when A is declared as one
+                if let Some(named_value) = compiler.named_values.try_get(fn_name) {
+                    //F(X) calls the procedure the ENTRY variable F holds
+                    if let (Type::Entry, None, Some(entry)) =
+                        (named_value._type, named_value.dimension, &named_value.entry)
+                    {
+                        return compiler
+                            .get_entry_prototype(fn_name, entry)
+                            .map_or(*_type, |(_parameters, return_type)| return_type);
+                    }
+                    return named_value._type;
+                }
+                //a procedure returning a number of the type of its name
+                if let Some((_parameter_types, return_type)) =
+                    compiler.get_procedure_prototype(fn_name)
+                {
+                    return return_type;
+                }
+                return *_type;
+            }
             Expr::Binary {
                 ref operator,
                 ref left,
                 ref right,
-            } => resolve_types(&left.get_type(compiler), &right.get_type(compiler)).unwrap(),
+            } => {
+                let left_type = left.get_type(compiler);
+                let right_type = right.get_type(compiler);
+
+                if let lexer::Token::CONCATENATE = operator {
+                    let max_length = left_type.get_character_length().unwrap_or(0)
+                        + right_type.get_character_length().unwrap_or(0);
+                    return Type::VaryingChar(max_length);
+                }
+                //strings, pointers and complex numbers can only be compared
+                if left_type.is_character()
+                    || right_type.is_character()
+                    || left_type == Type::Pointer
+                    || right_type == Type::Pointer
+                    || (*operator == lexer::Token::EQ
+                        && (left_type.is_complex() || right_type.is_complex()))
+                {
+                    return Type::TRUTH_VALUE;
+                }
+                //operands that can't be mixed are described by the type pass,
+                //and again when the expression is generated
+                get_binary_type(operator, &left_type, &right_type).unwrap_or(Type::TBD)
+            }
             Expr::Assignment {
                 ref variable_name,
+                ref locator,
+                ref subscript,
                 ref value,
-            } => Type::FixedDecimal,
+            } => Type::TRUTH_VALUE,
+            Expr::Qualified {
+                ref locator,
+                ref name,
+            } => match compiler.named_values.try_get(name) {
+                Some(named_value) => named_value._type,
+                None => Type::TBD,
+            },
             Expr::Char { value } => Type::Char(value.len() as u32),
+            Expr::Conversion { ref to, .. } => *to,
         }
     }
 }
@@ -99,6 +208,16 @@ pub struct Prototype {
     pub fn_name: String,
     pub args: Vec<String>, // the names of the arguments - used inside of the function itself.
     pub source_loc: SourceLocation,
+    pub linkage: ProcedureLinkage,
+}
+
+///Who can call a procedure besides the unit it is in. EXTERNAL procedures can
+///be called from other units, and both they and OPTIONS(C) ones from C.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcedureLinkage {
+    Internal,
+    External,
+    C,
 }
 ///Represents a function prototype
 #[derive(Debug, Clone)]
@@ -109,6 +228,8 @@ pub struct PrototypeArgument {
 
 #[derive(Debug, Clone)]
 pub struct SourceLocation {
+    ///The included file the location is in, None for the unit being compiled.
+    pub file_name: Option<String>,
     pub line_number: u32,
     pub column_number: u32,
 }
@@ -116,6 +237,7 @@ pub struct SourceLocation {
 impl Default for SourceLocation {
     fn default() -> Self {
         SourceLocation {
+            file_name: None,
             line_number: 0,
             column_number: 0,
         }
@@ -123,6 +245,9 @@ impl Default for SourceLocation {
 }
 impl Display for SourceLocation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(ref file_name) = self.file_name {
+            write!(f, "{}:", file_name);
+        }
         write!(f, "{}:{}", self.line_number, self.column_number);
         Ok(())
     }
@@ -142,6 +267,8 @@ pub struct Function {
 pub struct Statement {
     pub label: Option<String>, //The label attached to this statement
     pub command: Command,
+    ///Where the statement was written, for its debug info.
+    pub source_loc: SourceLocation,
 }
 
 ///A "command" is the first keyword in a PL/1 statement, denoting
@@ -159,6 +286,22 @@ pub enum Command {
     FunctionDec(Function),
     EXPR(Expr), //"EXPR" is not a command in pl/1 this just represents a expression statement.
     RETURN(Expr), // specifies the return value of a function
+    OPEN(Open),
+    CLOSE(Close),
+    ON(On),
+    READ(Read),
+    WRITE(Write),
+    REWRITE(Rewrite),
+    DELETE(Delete),
+    ALLOCATE(Allocate),
+    FREE(Free),
+    ///DEFAULT RANGE(A:H) FLOAT;
+    DEFAULT(DefaultRule),
+    DO(Do),
+    LEAVE(Leave),
+    ITERATE(Iterate),
+    ///STOP; or EXIT;, which end the program with the return code set by PLIRETC.
+    STOP,
 }
 
 impl Command {
@@ -179,24 +322,228 @@ pub struct If {
 #[derive(Debug, Clone)]
 pub struct Assignment {
     pub var_name: String,
+    ///The pointer the variable is reached through, when written as P->X
+    pub locator: Option<Expr>,
+    ///The index of the element being assigned, when written as A(I)
+    pub subscript: Option<Expr>,
     pub value: Expr,
 }
 #[derive(Debug, Clone)]
 pub struct Declare {
     pub var_name: String,
     pub attribute: Option<Type>,
+    ///Only set when the variable was declared with the FILE attribute.
+    pub file_attributes: Option<Vec<FileAttribute>>,
+    pub storage_class: StorageClass,
+    ///The number of elements, when the variable is an array declared as A(10).
+    ///Elements are numbered from 1.
+    pub dimension: Option<u32>,
+    ///The values from INITIAL(...), with iteration factors like (10)0 already
+    ///expanded. Only AUTOMATIC and STATIC variables are initialized.
+    pub initial: Option<Vec<Expr>>,
+    ///Only set when the name was declared with the ENTRY attribute, as a
+    ///procedure that is defined elsewhere.
+    pub entry: Option<Entry>,
+    ///Only set when the name was declared GENERIC, as the procedures it
+    ///stands for. The type pass replaces calls of it with calls of one of them.
+    pub generic: Option<Vec<GenericAlternative>>,
+}
+
+///The attributes of a name declared with ENTRY.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub parameters: Vec<Descriptor>,
+    ///The type from RETURNS(...), if there was one.
+    pub returns: Option<Descriptor>,
+    ///Set by OPTIONS(C), the entry is a C function.
+    pub is_c: bool,
+    ///Set by VARIABLE, the name holds one of the procedures taking these
+    ///parameters instead of naming one.
+    pub is_variable: bool,
+}
+
+///CIRCLE WHEN (FLOAT), the procedure a GENERIC name calls when its
+///arguments have these types.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenericAlternative {
+    pub procedure_name: String,
+    pub descriptors: Vec<Descriptor>,
+}
+
+///The type of a parameter or of the result of an entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Descriptor {
+    Data(Type),
+    ///FIXED BINARY(p), passed to C as an integer that holds p bits and a sign.
+    FixedBinary(u32),
+    ///CHAR(*), a string of any length.
+    AnyLengthCharacter,
+}
+
+///The procedure a compilation unit begins with. Only a unit whose procedure
+///has OPTIONS(MAIN) is where the program starts, the procedures of the other
+///units are called from it through ENTRY declarations.
+#[derive(Debug, Clone)]
+pub struct UnitProcedure {
+    pub name: String,
+    pub is_main: bool,
+}
+
+///Where the storage of a variable comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StorageClass {
+    ///Allocated on the stack of the procedure that declares it.
+    Automatic,
+    ///Has no storage of its own, it lives wherever its locator points.
+    ///The locator is the pointer named in BASED(P), if there was one.
+    Based { locator: Option<String> },
+    ///Has a stack of generations. ALLOCATE pushes one and FREE pops one,
+    ///references are to the newest.
+    Controlled,
+    ///Allocated once for the whole program, so it keeps its value between
+    ///calls of the procedure that declares it.
+    Static,
+    ///Static storage shared with every other compilation unit that declares
+    ///a variable of the same name as EXTERNAL.
+    External,
+}
+
+///The attributes a file can be declared or opened with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileAttribute {
+    Stream,
+    Input,
+    Output,
+    Print,
+    Record,
+    Sequential,
+    ///Records are reached by key. Implies KEYED.
+    Direct,
+    Keyed,
+    ///Records can be read, rewritten and deleted.
+    Update,
 }
 
 #[derive(Debug, Clone)]
 pub struct Put {
+    ///The file named in FILE(...), SYSPRINT is used when there is none.
+    pub file: Option<String>,
     pub messages_to_print: IOList,
+    pub options: Vec<PutOption>,
+}
+
+///The printing options of a PUT statement.
+///Regardless of the order they are written in, PAGE is applied first,
+///then LINE, then SKIP, and finally the data list is written.
+#[derive(Debug, Clone)]
+pub enum PutOption {
+    ///Starts a new page.
+    Page,
+    ///Moves down to the given line number of the current page.
+    Line(Expr),
+    ///Moves down the given number of lines. SKIP(0) returns to the start of the current line.
+    Skip(Expr),
 }
 
 #[derive(Debug, Clone)]
 pub struct Get {
+    ///The file named in FILE(...), SYSIN is used when there is none.
+    pub file: Option<String>,
     pub list_to_get: IOList,
 }
 
+#[derive(Debug, Clone)]
+pub struct Open {
+    pub files: Vec<OpenFile>,
+}
+
+///One FILE(...) of an OPEN statement, along with its options.
+#[derive(Debug, Clone)]
+pub struct OpenFile {
+    pub file_name: String,
+    pub attributes: Vec<FileAttribute>,
+    ///The name of the file on disk. Defaults to the name of the file constant.
+    pub title: Option<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Close {
+    pub file_names: Vec<String>,
+}
+
+///Establishes an ON-unit, the statement run when a condition is raised.
+#[derive(Debug, Clone)]
+pub struct On {
+    pub condition: Condition,
+    pub on_unit: Box<Statement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Condition {
+    ///Raised when a GET reads past the end of the named file.
+    EndFile(String),
+    ///Raised when a keyed operation on the named file cannot find its key,
+    ///or a WRITE uses a key that is already taken.
+    Key(String),
+    ///Raised when the result of FIXED DECIMAL arithmetic has more digits
+    ///before the point than can be held.
+    FixedOverflow,
+    ///Raised when a FIXED DECIMAL is divided by zero.
+    ZeroDivide,
+    ///Raised when a string converted to a number doesn't hold one.
+    Conversion,
+}
+
+///READ FILE(f) INTO(x): reads the next record of a RECORD file into a variable.
+#[derive(Debug, Clone)]
+pub struct Read {
+    pub file_name: String,
+    pub into: String,
+    ///Reads the record stored under this key instead of the next one.
+    pub key: Option<Expr>,
+    ///A CHAR variable that receives the key of the record that was read.
+    pub keyto: Option<String>,
+}
+
+///WRITE FILE(f) FROM(x): adds the storage of a variable to a RECORD file.
+#[derive(Debug, Clone)]
+pub struct Write {
+    pub file_name: String,
+    pub from: String,
+    pub keyfrom: Option<Expr>,
+}
+
+///REWRITE FILE(f) FROM(x): replaces the record read last, or the one stored under KEY(...).
+#[derive(Debug, Clone)]
+pub struct Rewrite {
+    pub file_name: String,
+    pub from: String,
+    pub key: Option<Expr>,
+}
+
+///DELETE FILE(f): removes the record read last, or the one stored under KEY(...).
+#[derive(Debug, Clone)]
+pub struct Delete {
+    pub file_name: String,
+    pub key: Option<Expr>,
+}
+
+///ALLOCATE X SET(P);
+#[derive(Debug, Clone)]
+pub struct Allocate {
+    pub var_name: String,
+    ///The pointer that gets the address of the new storage. When there is
+    ///no SET option the locator named in the declaration is used.
+    pub set: Option<String>,
+}
+
+///FREE X; or FREE P->X;
+#[derive(Debug, Clone)]
+pub struct Free {
+    pub var_name: String,
+    pub locator: Option<Expr>,
+}
+
 #[derive(Debug, Clone)]
 pub struct IOList {
     pub items: Vec<Expr>,
@@ -204,5 +551,49 @@ pub struct IOList {
 
 #[derive(Debug, Clone)]
 pub struct Go {
+    ///A statement label, or a LABEL variable holding one.
     pub label_to_go_to: String,
+    ///Set when going to the label held by an element of a LABEL array.
+    pub subscript: Option<Expr>,
+}
+
+///A DO group on its own, as opposed to the ones of an IF statement:
+///DO; DO WHILE (X < 10); DO I = 1 TO 10 BY 2; DO I = 1 TO N WHILE (X > 0);
+#[derive(Debug, Clone)]
+pub struct Do {
+    ///The label of the group, which LEAVE and ITERATE can name.
+    pub label: Option<String>,
+    pub control: Option<DoControl>,
+    pub while_condition: Option<Expr>,
+    pub statements: Vec<Statement>,
+}
+
+impl Do {
+    ///Whether the group loops, a plain DO; only groups statements.
+    pub fn is_iterative(&self) -> bool {
+        self.control.is_some() || self.while_condition.is_some()
+    }
+}
+
+///I = 1 TO 10 BY 2, the TO and BY expressions are evaluated once before
+///the first iteration.
+#[derive(Debug, Clone)]
+pub struct DoControl {
+    pub variable_name: String,
+    pub start: Expr,
+    pub end: Expr,
+    ///BY 1 when missing.
+    pub step: Option<Expr>,
+}
+
+///LEAVE; or LEAVE OUTER;
+#[derive(Debug, Clone)]
+pub struct Leave {
+    pub label: Option<String>,
+}
+
+///ITERATE; or ITERATE OUTER;
+#[derive(Debug, Clone)]
+pub struct Iterate {
+    pub label: Option<String>,
 }
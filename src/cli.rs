@@ -1,11 +1,34 @@
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
-#[derive(Debug, Parser)]
+#[derive(Debug, Clone, Parser)]
 #[clap(author, version, about)]
 pub struct Arguments {
-    ///The path to the file to compile
-    pub path_to_file: String,
+    ///The paths to the files to compile. They are linked into a single object
+    ///file, named after the first one.
+    #[arg(required = true)]
+    pub paths_to_files: Vec<String>,
     ///Whether to save the LLVM IR as a file instead of compiling to an executable
     #[arg(short, long)]
     pub save_as_ir: bool,
+    ///The directories %INCLUDE looks for files in, after the directory of the
+    ///file including them.
+    #[arg(short = 'I', value_name = "DIRECTORY")]
+    pub include_directories: Vec<String>,
+    ///Print the source after the preprocessor expanded it, without compiling it
+    #[arg(long)]
+    pub preprocess_only: bool,
+    ///What to write: the object file, or a C header declaring the procedures
+    ///exported with OPTIONS(C) or EXTERNAL.
+    #[arg(long, value_enum, default_value_t = Emit::Object)]
+    pub emit: Emit,
+    ///Store FIXED DECIMAL in record I/O buffers in the packed decimal form
+    ///mainframes use, a digit in each half byte and a sign in the last one.
+    #[arg(long)]
+    pub packed_decimal: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Emit {
+    Object,
+    Header,
 }
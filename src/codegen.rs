@@ -1,11 +1,17 @@
 pub mod ast_implementations;
+///The builtin functions, which are generated inline instead of being called.
+pub mod builtins;
+///Calls to C functions, declared as ENTRY ... OPTIONS(C), and procedures
+///exported to C.
+pub mod c_interface;
 pub mod named_value;
 pub mod named_value_store;
 pub mod prelude;
+pub mod runtime;
 pub mod utils;
 pub mod codegen {
 
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
     use std::error::Error;
     use std::vec;
 
@@ -13,7 +19,10 @@ pub mod codegen {
     use crate::ast::Command;
     use crate::ast::Expr;
     use crate::ast::Statement;
+    use crate::codegen::c_interface::{CEntry, ExportedProcedure};
     use crate::codegen::named_value::NamedValue;
+    use crate::codegen::runtime::controlled;
+    use crate::codegen::runtime::program;
     use crate::codegen::utils;
     use crate::codegen::utils::branch_only_if_no_terminator;
     use crate::codegen::utils::print_float_value;
@@ -24,6 +33,7 @@ pub mod codegen {
     use crate::lexer;
     use crate::types::character;
     use crate::types::character::CharValue;
+    use crate::types::defaults::{DefaultRule, DefaultRules};
     use crate::types::fixed_decimal;
     use crate::types::fixed_decimal::FixedValue;
     use crate::types::infer_pli_type_via_name;
@@ -40,6 +50,8 @@ pub mod codegen {
     use inkwell::module::Module;
     use inkwell::types::AnyTypeEnum;
     use inkwell::types::BasicMetadataTypeEnum;
+    use inkwell::types::BasicType;
+    use inkwell::types::BasicTypeEnum;
     use inkwell::types::FunctionType;
     use inkwell::values::ArrayValue;
     use inkwell::values::BasicMetadataValueEnum;
@@ -71,21 +83,67 @@ pub mod codegen {
         pub debug_controller: Option<&'a DebugController<'ctx>>,
 
         pub named_values: NamedValueHashmapStore<'ctx>,
+        ///The OPTIONS(C) entries declared so far, by their PL/I names.
+        pub c_entries: RefCell<HashMap<String, CEntry<'ctx>>>,
+        ///The procedures exported to C so far, for the generated header.
+        pub exported_procedures: RefCell<Vec<ExportedProcedure>>,
+        ///Whether record I/O holds FIXED DECIMAL in packed decimal.
+        pub packed_decimal: bool,
+        ///The types of the parameters and result of the procedures defined
+        ///or declared so far. LLVM holds every FIXED DECIMAL alike, so they
+        ///can't be told from the functions.
+        pub procedure_prototypes: RefCell<HashMap<String, (Vec<Type>, Type)>>,
+    }
+
+    ///Where LEAVE and ITERATE go inside a DO group.
+    #[derive(Debug, Clone)]
+    pub struct LoopTargets<'ctx> {
+        pub label: Option<String>,
+        ///The statement after the group.
+        pub break_block: BasicBlock<'ctx>,
+        ///The next iteration of the group, or its end for a plain DO;
+        pub continue_block: BasicBlock<'ctx>,
+    }
+
+    ///What LABEL variables need in a procedure.
+    #[derive(Debug, Clone, Default)]
+    pub struct LabelState<'ctx> {
+        ///The labels of the statements of the procedure, the LABEL constants.
+        constants: HashSet<String>,
+        ///The labels LABEL variables were given, which GO TO can go to.
+        targets: Vec<String>,
+        ///Where the number of the running invocation is kept.
+        activation: Option<PointerValue<'ctx>>,
+        ///The block doing the indirectbr, and where GO TO stores the address for it.
+        dispatch: Option<(BasicBlock<'ctx>, PointerValue<'ctx>)>,
     }
 
     #[derive(Debug, Clone)]
     pub struct FunctionProperties<'ctx> {
         labeled_blocks: HashMap<String, BasicBlock<'ctx>>,
         future_jump_blocks: HashMap<String, BasicBlock<'ctx>>,
+        ///The ON-units established so far, which are compiled inline where
+        ///their condition can be raised.
+        on_units: HashMap<ast::Condition, Statement>,
+        ///The DEFAULT statements of this block and the blocks containing it.
+        default_rules: DefaultRules,
+        ///The DO groups containing the statement being compiled, innermost last.
+        loops: Vec<LoopTargets<'ctx>>,
+        labels: LabelState<'ctx>,
     }
 
     impl<'ctx> FunctionProperties<'ctx> {
         pub fn new() -> Self {
             let labeled_blocks = HashMap::new();
             let future_jump_blocks = HashMap::new();
+            let on_units = HashMap::new();
             FunctionProperties {
                 labeled_blocks,
                 future_jump_blocks,
+                on_units,
+                default_rules: DefaultRules::new(),
+                loops: vec![],
+                labels: LabelState::default(),
             }
         }
 
@@ -102,6 +160,11 @@ pub mod codegen {
             for kvp in other.future_jump_blocks.iter() {
                 self.future_jump_blocks.insert(kvp.0.clone(), kvp.1.clone());
             }
+
+            self.on_units = other.on_units.clone();
+            self.default_rules = other.default_rules.clone();
+            self.loops = other.loops.clone();
+            self.labels = other.labels.clone();
         }
         pub fn get_labeled_block(&self, name: &str) -> Option<BasicBlock<'ctx>> {
             self.labeled_blocks.get(name).map(|bb| bb.clone())
@@ -135,6 +198,66 @@ pub mod codegen {
             self.future_jump_blocks.remove(name);
         }
 
+        pub fn store_on_unit(&mut self, condition: ast::Condition, on_unit: Statement) {
+            self.on_units.insert(condition, on_unit);
+        }
+        pub fn get_on_unit(&self, condition: &ast::Condition) -> Option<Statement> {
+            self.on_units.get(condition).map(|on_unit| on_unit.clone())
+        }
+
+        pub fn add_default_rule(&mut self, rule: DefaultRule) {
+            self.default_rules.add(rule);
+        }
+        pub fn get_default_type(&self, name: &str) -> Type {
+            self.default_rules.get_type(name)
+        }
+
+        pub fn enter_loop(&mut self, targets: LoopTargets<'ctx>) {
+            self.loops.push(targets);
+        }
+        pub fn exit_loop(&mut self) {
+            self.loops.pop();
+        }
+        ///A procedure can't leave the DO groups, or go to the labels through
+        ///LABEL variables, of the block containing it.
+        pub fn enter_procedure(&mut self) {
+            self.loops.clear();
+            self.labels = LabelState::default();
+        }
+        ///The innermost DO group, or the one with the label.
+        pub fn get_loop_targets(&self, label: Option<&str>) -> Option<LoopTargets<'ctx>> {
+            self.loops
+                .iter()
+                .rev()
+                .find(|targets| label.is_none() || targets.label.as_deref() == label)
+                .cloned()
+        }
+
+        pub fn add_label_constant(&mut self, name: String) {
+            self.labels.constants.insert(name);
+        }
+        pub fn is_label_constant(&self, name: &str) -> bool {
+            self.labels.constants.contains(name)
+        }
+        pub fn add_label_target(&mut self, name: String) {
+            self.labels.targets.push(name);
+        }
+        pub fn get_label_targets(&self) -> Vec<String> {
+            self.labels.targets.clone()
+        }
+        pub fn get_label_activation(&self) -> Option<PointerValue<'ctx>> {
+            self.labels.activation
+        }
+        pub fn set_label_activation(&mut self, activation: PointerValue<'ctx>) {
+            self.labels.activation = Some(activation);
+        }
+        pub fn get_label_dispatch(&self) -> Option<(BasicBlock<'ctx>, PointerValue<'ctx>)> {
+            self.labels.dispatch
+        }
+        pub fn set_label_dispatch(&mut self, dispatch: (BasicBlock<'ctx>, PointerValue<'ctx>)) {
+            self.labels.dispatch = Some(dispatch);
+        }
+
         pub fn are_there_any_placeholder_blocks(&self) -> bool {
             self.future_jump_blocks.len() > 0
         }
@@ -164,6 +287,14 @@ pub mod codegen {
             self,
             compiler: &'a Compiler<'a, 'ctx>,
         ) -> Box<dyn AnyValue<'ctx> + 'ctx> {
+            if let Some(dbg) = compiler.debug_controller {
+                if let Some(location) =
+                    dbg.create_statement_location(compiler.context, &self.source_loc)
+                {
+                    compiler.builder.set_current_debug_location(location);
+                }
+            }
+
             match self.label {
                 Some(label_name) => compiler.codegen_label(&label_name).unwrap(),
                 None => (),
@@ -178,6 +309,24 @@ pub mod codegen {
                 Command::IF(if_statement) => if_statement.codegen(compiler),
                 Command::Assignment(assn) => assn.codegen(compiler),
                 Command::FunctionDec(func) => func.codegen(compiler),
+                Command::OPEN(open) => open.codegen(compiler),
+                Command::CLOSE(close) => close.codegen(compiler),
+                Command::ON(on) => on.codegen(compiler),
+                Command::READ(read) => read.codegen(compiler),
+                Command::WRITE(write) => write.codegen(compiler),
+                Command::REWRITE(rewrite) => rewrite.codegen(compiler),
+                Command::DELETE(delete) => delete.codegen(compiler),
+                Command::ALLOCATE(allocate) => allocate.codegen(compiler),
+                Command::FREE(free) => free.codegen(compiler),
+                //already applied to the whole block, see apply_default_statements
+                Command::DEFAULT(_rule) => compiler.ret_zero(),
+                Command::DO(do_group) => do_group.codegen(compiler),
+                Command::LEAVE(leave) => leave.codegen(compiler),
+                Command::ITERATE(iterate) => iterate.codegen(compiler),
+                Command::STOP => {
+                    program::build_stop(compiler);
+                    compiler.ret_zero()
+                }
 
                 Command::END => {
                     compiler.error_module.store_error_msg("found END");
@@ -196,6 +345,7 @@ pub mod codegen {
             m: &'a Module<'ctx>,
             d: Option<&'a DebugController<'ctx>>,
             error_test: bool,
+            packed_decimal: bool,
         ) -> Compiler<'a, 'ctx> {
             let named_values: NamedValueHashmapStore = NamedValueHashmapStore::new();
             let error_module: ErrorModule = ErrorModule::new(error_test);
@@ -209,9 +359,30 @@ pub mod codegen {
                 function_properties,
                 debug_controller: d,
                 type_module: TypeModule::new(&c),
+                c_entries: RefCell::new(HashMap::new()),
+                exported_procedures: RefCell::new(vec![]),
+                packed_decimal,
+                procedure_prototypes: RefCell::new(HashMap::new()),
+            }
+        }
+
+        ///A DEFAULT statement applies to the whole block it is in, wherever
+        ///it is written, so the ones of a block are applied before it is compiled.
+        pub fn apply_default_statements(&self, statements: &[Statement]) {
+            for statement in statements {
+                if let Command::DEFAULT(rule) = &statement.command {
+                    self.function_properties
+                        .borrow_mut()
+                        .add_default_rule(rule.clone());
+                }
             }
         }
 
+        ///The type of a name that isn't declared with one.
+        pub fn get_default_type(&self, name: &str) -> Type {
+            self.function_properties.borrow().get_default_type(name)
+        }
+
         pub unsafe fn ret_zero(&self) -> Box<dyn AnyValue<'ctx> + 'ctx> {
             Box::new(self.context.i64_type().const_zero())
         }
@@ -251,7 +422,13 @@ pub mod codegen {
                     if named_value._type != *_type {
                         panic!("{} vs {}", named_value._type, _type)
                     }
-                    return named_value.pointer;
+                    match self.get_variable_storage(&named_value) {
+                        Ok(storage) => storage,
+                        Err(message) => {
+                            self.error_module.store_error_msg(&message);
+                            named_value.pointer
+                        }
+                    }
                 }
 
                 None => self.create_empty_variable_and_return_ptr(variable_name, _type),
@@ -260,11 +437,21 @@ pub mod codegen {
 
         pub fn get_format_string_for_type(_type: &Type) -> String {
             match _type {
-                Type::FixedDecimal => "%lf".to_string(),
-                Type::Float => "%lf".to_string(),
+                Type::FixedDecimal(..) => "%lf".to_string(),
+                Type::Float(_) => "%lf".to_string(),
+                //a pictured number is read as a number, then edited
+                Type::Picture(_picture) => "%lf".to_string(),
                 Type::Char(string_length) => " \'%[^\']\'".to_string(),
+                //the width keeps scanf from reading more than the string can hold
+                Type::VaryingChar(max_length) => format!(" \'%{}[^\']\'", max_length),
                 Type::Void => panic!("Can't get format string for type Void!"),
                 Type::TBD => panic!("Can't get format string for type TBD!"),
+                Type::Pointer => panic!("Can't get format string for type Pointer!"),
+                Type::Label => panic!("Can't get format string for type Label!"),
+                Type::Entry => panic!("Can't get format string for type Entry!"),
+                Type::FixedComplex | Type::FloatComplex => {
+                    panic!("Can't get format string for a complex type!")
+                }
             }
         }
 
@@ -298,12 +485,39 @@ pub mod codegen {
             fn_name: &String,
             args: &mut Vec<ast::Expr>,
         ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, String> {
-            let function_to_call: FunctionValue<'ctx> = self
-                .get_function(&fn_name)
-                .map_err(|_err| "lol".to_string())?;
+            //calling an ENTRY variable calls the procedure it holds
+            let entry_variable = self
+                .named_values
+                .try_get(fn_name)
+                .filter(|named_value| named_value._type == Type::Entry);
+            let (function_type, callee, parameter_types) = match entry_variable {
+                Some(named_value) => {
+                    let function_type = self.get_entry_variable_function_type(&named_value)?;
+                    let callee =
+                        self.generate_entry_variable_callee_code(&named_value, function_type)?;
+                    let (parameter_types, _return_type) =
+                        self.get_entry_variable_prototype(&named_value)?;
+                    (function_type, callee, parameter_types)
+                }
+                //a procedure is called through its address too, which LLVM
+                //still sees as a direct call
+                None => {
+                    let function_to_call: FunctionValue<'ctx> = self
+                        .get_function(&fn_name)
+                        .map_err(|_err| "lol".to_string())?;
+                    let parameter_types = self
+                        .get_procedure_prototype(fn_name)
+                        .map_or(vec![], |(parameter_types, _return_type)| parameter_types);
+                    (
+                        function_to_call.get_type(),
+                        function_to_call.as_global_value().as_pointer_value(),
+                        parameter_types,
+                    )
+                }
+            };
 
             //handle argument checks here
-            if args.len() != function_to_call.get_params().len() {
+            if args.len() != function_type.count_param_types() as usize {
                 return Err(format!(
                     "argument mismatch trying to create a call to function {}",
                     fn_name
@@ -313,10 +527,16 @@ pub mod codegen {
             let mut codegen_args: Vec<BasicMetadataValueEnum> = vec![];
 
             //TODO: perform typechecking on arguments here
-
             while args.len() > 0 {
                 let current_arg = args.remove(0);
-                let v: Box<dyn AnyValue<'ctx>> = current_arg.codegen(self);
+                let arg_type = current_arg.get_type(self);
+                let mut v: Box<dyn AnyValue<'ctx>> = current_arg.codegen(self);
+                //numbers are passed as the type of the parameter
+                if let Some(parameter_type) = parameter_types.get(codegen_args.len()) {
+                    if parameter_type.is_arithmetic() && arg_type.is_arithmetic() {
+                        v = self.convert_arithmetic_value(v, arg_type, *parameter_type)?;
+                    }
+                }
                 let bve: BasicValueEnum = match v.as_any_value_enum() {
                     AnyValueEnum::ArrayValue(v) => v.as_basic_value_enum(),
                     AnyValueEnum::IntValue(v) => v.as_basic_value_enum(),
@@ -331,11 +551,7 @@ pub mod codegen {
 
             let call_return_value = self
                 .builder
-                .build_call(
-                    function_to_call,
-                    &codegen_args,
-                    function_to_call.get_name().to_str().unwrap(),
-                )
+                .build_indirect_call(function_type, callee, &codegen_args, fn_name)
                 .map_err(|err| {
                     format!(
                         "Error trying to build a call to function {}: {}",
@@ -396,35 +612,231 @@ pub mod codegen {
                 "Generating variable code for variable named {}",
                 variable_name
             );
+            if self.named_values.try_get(variable_name).is_none()
+                && self.is_label_constant(variable_name)
+            {
+                return self.generate_label_constant_code(variable_name);
+            }
+            if let Some(function) = self.get_entry_constant(variable_name) {
+                return Ok(self.generate_entry_constant_code(function));
+            }
+            let named_value: NamedValue<'ctx> = self
+                .named_values
+                .try_get(variable_name)
+                .ok_or(get_error(&["12", variable_name]))?;
+            if named_value.dimension.is_some() {
+                return Err(get_error(&["23", variable_name]));
+            }
+
+            let var_ptr: PointerValue<'ctx> = self.get_variable_storage(&named_value)?;
+            self.load_variable(var_ptr, named_value._type, variable_name)
+        }
+
+        ///Generates the value of A(I), an element of an array.
+        pub unsafe fn generate_subscripted_variable_code(
+            &'a self,
+            variable_name: &str,
+            subscript: ast::Expr,
+        ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, String> {
             let named_value: NamedValue<'ctx> = self
                 .named_values
                 .try_get(variable_name)
-                .ok_or("Unable to find named value!")?;
+                .ok_or(get_error(&["12", variable_name]))?;
+
+            let element_ptr = self.get_element_storage(&named_value, subscript)?;
+            self.load_variable(element_ptr, named_value._type, variable_name)
+        }
 
-            let variable_type = named_value._type;
+        ///Finds the storage of an element of an array. Elements are numbered from 1.
+        pub unsafe fn get_element_storage(
+            &self,
+            named_value: &NamedValue<'ctx>,
+            subscript: ast::Expr,
+        ) -> Result<PointerValue<'ctx>, String> {
+            if named_value.dimension.is_none() {
+                return Err(get_error(&["22", &named_value.name]));
+            }
+            let array_ptr = self.get_variable_storage(named_value)?;
+
+            let i32_type = self.context.i32_type();
+            let subscript = self.generate_expression_as_i32(subscript);
+            let index = self
+                .builder
+                .build_int_sub(subscript, i32_type.const_int(1, false), "index")
+                .unwrap();
+            let element_ptr = self
+                .builder
+                .build_gep(array_ptr, &[i32_type.const_zero(), index], "element_ptr")
+                .map_err(|err| format!("error finding an array element: {}", err))?;
+            Ok(element_ptr)
+        }
+
+        ///Generates the value of P->X, the BASED variable X at the address in P.
+        pub unsafe fn generate_qualified_variable_code(
+            &'a self,
+            locator: ast::Expr,
+            variable_name: &str,
+        ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, String> {
+            let (var_ptr, variable_type) = self.get_qualified_storage(locator, variable_name)?;
+            self.load_variable(var_ptr, variable_type, variable_name)
+        }
+
+        ///Finds the storage of a variable. A BASED variable lives wherever the
+        ///locator it was declared with points.
+        pub unsafe fn get_variable_storage(
+            &self,
+            named_value: &NamedValue<'ctx>,
+        ) -> Result<PointerValue<'ctx>, String> {
+            match named_value.storage_class {
+                ast::StorageClass::Automatic
+                | ast::StorageClass::Static
+                | ast::StorageClass::External => Ok(named_value.pointer),
+                ast::StorageClass::Based {
+                    locator: Some(ref locator),
+                } => {
+                    let locator = ast::Expr::Variable {
+                        _type: Type::Pointer,
+                        name: locator.clone(),
+                    };
+                    self.get_based_storage(locator, named_value)
+                }
+                ast::StorageClass::Based { locator: None } => {
+                    Err(get_error(&["15", &named_value.name]))
+                }
+                ast::StorageClass::Controlled => Ok(self.get_controlled_storage(named_value)),
+            }
+        }
+
+        ///Finds the newest generation of a CONTROLLED variable. The program
+        ///ends if there is none.
+        unsafe fn get_controlled_storage(
+            &self,
+            named_value: &NamedValue<'ctx>,
+        ) -> PointerValue<'ctx> {
+            let anchor = controlled::get_or_add_anchor(self, &named_value.name);
+            let name = self
+                .builder
+                .build_global_string_ptr(&named_value.name, "controlled_name")
+                .unwrap()
+                .as_pointer_value();
+            let generation = self
+                .builder
+                .build_call(
+                    self.module
+                        .get_function(controlled::CONTROLLED_GENERATION_FUNCTION_NAME)
+                        .unwrap(),
+                    &[anchor.into(), name.into()],
+                    "generation",
+                )
+                .unwrap()
+                .try_as_basic_value()
+                .left()
+                .unwrap()
+                .into_pointer_value();
+
+            let value_type = self.get_storage_type(named_value._type, named_value.dimension);
+            let generation_type = controlled::get_generation_type(self, value_type);
+            let generation = self
+                .builder
+                .build_pointer_cast(
+                    generation,
+                    generation_type.ptr_type(AddressSpace::default()),
+                    "generation",
+                )
+                .unwrap();
+            self.builder
+                .build_struct_gep(generation, controlled::VALUE_FIELD, &named_value.name)
+                .unwrap()
+        }
+
+        ///Finds the storage of P->X, where X has to be BASED.
+        pub unsafe fn get_qualified_storage(
+            &self,
+            locator: ast::Expr,
+            variable_name: &str,
+        ) -> Result<(PointerValue<'ctx>, Type), String> {
+            let named_value: NamedValue<'ctx> = self
+                .named_values
+                .try_get(variable_name)
+                .ok_or(get_error(&["12", variable_name]))?;
+
+            if !matches!(named_value.storage_class, ast::StorageClass::Based { .. }) {
+                return Err(get_error(&["17", variable_name]));
+            }
+
+            let storage = self.get_based_storage(locator, &named_value)?;
+            Ok((storage, named_value._type))
+        }
+
+        unsafe fn get_based_storage(
+            &self,
+            locator: ast::Expr,
+            named_value: &NamedValue<'ctx>,
+        ) -> Result<PointerValue<'ctx>, String> {
+            let locator_type = locator.get_type(self);
+            if locator_type != Type::Pointer {
+                let locator_type: String = locator_type.into();
+                return Err(get_error(&["16", &named_value.name, &locator_type]));
+            }
+
+            let address = locator
+                .codegen(self)
+                .as_any_value_enum()
+                .into_pointer_value();
+            let storage_type = self
+                .get_storage_type(named_value._type, named_value.dimension)
+                .ptr_type(AddressSpace::default());
+            let storage = self
+                .builder
+                .build_pointer_cast(address, storage_type, &named_value.name)
+                .map_err(|err| format!("error finding a based variable: {}", err))?;
+            Ok(storage)
+        }
+
+        unsafe fn load_variable(
+            &self,
+            var_ptr: PointerValue<'ctx>,
+            variable_type: Type,
+            variable_name: &str,
+        ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, String> {
             log::trace!("Type is: {}", variable_type);
-            let var_ptr: PointerValue<'ctx> = named_value.pointer;
             let result_value: BasicValueEnum<'ctx> = self
                 .builder
                 .build_load(var_ptr, variable_name)
                 .map_err(|err| format!("error building a variable code: {}", err))?;
 
             match variable_type {
-                Type::FixedDecimal => {
-                    let fixed_decimal_struct = result_value.into_struct_value();
-                    return Ok(Box::new(fixed_decimal_struct));
+                Type::FixedDecimal(..) => {
+                    let fixed_decimal_scaled = result_value.into_int_value();
+                    return Ok(Box::new(fixed_decimal_scaled));
                 }
-                Type::Char(_size) => {
+                Type::Char(_size) | Type::Picture(_) => {
                     let character_array = result_value.into_array_value();
                     return Ok(Box::new(character_array));
                 }
+                Type::VaryingChar(_max_length) => {
+                    let varying_struct = result_value.into_struct_value();
+                    return Ok(Box::new(varying_struct));
+                }
+                Type::Pointer | Type::Entry => {
+                    let pointer = result_value.into_pointer_value();
+                    return Ok(Box::new(pointer));
+                }
+                Type::Label => {
+                    let label_struct = result_value.into_struct_value();
+                    return Ok(Box::new(label_struct));
+                }
+                Type::FixedComplex | Type::FloatComplex => {
+                    let complex_struct = result_value.into_struct_value();
+                    return Ok(Box::new(complex_struct));
+                }
 
                 Type::TBD => {
                     panic!("Tried to retrieve a variable of type TBD!")
                 }
-                Type::Float => {
-                    let float_decimal_struct = result_value.into_struct_value();
-                    return Ok(Box::new(float_decimal_struct));
+                Type::Float(_) => {
+                    let float_decimal = result_value.into_float_value();
+                    return Ok(Box::new(float_decimal));
                 }
                 Type::Void => {
                     panic!("Tried to retrieve a variable of type Void!")
@@ -438,26 +850,61 @@ pub mod codegen {
             fn_arguments: Vec<ast::PrototypeArgument>,
             return_type: Type,
         ) -> FunctionValue<'ctx> {
-            let llvm_return_type: AnyTypeEnum<'ctx> =
-                self.convert_plick_type_to_llvm_any_type(return_type);
-            let is_variable_num_of_args = false;
-
             let args_types: Vec<Type> = fn_arguments
                 .clone()
                 .into_iter()
                 .map(|arg| arg._type)
                 .collect();
 
-            let args_types: Vec<BasicMetadataTypeEnum> = args_types
-                .into_iter()
-                .map(|ty| self.convert_plick_type_to_llvm_basic_type(ty).into())
+            //create the function prototype type info
+            let fn_type = self.get_function_type(&args_types, return_type);
+            self.procedure_prototypes
+                .borrow_mut()
+                .insert(fn_name.clone(), (args_types, return_type));
+
+            // create a new function prototype, unless an ENTRY declaration already did
+            let llvm_function_value = match self.module.get_function(&fn_name) {
+                Some(declared_function)
+                    if declared_function.count_basic_blocks() == 0
+                        && declared_function.get_type() == fn_type =>
+                {
+                    declared_function
+                }
+                _ => self.module.add_function(&fn_name, fn_type, None),
+            };
+
+            //name the arguments in the IR
+            for (i, param) in llvm_function_value.get_param_iter().enumerate() {
+                param.set_name(fn_arguments[i].name.as_str());
+            }
+
+            llvm_function_value
+        }
+
+        ///The types of the parameters and result of the procedure `name`.
+        pub fn get_procedure_prototype(&self, name: &str) -> Option<(Vec<Type>, Type)> {
+            self.procedure_prototypes.borrow().get(name).cloned()
+        }
+
+        ///The type of the procedures taking `parameter_types` and returning
+        ///`return_type`, which is also the type ENTRY variables call them with.
+        pub fn get_function_type(
+            &self,
+            parameter_types: &[Type],
+            return_type: Type,
+        ) -> FunctionType<'ctx> {
+            let llvm_return_type: AnyTypeEnum<'ctx> =
+                self.convert_plick_type_to_llvm_any_type(return_type);
+            let is_variable_num_of_args = false;
+
+            let args_types: Vec<BasicMetadataTypeEnum> = parameter_types
+                .iter()
+                .map(|ty| self.convert_plick_type_to_llvm_basic_type(*ty).into())
                 .collect();
 
             let args_types = args_types.as_slice();
 
-            //create the function prototype type info
-
-            let fn_type: FunctionType<'ctx> = match llvm_return_type {
+            match llvm_return_type {
                 AnyTypeEnum::VoidType(ty) => ty.fn_type(args_types, is_variable_num_of_args),
                 AnyTypeEnum::ArrayType(_ty) => {
                     todo!("Not implemeneted returning arraytype!")
@@ -478,17 +925,7 @@ pub mod codegen {
                 AnyTypeEnum::VectorType(_ty) => {
                     todo!("Implement functions returning VectorType")
                 }
-            };
-
-            // create a new function prototype
-            let llvm_function_value = self.module.add_function(&fn_name, fn_type, None);
-
-            //name the arguments in the IR
-            for (i, param) in llvm_function_value.get_param_iter().enumerate() {
-                param.set_name(fn_arguments[i].name.as_str());
             }
-
-            llvm_function_value
         }
 
         pub fn create_entry_block_alloca(
@@ -499,9 +936,18 @@ pub mod codegen {
         ) -> PointerValue<'ctx> {
             log::info!("Generating declare code!");
 
-            let builder = self.context.create_builder();
             let llvm_type_of_alloca =
                 self.convert_plick_type_to_llvm_basic_type(variable_type.clone());
+            self.create_entry_block_alloca_of_type(variable_name, function, llvm_type_of_alloca)
+        }
+
+        pub fn create_entry_block_alloca_of_type(
+            &self,
+            variable_name: &str,
+            function: &FunctionValue,
+            llvm_type_of_alloca: BasicTypeEnum<'ctx>,
+        ) -> PointerValue<'ctx> {
+            let builder = self.context.create_builder();
             let entry = function.get_first_basic_block().unwrap();
 
             match entry.get_first_instruction() {
@@ -516,11 +962,38 @@ pub mod codegen {
 
         ///Generates a function DEFINITION, including the body
 
+        ///Creates the function the statements of a compilation unit go into.
+        ///A unit without OPTIONS(MAIN) becomes an external procedure named
+        ///after its label, returning a value of the default type like any other.
+        pub fn initialize_unit_function(
+            &self,
+            unit_procedure: Option<&ast::UnitProcedure>,
+        ) -> FunctionValue<'ctx> {
+            let procedure_name = match unit_procedure {
+                Some(unit_procedure) if !unit_procedure.is_main => &unit_procedure.name,
+                _ => return self.initalize_main_function(),
+            };
+
+            let return_type =
+                self.convert_plick_type_to_llvm_basic_type(infer_pli_type_via_name(procedure_name));
+            let function_type = return_type.fn_type(&[], false);
+            let function = self
+                .module
+                .add_function(procedure_name, function_type, None);
+            let entry = self.context.append_basic_block(function, "entry");
+            self.builder.position_at_end(entry);
+
+            function
+        }
+
         ///creates the main func and returns its value
         pub fn initalize_main_function(&self) -> FunctionValue<'ctx> {
             let args: Vec<BasicMetadataTypeEnum> = vec![];
-            let main_function_type = self.context.void_type().fn_type(&args, false);
-            let main_func = self.module.add_function("main", main_function_type, None);
+            //main returns the return code, so the shell can tell whether it failed
+            let main_function_type = self.context.i32_type().fn_type(&args, false);
+            let main_func =
+                self.module
+                    .add_function(program::MAIN_FUNCTION_NAME, main_function_type, None);
             //create a new scope block for the function
             let new_func_block = self.context.append_basic_block(main_func, "entry");
 
@@ -597,7 +1070,7 @@ mod tests {
     use std::collections::HashMap;
 
     use crate::{
-        ast::{Expr, Function, Prototype},
+        ast::{Expr, Function, ProcedureLinkage, Prototype},
         codegen::codegen::{CodeGenable, Compiler},
         lexer::Token,
     };
@@ -626,6 +1099,10 @@ mod tests {
             function_properties,
             debug_controller,
             type_module: TypeModule::new(&context),
+            c_entries: RefCell::new(HashMap::new()),
+            exported_procedures: RefCell::new(vec![]),
+            packed_decimal: false,
+            procedure_prototypes: RefCell::new(HashMap::new()),
         };
         compiler
     }
@@ -685,7 +1162,7 @@ mod tests {
             operator: Token::MINUS,
             left: Box::new(Expr::Variable {
                 name: String::from("APPLE"),
-                _type: Type::FixedDecimal,
+                _type: Type::DEFAULT_FIXED,
             }),
             right: Box::new(Expr::new_numval(5.0)),
         };
@@ -694,6 +1171,7 @@ mod tests {
             fn_name: String::from("myFuncName"),
             args: vec![String::from("APPLE")],
             source_loc,
+            linkage: ProcedureLinkage::Internal,
         };
         let my_func = Function {
             prototype: my_proto,
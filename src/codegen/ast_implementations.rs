@@ -1,8 +1,15 @@
+pub mod allocate;
 pub mod assignment;
+pub mod close;
 pub mod declare;
+pub mod r#do;
 pub mod expr;
+pub mod free;
 pub mod func;
 pub mod get;
 pub mod go;
 pub mod r#if;
+pub mod on;
+pub mod open;
 pub mod put;
+pub mod record_io;
@@ -0,0 +1,106 @@
+use inkwell::values::{AnyValue, PointerValue};
+
+use crate::ast;
+use crate::codegen::codegen::{CodeGenable, Compiler};
+use crate::codegen::named_value::NamedValue;
+use crate::codegen::named_value_store::NamedValueStore;
+use crate::codegen::runtime::controlled;
+use crate::error::get_error;
+use crate::types::Type;
+
+impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Allocate {
+    unsafe fn codegen(self, compiler: &'a Compiler<'a, 'ctx>) -> Box<dyn AnyValue<'ctx> + 'ctx> {
+        if let Err(message) = compiler.generate_allocate_code(self) {
+            compiler.error_module.store_error_msg(&message);
+        }
+        Box::new(compiler.context.i8_type().const_zero())
+    }
+}
+
+impl<'a, 'ctx> Compiler<'a, 'ctx> {
+    ///Gets new heap storage for a BASED variable and puts its address in
+    ///the SET pointer, or in the locator the variable was declared with.
+    ///A CONTROLLED variable gets a new generation instead.
+    unsafe fn generate_allocate_code(&'a self, allocate: ast::Allocate) -> Result<(), String> {
+        let named_value = self
+            .named_values
+            .try_get(&allocate.var_name)
+            .ok_or(get_error(&["12", &allocate.var_name]))?;
+
+        let declared_locator = match named_value.storage_class {
+            ast::StorageClass::Based { ref locator } => locator.clone(),
+            ast::StorageClass::Controlled => {
+                if allocate.set.is_some() {
+                    return Err(get_error(&["19", &allocate.var_name]));
+                }
+                return self.generate_controlled_allocate_code(&named_value);
+            }
+            ast::StorageClass::Automatic
+            | ast::StorageClass::Static
+            | ast::StorageClass::External => {
+                return Err(get_error(&["18", &allocate.var_name]));
+            }
+        };
+        let locator_name = allocate
+            .set
+            .or(declared_locator)
+            .ok_or(get_error(&["15", &allocate.var_name]))?;
+
+        let locator = self
+            .named_values
+            .try_get(&locator_name)
+            .ok_or(get_error(&["12", &locator_name]))?;
+        if locator._type != Type::Pointer {
+            let locator_type: String = locator._type.into();
+            return Err(get_error(&["16", &allocate.var_name, &locator_type]));
+        }
+        let locator_ptr = self.get_variable_storage(&locator)?;
+
+        let storage_type = self.get_storage_type(named_value._type, named_value.dimension);
+        let storage: PointerValue<'ctx> = self
+            .builder
+            .build_malloc(storage_type, &allocate.var_name)
+            .map_err(|err| err.to_string())?;
+        self.initialize_varying_storage(storage, named_value._type, named_value.dimension);
+
+        let address = self
+            .builder
+            .build_pointer_cast(storage, self.get_pointer_type(), "address")
+            .unwrap();
+        self.builder.build_store(locator_ptr, address).unwrap();
+        Ok(())
+    }
+
+    ///Pushes a new generation of a CONTROLLED variable.
+    unsafe fn generate_controlled_allocate_code(
+        &'a self,
+        named_value: &NamedValue<'ctx>,
+    ) -> Result<(), String> {
+        let value_type = self.get_storage_type(named_value._type, named_value.dimension);
+        let generation_type = controlled::get_generation_type(self, value_type);
+        let generation: PointerValue<'ctx> = self
+            .builder
+            .build_malloc(generation_type, &named_value.name)
+            .map_err(|err| err.to_string())?;
+        let storage = self
+            .builder
+            .build_struct_gep(generation, controlled::VALUE_FIELD, "storage")
+            .unwrap();
+        self.initialize_varying_storage(storage, named_value._type, named_value.dimension);
+
+        let anchor = controlled::get_or_add_anchor(self, &named_value.name);
+        let generation = self
+            .builder
+            .build_pointer_cast(generation, self.get_pointer_type(), "generation")
+            .unwrap();
+        self.builder
+            .build_call(
+                self.get_function(controlled::CONTROLLED_PUSH_FUNCTION_NAME)
+                    .unwrap(),
+                &[anchor.into(), generation.into()],
+                "push_generation",
+            )
+            .unwrap();
+        Ok(())
+    }
+}
@@ -31,6 +31,13 @@ impl<'a, 'ctx> ast::Assignment {
         &self,
         compiler: &'a crate::codegen::codegen::Compiler<'a, 'ctx>,
     ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, Box<dyn Error>> {
+        if let Some(ref locator) = self.locator {
+            return Ok(self.generate_qualified_assignment_code(locator.clone(), compiler)?);
+        }
+        if let Some(ref subscript) = self.subscript {
+            return Ok(self.generate_subscripted_assignment_code(subscript.clone(), compiler)?);
+        }
+
         let variable_in_map = compiler.named_values.try_get(&self.var_name);
 
         log::debug!(
@@ -40,9 +47,16 @@ impl<'a, 'ctx> ast::Assignment {
 
         let var_ptr: PointerValue<'ctx>; //= _named_value.pointer;
 
+        //an undeclared variable holding a number gets the default type of its
+        //name, any other one takes the type of the value
         let _type = match variable_in_map.clone() {
             Some(val) => val._type,
-            None => self.value.get_type(compiler),
+            None => match self.value.get_type(compiler) {
+                value_type if value_type.is_arithmetic() => {
+                    compiler.get_default_type(&self.var_name)
+                }
+                value_type => value_type,
+            },
         };
 
         log::debug!("Resolved type of {:?} to be {:?}", variable_in_map, _type);
@@ -65,21 +79,120 @@ impl<'a, 'ctx> ast::Assignment {
         let current_func = utils::get_current_function(compiler);
 
         var_ptr = match variable_in_map.clone() {
-            Some(val) => val.pointer,
+            Some(val) if val.dimension.is_some() => {
+                compiler
+                    .error_module
+                    .store_msg_from_number(&["23", &self.var_name]);
+                return Ok(compiler.ret_zero());
+            }
+            Some(val) => match compiler.get_variable_storage(&val) {
+                Ok(storage) => storage,
+                Err(message) => {
+                    compiler.error_module.store_error_msg(&message);
+                    return Ok(compiler.ret_zero());
+                }
+            },
             None => {
                 let ptr = compiler.create_entry_block_alloca(&self.var_name, &current_func, &_type);
-                let named_value = NamedValue {
-                    name: self.var_name.clone(),
-                    _type,
-                    pointer: ptr,
-                };
+                let named_value = NamedValue::new(self.var_name.clone(), _type, ptr);
                 compiler.named_values.insert(named_value);
                 ptr
             }
         };
 
-        let value_to_store: Box<dyn AnyValue> =
+        self.store_value(var_ptr, _type, type_of_assigned_value, compiler)
+    }
+
+    ///P->X = value;
+    unsafe fn generate_qualified_assignment_code(
+        &self,
+        locator: Expr,
+        compiler: &'a crate::codegen::codegen::Compiler<'a, 'ctx>,
+    ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, Box<dyn Error>> {
+        let (var_ptr, _type) = match compiler.get_qualified_storage(locator, &self.var_name) {
+            Ok(storage) => storage,
+            Err(message) => {
+                compiler.error_module.store_error_msg(&message);
+                return Ok(compiler.ret_zero());
+            }
+        };
+
+        let type_of_assigned_value = self.value.get_type(compiler);
+        if !do_types_match(&type_of_assigned_value, &_type) {
+            let lhs: String = _type.into();
+            let rhs: String = type_of_assigned_value.into();
+            compiler
+                .error_module
+                .store_msg_from_number(&["9", &lhs, &rhs]);
+            return Ok(compiler.ret_zero());
+        }
+
+        self.store_value(var_ptr, _type, type_of_assigned_value, compiler)
+    }
+
+    ///A(I) = value;
+    unsafe fn generate_subscripted_assignment_code(
+        &self,
+        subscript: Expr,
+        compiler: &'a crate::codegen::codegen::Compiler<'a, 'ctx>,
+    ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, Box<dyn Error>> {
+        let named_value = match compiler.named_values.try_get(&self.var_name) {
+            Some(named_value) => named_value,
+            None => {
+                compiler
+                    .error_module
+                    .store_msg_from_number(&["12", &self.var_name]);
+                return Ok(compiler.ret_zero());
+            }
+        };
+        let var_ptr = match compiler.get_element_storage(&named_value, subscript) {
+            Ok(storage) => storage,
+            Err(message) => {
+                compiler.error_module.store_error_msg(&message);
+                return Ok(compiler.ret_zero());
+            }
+        };
+        let _type = named_value._type;
+
+        let type_of_assigned_value = self.value.get_type(compiler);
+        if !do_types_match(&type_of_assigned_value, &_type) {
+            let lhs: String = _type.into();
+            let rhs: String = type_of_assigned_value.into();
+            compiler
+                .error_module
+                .store_msg_from_number(&["9", &lhs, &rhs]);
+            return Ok(compiler.ret_zero());
+        }
+
+        self.store_value(var_ptr, _type, type_of_assigned_value, compiler)
+    }
+
+    unsafe fn store_value(
+        &self,
+        var_ptr: PointerValue<'ctx>,
+        _type: Type,
+        type_of_assigned_value: Type,
+        compiler: &'a crate::codegen::codegen::Compiler<'a, 'ctx>,
+    ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, Box<dyn Error>> {
+        //only constants going into a CHAR are padded at compile time,
+        //every other string is copied up to the length of the variable
+        let is_constant_char =
+            matches!(_type, Type::Char(_)) && matches!(self.value, Expr::Char { .. });
+        if _type.is_character() && !is_constant_char {
+            let string_view = compiler.generate_string_view(
+                self.value.clone().codegen(compiler),
+                type_of_assigned_value,
+            )?;
+            compiler.store_string(var_ptr, _type, string_view);
+            return Ok(Box::new(var_ptr));
+        }
+
+        let mut value_to_store: Box<dyn AnyValue> =
             expr_assignment_gen::codegen_expr_assignment(self.value.clone(), &_type, compiler);
+        if _type.is_arithmetic() {
+            value_to_store =
+                compiler.convert_arithmetic_value(value_to_store, type_of_assigned_value, _type)?;
+        }
 
         let initial_value: BasicValueEnum<'ctx> =
             compiler.convert_anyvalue_to_basicvalue(value_to_store);
@@ -0,0 +1,24 @@
+use crate::ast;
+use crate::codegen::codegen::{CodeGenable, Compiler};
+use crate::codegen::runtime::stream;
+
+impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Close {
+    unsafe fn codegen(
+        self,
+        compiler: &'a Compiler<'a, 'ctx>,
+    ) -> Box<dyn inkwell::values::AnyValue<'ctx> + 'ctx> {
+        for file_name in self.file_names {
+            if let Some(file) = compiler.get_declared_stream_file(&file_name) {
+                compiler
+                    .builder
+                    .build_call(
+                        compiler.get_function(stream::CLOSE_FUNCTION_NAME).unwrap(),
+                        &[file.into()],
+                        "close_file",
+                    )
+                    .unwrap();
+            }
+        }
+        Box::new(compiler.context.i8_type().const_zero())
+    }
+}
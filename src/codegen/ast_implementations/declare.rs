@@ -1,14 +1,49 @@
 use std::error::Error;
 
-use crate::{ast, codegen::codegen::CodeGenable, types::infer_pli_type_via_name};
+use inkwell::{
+    module::Linkage,
+    types::{AnyType, BasicType, BasicTypeEnum},
+    values::{
+        ArrayValue, BasicValue, BasicValueEnum, FloatValue, FunctionValue, IntValue, PointerValue,
+        StructValue,
+    },
+    AddressSpace,
+};
+
+use crate::{
+    ast,
+    codegen::{
+        codegen::{CodeGenable, Compiler},
+        named_value::NamedValue,
+        named_value_store::NamedValueStore,
+        runtime::{controlled, stream},
+        utils,
+    },
+    error::get_error,
+    lexer,
+    types::{
+        character::{
+            generate_character_code, generate_character_code_for_size,
+            generate_constant_varying_string,
+        },
+        fixed_decimal::negate_digits,
+        float_decimal::generate_constant_float_decimal,
+        Type,
+    },
+};
 
 impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Declare {
     unsafe fn codegen(
         self,
         compiler: &'a crate::codegen::codegen::Compiler<'a, 'ctx>,
     ) -> Box<dyn inkwell::values::AnyValue<'ctx> + 'ctx> {
-        self.codegen_with_error_info(compiler)
-            .expect("Error generating DECLARE statement")
+        match self.codegen_with_error_info(compiler) {
+            Ok(value) => value,
+            Err(error) => {
+                compiler.error_module.store_error_msg(&error.to_string());
+                compiler.ret_zero()
+            }
+        }
     }
 }
 
@@ -20,11 +55,454 @@ impl<'a, 'ctx> ast::Declare {
         log::info!("Generating declare code!");
         let name = self.var_name.clone();
         log::info!("Name: {}", name);
-        let _type = self.attribute.unwrap_or(infer_pli_type_via_name(&name));
+
+        if let Some(file_attributes) = self.file_attributes {
+            //SYSIN and SYSPRINT are predeclared, declaring them again changes nothing
+            let file = match compiler.get_stream_file(&name) {
+                Some(file) => file,
+                None => stream::add_declared_file(compiler, &name, &file_attributes),
+            };
+            return Ok(Box::new(file));
+        }
+
+        //the calls of a GENERIC name were replaced by the type pass
+        if self.generic.is_some() {
+            return Ok(compiler.ret_zero());
+        }
+
+        if let Some(entry) = self.entry {
+            //an ENTRY parameter was already declared by the procedure taking it
+            if let Some(parameter) = compiler.named_values.try_get(&name) {
+                if parameter._type == Type::Entry {
+                    return Ok(Box::new(parameter.pointer));
+                }
+            }
+            if entry.is_variable {
+                let variable_ptr = compiler.declare_entry_variable(
+                    &name,
+                    entry,
+                    self.storage_class,
+                    self.dimension,
+                )?;
+                return Ok(Box::new(variable_ptr));
+            }
+            let function = match entry.is_c {
+                true => compiler.declare_c_entry(&name, entry)?,
+                false => compiler.declare_entry(&name, entry)?,
+            };
+            return Ok(Box::new(function));
+        }
+
+        //a parameter was already given the type of its declaration by the
+        //procedure taking it, and holds its argument
+        if is_parameter(compiler, &name) {
+            if let Some(parameter) = compiler.named_values.try_get(&name) {
+                return Ok(Box::new(parameter.pointer));
+            }
+        }
+
+        let _type = self
+            .attribute
+            .unwrap_or_else(|| compiler.get_default_type(&name));
 
         log::info!("Type: {}", _type);
-        //let current_function = get_current_function(self);
-        //self.create_entry_block_alloca(&name, &current_function, &_type)
-        Ok(Box::new(compiler.create_or_load_variable(&name, &_type)))
+
+        //BASED and CONTROLLED variables get their storage from ALLOCATE, not here
+        let null_pointer = compiler
+            .get_storage_type(_type, self.dimension)
+            .ptr_type(AddressSpace::default())
+            .const_null();
+        match self.storage_class {
+            ast::StorageClass::Based { locator } => {
+                compiler.named_values.insert(
+                    NamedValue::new_based(name, _type, null_pointer, locator)
+                        .with_dimension(self.dimension),
+                );
+                return Ok(Box::new(null_pointer));
+            }
+            ast::StorageClass::Controlled => {
+                controlled::get_or_add_anchor(compiler, &name);
+                compiler.named_values.insert(
+                    NamedValue::new_controlled(name, _type, null_pointer)
+                        .with_dimension(self.dimension),
+                );
+                return Ok(Box::new(null_pointer));
+            }
+            ast::StorageClass::Static => {
+                let global = compiler.add_static_variable(
+                    &name,
+                    _type,
+                    self.dimension,
+                    self.initial.unwrap_or_default(),
+                )?;
+                return Ok(Box::new(global));
+            }
+            ast::StorageClass::External => {
+                let global =
+                    compiler.add_external_variable(&name, _type, self.dimension, self.initial)?;
+                return Ok(Box::new(global));
+            }
+            ast::StorageClass::Automatic => (),
+        }
+
+        let variable_ptr = match self.dimension {
+            Some(_element_count) => {
+                let current_function = utils::get_current_function(compiler);
+                let array_ptr = compiler.create_entry_block_alloca_of_type(
+                    &name,
+                    &current_function,
+                    compiler.get_storage_type(_type, self.dimension),
+                );
+                compiler.named_values.insert(
+                    NamedValue::new(name.clone(), _type, array_ptr).with_dimension(self.dimension),
+                );
+                array_ptr
+            }
+            None => compiler.create_or_load_variable(&name, &_type),
+        };
+        compiler.initialize_varying_storage(variable_ptr, _type, self.dimension);
+        //a LABEL variable holds no label until it is given one
+        if _type == Type::Label {
+            let no_label = compiler
+                .get_storage_type(_type, self.dimension)
+                .const_zero();
+            compiler
+                .builder
+                .build_store(variable_ptr, no_label)
+                .unwrap();
+        }
+
+        //AUTOMATIC variables are initialized every time their DECLARE is reached
+        if let Some(initial) = self.initial {
+            check_initial_fits(&name, initial.len(), self.dimension)?;
+            for (index, value) in initial.into_iter().enumerate() {
+                let subscript = self
+                    .dimension
+                    .map(|_element_count| ast::Expr::new_numval((index + 1) as f64));
+                ast::Assignment {
+                    var_name: name.clone(),
+                    locator: None,
+                    subscript,
+                    value,
+                }
+                .codegen(compiler);
+            }
+        }
+        Ok(Box::new(variable_ptr))
+    }
+}
+
+///Whether `name` is a parameter of the procedure being generated.
+fn is_parameter<'a, 'ctx>(compiler: &'a Compiler<'a, 'ctx>, name: &str) -> bool {
+    utils::get_current_function(compiler)
+        .get_param_iter()
+        .any(|parameter| parameter.get_name().to_str() == Ok(name))
+}
+
+impl<'a, 'ctx> Compiler<'a, 'ctx> {
+    ///Adds the global holding a STATIC variable. It is named after the
+    ///procedure declaring it, so STATIC variables of different procedures
+    ///can share a name.
+    unsafe fn add_static_variable(
+        &self,
+        name: &str,
+        _type: Type,
+        dimension: Option<u32>,
+        initial: Vec<ast::Expr>,
+    ) -> Result<PointerValue<'ctx>, Box<dyn Error>> {
+        let initializer = self.generate_constant_initializer(name, _type, dimension, initial)?;
+
+        let current_function = utils::get_current_function(self);
+        let global_name = format!(
+            "plick_static_{}_{}",
+            current_function.get_name().to_str().unwrap(),
+            name
+        );
+        let global = self.module.add_global(
+            self.get_storage_type(_type, dimension),
+            Some(AddressSpace::default()),
+            &global_name,
+        );
+        global.set_initializer(&initializer);
+        global.set_linkage(Linkage::Internal);
+
+        let global_ptr = global.as_pointer_value();
+        self.named_values.insert(
+            NamedValue::new_static(name.to_string(), _type, global_ptr).with_dimension(dimension),
+        );
+        Ok(global_ptr)
+    }
+
+    ///Adds the global holding an EXTERNAL variable, or finds the one added by
+    ///an earlier declaration. It is named after the variable, so every unit
+    ///declaring it refers to the same storage. Only a unit giving it an INITIAL
+    ///value defines it, the linker defines the rest.
+    unsafe fn add_external_variable(
+        &self,
+        name: &str,
+        _type: Type,
+        dimension: Option<u32>,
+        initial: Option<Vec<ast::Expr>>,
+    ) -> Result<PointerValue<'ctx>, Box<dyn Error>> {
+        let storage_type = self.get_storage_type(_type, dimension);
+        let unit_name = self.module.get_name().to_str().unwrap().to_string();
+
+        let global = match self.module.get_global(name) {
+            Some(global) => {
+                let declared_type = global.as_pointer_value().get_type().get_element_type();
+                if declared_type != storage_type.as_any_type_enum() {
+                    return Err(get_error(&["24", name, &unit_name, &unit_name]).into());
+                }
+                if initial.is_some() && global.get_initializer().is_some() {
+                    return Err(get_error(&["25", name, &unit_name, &unit_name]).into());
+                }
+                global
+            }
+            None => self
+                .module
+                .add_global(storage_type, Some(AddressSpace::default()), name),
+        };
+
+        if let Some(initial) = initial {
+            let initializer =
+                self.generate_constant_initializer(name, _type, dimension, initial)?;
+            global.set_initializer(&initializer);
+        }
+
+        let global_ptr = global.as_pointer_value();
+        self.named_values.insert(
+            NamedValue::new_external(name.to_string(), _type, global_ptr).with_dimension(dimension),
+        );
+        Ok(global_ptr)
+    }
+
+    ///Declares a procedure that is defined in another unit, or later in this one.
+    unsafe fn declare_entry(
+        &self,
+        name: &str,
+        entry: ast::Entry,
+    ) -> Result<FunctionValue<'ctx>, Box<dyn Error>> {
+        let (parameters, return_type) = self.get_entry_prototype(name, &entry)?;
+
+        let function =
+            self.generate_function_prototype_code(name.to_string(), parameters, return_type);
+        //a procedure of that name with other parameters was already declared
+        if function.get_name().to_str().unwrap() != name {
+            function.delete();
+            let unit_name = self.module.get_name().to_str().unwrap().to_string();
+            return Err(get_error(&["24", name, &unit_name, &unit_name]).into());
+        }
+        Ok(function)
+    }
+
+    ///The initial value of the global holding a STATIC or EXTERNAL variable.
+    ///Elements without an INITIAL value start out as zeros.
+    fn generate_constant_initializer(
+        &self,
+        name: &str,
+        _type: Type,
+        dimension: Option<u32>,
+        initial: Vec<ast::Expr>,
+    ) -> Result<BasicValueEnum<'ctx>, Box<dyn Error>> {
+        check_initial_fits(name, initial.len(), dimension)?;
+
+        let mut values: Vec<BasicValueEnum<'ctx>> = vec![];
+        for value in initial {
+            values.push(self.generate_constant_initial_value(name, _type, value)?);
+        }
+
+        let element_type = self.convert_plick_type_to_llvm_basic_type(_type);
+        let initializer = match dimension {
+            None => values
+                .pop()
+                .unwrap_or_else(|| element_type.const_zero().as_basic_value_enum()),
+            Some(element_count) => {
+                values.resize(element_count as usize, element_type.const_zero());
+                build_constant_array(element_type, &values)
+            }
+        };
+        Ok(initializer)
+    }
+
+    ///Turns an INITIAL value of a STATIC variable into a constant, since it
+    ///is part of the program rather than computed when it runs.
+    fn generate_constant_initial_value(
+        &self,
+        name: &str,
+        _type: Type,
+        value: ast::Expr,
+    ) -> Result<BasicValueEnum<'ctx>, Box<dyn Error>> {
+        let not_constant = || get_error(&["20", name]);
+        let too_large = || get_error(&["42", name]);
+        let constant = match (_type, value) {
+            (Type::FixedDecimal(..) | Type::Float(_), ast::Expr::NumVal { value, digits, .. }) => {
+                self.generate_constant_number(_type, value, digits)
+                    .ok_or_else(too_large)?
+            }
+            (
+                Type::FixedDecimal(..) | Type::Float(_),
+                ast::Expr::Infix {
+                    operator: lexer::Token::MINUS,
+                    operand,
+                },
+            ) => match *operand {
+                ast::Expr::NumVal { value, digits, .. } => self
+                    .generate_constant_number(
+                        _type,
+                        -value,
+                        digits.map(|digits| negate_digits(&digits)),
+                    )
+                    .ok_or_else(too_large)?,
+                _other => return Err(not_constant().into()),
+            },
+            (Type::Picture(picture), ast::Expr::NumVal { value, .. }) => {
+                generate_character_code(self.context, &picture.edit(value))
+                    .value
+                    .as_basic_value_enum()
+            }
+            (
+                Type::Picture(picture),
+                ast::Expr::Infix {
+                    operator: lexer::Token::MINUS,
+                    operand,
+                },
+            ) => match *operand {
+                ast::Expr::NumVal { value, .. } => {
+                    generate_character_code(self.context, &picture.edit(-value))
+                        .value
+                        .as_basic_value_enum()
+                }
+                _other => return Err(not_constant().into()),
+            },
+            (Type::Char(size), ast::Expr::Char { value }) => {
+                let truncated: String = value.chars().take(size as usize).collect();
+                generate_character_code_for_size(self.context, &truncated, size)
+                    .value
+                    .as_basic_value_enum()
+            }
+            (Type::VaryingChar(max_length), ast::Expr::Char { value }) => {
+                generate_constant_varying_string(self.context, &value, max_length)
+                    .as_basic_value_enum()
+            }
+            (Type::FixedComplex | Type::FloatComplex, ast::Expr::NumVal { value, .. }) => self
+                .generate_constant_complex(_type, value, 0.0)
+                .ok_or_else(too_large)?,
+            //an imaginary constant like 2I is COMPLEX(0, 2)
+            (Type::FixedComplex | Type::FloatComplex, ast::Expr::Call { fn_name, args, .. })
+                if fn_name == "COMPLEX" =>
+            {
+                match args.as_slice() {
+                    [ast::Expr::NumVal { value: real, .. }, ast::Expr::NumVal {
+                        value: imaginary, ..
+                    }] => self
+                        .generate_constant_complex(_type, *real, *imaginary)
+                        .ok_or_else(too_large)?,
+                    _other => return Err(not_constant().into()),
+                }
+            }
+            (Type::Pointer, ast::Expr::Call { fn_name, args, .. })
+                if fn_name == "NULL" && args.is_empty() =>
+            {
+                self.get_pointer_type().const_null().as_basic_value_enum()
+            }
+            _other => return Err(not_constant().into()),
+        };
+        Ok(constant)
+    }
+
+    ///The constant `value` of a number type, or None when it has too many
+    ///digits before the point for a FIXED DECIMAL to hold.
+    ///A FIXED DECIMAL is built from the `digits` the constant was written
+    ///with, when there are some, so that none of them are lost.
+    fn generate_constant_number(
+        &self,
+        _type: Type,
+        value: f64,
+        digits: Option<String>,
+    ) -> Option<BasicValueEnum<'ctx>> {
+        match _type {
+            Type::Float(precision) => {
+                Some(generate_constant_float_decimal(self, value, precision).as_basic_value_enum())
+            }
+            Type::FixedDecimal(precision, scale) => self
+                .gen_const_fixed_decimal(
+                    &digits.unwrap_or_else(|| value.to_string()),
+                    precision,
+                    scale,
+                )
+                .map(|constant| constant.as_basic_value_enum()),
+            other => panic!("Expected a number type, found {}", other),
+        }
+    }
+
+    fn generate_constant_complex(
+        &self,
+        _type: Type,
+        real: f64,
+        imaginary: f64,
+    ) -> Option<BasicValueEnum<'ctx>> {
+        let part_type = _type.get_real_type();
+        Some(
+            self.get_complex_struct_type(_type)
+                .const_named_struct(&[
+                    self.generate_constant_number(part_type, real, None)?,
+                    self.generate_constant_number(part_type, imaginary, None)?,
+                ])
+                .as_basic_value_enum(),
+        )
+    }
+}
+
+fn check_initial_fits(
+    name: &str,
+    value_count: usize,
+    dimension: Option<u32>,
+) -> Result<(), Box<dyn Error>> {
+    let room = dimension.unwrap_or(1);
+    if value_count > room as usize {
+        return Err(get_error(&["21", name, &value_count.to_string(), &room.to_string()]).into());
+    }
+    Ok(())
+}
+
+fn build_constant_array<'ctx>(
+    element_type: BasicTypeEnum<'ctx>,
+    values: &[BasicValueEnum<'ctx>],
+) -> BasicValueEnum<'ctx> {
+    match element_type {
+        BasicTypeEnum::StructType(struct_type) => {
+            let elements: Vec<StructValue> = values
+                .iter()
+                .map(|value| value.into_struct_value())
+                .collect();
+            struct_type.const_array(&elements).as_basic_value_enum()
+        }
+        BasicTypeEnum::IntType(int_type) => {
+            let elements: Vec<IntValue> =
+                values.iter().map(|value| value.into_int_value()).collect();
+            int_type.const_array(&elements).as_basic_value_enum()
+        }
+        BasicTypeEnum::FloatType(float_type) => {
+            let elements: Vec<FloatValue> = values
+                .iter()
+                .map(|value| value.into_float_value())
+                .collect();
+            float_type.const_array(&elements).as_basic_value_enum()
+        }
+        BasicTypeEnum::ArrayType(array_type) => {
+            let elements: Vec<ArrayValue> = values
+                .iter()
+                .map(|value| value.into_array_value())
+                .collect();
+            array_type.const_array(&elements).as_basic_value_enum()
+        }
+        BasicTypeEnum::PointerType(pointer_type) => {
+            let elements: Vec<PointerValue> = values
+                .iter()
+                .map(|value| value.into_pointer_value())
+                .collect();
+            pointer_type.const_array(&elements).as_basic_value_enum()
+        }
+        other => panic!("Arrays of {} are not supported", other),
     }
 }
@@ -0,0 +1,262 @@
+use std::error::Error;
+
+use inkwell::{
+    basic_block::BasicBlock,
+    values::{AnyValue, FloatValue, IntValue},
+    FloatPredicate,
+};
+
+use crate::{
+    ast,
+    codegen::{
+        codegen::{CodeGenable, Compiler, LoopTargets},
+        utils::{self, get_current_function},
+    },
+    error::get_error,
+    types::Type,
+};
+
+impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Do {
+    unsafe fn codegen(
+        self,
+        compiler: &'a crate::codegen::codegen::Compiler<'a, 'ctx>,
+    ) -> Box<dyn inkwell::values::AnyValue<'ctx> + 'ctx> {
+        self.codegen_with_error_info(compiler).unwrap()
+    }
+}
+
+impl<'a, 'ctx> ast::Do {
+    unsafe fn codegen_with_error_info(
+        self,
+        compiler: &'a crate::codegen::codegen::Compiler<'a, 'ctx>,
+    ) -> Result<Box<dyn inkwell::values::AnyValue<'ctx> + 'ctx>, Box<dyn Error>> {
+        let current_func = get_current_function(compiler);
+        let is_iterative = self.is_iterative();
+        let ast::Do {
+            label,
+            control,
+            while_condition,
+            statements,
+        } = self;
+
+        //DO; runs its statements once, ITERATE just goes to its end
+        if !is_iterative {
+            let end_block = compiler.context.append_basic_block(current_func, "doend");
+            let targets = LoopTargets {
+                label,
+                break_block: end_block,
+                continue_block: end_block,
+            };
+            compiler.generate_do_body_code(targets, statements);
+            compiler.builder.position_at_end(end_block);
+            return Ok(compiler.ret_zero());
+        }
+
+        //I = 1, the TO and BY values are evaluated before the first iteration
+        let bounds = match control {
+            Some(ref control) => Some(control.generate_start_code(compiler)?),
+            None => None,
+        };
+
+        let condition_block = compiler.context.append_basic_block(current_func, "docond");
+        let body_block = compiler.context.append_basic_block(current_func, "dobody");
+        let next_block = compiler.context.append_basic_block(current_func, "donext");
+        let end_block = compiler.context.append_basic_block(current_func, "doend");
+
+        utils::branch_only_if_no_terminator(compiler, condition_block);
+        compiler.builder.position_at_end(condition_block);
+
+        if let (Some(control), Some((end, step))) = (&control, bounds) {
+            let is_within_bounds = control.generate_bounds_check_code(compiler, end, step)?;
+            //the WHILE condition is only evaluated when I is still within bounds
+            let while_block = compiler.context.append_basic_block(current_func, "dowhile");
+            compiler
+                .builder
+                .build_conditional_branch(is_within_bounds, while_block, end_block)?;
+            compiler.builder.position_at_end(while_block);
+        }
+
+        match while_condition {
+            Some(while_condition) => {
+                let comparison = compiler.generate_checked_condition_code(while_condition);
+                compiler
+                    .builder
+                    .build_conditional_branch(comparison, body_block, end_block)?;
+            }
+            None => {
+                compiler.builder.build_unconditional_branch(body_block)?;
+            }
+        }
+
+        compiler.builder.position_at_end(body_block);
+        let targets = LoopTargets {
+            label,
+            break_block: end_block,
+            continue_block: next_block,
+        };
+        compiler.generate_do_body_code(targets, statements);
+
+        compiler.builder.position_at_end(next_block);
+        if let (Some(control), Some((_end, step))) = (&control, bounds) {
+            control.generate_increment_code(compiler, step)?;
+        }
+        compiler
+            .builder
+            .build_unconditional_branch(condition_block)?;
+
+        compiler.builder.position_at_end(end_block);
+        Ok(compiler.ret_zero())
+    }
+}
+
+impl<'a, 'ctx> ast::DoControl {
+    ///Assigns the start value to the control variable and evaluates the TO
+    ///and BY values, which don't change while the group runs.
+    unsafe fn generate_start_code(
+        &self,
+        compiler: &'a Compiler<'a, 'ctx>,
+    ) -> Result<(FloatValue<'ctx>, FloatValue<'ctx>), Box<dyn Error>> {
+        let assignment = ast::Assignment {
+            var_name: self.variable_name.clone(),
+            locator: None,
+            subscript: None,
+            value: self.start.clone(),
+        };
+        assignment.codegen(compiler);
+
+        let end = compiler.generate_expression_as_float(self.end.clone())?;
+        let step = match self.step {
+            Some(ref step) => compiler.generate_expression_as_float(step.clone())?,
+            None => compiler.generate_float_code(1.0),
+        };
+        Ok((end, step))
+    }
+
+    ///Whether the control variable hasn't gone past the TO value yet. When
+    ///counting down with a negative BY, that means it is still above it.
+    unsafe fn generate_bounds_check_code(
+        &self,
+        compiler: &'a Compiler<'a, 'ctx>,
+        end: FloatValue<'ctx>,
+        step: FloatValue<'ctx>,
+    ) -> Result<IntValue<'ctx>, Box<dyn Error>> {
+        let current = self.generate_control_variable_code(compiler)?;
+        let zero = compiler.generate_float_code(0.0);
+
+        let builder = compiler.builder;
+        let is_counting_up =
+            builder.build_float_compare(FloatPredicate::OGE, step, zero, "is_counting_up")?;
+        let is_below_end =
+            builder.build_float_compare(FloatPredicate::OLE, current, end, "is_below_end")?;
+        let is_above_end =
+            builder.build_float_compare(FloatPredicate::OGE, current, end, "is_above_end")?;
+        let is_within_bounds = builder.build_select(
+            is_counting_up,
+            is_below_end,
+            is_above_end,
+            "is_within_bounds",
+        )?;
+        Ok(is_within_bounds.into_int_value())
+    }
+
+    ///I = I + step;
+    unsafe fn generate_increment_code(
+        &self,
+        compiler: &'a Compiler<'a, 'ctx>,
+        step: FloatValue<'ctx>,
+    ) -> Result<(), Box<dyn Error>> {
+        let current = self.generate_control_variable_code(compiler)?;
+        let next = compiler.builder.build_float_add(current, step, "next")?;
+
+        let named_value = compiler
+            .named_values
+            .try_get(&self.variable_name)
+            .ok_or(get_error(&["12", &self.variable_name]))?;
+        let variable_ptr = compiler.get_variable_storage(&named_value)?;
+        let next_value = compiler.create_arithmetic_value(&next, named_value._type)?;
+        let next_value = compiler.convert_anyvalue_to_basicvalue(next_value);
+        compiler.builder.build_store(variable_ptr, next_value)?;
+        Ok(())
+    }
+
+    unsafe fn generate_control_variable_code(
+        &self,
+        compiler: &'a Compiler<'a, 'ctx>,
+    ) -> Result<FloatValue<'ctx>, Box<dyn Error>> {
+        let variable = ast::Expr::Variable {
+            _type: Type::TBD,
+            name: self.variable_name.clone(),
+        };
+        Ok(compiler.generate_expression_as_float(variable)?)
+    }
+}
+
+impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Leave {
+    unsafe fn codegen(
+        self,
+        compiler: &'a crate::codegen::codegen::Compiler<'a, 'ctx>,
+    ) -> Box<dyn inkwell::values::AnyValue<'ctx> + 'ctx> {
+        compiler.generate_loop_exit_code("LEAVE", self.label.as_deref(), |targets| {
+            targets.break_block
+        })
+    }
+}
+
+impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Iterate {
+    unsafe fn codegen(
+        self,
+        compiler: &'a crate::codegen::codegen::Compiler<'a, 'ctx>,
+    ) -> Box<dyn inkwell::values::AnyValue<'ctx> + 'ctx> {
+        compiler.generate_loop_exit_code("ITERATE", self.label.as_deref(), |targets| {
+            targets.continue_block
+        })
+    }
+}
+
+impl<'a, 'ctx> Compiler<'a, 'ctx> {
+    ///Generates the statements of a DO group, with LEAVE and ITERATE going to
+    ///the blocks in `targets`.
+    unsafe fn generate_do_body_code(
+        &'a self,
+        targets: LoopTargets<'ctx>,
+        statements: Vec<ast::Statement>,
+    ) {
+        let continue_block = targets.continue_block;
+        self.function_properties.borrow_mut().enter_loop(targets);
+        for statement in statements {
+            statement.codegen(self);
+        }
+        self.function_properties.borrow_mut().exit_loop();
+        utils::branch_only_if_no_terminator(self, continue_block);
+    }
+
+    ///LEAVE and ITERATE jump to a block of the innermost DO group, or of the
+    ///one with the label.
+    unsafe fn generate_loop_exit_code(
+        &'a self,
+        statement_name: &str,
+        label: Option<&str>,
+        get_target: impl Fn(&LoopTargets<'ctx>) -> BasicBlock<'ctx>,
+    ) -> Box<dyn AnyValue<'ctx> + 'ctx> {
+        let targets = self.function_properties.borrow().get_loop_targets(label);
+        match (targets, label) {
+            (Some(targets), _) => {
+                utils::branch_only_if_no_terminator(self, get_target(&targets));
+                //the statements after it can't be reached, but still need a block
+                let unreachable_block = self
+                    .context
+                    .append_basic_block(get_current_function(self), "afterexit");
+                self.builder.position_at_end(unreachable_block);
+            }
+            (None, Some(label)) => {
+                self.error_module
+                    .store_msg_from_number(&["34", statement_name, label]);
+            }
+            (None, None) => {
+                self.error_module
+                    .store_msg_from_number(&["35", statement_name]);
+            }
+        }
+        self.ret_zero()
+    }
+}
@@ -1,9 +1,11 @@
 use std::borrow::BorrowMut;
 use std::error::Error;
 
-use crate::codegen::{named_value_store::NamedValueStore, utils::build_pow};
+use crate::codegen::runtime::character as character_runtime;
+use crate::codegen::{builtins, named_value_store::NamedValueStore, utils::build_pow};
+use crate::error::get_error;
 use crate::lexer::Token;
-use crate::types::float_decimal::PLIFloatDecimalValue;
+use crate::types::float_decimal::{generate_constant_float_decimal, PLIFloatDecimalValue};
 use crate::{
     ast,
     codegen::{
@@ -12,13 +14,16 @@ use crate::{
     },
     lexer,
     types::{
-        character, fixed_decimal::FixedValue, resolve_types, traits::get_mathable_type,
-        traits::MathableFactory, Type,
+        character,
+        fixed_decimal::{scaled, FixedValue},
+        get_binary_type, resolve_types,
+        traits::get_mathable_type,
+        Type,
     },
 };
 use inkwell::{
-    values::{AnyValue, ArrayValue, BasicMetadataValueEnum, FloatValue, StructValue},
-    FloatPredicate,
+    values::{AnyValue, ArrayValue, BasicMetadataValueEnum, FloatValue, IntValue, StructValue},
+    FloatPredicate, IntPredicate,
 };
 
 impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Expr {
@@ -29,8 +34,17 @@ impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Expr {
         match self {
             ast::Expr::Variable { name, _type } => {
                 let variable_result = compiler.generate_variable_code(&name);
-                if let Err(_msg) = variable_result {
-                    compiler.error_module.store_msg_from_number(&["12", &name]);
+                if let Err(msg) = variable_result {
+                    compiler.error_module.store_error_msg(&msg);
+                    compiler.ret_zero()
+                } else {
+                    variable_result.unwrap()
+                }
+            }
+            ast::Expr::Qualified { locator, name } => {
+                let variable_result = compiler.generate_qualified_variable_code(*locator, &name);
+                if let Err(msg) = variable_result {
+                    compiler.error_module.store_error_msg(&msg);
                     compiler.ret_zero()
                 } else {
                     variable_result.unwrap()
@@ -51,28 +65,51 @@ impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Expr {
             }
             ast::Expr::Assignment {
                 variable_name,
+                locator,
+                subscript,
                 value,
             } => {
-                let variable_type = compiler.named_values.try_get(&variable_name).unwrap()._type;
+                let variable = match (locator, subscript) {
+                    (Some(locator), _) => ast::Expr::Qualified {
+                        locator,
+                        name: variable_name,
+                    },
+                    (None, Some(subscript)) => ast::Expr::Call {
+                        fn_name: variable_name,
+                        args: vec![*subscript],
+                        _type: Type::TBD,
+                    },
+                    (None, None) => ast::Expr::Variable {
+                        _type: compiler.named_values.try_get(&variable_name).unwrap()._type,
+                        name: variable_name,
+                    },
+                };
                 let comparison_operation = ast::Expr::Binary {
                     operator: lexer::Token::EQ,
-                    left: Box::new(ast::Expr::Variable {
-                        _type: variable_type,
-                        name: variable_name,
-                    }),
+                    left: Box::new(variable),
                     right: value,
                 };
 
                 comparison_operation.codegen(compiler)
             }
-            ast::Expr::NumVal { value, _type } => match _type {
-                Type::FixedDecimal => Box::new(compiler.gen_const_fixed_decimal(value as f64)),
-                Type::Float => {
-                    let const_as_float = compiler.context.f64_type().const_float(value);
-                    let pli_floatvalue: Box<PLIFloatDecimalValue> =
-                        PLIFloatDecimalValue::create_mathable(&const_as_float, compiler);
-
-                    Box::new(pli_floatvalue.value)
+            ast::Expr::NumVal {
+                value,
+                _type,
+                digits,
+            } => match _type {
+                Type::FixedDecimal(precision, scale) => {
+                    let digits = digits.unwrap_or_else(|| value.to_string());
+                    match compiler.gen_const_fixed_decimal(&digits, precision, scale) {
+                        Some(constant) => Box::new(constant),
+                        //a constant with more digits than any FIXED DECIMAL
+                        None => {
+                            compiler.generate_raise_code(ast::Condition::FixedOverflow, None);
+                            Box::new(compiler.type_module.fixed_type.const_zero())
+                        }
+                    }
+                }
+                Type::Float(precision) => {
+                    Box::new(generate_constant_float_decimal(compiler, value, precision))
                 }
                 other => {
                     panic!("don't how how to generate for type {:#?}", other);
@@ -82,16 +119,57 @@ impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Expr {
                 let operand_type = operand.get_type(compiler);
                 let operand_as_codegen = operand.codegen(compiler);
 
+                if operand_type.is_complex() {
+                    let negation_result = compiler.generate_complex_infix_code(
+                        operator,
+                        operand_as_codegen,
+                        operand_type,
+                    );
+                    return match negation_result {
+                        Ok(negated) => negated,
+                        Err(msg) => {
+                            compiler.error_module.store_error_msg(&msg);
+                            compiler.ret_zero()
+                        }
+                    };
+                }
+
+                if let (Token::MINUS, Type::FixedDecimal(precision, scale)) =
+                    (&operator, operand_type)
+                {
+                    let operand_fixed = FixedValue::new(
+                        operand_as_codegen.as_any_value_enum().into_int_value(),
+                        precision,
+                        scale,
+                    );
+                    return compiler.generate_exact_fixed_decimal_negation(operand_fixed);
+                }
+
                 let operand_mathable = get_mathable_type(operand_as_codegen, operand_type).unwrap();
 
-                let operand_float = operand_mathable.convert_to_float(compiler);
+                let output_type = operand_type.get_computational_type();
+                let operand_float = operand_mathable.convert_to_float_of_type(
+                    compiler,
+                    compiler.get_working_float_type(output_type),
+                );
 
-                let mather = InfixMathCodeEmitter::new(operand_float, operator, operand_type);
+                let mather = InfixMathCodeEmitter::new(operand_float, operator, output_type);
                 let result = mather.gen_into_type(compiler);
                 let res: Box<dyn AnyValue<'ctx> + 'ctx> = result.unwrap();
 
                 res
             }
+            ast::Expr::Conversion { operand, to } => {
+                let from = operand.get_type(compiler);
+                let operand_value = operand.codegen(compiler);
+                match compiler.generate_conversion_code(operand_value, from, to) {
+                    Ok(converted) => converted,
+                    Err(msg) => {
+                        compiler.error_module.store_error_msg(&msg);
+                        compiler.ret_zero()
+                    }
+                }
+            }
             ast::Expr::Char { value } => {
                 let character_value = character::generate_character_code(compiler.context, &value);
                 let arr_value: ArrayValue = character_value.into();
@@ -102,6 +180,45 @@ impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Expr {
                 ref mut args,
                 _type,
             } => {
+                if builtins::is_builtin(fn_name) {
+                    return compiler
+                        .generate_builtin_call(fn_name, args.clone())
+                        .unwrap();
+                }
+                if let Some(c_entry) = compiler.get_c_entry(fn_name) {
+                    return match compiler.generate_c_call(fn_name, c_entry, args.clone()) {
+                        Ok(result) => result,
+                        Err(msg) => {
+                            compiler.error_module.store_error_msg(&msg);
+                            compiler.ret_zero()
+                        }
+                    };
+                }
+                //F(X) calls the procedure the ENTRY variable F holds
+                let is_entry_variable =
+                    compiler
+                        .named_values
+                        .try_get(fn_name)
+                        .is_some_and(|named_value| {
+                            named_value._type == Type::Entry && named_value.dimension.is_none()
+                        });
+                if compiler.named_values.try_get(fn_name).is_some() && !is_entry_variable {
+                    if args.len() != 1 {
+                        compiler
+                            .error_module
+                            .store_msg_from_number(&["22", fn_name]);
+                        return compiler.ret_zero();
+                    }
+                    let element_result =
+                        compiler.generate_subscripted_variable_code(fn_name, args[0].clone());
+                    return match element_result {
+                        Ok(element) => element,
+                        Err(msg) => {
+                            compiler.error_module.store_error_msg(&msg);
+                            compiler.ret_zero()
+                        }
+                    };
+                }
                 let function_call_result = compiler.generate_function_call_code(fn_name, args);
                 function_call_result.unwrap()
             }
@@ -114,7 +231,7 @@ impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Expr {
 
 impl<'a, 'ctx> Compiler<'a, 'ctx> {
     pub unsafe fn generate_binary_expression_code(
-        &self,
+        &'a self,
         binary_expr: ast::Expr,
     ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, String> {
         if let ast::Expr::Binary {
@@ -131,6 +248,65 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
             let lhs_codegen = left.codegen(self);
             let rhs_codegen = right.codegen(self);
 
+            if operator == Token::CONCATENATE || lhstype.is_character() || rhstype.is_character() {
+                return self.generate_string_expression_code(
+                    operator,
+                    (lhs_codegen, lhstype),
+                    (rhs_codegen, rhstype),
+                );
+            }
+
+            if lhstype == Type::Pointer || rhstype == Type::Pointer {
+                return self.generate_pointer_comparison_code(
+                    operator,
+                    (lhs_codegen, lhstype),
+                    (rhs_codegen, rhstype),
+                );
+            }
+
+            if lhstype.is_complex() || rhstype.is_complex() {
+                return self.generate_complex_expression_code(
+                    operator,
+                    (lhs_codegen, lhstype),
+                    (rhs_codegen, rhstype),
+                );
+            }
+
+            //a pictured number is worked on as the FIXED DECIMAL it edits
+            if let (
+                Type::FixedDecimal(lhs_precision, lhs_scale),
+                Type::FixedDecimal(rhs_precision, rhs_scale),
+            ) = (
+                lhstype.get_computational_type(),
+                rhstype.get_computational_type(),
+            ) {
+                if scaled::is_exact_operator(&operator) {
+                    let lhs_codegen = self.convert_arithmetic_value(
+                        lhs_codegen,
+                        lhstype,
+                        lhstype.get_computational_type(),
+                    )?;
+                    let rhs_codegen = self.convert_arithmetic_value(
+                        rhs_codegen,
+                        rhstype,
+                        rhstype.get_computational_type(),
+                    )?;
+                    return self.generate_exact_fixed_decimal_code(
+                        operator,
+                        FixedValue::new(
+                            lhs_codegen.as_any_value_enum().into_int_value(),
+                            lhs_precision,
+                            lhs_scale,
+                        ),
+                        FixedValue::new(
+                            rhs_codegen.as_any_value_enum().into_int_value(),
+                            rhs_precision,
+                            rhs_scale,
+                        ),
+                    );
+                }
+            }
+
             log::trace!(
                 "Binary expression codegens: {:?}, {:?}",
                 &lhs_codegen,
@@ -141,13 +317,15 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
             let rhs_float: FloatValue<'ctx>;
             //new mathable code
 
+            let output_type = get_binary_type(&operator, &lhstype, &rhstype)?;
+            //both operands are worked on in a format holding the result's digits
+            let working_type = self.get_working_float_type(output_type);
+
             let lhs_mathable = get_mathable_type(lhs_codegen, lhstype)?;
-            lhs_float = lhs_mathable.convert_to_float(self);
+            lhs_float = lhs_mathable.convert_to_float_of_type(self, working_type);
 
             let rhs_mathable = get_mathable_type(rhs_codegen, rhstype)?;
-            rhs_float = rhs_mathable.convert_to_float(self);
-
-            let output_type = resolve_types(&lhstype, &rhstype).unwrap();
+            rhs_float = rhs_mathable.convert_to_float_of_type(self, working_type);
 
             log::debug!(
                 "Return type for {:#?}, {:#?}? {:#?}",
@@ -174,6 +352,257 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
         }
     }
 
+    ///Concatenates or compares two strings. Comparisons give 1 or 0, like numeric ones do.
+    unsafe fn generate_string_expression_code(
+        &self,
+        operator: lexer::Token,
+        lhs: (Box<dyn AnyValue<'ctx> + 'ctx>, Type),
+        rhs: (Box<dyn AnyValue<'ctx> + 'ctx>, Type),
+    ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, String> {
+        let (lhs_codegen, lhstype) = lhs;
+        let (rhs_codegen, rhstype) = rhs;
+        if !lhstype.is_character() || !rhstype.is_character() {
+            return Err(get_error(&[
+                "5",
+                &lhstype.to_string(),
+                &rhstype.to_string(),
+            ]));
+        }
+
+        let lhs_view = self.generate_string_view(lhs_codegen, lhstype)?;
+        let rhs_view = self.generate_string_view(rhs_codegen, rhstype)?;
+
+        let predicate = match operator {
+            Token::CONCATENATE => {
+                let max_length = lhstype.get_character_length().unwrap()
+                    + rhstype.get_character_length().unwrap();
+                let concatenated = self.generate_concatenation(lhs_view, rhs_view, max_length);
+                return Ok(Box::new(concatenated));
+            }
+            Token::EQ => IntPredicate::EQ,
+            Token::LESS_THAN => IntPredicate::SLT,
+            Token::GREATER_THAN => IntPredicate::SGT,
+            other => {
+                return Err(format!("Can't use operator {:?} on strings", other));
+            }
+        };
+
+        let comparison = self
+            .builder
+            .build_call(
+                self.get_function(character_runtime::COMPARE_STRINGS_FUNCTION_NAME)
+                    .unwrap(),
+                &[
+                    lhs_view.characters.into(),
+                    lhs_view.length.into(),
+                    rhs_view.characters.into(),
+                    rhs_view.length.into(),
+                ],
+                "compare_strings",
+            )
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_int_value();
+        let is_true = self
+            .builder
+            .build_int_compare(
+                predicate,
+                comparison,
+                self.context.i32_type().const_zero(),
+                "string_comparison",
+            )
+            .unwrap();
+        Ok(Box::new(self.generate_fixed_truth_value(is_true)))
+    }
+
+    ///Pointers can only be compared for equality, which gives 1 or 0.
+    unsafe fn generate_pointer_comparison_code(
+        &self,
+        operator: lexer::Token,
+        lhs: (Box<dyn AnyValue<'ctx> + 'ctx>, Type),
+        rhs: (Box<dyn AnyValue<'ctx> + 'ctx>, Type),
+    ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, String> {
+        let (lhs_codegen, lhstype) = lhs;
+        let (rhs_codegen, rhstype) = rhs;
+        if lhstype != Type::Pointer || rhstype != Type::Pointer {
+            return Err(get_error(&[
+                "5",
+                &lhstype.to_string(),
+                &rhstype.to_string(),
+            ]));
+        }
+        if operator != Token::EQ {
+            return Err(format!("Can't use operator {:?} on pointers", operator));
+        }
+
+        let i64_type = self.context.i64_type();
+        let lhs_address = self
+            .builder
+            .build_ptr_to_int(
+                lhs_codegen.as_any_value_enum().into_pointer_value(),
+                i64_type,
+                "lhs_address",
+            )
+            .unwrap();
+        let rhs_address = self
+            .builder
+            .build_ptr_to_int(
+                rhs_codegen.as_any_value_enum().into_pointer_value(),
+                i64_type,
+                "rhs_address",
+            )
+            .unwrap();
+        let is_true = self
+            .builder
+            .build_int_compare(
+                IntPredicate::EQ,
+                lhs_address,
+                rhs_address,
+                "pointer_comparison",
+            )
+            .unwrap();
+        Ok(Box::new(self.generate_fixed_truth_value(is_true)))
+    }
+
+    ///Adds, subtracts, multiplies or divides two numbers, at least one of
+    ///them complex, part by part. Complex numbers can only be compared for
+    ///equality, which gives 1 or 0.
+    unsafe fn generate_complex_expression_code(
+        &self,
+        operator: lexer::Token,
+        lhs: (Box<dyn AnyValue<'ctx> + 'ctx>, Type),
+        rhs: (Box<dyn AnyValue<'ctx> + 'ctx>, Type),
+    ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, String> {
+        let (lhs_codegen, lhstype) = lhs;
+        let (rhs_codegen, rhstype) = rhs;
+        let output_type = resolve_types(&lhstype, &rhstype)?;
+
+        //(a+bI) and (c+dI)
+        let (a, b) = self.get_complex_parts(lhs_codegen, lhstype)?;
+        let (c, d) = self.get_complex_parts(rhs_codegen, rhstype)?;
+        let builder = self.builder;
+
+        let (real, imaginary) = match operator {
+            Token::PLUS => (
+                builder.build_float_add(a, c, "complex_add_real").unwrap(),
+                builder.build_float_add(b, d, "complex_add_imag").unwrap(),
+            ),
+            Token::MINUS => (
+                builder.build_float_sub(a, c, "complex_sub_real").unwrap(),
+                builder.build_float_sub(b, d, "complex_sub_imag").unwrap(),
+            ),
+            //(ac-bd) + (ad+bc)I
+            Token::MULTIPLY => {
+                let ac = builder.build_float_mul(a, c, "ac").unwrap();
+                let bd = builder.build_float_mul(b, d, "bd").unwrap();
+                let ad = builder.build_float_mul(a, d, "ad").unwrap();
+                let bc = builder.build_float_mul(b, c, "bc").unwrap();
+                (
+                    builder.build_float_sub(ac, bd, "complex_mul_real").unwrap(),
+                    builder.build_float_add(ad, bc, "complex_mul_imag").unwrap(),
+                )
+            }
+            //((ac+bd) + (bc-ad)I) / (cc+dd)
+            Token::DIVIDE => {
+                let ac = builder.build_float_mul(a, c, "ac").unwrap();
+                let bd = builder.build_float_mul(b, d, "bd").unwrap();
+                let ad = builder.build_float_mul(a, d, "ad").unwrap();
+                let bc = builder.build_float_mul(b, c, "bc").unwrap();
+                let cc = builder.build_float_mul(c, c, "cc").unwrap();
+                let dd = builder.build_float_mul(d, d, "dd").unwrap();
+                let denominator = builder.build_float_add(cc, dd, "denominator").unwrap();
+                let real_numerator = builder.build_float_add(ac, bd, "real_numerator").unwrap();
+                let imaginary_numerator =
+                    builder.build_float_sub(bc, ad, "imag_numerator").unwrap();
+                (
+                    builder
+                        .build_float_div(real_numerator, denominator, "complex_div_real")
+                        .unwrap(),
+                    builder
+                        .build_float_div(imaginary_numerator, denominator, "complex_div_imag")
+                        .unwrap(),
+                )
+            }
+            Token::EQ => {
+                let is_real_equal = builder
+                    .build_float_compare(FloatPredicate::OEQ, a, c, "real_equal")
+                    .unwrap();
+                let is_imaginary_equal = builder
+                    .build_float_compare(FloatPredicate::OEQ, b, d, "imag_equal")
+                    .unwrap();
+                let is_true = builder
+                    .build_and(is_real_equal, is_imaginary_equal, "complex_equal")
+                    .unwrap();
+                return Ok(Box::new(self.generate_fixed_truth_value(is_true)));
+            }
+            other => {
+                return Err(format!("Can't use operator {:?} on complex numbers", other));
+            }
+        };
+
+        Ok(Box::new(self.create_complex_value(
+            real,
+            imaginary,
+            output_type,
+        )?))
+    }
+
+    ///Negates a complex number. NOT can't be used on one.
+    unsafe fn generate_complex_infix_code(
+        &self,
+        operator: lexer::Token,
+        operand: Box<dyn AnyValue<'ctx> + 'ctx>,
+        operand_type: Type,
+    ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, String> {
+        if operator != Token::MINUS {
+            return Err(format!(
+                "Can't use operator {:?} on complex numbers",
+                operator
+            ));
+        }
+        let (real, imaginary) = self.get_complex_parts(operand, operand_type)?;
+        //subtracting from zero keeps a negated zero from printing as -0
+        let zero_value = self.context.f64_type().const_zero();
+        let negated_real = self
+            .builder
+            .build_float_sub(zero_value, real, "negate_real")
+            .unwrap();
+        let negated_imaginary = self
+            .builder
+            .build_float_sub(zero_value, imaginary, "negate_imag")
+            .unwrap();
+
+        Ok(Box::new(self.create_complex_value(
+            negated_real,
+            negated_imaginary,
+            operand_type,
+        )?))
+    }
+
+    ///Evaluates an arithmetic expression and truncates the result into an i32,
+    ///for the places where an integer is expected, like SKIP(n).
+    pub unsafe fn generate_expression_as_i32(&self, expr: ast::Expr) -> IntValue<'ctx> {
+        let expr_float = self.generate_expression_as_float(expr).unwrap();
+
+        self.builder
+            .build_float_to_signed_int(expr_float, self.context.i32_type(), "expr_as_i32")
+            .unwrap()
+    }
+
+    ///Evaluates an arithmetic expression into an f64.
+    pub unsafe fn generate_expression_as_float(
+        &self,
+        expr: ast::Expr,
+    ) -> Result<FloatValue<'ctx>, String> {
+        let expr_type = expr.get_type(self);
+        let expr_codegen = expr.codegen(self);
+
+        let expr_mathable = get_mathable_type(expr_codegen, expr_type)?;
+        Ok(expr_mathable.convert_to_float(self))
+    }
+
     unsafe fn generate_binary_math_code(
         &self,
         lhs_float: FloatValue<'ctx>,
@@ -209,15 +638,15 @@ trait MathCodeEmitter<'ctx> {
         let x = math_float_result.unwrap();
 
         let result = match self.get_type() {
-            Type::FixedDecimal => {
-                let fixed_value = FixedValue::create_mathable(&x, compiler);
-                let fd_as_struct: StructValue<'ctx> = fixed_value.value;
-                return Ok(Box::new(fd_as_struct));
+            Type::FixedDecimal(precision, scale) => {
+                let fixed_value = compiler.float_value_to_fixed_decimal(x, precision, scale);
+                let fd_as_scaled: IntValue<'ctx> = fixed_value.value;
+                return Ok(Box::new(fd_as_scaled));
             }
-            Type::Float => {
-                let float_value = PLIFloatDecimalValue::create_mathable(&x, compiler);
-                let fd_as_struct: StructValue<'ctx> = float_value.value;
-                return Ok(Box::new(fd_as_struct));
+            Type::Float(precision) => {
+                let float_value = PLIFloatDecimalValue::from_float(x, precision, compiler);
+                let float_decimal: FloatValue<'ctx> = float_value.value;
+                return Ok(Box::new(float_decimal));
             }
             other => {
                 panic!("Can't convert math output into type {}", other);
@@ -265,15 +694,15 @@ impl<'ctx> BinaryMathCodeEmitter<'ctx> {
         let x = math_float_result.unwrap();
 
         let result = match self.output_type {
-            Type::FixedDecimal => {
-                let fixed_value = FixedValue::create_mathable(&x, compiler);
-                let fd_as_struct: StructValue<'ctx> = fixed_value.value;
-                return Ok(Box::new(fd_as_struct));
+            Type::FixedDecimal(precision, scale) => {
+                let fixed_value = compiler.float_value_to_fixed_decimal(x, precision, scale);
+                let fd_as_scaled: IntValue<'ctx> = fixed_value.value;
+                return Ok(Box::new(fd_as_scaled));
             }
-            Type::Float => {
-                let pli_float_value = PLIFloatDecimalValue::create_mathable(&x, compiler);
-                let pli_float_as_struct: StructValue<'ctx> = pli_float_value.value;
-                return Ok(Box::new(pli_float_as_struct));
+            Type::Float(precision) => {
+                let pli_float_value = PLIFloatDecimalValue::from_float(x, precision, compiler);
+                let pli_float: FloatValue<'ctx> = pli_float_value.value;
+                return Ok(Box::new(pli_float));
             }
             other => {
                 panic!("Can't convert math output into type {}", other);
@@ -358,7 +787,7 @@ impl<'ctx> BinaryMathCodeEmitter<'ctx> {
         &self,
         compiler: &'a Compiler<'a, 'ctx>,
     ) -> Result<FloatValue<'ctx>, Box<dyn Error>> {
-        let zero_intval = compiler.context.f64_type().const_zero();
+        let zero_intval = self.lhs_float.get_type().const_zero();
         let left_true_or_false = compiler
             .builder
             .build_float_compare(FloatPredicate::ONE, self.lhs_float, zero_intval, "left_and")
@@ -385,7 +814,7 @@ impl<'ctx> BinaryMathCodeEmitter<'ctx> {
 
         let cmp_as_float = compiler
             .builder
-            .build_unsigned_int_to_float(cmp_for_and, compiler.context.f64_type(), "tmpbool")
+            .build_unsigned_int_to_float(cmp_for_and, self.lhs_float.get_type(), "tmpbool")
             .map_err(|e| format!("Unable to convert unsigned int to float: {}", e))?;
         Ok(cmp_as_float)
     }
@@ -405,7 +834,7 @@ impl<'ctx> BinaryMathCodeEmitter<'ctx> {
 
         let cmp_as_float = compiler
             .builder
-            .build_unsigned_int_to_float(val, compiler.context.f64_type(), "tmpbool")
+            .build_unsigned_int_to_float(val, self.lhs_float.get_type(), "tmpbool")
             .map_err(|e| format!("Unable to convert unsigned int to float: {}", e))?;
         Ok(cmp_as_float)
     }
@@ -430,7 +859,7 @@ impl<'ctx> InfixMathCodeEmitter<'ctx> {
         &self,
         compiler: &'a Compiler<'a, 'ctx>,
     ) -> Result<FloatValue<'ctx>, Box<dyn Error>> {
-        let zero_intval = compiler.context.f64_type().const_zero();
+        let zero_intval = self.operand.get_type().const_zero();
         let current_value_as_boolean = compiler
             .builder
             .build_float_compare(FloatPredicate::ONE, self.operand, zero_intval, "left_and")
@@ -452,14 +881,15 @@ impl<'ctx> InfixMathCodeEmitter<'ctx> {
         &self,
         compiler: &'a Compiler<'a, 'ctx>,
     ) -> Result<FloatValue<'ctx>, Box<dyn Error>> {
-        let negative_one_value = compiler.context.f64_type().const_float(-1.0);
+        //subtracting from zero keeps a negated zero from printing as -0
+        let zero_value = self.operand.get_type().const_zero();
 
-        let multied_value = compiler
+        let negated_value = compiler
             .builder
-            .build_float_mul(self.operand, negative_one_value, "negate_float")
+            .build_float_sub(zero_value, self.operand, "negate_float")
             .map_err(|builder_error| format!("Unable to negate: {}", builder_error))?;
 
-        Ok(multied_value)
+        Ok(negated_value)
     }
 }
 
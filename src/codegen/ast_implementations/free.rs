@@ -0,0 +1,68 @@
+use inkwell::values::AnyValue;
+
+use crate::ast;
+use crate::codegen::codegen::{CodeGenable, Compiler};
+use crate::codegen::named_value_store::NamedValueStore;
+use crate::codegen::runtime::controlled;
+use crate::error::get_error;
+
+impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Free {
+    unsafe fn codegen(self, compiler: &'a Compiler<'a, 'ctx>) -> Box<dyn AnyValue<'ctx> + 'ctx> {
+        if let Err(message) = compiler.generate_free_code(self) {
+            compiler.error_module.store_error_msg(&message);
+        }
+        Box::new(compiler.context.i8_type().const_zero())
+    }
+}
+
+impl<'a, 'ctx> Compiler<'a, 'ctx> {
+    ///Gives the storage of a BASED variable back to the heap. The pointers
+    ///to it are left as they are. A CONTROLLED variable loses its newest
+    ///generation.
+    unsafe fn generate_free_code(&'a self, free: ast::Free) -> Result<(), String> {
+        let storage = match free.locator {
+            Some(locator) => self.get_qualified_storage(locator, &free.var_name)?.0,
+            None => {
+                let named_value = self
+                    .named_values
+                    .try_get(&free.var_name)
+                    .ok_or(get_error(&["12", &free.var_name]))?;
+                match named_value.storage_class {
+                    ast::StorageClass::Automatic
+                    | ast::StorageClass::Static
+                    | ast::StorageClass::External => {
+                        return Err(get_error(&["18", &free.var_name]));
+                    }
+                    ast::StorageClass::Controlled => {
+                        self.generate_controlled_free_code(&free.var_name);
+                        return Ok(());
+                    }
+                    ast::StorageClass::Based { .. } => self.get_variable_storage(&named_value)?,
+                }
+            }
+        };
+
+        self.builder
+            .build_free(storage)
+            .map_err(|err| err.to_string())?;
+        Ok(())
+    }
+
+    ///Pops the newest generation of a CONTROLLED variable.
+    unsafe fn generate_controlled_free_code(&'a self, variable_name: &str) {
+        let anchor = controlled::get_or_add_anchor(self, variable_name);
+        let name = self
+            .builder
+            .build_global_string_ptr(variable_name, "controlled_name")
+            .unwrap()
+            .as_pointer_value();
+        self.builder
+            .build_call(
+                self.get_function(controlled::CONTROLLED_POP_FUNCTION_NAME)
+                    .unwrap(),
+                &[anchor.into(), name.into()],
+                "pop_generation",
+            )
+            .unwrap();
+    }
+}
@@ -7,12 +7,14 @@ use crate::{
         named_value::NamedValue,
         utils::get_current_function,
     },
+    debugger::ProcedureScope,
     error::errors::CodegenError,
 };
 
 use inkwell::{
     basic_block::BasicBlock,
     debug_info::{AsDIScope, DISubprogram},
+    module::Linkage,
     values::{BasicValue, FunctionValue, PointerValue},
 };
 
@@ -34,6 +36,7 @@ impl<'a, 'ctx> ast::Function {
         compiler: &'a crate::codegen::codegen::Compiler<'a, 'ctx>,
     ) -> Result<Box<dyn inkwell::values::AnyValue<'ctx> + 'ctx>, Box<dyn Error>> {
         let current_function = compiler.builder.get_insert_block().unwrap();
+        let current_location = compiler.builder.get_current_debug_location();
 
         let old_function_info = compiler.function_properties.borrow().clone();
 
@@ -51,6 +54,11 @@ impl<'a, 'ctx> ast::Function {
             generated_code_result.map_err(|message| CodegenError { message })?;
 
         compiler.builder.position_at_end(current_function);
+        //the statements after it are back in the scope of the containing block
+        match current_location {
+            Some(location) => compiler.builder.set_current_debug_location(location),
+            None => compiler.builder.unset_current_debug_location(),
+        }
         Ok(Box::new(llvm_created_function))
     }
 }
@@ -58,19 +66,24 @@ impl<'a, 'ctx> ast::Function {
 impl<'a, 'ctx> Compiler<'a, 'ctx> {
     pub unsafe fn generate_function_code(
         &self,
-        function_ast: ast::Function,
+        mut function_ast: ast::Function,
     ) -> Result<FunctionValue<'ctx>, String> {
         //1. see if the function has already been defined
         self.handle_if_function_has_already_been_defined(&function_ast)?;
+        //the result has the default type of the name, in the block containing it
+        function_ast.return_type = self.get_default_type(&function_ast.prototype.fn_name);
         //2. clear the named values, which stores all the recognized identifiers
         self.named_values.clear();
+        self.function_properties.borrow_mut().enter_procedure();
 
         let func_name = function_ast.prototype.fn_name.clone();
-        let proto_args = function_ast.prototype.args.clone();
         let current_subprogram = self.try_attach_debug_info(&function_ast);
 
+        //the DEFAULT rules of the body give the parameters their types too
+        self.apply_default_statements(&function_ast.body_statements);
+
         //3. get a list of the arguments with their names and types
-        let args: Vec<ast::PrototypeArgument> = self.get_function_argument_array(proto_args);
+        let args: Vec<ast::PrototypeArgument> = self.get_function_argument_array(&function_ast);
 
         let llvm_function = self.generate_function_prototype_code(
             func_name.clone(),
@@ -78,6 +91,11 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
             function_ast.return_type,
         );
 
+        llvm_function.set_linkage(match function_ast.prototype.linkage {
+            ast::ProcedureLinkage::External => Linkage::External,
+            ast::ProcedureLinkage::Internal | ast::ProcedureLinkage::C => Linkage::Internal,
+        });
+
         self.check_if_function_body_is_empty();
 
         //create a new scope block for the function
@@ -88,6 +106,7 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
 
         self.fill_named_values_array(&llvm_function, &function_ast, &args)?;
 
+        self.declare_label_constants(&function_ast.body_statements);
         self.generate_body_statements_in_function(&function_ast);
 
         self.remove_debug_lexical_block_if_debug(current_subprogram, &llvm_function);
@@ -97,8 +116,18 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
         if let None = self.builder.get_insert_block().unwrap().get_terminator() {
             self.build_return_value(&function_ast)?;
         }
+        self.finish_label_dispatch();
         self.verify_function(llvm_function, &function_ast)?;
 
+        if function_ast.prototype.linkage != ast::ProcedureLinkage::Internal {
+            self.generate_c_export(
+                llvm_function,
+                &function_ast.prototype.fn_name,
+                &args,
+                function_ast.return_type,
+            )?;
+        }
+
         Ok(llvm_function)
     }
 
@@ -106,21 +135,30 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
         &self,
         func: &ast::Function,
     ) -> Result<(), String> {
-        if let Some(_) = self.module.get_function(&func.prototype.fn_name) {
-            return Err(format!(
-                "function named {} already exists!",
-                func.prototype.fn_name
-            ));
+        if let Some(existing_function) = self.module.get_function(&func.prototype.fn_name) {
+            //an ENTRY declaration of it is fine, as long as the parameters agree
+            let is_entry_declaration = existing_function.count_basic_blocks() == 0
+                && existing_function.count_params() as usize == func.prototype.args.len();
+            if !is_entry_declaration {
+                return Err(format!(
+                    "function named {} already exists!",
+                    func.prototype.fn_name
+                ));
+            }
         }
         Ok(())
     }
-    fn get_function_argument_array(&self, proto_args: Vec<String>) -> Vec<ast::PrototypeArgument> {
-        proto_args
-            .clone()
-            .into_iter()
+    ///Parameters have the type they are declared with in the body, or else
+    ///the default type of their name. The ones declared with ENTRY are
+    ///passed procedures.
+    fn get_function_argument_array(&self, func: &ast::Function) -> Vec<ast::PrototypeArgument> {
+        func.prototype
+            .args
+            .iter()
             .map(|name| ast::PrototypeArgument {
-                name,
-                _type: Type::FixedDecimal,
+                name: name.clone(),
+                _type: get_declared_parameter_type(func, name)
+                    .unwrap_or_else(|| self.get_default_type(name)),
             })
             .collect()
     }
@@ -146,11 +184,9 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
                 })?;
 
             let name = func.prototype.args[i].clone();
-            self.named_values.insert(NamedValue {
-                name,
-                _type: Type::FixedDecimal,
-                pointer: alloca,
-            });
+            let entry = get_entry_parameter(func, &name);
+            self.named_values
+                .insert(NamedValue::new(name, args[i]._type, alloca).with_entry(entry));
         }
         Ok(())
     }
@@ -188,25 +224,61 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
 
         let return_expr = func.return_value.clone().unwrap();
         dbg!(&return_expr);
-        let function_return_value = return_expr.codegen(self);
+        let return_expr_type = return_expr.get_type(self);
+        let mut function_return_value = return_expr.codegen(self);
         dbg!(&function_return_value);
+        //a number is returned as the type of the procedure
+        if function_return_type.is_arithmetic() && return_expr_type.is_arithmetic() {
+            function_return_value = self.convert_arithmetic_value(
+                function_return_value,
+                return_expr_type,
+                function_return_type,
+            )?;
+        }
         let return_value_as_enum = function_return_value.as_any_value_enum();
 
         match function_return_type {
-            Type::FixedDecimal => {
+            Type::FixedDecimal(..) => {
+                let scaled_value = return_value_as_enum.into_int_value();
+                self.builder
+                    .build_return(Some(&scaled_value as &dyn BasicValue))
+                    .map_err(|err| err.to_string())?;
+            }
+            Type::Float(_) => {
+                let float_value = return_value_as_enum.into_float_value();
+                self.builder
+                    .build_return(Some(&float_value as &dyn BasicValue))
+                    .map_err(|err| err.to_string())?;
+            }
+            Type::FixedComplex | Type::FloatComplex => {
                 let struct_value = return_value_as_enum.into_struct_value();
                 self.builder
                     .build_return(Some(&struct_value as &dyn BasicValue))
                     .map_err(|err| err.to_string())?;
             }
-            Type::Char(size) => {
+            Type::Char(_) | Type::Picture(_) => {
                 let struct_value = return_value_as_enum.into_array_value();
                 self.builder
                     .build_return(Some(&struct_value as &dyn BasicValue))
                     .map_err(|err| err.to_string())?;
             }
-            Type::Float => {
-                todo!("Implement functions that return Float!");
+            Type::VaryingChar(_max_length) => {
+                let struct_value = return_value_as_enum.into_struct_value();
+                self.builder
+                    .build_return(Some(&struct_value as &dyn BasicValue))
+                    .map_err(|err| err.to_string())?;
+            }
+            Type::Pointer | Type::Entry => {
+                let pointer_value = return_value_as_enum.into_pointer_value();
+                self.builder
+                    .build_return(Some(&pointer_value as &dyn BasicValue))
+                    .map_err(|err| err.to_string())?;
+            }
+            Type::Label => {
+                return Err(format!(
+                    "Procedure {} can't return a LABEL",
+                    func.prototype.fn_name
+                ));
             }
             Type::TBD => {
                 todo!("Implement functions that return TBD!");
@@ -246,7 +318,12 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
             let flags = 0;
             let is_optimized = dbg.optimized;
 
-            let scope = dbg.builder.create_file(&dbg.filename, &dbg.directory);
+            //a procedure from an included file is described as being there
+            let filename = match func.prototype.source_loc.file_name {
+                Some(ref file_name) => file_name.as_str(),
+                None => dbg.filename.as_str(),
+            };
+            let scope = dbg.builder.create_file(filename, &dbg.directory);
 
             //TODO: Fill out parameter and return stuff here.
             let ditype = dbg.builder.create_subroutine_type(scope, None, &[], 0);
@@ -265,9 +342,10 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
                 is_optimized,
             );
 
-            dbg.lexical_blocks
-                .borrow_mut()
-                .push(myfunc.as_debug_info_scope());
+            dbg.lexical_blocks.borrow_mut().push(ProcedureScope::new(
+                myfunc.as_debug_info_scope(),
+                func.prototype.source_loc.file_name.clone(),
+            ));
 
             let current_loc = dbg.builder.create_debug_location(
                 self.context,
@@ -288,3 +366,26 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
         None
     }
 }
+
+///The ENTRY declaration of the parameter `name` in the body of the procedure.
+fn get_entry_parameter(func: &ast::Function, name: &str) -> Option<ast::Entry> {
+    func.body_statements
+        .iter()
+        .find_map(|statement| match statement.command {
+            ast::Command::Declare(ref declare) if declare.var_name == name => declare.entry.clone(),
+            _ => None,
+        })
+}
+
+///The type the parameter `name` is declared with in the body of the procedure.
+fn get_declared_parameter_type(func: &ast::Function, name: &str) -> Option<Type> {
+    func.body_statements
+        .iter()
+        .find_map(|statement| match statement.command {
+            ast::Command::Declare(ref declare) if declare.var_name == name => match declare.entry {
+                Some(_) => Some(Type::Entry),
+                None => declare.attribute,
+            },
+            _ => None,
+        })
+}
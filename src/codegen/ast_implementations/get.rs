@@ -2,14 +2,16 @@ use std::error::Error;
 
 use inkwell::types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum};
 use inkwell::values::{BasicMetadataValueEnum, IntValue};
-use inkwell::AddressSpace;
+use inkwell::{AddressSpace, IntPredicate};
 
 use crate::ast::{self, Expr};
 use crate::codegen::codegen::{CodeGenable, Compiler};
 use crate::codegen::named_value_store::NamedValueStore;
+use crate::codegen::runtime::stream;
 use crate::codegen::utils::print_float_value;
-use crate::types::fixed_decimal::FixedValue;
-use crate::types::traits::MathableFactory;
+use crate::codegen::utils::{branch_only_if_no_terminator, get_current_function};
+use crate::types::character::StringView;
+use crate::types::float_decimal::PLIFloatDecimalValue;
 use crate::types::Type;
 
 impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Get {
@@ -17,15 +19,36 @@ impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Get {
         self,
         compiler: &'a crate::codegen::codegen::Compiler<'a, 'ctx>,
     ) -> Box<dyn inkwell::values::AnyValue<'ctx> + 'ctx> {
-        let _res = compiler.generate_get_code(self.list_to_get).unwrap();
+        let file_name = self.file.unwrap_or(stream::DEFAULT_INPUT_FILE.to_string());
+        let _res = compiler
+            .generate_get_code(&file_name, self.list_to_get)
+            .unwrap();
         Box::new(compiler.generate_float_code(-999.0))
     }
 }
 
 impl<'a, 'ctx> Compiler<'a, 'ctx> {
-    pub unsafe fn generate_get_code(&self, list: ast::IOList) -> Result<(), Box<dyn Error>> {
+    ///Reads each item of the list from the file. If the end of the file is reached,
+    ///ENDFILE is raised and the rest of the list is left alone.
+    pub unsafe fn generate_get_code(
+        &'a self,
+        file_name: &str,
+        list: ast::IOList,
+    ) -> Result<(), Box<dyn Error>> {
         log::trace!("Calling generate get code!");
 
+        let file = match self.get_declared_stream_file(file_name) {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+        self.build_open_call(file, None, stream::INPUT_FLAG);
+
+        let current_function = get_current_function(self);
+        let endfile_block = self.context.append_basic_block(current_function, "endfile");
+        let after_get_block = self
+            .context
+            .append_basic_block(current_function, "after_get");
+
         let mut result: IntValue<'ctx>;
         for i in list.items.iter() {
             log::debug!("{:#?}", i);
@@ -42,7 +65,7 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
                     .named_values
                     .try_get(name)
                     .map(|value| value._type)
-                    .unwrap_or(_type.clone());
+                    .unwrap_or_else(|| self.get_default_type(name));
 
                 log::trace!("getting variable {} of type {}", name, real_type);
 
@@ -52,7 +75,7 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
                     .build_global_string_ptr(format_string, "format_string")?
                     .as_pointer_value();
 
-                let scanf_func = self.get_function("scanf")?;
+                let read_item_func = self.get_function(stream::READ_ITEM_FUNCTION_NAME)?;
 
                 let final_variable_ptr = self.create_or_load_variable(name, &real_type);
 
@@ -62,28 +85,88 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
                     .builder
                     .build_malloc(type_of_tmp_scan_var, "what")
                     .unwrap();
+                let tmp_scan_tr_as_bytes = self.builder.build_bitcast(
+                    tmp_scan_tr,
+                    self.context.i8_type().ptr_type(AddressSpace::default()),
+                    "scan_target",
+                )?;
 
                 let mut args: Vec<BasicMetadataValueEnum> = vec![];
+                args.push(file.into());
                 args.push(format_string_ptr.into());
-                args.push(tmp_scan_tr.into());
+                args.push(tmp_scan_tr_as_bytes.into());
+
+                let scanf_return_value =
+                    self.builder
+                        .build_call(read_item_func, &args[..], "read_item")?;
+
+                result = scanf_return_value
+                    .try_as_basic_value()
+                    .left()
+                    .unwrap()
+                    .into_int_value();
 
-                let scanf_return_value = self.builder.build_call(scanf_func, &args[..], "scanf")?;
+                let end_of_file = self
+                    .context
+                    .i32_type()
+                    .const_int(stream::END_OF_FILE as u64, true);
+                let is_end_of_file = self.builder.build_int_compare(
+                    IntPredicate::EQ,
+                    result,
+                    end_of_file,
+                    "is_end_of_file",
+                )?;
+                let item_read_block = self
+                    .context
+                    .append_basic_block(current_function, "item_read");
+                self.builder.build_conditional_branch(
+                    is_end_of_file,
+                    endfile_block,
+                    item_read_block,
+                )?;
+                self.builder.position_at_end(item_read_block);
 
                 //now load variable_ptr with tmp_scan_tr
 
                 match &real_type {
-                    Type::FixedDecimal => {
+                    Type::FixedDecimal(precision, scale) => {
+                        let scanned_float_value = self
+                            .builder
+                            .build_load(tmp_scan_tr, "load scanned")
+                            .unwrap()
+                            .into_float_value();
+                        let x = self.float_value_to_fixed_decimal(
+                            scanned_float_value,
+                            *precision,
+                            *scale,
+                        );
+                        self.builder
+                            .build_store(final_variable_ptr, x.value)
+                            .unwrap();
+                    }
+                    Type::Float(precision) => {
                         let scanned_float_value = self
                             .builder
                             .build_load(tmp_scan_tr, "load scanned")
                             .unwrap()
                             .into_float_value();
-                        let x: Box<FixedValue<'ctx>> =
-                            FixedValue::create_mathable(&scanned_float_value, self);
+                        let x =
+                            PLIFloatDecimalValue::from_float(scanned_float_value, *precision, self);
                         self.builder
                             .build_store(final_variable_ptr, x.value)
                             .unwrap();
                     }
+                    Type::Picture(picture) => {
+                        let scanned_float_value = self
+                            .builder
+                            .build_load(tmp_scan_tr, "load scanned")
+                            .unwrap()
+                            .into_float_value();
+                        let edited = self.edit_into_picture(scanned_float_value, *picture);
+                        self.builder
+                            .build_store(final_variable_ptr, edited)
+                            .unwrap();
+                    }
                     Type::Char(_size) => {
                         let scanned_chars = self
                             .builder
@@ -94,30 +177,40 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
                             .build_store(final_variable_ptr, scanned_chars)
                             .unwrap();
                     }
+                    Type::VaryingChar(_max_length) => {
+                        let scanned_chars = tmp_scan_tr_as_bytes.into_pointer_value();
+                        let scanned_string = StringView {
+                            characters: scanned_chars,
+                            length: self.build_string_length(scanned_chars),
+                        };
+                        self.store_string(final_variable_ptr, real_type, scanned_string);
+                    }
                     _ => {
                         panic!("Don't know how to get this type!");
                     }
                 }
 
                 //end
-
-                //now we need
-                result = scanf_return_value
-                    .try_as_basic_value()
-                    .left()
-                    .unwrap()
-                    .into_int_value();
             } else {
                 panic!("Expected a variable in the GET LIST, recieved a {:#?}", i);
             }
         }
+        self.builder.build_unconditional_branch(after_get_block)?;
+
+        self.builder.position_at_end(endfile_block);
+        self.generate_raise_code(ast::Condition::EndFile(file_name.to_string()), Some(file));
+        branch_only_if_no_terminator(self, after_get_block);
+
+        self.builder.position_at_end(after_get_block);
         Ok(())
     }
 
     fn determine_scanf_type_from_plick_type(&self, _type: Type) -> BasicTypeEnum<'ctx> {
         match _type {
-            Type::FixedDecimal => self.context.f64_type().into(),
-            Type::Char(_size) => self.get_character_type(_size).into(),
+            Type::FixedDecimal(..) | Type::Float(_) | Type::Picture(_) => {
+                self.context.f64_type().into()
+            }
+            Type::Char(_size) | Type::VaryingChar(_size) => self.get_character_type(_size).into(),
             _ => panic!("Don't know how to scan this type!"),
         }
     }
@@ -1,13 +1,20 @@
 use std::error::Error;
 
+use inkwell::{values::AnyValue, IntPredicate};
 use log::debug;
 
 use crate::{
     ast,
     codegen::{
         codegen::CodeGenable,
+        named_value_store::NamedValueStore,
+        runtime::stream,
         utils::{self, get_current_function},
     },
+    types::{
+        label::{ACTIVATION_FIELD, ADDRESS_FIELD},
+        Type,
+    },
 };
 
 impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Go {
@@ -24,6 +31,14 @@ impl<'a, 'ctx> ast::Go {
         self,
         compiler: &'a crate::codegen::codegen::Compiler<'a, 'ctx>,
     ) -> Result<Box<dyn inkwell::values::AnyValue<'ctx> + 'ctx>, Box<dyn Error>> {
+        let is_label_variable = compiler
+            .named_values
+            .try_get(&self.label_to_go_to)
+            .is_some_and(|named_value| named_value._type == Type::Label);
+        if is_label_variable {
+            return self.generate_label_variable_code(compiler);
+        }
+
         let does_labeled_block_exist = compiler
             .function_properties
             .borrow()
@@ -76,4 +91,68 @@ impl<'a, 'ctx> ast::Go {
             }
         }
     }
+
+    ///GO TO L; and GO TO CASE(I); go to the label held by a LABEL variable,
+    ///through the indirectbr of the procedure. The label has to be one of the
+    ///running invocation, anything else, like a label never assigned, is an
+    ///error at run time.
+    unsafe fn generate_label_variable_code(
+        self,
+        compiler: &'a crate::codegen::codegen::Compiler<'a, 'ctx>,
+    ) -> Result<Box<dyn inkwell::values::AnyValue<'ctx> + 'ctx>, Box<dyn Error>> {
+        let label_value = match self.subscript {
+            Some(subscript) => {
+                compiler.generate_subscripted_variable_code(&self.label_to_go_to, subscript)?
+            }
+            None => compiler.generate_variable_code(&self.label_to_go_to)?,
+        };
+        let label_value = label_value.as_any_value_enum().into_struct_value();
+
+        let builder = compiler.builder;
+        let address = builder
+            .build_extract_value(label_value, ADDRESS_FIELD, "label_address")
+            .unwrap();
+        let activation = builder
+            .build_extract_value(label_value, ACTIVATION_FIELD, "label_activation")
+            .unwrap()
+            .into_int_value();
+
+        let current_activation = builder
+            .build_load(compiler.get_label_activation(), "current_activation")?
+            .into_int_value();
+        let is_active = builder.build_int_compare(
+            IntPredicate::EQ,
+            activation,
+            current_activation,
+            "is_active",
+        )?;
+
+        let current_func = get_current_function(compiler);
+        let goto_block = compiler.context.append_basic_block(current_func, "goto");
+        let inactive_block = compiler
+            .context
+            .append_basic_block(current_func, "inactivelabel");
+        builder.build_conditional_branch(is_active, goto_block, inactive_block)?;
+
+        builder.position_at_end(inactive_block);
+        let variable_name =
+            builder.build_global_string_ptr(&self.label_to_go_to, "label_variable_name")?;
+        stream::build_runtime_error(
+            compiler,
+            "GO TO %s: the label it holds is not in an active block\n",
+            variable_name.as_pointer_value(),
+        );
+
+        builder.position_at_end(goto_block);
+        let (dispatch_block, address_ptr) = compiler.get_label_dispatch();
+        builder.build_store(address_ptr, address)?;
+        builder.build_unconditional_branch(dispatch_block)?;
+
+        //the statements after it can't be reached, but still need a block
+        let unreachable_block = compiler
+            .context
+            .append_basic_block(current_func, "aftergoto");
+        builder.position_at_end(unreachable_block);
+        Ok(compiler.ret_zero())
+    }
 }
@@ -1,15 +1,18 @@
 use std::error::Error;
 
-use inkwell::values::FloatValue;
+use inkwell::values::{FloatValue, IntValue};
 
 use crate::{
     ast,
     codegen::{
-        codegen::CodeGenable,
+        codegen::{CodeGenable, Compiler},
         utils::{self, get_current_function},
     },
     error::get_error,
-    types::{fixed_decimal::FixedValue, Type},
+    types::{
+        fixed_decimal::FixedValue, float_decimal::PLIFloatDecimalValue, picture::PictureValue,
+        traits::Mathable, Type,
+    },
 };
 
 impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::If {
@@ -34,42 +37,7 @@ impl<'a, 'ctx> ast::If {
         self,
         compiler: &'a crate::codegen::codegen::Compiler<'a, 'ctx>,
     ) -> Result<Box<dyn inkwell::values::AnyValue<'ctx> + 'ctx>, Box<dyn Error>> {
-        let conditional_type = self.conditional.get_type(compiler);
-
-        log::trace!("Conditional in if statement: {:#?}", &self.conditional);
-
-        let conditional_code = self.conditional.codegen(compiler);
-        let conditional_as_float: FloatValue;
-
-        match conditional_type {
-            Type::FixedDecimal => {
-                let fixed_value =
-                    FixedValue::from(conditional_code.as_any_value_enum().into_struct_value());
-                conditional_as_float = compiler.fixed_decimal_to_float(&fixed_value);
-            }
-            Type::Char(_size) => {
-                panic!("Can't support type Char in if conditional!");
-            }
-            Type::TBD => {
-                todo!("Can't support type TBD in if conditional!");
-            }
-            Type::Float => {
-                todo!("Can't support type Float in if conditional!");
-            }
-            Type::Void => {
-                todo!("Can't support type Void in if conditional!");
-            }
-        };
-        dbg!(&conditional_as_float);
-        let comparison = compiler
-            .builder
-            .build_float_compare(
-                inkwell::FloatPredicate::ONE,
-                conditional_as_float,
-                compiler.generate_float_code(0.0),
-                "ifcond",
-            )
-            .unwrap();
+        let comparison = compiler.generate_checked_condition_code(self.conditional);
 
         //now we build the THEN block
         let current_func = get_current_function(compiler);
@@ -116,3 +84,75 @@ impl<'a, 'ctx> ast::If {
         Ok(Box::new(return_value))
     }
 }
+
+impl<'a, 'ctx> Compiler<'a, 'ctx> {
+    ///Whether `conditional` is true, meaning not zero, as an i1. Only numbers
+    ///can be conditions.
+    pub unsafe fn generate_condition_code(
+        &'a self,
+        conditional: ast::Expr,
+    ) -> Result<IntValue<'ctx>, String> {
+        let conditional_type = conditional.get_type(self);
+
+        log::trace!("Conditional in if statement: {:#?}", &conditional);
+
+        let conditional_code = conditional.codegen(self);
+        let conditional_as_float: FloatValue = match conditional_type {
+            Type::FixedDecimal(precision, scale) => {
+                let fixed_value = FixedValue::new(
+                    conditional_code.as_any_value_enum().into_int_value(),
+                    precision,
+                    scale,
+                );
+                self.fixed_decimal_to_float(&fixed_value)
+            }
+            Type::Float(precision) => {
+                let float_value = PLIFloatDecimalValue::new(
+                    conditional_code.as_any_value_enum().into_float_value(),
+                    precision,
+                );
+                float_value.convert_to_float(self)
+            }
+            Type::Picture(picture) => {
+                let pictured_value = PictureValue::new(
+                    conditional_code.as_any_value_enum().into_array_value(),
+                    picture,
+                );
+                self.de_edit_picture(&pictured_value)
+            }
+            Type::Char(_)
+            | Type::VaryingChar(_)
+            | Type::Pointer
+            | Type::Label
+            | Type::Entry
+            | Type::FixedComplex
+            | Type::FloatComplex
+            | Type::TBD
+            | Type::Void => return Err(get_error(&["43", &conditional_type.to_string()])),
+        };
+        Ok(self
+            .builder
+            .build_float_compare(
+                inkwell::FloatPredicate::ONE,
+                conditional_as_float,
+                self.generate_float_code(0.0),
+                "ifcond",
+            )
+            .unwrap())
+    }
+
+    ///Like generate_condition_code, describing a condition that isn't a
+    ///number and taking it as false.
+    pub unsafe fn generate_checked_condition_code(
+        &'a self,
+        conditional: ast::Expr,
+    ) -> IntValue<'ctx> {
+        match self.generate_condition_code(conditional) {
+            Ok(condition) => condition,
+            Err(message) => {
+                self.error_module.store_error_msg(&message);
+                self.context.bool_type().const_zero()
+            }
+        }
+    }
+}
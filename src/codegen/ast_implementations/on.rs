@@ -0,0 +1,58 @@
+use inkwell::values::{BasicMetadataValueEnum, PointerValue};
+
+use crate::ast;
+use crate::codegen::codegen::{CodeGenable, Compiler};
+use crate::codegen::runtime::{character, decimal, record, stream};
+
+///The ON-unit is not generated here. It is stored, and a copy of it is generated
+///at every statement after this one that can raise its condition.
+impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::On {
+    unsafe fn codegen(
+        self,
+        compiler: &'a Compiler<'a, 'ctx>,
+    ) -> Box<dyn inkwell::values::AnyValue<'ctx> + 'ctx> {
+        compiler
+            .function_properties
+            .borrow_mut()
+            .store_on_unit(self.condition, *self.on_unit);
+
+        Box::new(compiler.context.i8_type().const_zero())
+    }
+}
+
+impl<'a, 'ctx> Compiler<'a, 'ctx> {
+    ///Runs the ON-unit established for the condition,
+    ///or ends the program if there isn't one.
+    ///`file` is the file an I/O condition was raised for.
+    pub unsafe fn generate_raise_code(
+        &'a self,
+        condition: ast::Condition,
+        file: Option<PointerValue<'ctx>>,
+    ) {
+        let raise_function_name = match condition {
+            ast::Condition::EndFile(_) => stream::RAISE_ENDFILE_FUNCTION_NAME,
+            ast::Condition::Key(_) => record::RAISE_KEY_FUNCTION_NAME,
+            ast::Condition::FixedOverflow => decimal::RAISE_FIXEDOVERFLOW_FUNCTION_NAME,
+            ast::Condition::ZeroDivide => decimal::RAISE_ZERODIVIDE_FUNCTION_NAME,
+            ast::Condition::Conversion => character::RAISE_CONVERSION_FUNCTION_NAME,
+        };
+        let on_unit = self.function_properties.borrow().get_on_unit(&condition);
+
+        match on_unit {
+            Some(on_unit) => {
+                on_unit.codegen(self);
+            }
+            None => {
+                let args: Vec<BasicMetadataValueEnum<'ctx>> =
+                    file.into_iter().map(|file| file.into()).collect();
+                self.builder
+                    .build_call(
+                        self.get_function(raise_function_name).unwrap(),
+                        &args,
+                        "raise_condition",
+                    )
+                    .unwrap();
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use inkwell::values::PointerValue;
+
+use crate::ast;
+use crate::codegen::codegen::{CodeGenable, Compiler};
+use crate::codegen::runtime::stream;
+use crate::types::traits::get_puttable_type;
+
+impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Open {
+    unsafe fn codegen(
+        self,
+        compiler: &'a Compiler<'a, 'ctx>,
+    ) -> Box<dyn inkwell::values::AnyValue<'ctx> + 'ctx> {
+        for file_to_open in self.files {
+            compiler.generate_open_code(file_to_open);
+        }
+        Box::new(compiler.context.i8_type().const_zero())
+    }
+}
+
+impl<'a, 'ctx> Compiler<'a, 'ctx> {
+    unsafe fn generate_open_code(&'a self, file_to_open: ast::OpenFile) {
+        let file = match self.get_declared_stream_file(&file_to_open.file_name) {
+            Some(file) => file,
+            None => return,
+        };
+
+        let title: Option<PointerValue<'ctx>> = file_to_open.title.map(|title| {
+            let title_type = title.get_type(self);
+            let title_value = get_puttable_type(title.codegen(self), title_type).unwrap();
+            title_value.get_pointer_to_printable_string(self)
+        });
+
+        let attribute_flags = stream::get_attribute_flags(&file_to_open.attributes);
+
+        self.build_open_call(file, title, attribute_flags);
+    }
+}
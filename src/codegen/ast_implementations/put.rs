@@ -1,13 +1,14 @@
 use std::error::Error;
 
 use inkwell::values::{
-    ArrayValue, BasicMetadataValueEnum, BasicValueEnum, CallSiteValue, IntValue,
+    ArrayValue, BasicMetadataValueEnum, BasicValueEnum, CallSiteValue, IntValue, PointerValue,
 };
 use inkwell::AddressSpace;
 
-use crate::ast::{self, Expr};
+use crate::ast::{self, Expr, PutOption};
 use crate::codegen::codegen::{self, CodeGenable, Compiler};
 use crate::codegen::named_value_store::NamedValueStore;
+use crate::codegen::runtime::stream;
 use crate::types;
 use crate::types::character::CharValue;
 use crate::types::traits::{get_puttable_type, Puttable};
@@ -18,23 +19,84 @@ impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Put {
         compiler: &'a Compiler<'a, 'ctx>,
     ) -> Box<dyn inkwell::values::AnyValue<'ctx> + 'ctx> {
         //Box::new(compiler.print_string(self.message_to_print))
+        let return_type = compiler.context.i8_type().const_zero();
+
+        let file_name = self.file.unwrap_or(stream::DEFAULT_OUTPUT_FILE.to_string());
+        let file = match compiler.get_declared_stream_file(&file_name) {
+            Some(file) => file,
+            None => return Box::new(return_type),
+        };
+
+        compiler.build_open_call(file, None, stream::OUTPUT_FLAG);
+
+        compiler.apply_put_options(file, self.options);
+
         for expression in self.messages_to_print.items {
-            compiler.print_from_put(expression);
+            compiler.print_from_put(file, expression);
         }
-        let return_type = compiler.context.i8_type().const_zero();
         Box::new(return_type)
     }
 }
 
 impl<'a, 'ctx> Compiler<'a, 'ctx> {
-    unsafe fn print_from_put(&'a self, message: Expr) {
+    unsafe fn print_from_put(&'a self, file: PointerValue<'ctx>, message: Expr) {
         let expr_type = message.get_type(self);
         log::trace!("Putting {:#?},", message);
         let genned_result = message.codegen(self);
 
         let puttable_obj = get_puttable_type(genned_result, expr_type).unwrap();
 
-        puttable_obj.print_object(self);
+        let string_ptr = puttable_obj.get_pointer_to_printable_string(self);
+
+        self.builder
+            .build_call(
+                self.get_function(stream::PUT_ITEM_FUNCTION_NAME).unwrap(),
+                &[file.into(), string_ptr.into()],
+                "put_item",
+            )
+            .unwrap();
+    }
+
+    ///Applies the PAGE, LINE and SKIP options of a PUT statement, in that order.
+    unsafe fn apply_put_options(&'a self, file: PointerValue<'ctx>, options: Vec<PutOption>) {
+        if options
+            .iter()
+            .any(|option| matches!(option, PutOption::Page))
+        {
+            self.builder
+                .build_call(
+                    self.get_function(stream::PUT_PAGE_FUNCTION_NAME).unwrap(),
+                    &[file.into()],
+                    "put_page",
+                )
+                .unwrap();
+        }
+
+        for option in options.iter() {
+            if let PutOption::Line(line_number) = option {
+                let line_number = self.generate_expression_as_i32(line_number.clone());
+                self.builder
+                    .build_call(
+                        self.get_function(stream::PUT_LINE_FUNCTION_NAME).unwrap(),
+                        &[file.into(), line_number.into()],
+                        "put_line",
+                    )
+                    .unwrap();
+            }
+        }
+
+        for option in options.into_iter() {
+            if let PutOption::Skip(lines_to_skip) = option {
+                let lines_to_skip = self.generate_expression_as_i32(lines_to_skip);
+                self.builder
+                    .build_call(
+                        self.get_function(stream::PUT_SKIP_FUNCTION_NAME).unwrap(),
+                        &[file.into(), lines_to_skip.into()],
+                        "put_skip",
+                    )
+                    .unwrap();
+            }
+        }
     }
     unsafe fn print_string(&'a self, message: Expr) -> CallSiteValue<'ctx> {
         if let Expr::Char { value } = message.clone() {
@@ -78,16 +140,18 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
             );
 
             return res.unwrap();
-        } else if let Expr::NumVal { value, _type } = message.clone() {
-            let print_func = self.module.get_function("print_fixed_decimal").unwrap();
-            let numval = Expr::NumVal { value, _type };
-            let struc = numval.codegen(self);
-
-            let struc = self.convert_anyvalue_to_basicvalue(struc);
+        } else if let Expr::NumVal { _type, .. } = message.clone() {
+            let numval = message.codegen(self);
+            let bitc = get_puttable_type(numval, _type)
+                .unwrap()
+                .get_pointer_to_printable_string(self);
 
-            let name = "lolz";
             self.builder
-                .build_call(print_func, &[struc.into()], name)
+                .build_call(
+                    self.get_function("printf").unwrap(),
+                    &[BasicMetadataValueEnum::from(bitc)],
+                    "printf",
+                )
                 .unwrap()
         } else {
             todo!(
@@ -0,0 +1,603 @@
+use inkwell::values::{BasicMetadataValueEnum, IntValue, PointerValue};
+use inkwell::{AddressSpace, IntPredicate};
+
+use crate::ast::{self, Condition, Expr};
+use crate::codegen::codegen::{CodeGenable, Compiler};
+use crate::codegen::named_value_store::NamedValueStore;
+use crate::codegen::runtime::{decimal, record, stream};
+use crate::codegen::utils::{branch_only_if_no_terminator, get_current_function};
+use crate::types::fixed_decimal::const_power_of_ten;
+use crate::types::fixed_decimal::packed::{get_packed_length, PACKED_LENGTH};
+use crate::types::traits::get_puttable_type;
+use crate::types::Type;
+
+///A FIXED DECIMAL variable transferred packed, with --packed-decimal.
+struct PackedVariable<'ctx> {
+    pointer: PointerValue<'ctx>,
+    precision: u32,
+    dimension: Option<u32>,
+}
+
+impl<'ctx> PackedVariable<'ctx> {
+    ///The bytes each element takes in the record.
+    fn get_field_length(&self) -> u32 {
+        get_packed_length(self.precision)
+    }
+
+    fn get_element_count(&self) -> u32 {
+        self.dimension.unwrap_or(1)
+    }
+}
+
+impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Read {
+    unsafe fn codegen(
+        self,
+        compiler: &'a Compiler<'a, 'ctx>,
+    ) -> Box<dyn inkwell::values::AnyValue<'ctx> + 'ctx> {
+        compiler.generate_read_code(self);
+        Box::new(compiler.context.i8_type().const_zero())
+    }
+}
+
+impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Write {
+    unsafe fn codegen(
+        self,
+        compiler: &'a Compiler<'a, 'ctx>,
+    ) -> Box<dyn inkwell::values::AnyValue<'ctx> + 'ctx> {
+        compiler.generate_write_code(self);
+        Box::new(compiler.context.i8_type().const_zero())
+    }
+}
+
+impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Rewrite {
+    unsafe fn codegen(
+        self,
+        compiler: &'a Compiler<'a, 'ctx>,
+    ) -> Box<dyn inkwell::values::AnyValue<'ctx> + 'ctx> {
+        compiler.generate_rewrite_code(self);
+        Box::new(compiler.context.i8_type().const_zero())
+    }
+}
+
+impl<'a, 'ctx> CodeGenable<'a, 'ctx> for ast::Delete {
+    unsafe fn codegen(
+        self,
+        compiler: &'a Compiler<'a, 'ctx>,
+    ) -> Box<dyn inkwell::values::AnyValue<'ctx> + 'ctx> {
+        compiler.generate_delete_code(self);
+        Box::new(compiler.context.i8_type().const_zero())
+    }
+}
+
+impl<'a, 'ctx> Compiler<'a, 'ctx> {
+    unsafe fn generate_read_code(&'a self, read: ast::Read) {
+        let file = match self.get_declared_stream_file(&read.file_name) {
+            Some(file) => file,
+            None => return,
+        };
+        let packed_variable = self.get_packed_decimal_variable(&read.into);
+        let (record, size) = match packed_variable {
+            Some(variable) => self.build_packed_record(&variable),
+            None => match self.get_record_storage(&read.into) {
+                Some(storage) => storage,
+                None => return,
+            },
+        };
+        self.build_open_call(file, None, stream::INPUT_FLAG | stream::RECORD_FLAG);
+
+        let status = match read.key {
+            Some(key) => {
+                let key = self.generate_key_pointer(Some(key));
+                self.build_record_call(
+                    record::RECORD_READ_KEY_FUNCTION_NAME,
+                    &[file.into(), record.into(), size.into(), key.into()],
+                )
+            }
+            None => {
+                let (keyto, keyto_length) = match read.keyto {
+                    Some(ref keyto) => match self.get_keyto_storage(keyto) {
+                        Some(storage) => storage,
+                        None => return,
+                    },
+                    None => (
+                        self.get_null_byte_pointer(),
+                        self.context.i64_type().const_zero(),
+                    ),
+                };
+                self.build_record_call(
+                    record::RECORD_READ_FUNCTION_NAME,
+                    &[
+                        file.into(),
+                        record.into(),
+                        size.into(),
+                        keyto.into(),
+                        keyto_length.into(),
+                    ],
+                )
+            }
+        };
+
+        if let Some(variable) = packed_variable {
+            self.generate_unpack_code(&variable, record, status);
+        }
+        self.generate_record_status_check(status, &read.file_name, file);
+    }
+
+    unsafe fn generate_write_code(&'a self, write: ast::Write) {
+        let file = match self.get_declared_stream_file(&write.file_name) {
+            Some(file) => file,
+            None => return,
+        };
+        let (record, size) = match self.get_record_to_write(&write.from) {
+            Some(storage) => storage,
+            None => return,
+        };
+        self.build_open_call(file, None, stream::OUTPUT_FLAG | stream::RECORD_FLAG);
+
+        let key = self.generate_key_pointer(write.keyfrom);
+        let status = self.build_record_call(
+            record::RECORD_WRITE_FUNCTION_NAME,
+            &[file.into(), record.into(), size.into(), key.into()],
+        );
+
+        self.generate_record_status_check(status, &write.file_name, file);
+    }
+
+    unsafe fn generate_rewrite_code(&'a self, rewrite: ast::Rewrite) {
+        let file = match self.get_declared_stream_file(&rewrite.file_name) {
+            Some(file) => file,
+            None => return,
+        };
+        let (record, size) = match self.get_record_to_write(&rewrite.from) {
+            Some(storage) => storage,
+            None => return,
+        };
+        self.build_open_call(file, None, stream::UPDATE_FLAG | stream::RECORD_FLAG);
+
+        let key = self.generate_key_pointer(rewrite.key);
+        let status = self.build_record_call(
+            record::RECORD_REWRITE_FUNCTION_NAME,
+            &[file.into(), record.into(), size.into(), key.into()],
+        );
+
+        self.generate_record_status_check(status, &rewrite.file_name, file);
+    }
+
+    unsafe fn generate_delete_code(&'a self, delete: ast::Delete) {
+        let file = match self.get_declared_stream_file(&delete.file_name) {
+            Some(file) => file,
+            None => return,
+        };
+        self.build_open_call(file, None, stream::UPDATE_FLAG | stream::RECORD_FLAG);
+
+        let key = self.generate_key_pointer(delete.key);
+        let status = self.build_record_call(
+            record::RECORD_DELETE_FUNCTION_NAME,
+            &[file.into(), key.into()],
+        );
+
+        self.generate_record_status_check(status, &delete.file_name, file);
+    }
+
+    ///Returns the storage of a variable as bytes, along with how many bytes there are.
+    unsafe fn get_record_storage(
+        &'a self,
+        variable_name: &str,
+    ) -> Option<(PointerValue<'ctx>, IntValue<'ctx>)> {
+        let named_value = match self.named_values.try_get(variable_name) {
+            Some(named_value) => named_value,
+            None => {
+                self.error_module
+                    .store_msg_from_number(&["12", variable_name]);
+                return None;
+            }
+        };
+
+        let record = self
+            .builder
+            .build_bitcast(
+                named_value.pointer,
+                self.context.i8_type().ptr_type(AddressSpace::default()),
+                "record",
+            )
+            .unwrap()
+            .into_pointer_value();
+        let size = self
+            .get_storage_type(named_value._type, named_value.dimension)
+            .size_of()
+            .unwrap();
+
+        Some((record, size))
+    }
+
+    ///The bytes written from a variable. With --packed-decimal, those of a
+    ///FIXED DECIMAL variable are a packed copy of its values, raising
+    ///FIXEDOVERFLOW when one has more digits than it was declared with.
+    unsafe fn get_record_to_write(
+        &'a self,
+        variable_name: &str,
+    ) -> Option<(PointerValue<'ctx>, IntValue<'ctx>)> {
+        let variable = match self.get_packed_decimal_variable(variable_name) {
+            Some(variable) => variable,
+            None => return self.get_record_storage(variable_name),
+        };
+
+        let (record, size) = self.build_packed_record(&variable);
+        let (packed, packed_value) = self.build_packed_buffer(variable.precision);
+        self.build_packed_element_loop(&variable, record, |element, field| {
+            let value = self
+                .builder
+                .build_load(element, "fixed_value")
+                .unwrap()
+                .into_int_value();
+            self.generate_fixedoverflow_check(
+                value,
+                const_power_of_ten(value.get_type(), variable.precision),
+            );
+            self.builder
+                .build_call(
+                    self.get_function(decimal::FIXED_TO_PACKED_FUNCTION_NAME)
+                        .unwrap(),
+                    &[value.into(), packed.into()],
+                    "",
+                )
+                .unwrap();
+            self.build_copy_packed_field(field, packed_value, &variable);
+        });
+
+        Some((record, size))
+    }
+
+    ///With --packed-decimal, the records of a FIXED DECIMAL variable are
+    ///packed, those of an array holding each element packed in turn.
+    fn get_packed_decimal_variable(&self, variable_name: &str) -> Option<PackedVariable<'ctx>> {
+        if !self.packed_decimal {
+            return None;
+        }
+
+        let named_value = self.named_values.try_get(variable_name)?;
+        match named_value._type {
+            Type::FixedDecimal(precision, _scale) => Some(PackedVariable {
+                pointer: named_value.pointer,
+                precision,
+                dimension: named_value.dimension,
+            }),
+            _ => None,
+        }
+    }
+
+    ///Room for the packed record of `variable`, cleared to zero, and its
+    ///number of bytes.
+    unsafe fn build_packed_record(
+        &'a self,
+        variable: &PackedVariable<'ctx>,
+    ) -> (PointerValue<'ctx>, IntValue<'ctx>) {
+        let i8_type = self.context.i8_type();
+        let size = variable.get_field_length() * variable.get_element_count();
+        let record_type = i8_type.array_type(size);
+        let record = self.create_entry_block_alloca_of_type(
+            "packed_record",
+            &get_current_function(self),
+            record_type.into(),
+        );
+        self.builder
+            .build_store(record, record_type.const_zero())
+            .unwrap();
+        let record = self
+            .builder
+            .build_bitcast(
+                record,
+                i8_type.ptr_type(AddressSpace::default()),
+                "packed_record",
+            )
+            .unwrap()
+            .into_pointer_value();
+
+        (
+            record,
+            self.context.i64_type().const_int(size as u64, false),
+        )
+    }
+
+    ///Room for a packed value of the most digits, which the runtime converts,
+    ///cleared to zero. A value of `precision` digits is its last bytes, which
+    ///are returned after it.
+    unsafe fn build_packed_buffer(
+        &'a self,
+        precision: u32,
+    ) -> (PointerValue<'ctx>, PointerValue<'ctx>) {
+        let i8_type = self.context.i8_type();
+        let buffer_type = i8_type.array_type(PACKED_LENGTH);
+        let packed = self.create_entry_block_alloca_of_type(
+            "packed",
+            &get_current_function(self),
+            buffer_type.into(),
+        );
+        self.builder
+            .build_store(packed, buffer_type.const_zero())
+            .unwrap();
+        let packed = self
+            .builder
+            .build_bitcast(
+                packed,
+                i8_type.ptr_type(AddressSpace::default()),
+                "packed_buffer",
+            )
+            .unwrap()
+            .into_pointer_value();
+
+        let length = get_packed_length(precision);
+        let packed_value = self
+            .builder
+            .build_gep(
+                packed,
+                &[self
+                    .context
+                    .i64_type()
+                    .const_int((PACKED_LENGTH - length) as u64, false)],
+                "packed_value",
+            )
+            .unwrap();
+
+        (packed, packed_value)
+    }
+
+    ///Stores the values of the packed record read into `variable`, which
+    ///keeps its values when no record was read. FIXEDOVERFLOW is raised when
+    ///the record holds a value with more digits than the variable's precision.
+    unsafe fn generate_unpack_code(
+        &'a self,
+        variable: &PackedVariable<'ctx>,
+        record: PointerValue<'ctx>,
+        status: IntValue<'ctx>,
+    ) {
+        let builder = self.builder;
+        let (packed, packed_value) = self.build_packed_buffer(variable.precision);
+        let was_read = builder
+            .build_int_compare(
+                IntPredicate::EQ,
+                status,
+                status.get_type().const_int(record::RECORD_OK, false),
+                "was_read",
+            )
+            .unwrap();
+
+        self.build_packed_element_loop(variable, record, |element, field| {
+            self.build_copy_packed_field(packed_value, field, variable);
+            let unpacked = builder
+                .build_call(
+                    self.get_function(decimal::PACKED_TO_FIXED_FUNCTION_NAME)
+                        .unwrap(),
+                    &[packed.into()],
+                    "unpacked",
+                )
+                .unwrap()
+                .try_as_basic_value()
+                .left()
+                .unwrap()
+                .into_int_value();
+            self.generate_fixedoverflow_check(
+                unpacked,
+                const_power_of_ten(unpacked.get_type(), variable.precision),
+            );
+            let old_value = builder.build_load(element, "old_value").unwrap();
+            let value = builder
+                .build_select(was_read, unpacked, old_value, "fixed_value")
+                .unwrap();
+            builder.build_store(element, value).unwrap();
+        });
+    }
+
+    ///Generates `body` for each element of `variable`, given a pointer to
+    ///the element and to the bytes of the packed record holding it.
+    unsafe fn build_packed_element_loop(
+        &'a self,
+        variable: &PackedVariable<'ctx>,
+        record: PointerValue<'ctx>,
+        mut body: impl FnMut(PointerValue<'ctx>, PointerValue<'ctx>),
+    ) {
+        let builder = self.builder;
+        let i64_type = self.context.i64_type();
+        let current_function = get_current_function(self);
+        let before_block = builder.get_insert_block().unwrap();
+        let element_block = self
+            .context
+            .append_basic_block(current_function, "packed_element");
+        let after_elements_block = self
+            .context
+            .append_basic_block(current_function, "after_packed_elements");
+        builder.build_unconditional_branch(element_block).unwrap();
+
+        builder.position_at_end(element_block);
+        let index = builder.build_phi(i64_type, "element_index").unwrap();
+        index.add_incoming(&[(&i64_type.const_zero(), before_block)]);
+        let index_value = index.as_basic_value().into_int_value();
+        let element = match variable.dimension {
+            Some(_element_count) => builder
+                .build_gep(
+                    variable.pointer,
+                    &[i64_type.const_zero(), index_value],
+                    "packed_element",
+                )
+                .unwrap(),
+            None => variable.pointer,
+        };
+        body(
+            element,
+            self.get_packed_field(record, variable, index_value),
+        );
+
+        let next_index = builder
+            .build_int_add(index_value, i64_type.const_int(1, false), "next_index")
+            .unwrap();
+        let is_done = builder
+            .build_int_compare(
+                IntPredicate::EQ,
+                next_index,
+                i64_type.const_int(variable.get_element_count() as u64, false),
+                "is_done",
+            )
+            .unwrap();
+        index.add_incoming(&[(&next_index, builder.get_insert_block().unwrap())]);
+        builder
+            .build_conditional_branch(is_done, after_elements_block, element_block)
+            .unwrap();
+
+        builder.position_at_end(after_elements_block);
+    }
+
+    ///The bytes of the packed record holding element `index`.
+    unsafe fn get_packed_field(
+        &'a self,
+        record: PointerValue<'ctx>,
+        variable: &PackedVariable<'ctx>,
+        index: IntValue<'ctx>,
+    ) -> PointerValue<'ctx> {
+        let i64_type = self.context.i64_type();
+        let offset = self
+            .builder
+            .build_int_mul(
+                index,
+                i64_type.const_int(variable.get_field_length() as u64, false),
+                "field_offset",
+            )
+            .unwrap();
+        self.builder
+            .build_gep(record, &[offset], "packed_field")
+            .unwrap()
+    }
+
+    ///Copies the packed bytes of one element.
+    fn build_copy_packed_field(
+        &self,
+        destination: PointerValue<'ctx>,
+        source: PointerValue<'ctx>,
+        variable: &PackedVariable<'ctx>,
+    ) {
+        let length = self
+            .context
+            .i64_type()
+            .const_int(variable.get_field_length() as u64, false);
+        self.builder
+            .build_memcpy(destination, 1, source, 1, length)
+            .unwrap();
+    }
+
+    ///KEYTO needs a CHARACTER variable. The key is cut short to fit in it.
+    unsafe fn get_keyto_storage(
+        &'a self,
+        variable_name: &str,
+    ) -> Option<(PointerValue<'ctx>, IntValue<'ctx>)> {
+        let named_value = match self.named_values.try_get(variable_name) {
+            Some(named_value) => named_value,
+            None => {
+                self.error_module
+                    .store_msg_from_number(&["12", variable_name]);
+                return None;
+            }
+        };
+
+        let length = match named_value._type {
+            Type::Char(length) => length,
+            _ => {
+                self.error_module
+                    .store_msg_from_number(&["14", variable_name]);
+                return None;
+            }
+        };
+
+        let keyto = self
+            .builder
+            .build_bitcast(
+                named_value.pointer,
+                self.context.i8_type().ptr_type(AddressSpace::default()),
+                "keyto",
+            )
+            .unwrap()
+            .into_pointer_value();
+
+        Some((
+            keyto,
+            self.context.i64_type().const_int(length as u64, false),
+        ))
+    }
+
+    ///Keys are passed as strings, so numbers are used in the form PUT would print them.
+    ///No key is passed as a null pointer.
+    unsafe fn generate_key_pointer(&'a self, key: Option<Expr>) -> PointerValue<'ctx> {
+        match key {
+            Some(key) => {
+                let key_type = key.get_type(self);
+                let key_value = get_puttable_type(key.codegen(self), key_type).unwrap();
+                key_value.get_pointer_to_printable_string(self)
+            }
+            None => self.get_null_byte_pointer(),
+        }
+    }
+
+    fn get_null_byte_pointer(&self) -> PointerValue<'ctx> {
+        self.context
+            .i8_type()
+            .ptr_type(AddressSpace::default())
+            .const_null()
+    }
+
+    unsafe fn build_record_call(
+        &'a self,
+        function_name: &str,
+        args: &[BasicMetadataValueEnum<'ctx>],
+    ) -> IntValue<'ctx> {
+        self.builder
+            .build_call(self.get_function(function_name).unwrap(), args, "status")
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_int_value()
+    }
+
+    ///Raises ENDFILE or KEY when the record function reports them.
+    unsafe fn generate_record_status_check(
+        &'a self,
+        status: IntValue<'ctx>,
+        file_name: &str,
+        file: PointerValue<'ctx>,
+    ) {
+        let current_function = get_current_function(self);
+        let endfile_block = self.context.append_basic_block(current_function, "endfile");
+        let key_block = self
+            .context
+            .append_basic_block(current_function, "key_condition");
+        let after_record_io_block = self
+            .context
+            .append_basic_block(current_function, "after_record_io");
+
+        let i32_type = self.context.i32_type();
+        self.builder
+            .build_switch(
+                status,
+                after_record_io_block,
+                &[
+                    (
+                        i32_type.const_int(record::RECORD_END_OF_FILE, false),
+                        endfile_block,
+                    ),
+                    (
+                        i32_type.const_int(record::RECORD_KEY_CONDITION, false),
+                        key_block,
+                    ),
+                ],
+            )
+            .unwrap();
+
+        self.builder.position_at_end(endfile_block);
+        self.generate_raise_code(Condition::EndFile(file_name.to_string()), Some(file));
+        branch_only_if_no_terminator(self, after_record_io_block);
+
+        self.builder.position_at_end(key_block);
+        self.generate_raise_code(Condition::Key(file_name.to_string()), Some(file));
+        branch_only_if_no_terminator(self, after_record_io_block);
+
+        self.builder.position_at_end(after_record_io_block);
+    }
+}
@@ -0,0 +1,229 @@
+use inkwell::values::{AnyValue, IntValue};
+
+use super::codegen::{CodeGenable, Compiler};
+use super::named_value_store::NamedValueStore;
+use super::runtime::{controlled, program};
+use crate::ast::{Expr, StorageClass};
+use crate::error::get_error;
+use crate::types::conversion::get_fixed_binary_type;
+use crate::types::{resolve_types, Type};
+
+///LENGTH(string): the current length of a string.
+pub const LENGTH: &str = "LENGTH";
+///ADDR(X): a pointer to the storage of X.
+pub const ADDR: &str = "ADDR";
+///NULL(): the pointer that points to nothing.
+pub const NULL: &str = "NULL";
+///ALLOCATION(X): how many generations the CONTROLLED variable X has.
+pub const ALLOCATION: &str = "ALLOCATION";
+///PLIRETC(n): sets the return code of the program to n.
+pub const PLIRETC: &str = "PLIRETC";
+///PLIRETV(): the return code set so far.
+pub const PLIRETV: &str = "PLIRETV";
+///REAL(Z): the real part of the complex number Z.
+pub const REAL: &str = "REAL";
+///IMAG(Z): the imaginary part of the complex number Z.
+pub const IMAG: &str = "IMAG";
+///CONJG(Z): the complex conjugate of Z.
+pub const CONJG: &str = "CONJG";
+///COMPLEX(A, B): the complex number with real part A and imaginary part B.
+pub const COMPLEX: &str = "COMPLEX";
+
+///LENGTH, ALLOCATION and PLIRETV return a FIXED BINARY of this precision.
+const INTEGER_RESULT_PRECISION: u32 = 31;
+
+pub fn is_builtin(fn_name: &str) -> bool {
+    matches!(
+        fn_name,
+        LENGTH | ADDR | NULL | ALLOCATION | PLIRETC | PLIRETV | REAL | IMAG | CONJG | COMPLEX
+    )
+}
+
+///Returns the type a builtin function returns when called with arguments of
+///`argument_types`, or None if `fn_name` is not a builtin.
+pub fn get_builtin_type(fn_name: &str, argument_types: &[Type]) -> Option<Type> {
+    match fn_name {
+        LENGTH | ALLOCATION | PLIRETV => Some(get_fixed_binary_type(INTEGER_RESULT_PRECISION)),
+        ADDR | NULL => Some(Type::Pointer),
+        PLIRETC => Some(Type::Void),
+        REAL | IMAG => Some(match argument_types {
+            [argument_type] => argument_type.get_real_type(),
+            _ => Type::TBD,
+        }),
+        CONJG => Some(match argument_types {
+            [argument_type] => argument_type.get_complex_type(),
+            _ => Type::TBD,
+        }),
+        COMPLEX => Some(match argument_types {
+            [real_type, imaginary_type] => resolve_types(real_type, imaginary_type)
+                .map_or(Type::TBD, |part_type| part_type.get_complex_type()),
+            _ => Type::TBD,
+        }),
+        _ => None,
+    }
+}
+
+impl<'a, 'ctx> Compiler<'a, 'ctx> {
+    pub unsafe fn generate_builtin_call(
+        &self,
+        fn_name: &str,
+        mut args: Vec<Expr>,
+    ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, String> {
+        match fn_name {
+            LENGTH => {
+                if args.len() != 1 {
+                    return Err(format!("LENGTH takes 1 argument, found {}", args.len()));
+                }
+                let string = args.remove(0);
+                let string_type = string.get_type(self);
+                let view = self.generate_string_view(string.codegen(self), string_type)?;
+
+                let length: IntValue<'ctx> = self
+                    .builder
+                    .build_int_z_extend(view.length, self.type_module.fixed_type, "length")
+                    .unwrap();
+                Ok(Box::new(length))
+            }
+            ADDR => {
+                if args.len() != 1 {
+                    return Err(format!("ADDR takes 1 argument, found {}", args.len()));
+                }
+                let storage = match args.remove(0) {
+                    Expr::Variable { name, _type } => {
+                        let named_value = self
+                            .named_values
+                            .try_get(&name)
+                            .ok_or(get_error(&["12", &name]))?;
+                        self.get_variable_storage(&named_value)?
+                    }
+                    Expr::Qualified { locator, name } => {
+                        self.get_qualified_storage(*locator, &name)?.0
+                    }
+                    other => return Err(format!("Can't take the address of {:?}", other)),
+                };
+                let address = self
+                    .builder
+                    .build_pointer_cast(storage, self.get_pointer_type(), "address")
+                    .unwrap();
+                Ok(Box::new(address))
+            }
+            NULL => {
+                if !args.is_empty() {
+                    return Err(format!("NULL takes no arguments, found {}", args.len()));
+                }
+                Ok(Box::new(self.get_pointer_type().const_null()))
+            }
+            ALLOCATION => {
+                if args.len() != 1 {
+                    return Err(format!("ALLOCATION takes 1 argument, found {}", args.len()));
+                }
+                let name = match args.remove(0) {
+                    Expr::Variable { name, _type } => name,
+                    other => return Err(format!("ALLOCATION needs a variable, found {:?}", other)),
+                };
+                let named_value = self
+                    .named_values
+                    .try_get(&name)
+                    .ok_or(get_error(&["12", &name]))?;
+                if named_value.storage_class != StorageClass::Controlled {
+                    return Err(format!(
+                        "ALLOCATION needs a CONTROLLED variable, {} is not",
+                        name
+                    ));
+                }
+
+                let anchor = controlled::get_or_add_anchor(self, &name);
+                let allocation = self
+                    .builder
+                    .build_call(
+                        self.module
+                            .get_function(controlled::CONTROLLED_ALLOCATION_FUNCTION_NAME)
+                            .unwrap(),
+                        &[anchor.into()],
+                        "allocation",
+                    )
+                    .unwrap()
+                    .try_as_basic_value()
+                    .left()
+                    .unwrap()
+                    .into_int_value();
+                let allocation: IntValue<'ctx> = self
+                    .builder
+                    .build_int_z_extend(allocation, self.type_module.fixed_type, "allocation")
+                    .unwrap();
+                Ok(Box::new(allocation))
+            }
+            PLIRETC => {
+                if args.len() != 1 {
+                    return Err(format!("PLIRETC takes 1 argument, found {}", args.len()));
+                }
+                let return_code = self.generate_expression_as_i32(args.remove(0));
+                program::build_store_return_code(self, return_code);
+                Ok(self.ret_zero())
+            }
+            PLIRETV => {
+                if !args.is_empty() {
+                    return Err(format!("PLIRETV takes no arguments, found {}", args.len()));
+                }
+                let return_code = program::build_load_return_code(self);
+                let return_code: IntValue<'ctx> = self
+                    .builder
+                    .build_int_s_extend(return_code, self.type_module.fixed_type, "return_code")
+                    .unwrap();
+                Ok(Box::new(return_code))
+            }
+            REAL | IMAG => {
+                if args.len() != 1 {
+                    return Err(format!(
+                        "{} takes 1 argument, found {}",
+                        fn_name,
+                        args.len()
+                    ));
+                }
+                let number = args.remove(0);
+                let number_type = number.get_type(self);
+                if !number_type.is_arithmetic() {
+                    return Err(format!("{} needs a number, found {}", fn_name, number_type));
+                }
+                let (real, imaginary) =
+                    self.get_complex_parts(number.codegen(self), number_type)?;
+                let part = if fn_name == REAL { real } else { imaginary };
+                self.create_arithmetic_value(&part, number_type.get_real_type())
+            }
+            CONJG => {
+                if args.len() != 1 {
+                    return Err(format!("CONJG takes 1 argument, found {}", args.len()));
+                }
+                let number = args.remove(0);
+                let number_type = number.get_type(self);
+                if !number_type.is_arithmetic() {
+                    return Err(format!("CONJG needs a number, found {}", number_type));
+                }
+                let (real, imaginary) =
+                    self.get_complex_parts(number.codegen(self), number_type)?;
+                //subtracting from zero keeps a conjugated zero from printing as -0
+                let conjugate = self
+                    .builder
+                    .build_float_sub(self.context.f64_type().const_zero(), imaginary, "conjugate")
+                    .unwrap();
+                let complex_value =
+                    self.create_complex_value(real, conjugate, number_type.get_complex_type())?;
+                Ok(Box::new(complex_value))
+            }
+            COMPLEX => {
+                if args.len() != 2 {
+                    return Err(format!("COMPLEX takes 2 arguments, found {}", args.len()));
+                }
+                let real = args.remove(0);
+                let imaginary = args.remove(0);
+                let complex_type = resolve_types(&real.get_type(self), &imaginary.get_type(self))?
+                    .get_complex_type();
+                let real = self.generate_expression_as_float(real)?;
+                let imaginary = self.generate_expression_as_float(imaginary)?;
+                let complex_value = self.create_complex_value(real, imaginary, complex_type)?;
+                Ok(Box::new(complex_value))
+            }
+            other => Err(format!("{} is not a builtin function", other)),
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use inkwell::{
+    module::Linkage,
+    types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum, FunctionType},
+    values::{AnyValue, BasicMetadataValueEnum, BasicValue, FunctionValue},
+    AddressSpace,
+};
+
+use super::codegen::{CodeGenable, Compiler};
+use crate::{
+    ast::{self, Descriptor},
+    error::get_error,
+    types::{
+        conversion::get_fixed_binary_type,
+        float_decimal::{FloatFormat, PLIFloatDecimalValue},
+        traits::get_mathable_type,
+        Type,
+    },
+};
+
+///A C function declared as ENTRY ... OPTIONS(C).
+#[derive(Debug, Clone)]
+pub struct CEntry<'ctx> {
+    ///The C function, named like the entry in lower case.
+    pub function: FunctionValue<'ctx>,
+    ///The type the function is called with. It differs from the type of
+    ///`function` when the prelude already declared a function of that name.
+    pub function_type: FunctionType<'ctx>,
+    pub entry: ast::Entry,
+}
+
+///A procedure callable from C, declared in the header of the compiled units.
+#[derive(Debug, Clone)]
+pub struct ExportedProcedure {
+    ///The name of the C function, the procedure's name in lower case.
+    pub c_name: String,
+    pub parameters: Vec<ast::PrototypeArgument>,
+    pub return_type: Type,
+}
+
+///How a value of `descriptor` is passed to C. Numbers are passed as native
+///integers or floats of their precision, strings as pointers to their NUL-terminated characters.
+pub fn get_c_type<'ctx>(
+    compiler: &Compiler<'_, 'ctx>,
+    descriptor: Descriptor,
+) -> BasicTypeEnum<'ctx> {
+    let context = compiler.context;
+    match descriptor {
+        Descriptor::FixedBinary(precision) => {
+            //one more bit for the sign, rounded up to a C integer type
+            let bits = match precision + 1 {
+                0..=8 => 8,
+                9..=16 => 16,
+                17..=32 => 32,
+                _ => 64,
+            };
+            context.custom_width_int_type(bits).as_basic_type_enum()
+        }
+        Descriptor::Data(Type::FixedDecimal(..)) => context.f64_type().as_basic_type_enum(),
+        Descriptor::Data(Type::Float(precision)) => FloatFormat::from_precision(precision)
+            .get_float_type(context)
+            .as_basic_type_enum(),
+        Descriptor::Data(_) | Descriptor::AnyLengthCharacter => {
+            compiler.get_pointer_type().as_basic_type_enum()
+        }
+    }
+}
+
+///The type a call of the entry has in PL/I.
+pub fn get_result_type(entry: &ast::Entry) -> Type {
+    match entry.returns {
+        None => Type::Void,
+        Some(Descriptor::FixedBinary(precision)) => get_fixed_binary_type(precision),
+        Some(Descriptor::Data(_type)) => _type,
+        Some(Descriptor::AnyLengthCharacter) => Type::TBD,
+    }
+}
+
+impl<'a, 'ctx> Compiler<'a, 'ctx> {
+    pub fn get_c_entry(&self, name: &str) -> Option<CEntry<'ctx>> {
+        self.c_entries.borrow().get(name).cloned()
+    }
+
+    ///Declares the C function `name` is called through.
+    pub fn declare_c_entry(
+        &self,
+        name: &str,
+        entry: ast::Entry,
+    ) -> Result<FunctionValue<'ctx>, String> {
+        if get_result_type(&entry).is_character()
+            || entry.returns == Some(Descriptor::AnyLengthCharacter)
+        {
+            return Err(get_error(&["26", name]));
+        }
+
+        let parameter_types: Vec<BasicMetadataTypeEnum> = entry
+            .parameters
+            .iter()
+            .map(|descriptor| get_c_type(self, *descriptor).into())
+            .collect();
+        let is_variable_num_of_args = false;
+        let function_type = match entry.returns {
+            Some(descriptor) => {
+                get_c_type(self, descriptor).fn_type(&parameter_types, is_variable_num_of_args)
+            }
+            None => self
+                .context
+                .void_type()
+                .fn_type(&parameter_types, is_variable_num_of_args),
+        };
+
+        let c_name = name.to_lowercase();
+        let function = match self.module.get_function(&c_name) {
+            Some(declared_function) => declared_function,
+            None => self
+                .module
+                .add_function(&c_name, function_type, Some(Linkage::External)),
+        };
+
+        self.c_entries.borrow_mut().insert(
+            name.to_string(),
+            CEntry {
+                function,
+                function_type,
+                entry,
+            },
+        );
+        Ok(function)
+    }
+
+    ///Calls a C function, converting the arguments to its types and the
+    ///result back to a PL/I value.
+    pub unsafe fn generate_c_call(
+        &'a self,
+        fn_name: &str,
+        c_entry: CEntry<'ctx>,
+        args: Vec<ast::Expr>,
+    ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, String> {
+        if args.len() != c_entry.entry.parameters.len() {
+            return Err(format!(
+                "argument mismatch trying to create a call to function {}",
+                fn_name
+            ));
+        }
+
+        let mut c_args: Vec<BasicMetadataValueEnum<'ctx>> = vec![];
+        for (index, (arg, descriptor)) in args
+            .into_iter()
+            .zip(c_entry.entry.parameters.iter())
+            .enumerate()
+        {
+            c_args.push(self.generate_c_argument(fn_name, index + 1, arg, *descriptor)?);
+        }
+
+        let call = if c_entry.function.get_type() == c_entry.function_type {
+            self.builder.build_call(c_entry.function, &c_args, fn_name)
+        } else {
+            let function_pointer = self
+                .builder
+                .build_pointer_cast(
+                    c_entry.function.as_global_value().as_pointer_value(),
+                    c_entry.function_type.ptr_type(AddressSpace::default()),
+                    "c_function",
+                )
+                .unwrap();
+            self.builder.build_indirect_call(
+                c_entry.function_type,
+                function_pointer,
+                &c_args,
+                fn_name,
+            )
+        }
+        .map_err(|err| {
+            format!(
+                "Error trying to build a call to function {}: {}",
+                fn_name, err
+            )
+        })?;
+
+        let result = match call.try_as_basic_value().left() {
+            Some(result) => result,
+            None => return Ok(self.ret_zero()),
+        };
+        match c_entry.entry.returns {
+            Some(Descriptor::FixedBinary(_precision)) => {
+                //a FIXED BINARY result is an integer, held exactly
+                let fixed = self
+                    .builder
+                    .build_int_s_extend(
+                        result.into_int_value(),
+                        self.type_module.fixed_type,
+                        "c_result",
+                    )
+                    .unwrap();
+                Ok(Box::new(fixed))
+            }
+            Some(Descriptor::Data(Type::FixedDecimal(precision, scale))) => {
+                let fixed =
+                    self.float_value_to_fixed_decimal(result.into_float_value(), precision, scale);
+                Ok(Box::new(fixed.value))
+            }
+            Some(Descriptor::Data(Type::Float(precision))) => {
+                let float =
+                    PLIFloatDecimalValue::from_float(result.into_float_value(), precision, self);
+                Ok(Box::new(float.value))
+            }
+            _pointer => Ok(Box::new(result)),
+        }
+    }
+
+    unsafe fn generate_c_argument(
+        &'a self,
+        fn_name: &str,
+        position: usize,
+        arg: ast::Expr,
+        descriptor: Descriptor,
+    ) -> Result<BasicMetadataValueEnum<'ctx>, String> {
+        let arg_type = arg.get_type(self);
+        let expected = match descriptor {
+            Descriptor::FixedBinary(_precision)
+            | Descriptor::Data(Type::FixedDecimal(..))
+            | Descriptor::Data(Type::Float(_)) => {
+                if !arg_type.is_arithmetic() {
+                    "number"
+                } else {
+                    let mathable = get_mathable_type(arg.codegen(self), arg_type)?;
+                    let c_value = match get_c_type(self, descriptor) {
+                        BasicTypeEnum::IntType(int_type) => self
+                            .builder
+                            .build_float_to_signed_int(
+                                mathable.convert_to_float(self),
+                                int_type,
+                                "c_argument",
+                            )
+                            .unwrap()
+                            .as_basic_value_enum(),
+                        BasicTypeEnum::FloatType(float_type) => mathable
+                            .convert_to_float_of_type(self, float_type)
+                            .as_basic_value_enum(),
+                        _ => unreachable!("numbers are passed to C as integers or floats"),
+                    };
+                    return Ok(c_value.into());
+                }
+            }
+            Descriptor::Data(Type::Pointer) => {
+                if arg_type != Type::Pointer {
+                    "POINTER"
+                } else {
+                    let pointer = arg.codegen(self).as_any_value_enum().into_pointer_value();
+                    return Ok(pointer.into());
+                }
+            }
+            //a string can also be passed by a pointer to its characters
+            _string => {
+                if arg_type == Type::Pointer {
+                    let pointer = arg.codegen(self).as_any_value_enum().into_pointer_value();
+                    return Ok(pointer.into());
+                }
+                if !arg_type.is_character() {
+                    "string"
+                } else {
+                    let string_view = self.generate_string_view(arg.codegen(self), arg_type)?;
+                    return Ok(string_view.characters.into());
+                }
+            }
+        };
+
+        Err(get_error(&["27", &position.to_string(), fn_name, expected]))
+    }
+
+    ///Exports `procedure` to C under its name in lower case. The C function
+    ///takes its arguments by pointer, and unless the procedure returns
+    ///nothing, first a pointer to store the result in.
+    pub fn generate_c_export(
+        &self,
+        procedure: FunctionValue<'ctx>,
+        name: &str,
+        parameters: &[ast::PrototypeArgument],
+        return_type: Type,
+    ) -> Result<(), String> {
+        let c_name = name.to_lowercase();
+        if self.module.get_function(&c_name).is_some() {
+            return Err(get_error(&["29", name, &c_name]));
+        }
+
+        let returns_value = return_type != Type::Void;
+        let mut c_types = parameters
+            .iter()
+            .map(|parameter| parameter._type)
+            .collect::<Vec<_>>();
+        if returns_value {
+            c_types.insert(0, return_type);
+        }
+        let parameter_types: Vec<BasicMetadataTypeEnum> = c_types
+            .into_iter()
+            .map(|_type| {
+                self.convert_plick_type_to_llvm_basic_type(_type)
+                    .ptr_type(AddressSpace::default())
+                    .into()
+            })
+            .collect();
+        let is_variable_num_of_args = false;
+        let function_type = self
+            .context
+            .void_type()
+            .fn_type(&parameter_types, is_variable_num_of_args);
+        let c_function = self
+            .module
+            .add_function(&c_name, function_type, Some(Linkage::External));
+
+        //the C function has no debug info of its own
+        let debug_location = self.builder.get_current_debug_location();
+        self.builder.unset_current_debug_location();
+        let entry_block = self.context.append_basic_block(c_function, "entry");
+        self.builder.position_at_end(entry_block);
+
+        let mut c_parameters = c_function.get_param_iter();
+        let result_pointer = match returns_value {
+            true => c_parameters.next(),
+            false => None,
+        };
+        let mut args: Vec<BasicMetadataValueEnum<'ctx>> = vec![];
+        for (c_parameter, parameter) in c_parameters.zip(parameters) {
+            let arg = self
+                .builder
+                .build_load(c_parameter.into_pointer_value(), &parameter.name)
+                .unwrap();
+            args.push(arg.into());
+        }
+        let call = self
+            .builder
+            .build_call(procedure, &args, name)
+            .map_err(|err| format!("Error trying to export {} to C: {}", name, err))?;
+        if let (Some(pointer), Some(result)) = (result_pointer, call.try_as_basic_value().left()) {
+            self.builder
+                .build_store(pointer.into_pointer_value(), result)
+                .unwrap();
+        }
+        self.builder.build_return(None).unwrap();
+
+        if let Some(location) = debug_location {
+            self.builder.set_current_debug_location(location);
+        }
+
+        self.exported_procedures
+            .borrow_mut()
+            .push(ExportedProcedure {
+                c_name,
+                parameters: parameters.to_vec(),
+                return_type,
+            });
+        Ok(())
+    }
+}
@@ -1,12 +1,22 @@
 use inkwell::values::PointerValue;
 
-use crate::types::Type;
+use crate::{
+    ast::{Entry, StorageClass},
+    types::Type,
+};
 
 #[derive(Debug, Clone)]
 pub struct NamedValue<'ctx> {
     pub name: String,
     pub _type: Type,
+    ///BASED and CONTROLLED variables have no storage of their own, so this
+    ///is a null pointer of the right type and the storage is found at runtime.
     pub pointer: PointerValue<'ctx>,
+    pub storage_class: StorageClass,
+    ///The number of elements when the variable is an array of `_type`.
+    pub dimension: Option<u32>,
+    ///The parameters and result of the procedures an ENTRY variable holds.
+    pub entry: Option<Entry>,
 }
 
 impl<'ctx> NamedValue<'ctx> {
@@ -15,6 +25,72 @@ impl<'ctx> NamedValue<'ctx> {
             name,
             _type,
             pointer: value,
+            storage_class: StorageClass::Automatic,
+            dimension: None,
+            entry: None,
         }
     }
+
+    pub fn new_static(name: String, _type: Type, global: PointerValue<'ctx>) -> NamedValue<'ctx> {
+        NamedValue {
+            name,
+            _type,
+            pointer: global,
+            storage_class: StorageClass::Static,
+            dimension: None,
+            entry: None,
+        }
+    }
+
+    pub fn new_external(name: String, _type: Type, global: PointerValue<'ctx>) -> NamedValue<'ctx> {
+        NamedValue {
+            name,
+            _type,
+            pointer: global,
+            storage_class: StorageClass::External,
+            dimension: None,
+            entry: None,
+        }
+    }
+
+    pub fn new_based(
+        name: String,
+        _type: Type,
+        null_pointer: PointerValue<'ctx>,
+        locator: Option<String>,
+    ) -> NamedValue<'ctx> {
+        NamedValue {
+            name,
+            _type,
+            pointer: null_pointer,
+            storage_class: StorageClass::Based { locator },
+            dimension: None,
+            entry: None,
+        }
+    }
+
+    pub fn new_controlled(
+        name: String,
+        _type: Type,
+        null_pointer: PointerValue<'ctx>,
+    ) -> NamedValue<'ctx> {
+        NamedValue {
+            name,
+            _type,
+            pointer: null_pointer,
+            storage_class: StorageClass::Controlled,
+            dimension: None,
+            entry: None,
+        }
+    }
+
+    pub fn with_dimension(mut self, dimension: Option<u32>) -> NamedValue<'ctx> {
+        self.dimension = dimension;
+        self
+    }
+
+    pub fn with_entry(mut self, entry: Option<Entry>) -> NamedValue<'ctx> {
+        self.entry = entry;
+        self
+    }
 }
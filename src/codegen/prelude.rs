@@ -4,11 +4,9 @@ use inkwell::{
     AddressSpace,
 };
 
-use crate::types::fixed_decimal::FixedValue;
-
-use crate::types::fixed_decimal::add_fd_print_function;
-
 use super::codegen::Compiler;
+use super::runtime::add_runtime_functions;
+use super::runtime::record::{get_seek_offset_type, SEEK_FUNCTION_NAME};
 //use crate::types::
 
 pub fn add_extern_functions<'a, 'ctx>(compiler: &mut Compiler<'a, 'ctx>) {
@@ -39,6 +37,21 @@ pub fn add_extern_functions<'a, 'ctx>(compiler: &mut Compiler<'a, 'ctx>) {
         .module
         .add_function("pow", pow_type, Some(module::Linkage::DLLImport));
 
+    //reads the number a string holds, when it is used in arithmetic
+    let string_type = compiler.context.i8_type().ptr_type(AddressSpace::default());
+    let strtod_type: FunctionType<'ctx> = double_type.fn_type(
+        &[
+            BasicMetadataTypeEnum::from(string_type),
+            BasicMetadataTypeEnum::from(string_type.ptr_type(AddressSpace::default())),
+        ],
+        false,
+    );
+
+    let _strtod_func =
+        compiler
+            .module
+            .add_function("strtod", strtod_type, Some(module::Linkage::DLLImport));
+
     let scanf_arg_type: PointerType<'ctx> =
         compiler.context.i8_type().ptr_type(AddressSpace::default());
 
@@ -52,6 +65,163 @@ pub fn add_extern_functions<'a, 'ctx>(compiler: &mut Compiler<'a, 'ctx>) {
             .module
             .add_function("scanf", scanf_type, Some(module::Linkage::DLLImport));
 
-    //fd print
-    add_fd_print_function(compiler);
+    let sprintf_type: FunctionType<'ctx> = compiler.context.i32_type().fn_type(
+        &[
+            BasicMetadataTypeEnum::from(printf_arg_type),
+            BasicMetadataTypeEnum::from(printf_arg_type),
+        ],
+        true,
+    );
+
+    let _sprintf_func =
+        compiler
+            .module
+            .add_function("sprintf", sprintf_type, Some(module::Linkage::DLLImport));
+
+    //FILE* handles are passed around as i8 pointers.
+    let file_handle_type: PointerType<'ctx> =
+        compiler.context.i8_type().ptr_type(AddressSpace::default());
+
+    let fopen_type: FunctionType<'ctx> = file_handle_type.fn_type(
+        &[
+            BasicMetadataTypeEnum::from(printf_arg_type),
+            BasicMetadataTypeEnum::from(printf_arg_type),
+        ],
+        false,
+    );
+
+    let _fopen_func =
+        compiler
+            .module
+            .add_function("fopen", fopen_type, Some(module::Linkage::DLLImport));
+
+    let fclose_type: FunctionType<'ctx> = compiler
+        .context
+        .i32_type()
+        .fn_type(&[BasicMetadataTypeEnum::from(file_handle_type)], false);
+
+    let _fclose_func =
+        compiler
+            .module
+            .add_function("fclose", fclose_type, Some(module::Linkage::DLLImport));
+
+    let file_format_args = [
+        BasicMetadataTypeEnum::from(file_handle_type),
+        BasicMetadataTypeEnum::from(printf_arg_type),
+    ];
+
+    let fprintf_type: FunctionType<'ctx> =
+        compiler.context.i32_type().fn_type(&file_format_args, true);
+
+    let _fprintf_func =
+        compiler
+            .module
+            .add_function("fprintf", fprintf_type, Some(module::Linkage::DLLImport));
+
+    let fscanf_type: FunctionType<'ctx> =
+        compiler.context.i32_type().fn_type(&file_format_args, true);
+
+    let _fscanf_func =
+        compiler
+            .module
+            .add_function("fscanf", fscanf_type, Some(module::Linkage::DLLImport));
+
+    let size_type = compiler.context.i64_type();
+
+    let fread_type: FunctionType<'ctx> = size_type.fn_type(
+        &[
+            BasicMetadataTypeEnum::from(printf_arg_type),
+            BasicMetadataTypeEnum::from(size_type),
+            BasicMetadataTypeEnum::from(size_type),
+            BasicMetadataTypeEnum::from(file_handle_type),
+        ],
+        false,
+    );
+
+    let _fread_func =
+        compiler
+            .module
+            .add_function("fread", fread_type, Some(module::Linkage::DLLImport));
+
+    let _fwrite_func =
+        compiler
+            .module
+            .add_function("fwrite", fread_type, Some(module::Linkage::DLLImport));
+
+    let fseek_type: FunctionType<'ctx> = compiler.context.i32_type().fn_type(
+        &[
+            BasicMetadataTypeEnum::from(file_handle_type),
+            BasicMetadataTypeEnum::from(get_seek_offset_type(compiler.context)),
+            BasicMetadataTypeEnum::from(compiler.context.i32_type()),
+        ],
+        false,
+    );
+
+    let _fseek_func = compiler.module.add_function(
+        SEEK_FUNCTION_NAME,
+        fseek_type,
+        Some(module::Linkage::DLLImport),
+    );
+
+    let strncmp_type: FunctionType<'ctx> = compiler.context.i32_type().fn_type(
+        &[
+            BasicMetadataTypeEnum::from(printf_arg_type),
+            BasicMetadataTypeEnum::from(printf_arg_type),
+            BasicMetadataTypeEnum::from(size_type),
+        ],
+        false,
+    );
+
+    let _strncmp_func =
+        compiler
+            .module
+            .add_function("strncmp", strncmp_type, Some(module::Linkage::DLLImport));
+
+    let strncpy_type: FunctionType<'ctx> = printf_arg_type.fn_type(
+        &[
+            BasicMetadataTypeEnum::from(printf_arg_type),
+            BasicMetadataTypeEnum::from(printf_arg_type),
+            BasicMetadataTypeEnum::from(size_type),
+        ],
+        false,
+    );
+
+    let _strncpy_func =
+        compiler
+            .module
+            .add_function("strncpy", strncpy_type, Some(module::Linkage::DLLImport));
+
+    let strlen_type: FunctionType<'ctx> =
+        size_type.fn_type(&[BasicMetadataTypeEnum::from(printf_arg_type)], false);
+
+    let _strlen_func =
+        compiler
+            .module
+            .add_function("strlen", strlen_type, Some(module::Linkage::DLLImport));
+
+    let memcmp_type: FunctionType<'ctx> = compiler.context.i32_type().fn_type(
+        &[
+            BasicMetadataTypeEnum::from(printf_arg_type),
+            BasicMetadataTypeEnum::from(printf_arg_type),
+            BasicMetadataTypeEnum::from(size_type),
+        ],
+        false,
+    );
+
+    let _memcmp_func =
+        compiler
+            .module
+            .add_function("memcmp", memcmp_type, Some(module::Linkage::DLLImport));
+
+    let exit_type: FunctionType<'ctx> = compiler.context.void_type().fn_type(
+        &[BasicMetadataTypeEnum::from(compiler.context.i32_type())],
+        false,
+    );
+
+    let _exit_func =
+        compiler
+            .module
+            .add_function("exit", exit_type, Some(module::Linkage::DLLImport));
+
+    add_runtime_functions(compiler);
 }
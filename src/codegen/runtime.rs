@@ -0,0 +1,41 @@
+use super::codegen::Compiler;
+
+///The runtime holds the support routines that compiled programs call into.
+///They are generated as internal LLVM functions in every module, so the
+///output object files only need the C standard library to link.
+pub mod stream;
+
+///Record files hold the storage of each record back to back.
+///KEYED files keep every record in a slot instead: one byte that is set to 0
+///once the record is deleted, the key padded with zeros to KEY_LENGTH bytes,
+///then the record itself.
+pub mod record;
+
+///Support for CHARACTER strings.
+pub mod character;
+
+///CONTROLLED variables keep a stack of generations on the heap. A global
+///for each variable points to the newest one.
+pub mod controlled;
+
+///The return code of the program, which PLIRETC sets, main returns, and
+///STOP and EXIT end the program with.
+pub mod program;
+
+///Exact FIXED DECIMAL arithmetic on the scaled integers the values are
+///stored as. The arithmetic functions report FIXEDOVERFLOW and ZERODIVIDE
+///instead of losing digits.
+pub mod decimal;
+
+///Printing FLOAT values wider than a double, which printf can't print
+///with all their digits. These functions are only generated in modules
+///printing such a value.
+pub mod float;
+
+pub fn add_runtime_functions<'a, 'ctx>(compiler: &mut Compiler<'a, 'ctx>) {
+    stream::add_stream_runtime(compiler);
+    record::add_record_runtime(compiler);
+    character::add_character_runtime(compiler);
+    controlled::add_controlled_runtime(compiler);
+    decimal::add_decimal_runtime(compiler);
+}
@@ -0,0 +1,181 @@
+use inkwell::{values::IntValue, AddressSpace, IntPredicate};
+
+use super::{decimal::add_raise_function, stream::create_runtime_function_of_type};
+use crate::codegen::codegen::Compiler;
+
+pub const COMPARE_STRINGS_FUNCTION_NAME: &str = "plick_compare_strings";
+pub const RAISE_CONVERSION_FUNCTION_NAME: &str = "plick_raise_conversion";
+
+const BLANK_ASCII_CODE: u64 = 32;
+
+pub fn add_character_runtime<'a, 'ctx>(compiler: &mut Compiler<'a, 'ctx>) {
+    let current_bb = compiler.builder.get_insert_block().unwrap();
+
+    add_compare_strings_function(compiler);
+    add_raise_function(
+        compiler,
+        RAISE_CONVERSION_FUNCTION_NAME,
+        "CONVERSION condition raised\n",
+    );
+
+    compiler.builder.position_at_end(current_bb);
+}
+
+///plick_compare_strings(left, left_length, right, right_length): returns a negative number,
+///zero or a positive number when left is less than, equal to or greater than right.
+///The shorter string is compared as if it was padded with blanks.
+fn add_compare_strings_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let i8_type = compiler.context.i8_type();
+    let i8_ptr_type = i8_type.ptr_type(AddressSpace::default());
+    let i32_type = compiler.context.i32_type();
+    let function_type = i32_type.fn_type(
+        &[
+            i8_ptr_type.into(),
+            i32_type.into(),
+            i8_ptr_type.into(),
+            i32_type.into(),
+        ],
+        false,
+    );
+    let function =
+        create_runtime_function_of_type(compiler, COMPARE_STRINGS_FUNCTION_NAME, function_type);
+    let left = function.get_first_param().unwrap().into_pointer_value();
+    let left_length = function.get_nth_param(1).unwrap().into_int_value();
+    let right = function.get_nth_param(2).unwrap().into_pointer_value();
+    let right_length = function.get_nth_param(3).unwrap().into_int_value();
+    let builder = compiler.builder;
+
+    let differs_block = compiler.context.append_basic_block(function, "differs");
+    let padding_block = compiler.context.append_basic_block(function, "padding");
+    let loop_block = compiler
+        .context
+        .append_basic_block(function, "padding_loop");
+    let check_block = compiler
+        .context
+        .append_basic_block(function, "padding_check");
+    let next_block = compiler
+        .context
+        .append_basic_block(function, "padding_next");
+    let found_block = compiler
+        .context
+        .append_basic_block(function, "padding_differs");
+    let equal_block = compiler.context.append_basic_block(function, "equal");
+
+    let left_is_longer = builder
+        .build_int_compare(
+            IntPredicate::SGT,
+            left_length,
+            right_length,
+            "left_is_longer",
+        )
+        .unwrap();
+    let common_length = select_int(compiler, left_is_longer, right_length, left_length);
+    let longer_length = select_int(compiler, left_is_longer, left_length, right_length);
+    let common_length_as_i64 = builder
+        .build_int_z_extend(common_length, compiler.context.i64_type(), "common_length")
+        .unwrap();
+    let comparison = builder
+        .build_call(
+            compiler.module.get_function("memcmp").unwrap(),
+            &[left.into(), right.into(), common_length_as_i64.into()],
+            "memcmp",
+        )
+        .unwrap()
+        .try_as_basic_value()
+        .left()
+        .unwrap()
+        .into_int_value();
+    let is_different = builder
+        .build_int_compare(
+            IntPredicate::NE,
+            comparison,
+            i32_type.const_zero(),
+            "is_different",
+        )
+        .unwrap();
+    builder
+        .build_conditional_branch(is_different, differs_block, padding_block)
+        .unwrap();
+
+    builder.position_at_end(differs_block);
+    builder.build_return(Some(&comparison)).unwrap();
+
+    //the rest of the longer string is compared with blanks
+    builder.position_at_end(padding_block);
+    let longer = builder
+        .build_select(left_is_longer, left, right, "longer")
+        .unwrap()
+        .into_pointer_value();
+    builder.build_unconditional_branch(loop_block).unwrap();
+
+    builder.position_at_end(loop_block);
+    let index = builder.build_phi(i32_type, "index").unwrap();
+    index.add_incoming(&[(&common_length, padding_block)]);
+    let index_value = index.as_basic_value().into_int_value();
+    let is_done = builder
+        .build_int_compare(IntPredicate::SGE, index_value, longer_length, "is_done")
+        .unwrap();
+    builder
+        .build_conditional_branch(is_done, equal_block, check_block)
+        .unwrap();
+
+    builder.position_at_end(check_block);
+    let character_ptr = unsafe {
+        builder
+            .build_gep(longer, &[index_value], "character_ptr")
+            .unwrap()
+    };
+    let character = builder
+        .build_load(character_ptr, "character")
+        .unwrap()
+        .into_int_value();
+    let character = builder
+        .build_int_z_extend(character, i32_type, "character")
+        .unwrap();
+    let difference = builder
+        .build_int_sub(
+            character,
+            i32_type.const_int(BLANK_ASCII_CODE, false),
+            "difference",
+        )
+        .unwrap();
+    let is_blank = builder
+        .build_int_compare(
+            IntPredicate::EQ,
+            difference,
+            i32_type.const_zero(),
+            "is_blank",
+        )
+        .unwrap();
+    builder
+        .build_conditional_branch(is_blank, next_block, found_block)
+        .unwrap();
+
+    builder.position_at_end(next_block);
+    let next_index = builder
+        .build_int_add(index_value, i32_type.const_int(1, false), "next_index")
+        .unwrap();
+    index.add_incoming(&[(&next_index, next_block)]);
+    builder.build_unconditional_branch(loop_block).unwrap();
+
+    builder.position_at_end(found_block);
+    let negated_difference = builder.build_int_neg(difference, "negated").unwrap();
+    let result = select_int(compiler, left_is_longer, difference, negated_difference);
+    builder.build_return(Some(&result)).unwrap();
+
+    builder.position_at_end(equal_block);
+    builder.build_return(Some(&i32_type.const_zero())).unwrap();
+}
+
+fn select_int<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    condition: IntValue<'ctx>,
+    then_value: IntValue<'ctx>,
+    else_value: IntValue<'ctx>,
+) -> IntValue<'ctx> {
+    compiler
+        .builder
+        .build_select(condition, then_value, else_value, "selected")
+        .unwrap()
+        .into_int_value()
+}
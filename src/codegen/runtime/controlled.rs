@@ -0,0 +1,244 @@
+use inkwell::{
+    module::Linkage,
+    types::{BasicType, BasicTypeEnum, StructType},
+    values::PointerValue,
+    AddressSpace,
+};
+
+use super::stream::{
+    build_runtime_error, create_runtime_function, create_runtime_function_of_type,
+};
+use crate::codegen::codegen::Compiler;
+
+pub const CONTROLLED_PUSH_FUNCTION_NAME: &str = "plick_controlled_push";
+pub const CONTROLLED_POP_FUNCTION_NAME: &str = "plick_controlled_pop";
+pub const CONTROLLED_GENERATION_FUNCTION_NAME: &str = "plick_controlled_generation";
+pub const CONTROLLED_ALLOCATION_FUNCTION_NAME: &str = "plick_controlled_allocation";
+
+///The field of a generation holding the value of the variable.
+pub const VALUE_FIELD: u32 = 1;
+
+pub fn add_controlled_runtime<'a, 'ctx>(compiler: &mut Compiler<'a, 'ctx>) {
+    let current_bb = compiler.builder.get_insert_block().unwrap();
+
+    add_push_function(compiler);
+    add_pop_function(compiler);
+    add_generation_function(compiler);
+    add_allocation_function(compiler);
+
+    compiler.builder.position_at_end(current_bb);
+}
+
+///A generation starts with a pointer to the generation allocated before it,
+///so the runtime can walk the stack without knowing the type of the variable.
+pub fn get_generation_type<'ctx>(
+    compiler: &Compiler<'_, 'ctx>,
+    value_type: BasicTypeEnum<'ctx>,
+) -> StructType<'ctx> {
+    let i8_ptr_type = compiler.context.i8_type().ptr_type(AddressSpace::default());
+    compiler
+        .context
+        .struct_type(&[i8_ptr_type.into(), value_type], false)
+}
+
+///Returns the global pointing to the newest generation of `variable_name`,
+///adding it the first time. It is null while there are no generations.
+pub fn get_or_add_anchor<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    variable_name: &str,
+) -> PointerValue<'ctx> {
+    let anchor_name = format!("plick_controlled_{}", variable_name);
+    if let Some(anchor) = compiler.module.get_global(&anchor_name) {
+        return anchor.as_pointer_value();
+    }
+
+    let i8_ptr_type = compiler.context.i8_type().ptr_type(AddressSpace::default());
+    let anchor =
+        compiler
+            .module
+            .add_global(i8_ptr_type, Some(AddressSpace::default()), &anchor_name);
+    anchor.set_initializer(&i8_ptr_type.const_null());
+    anchor.set_linkage(Linkage::Internal);
+    anchor.as_pointer_value()
+}
+
+fn get_anchor_parameter_type<'ctx>(compiler: &Compiler<'_, 'ctx>) -> BasicTypeEnum<'ctx> {
+    compiler
+        .context
+        .i8_type()
+        .ptr_type(AddressSpace::default())
+        .ptr_type(AddressSpace::default())
+        .as_basic_type_enum()
+}
+
+///Casts a generation to a pointer to the field holding the previous one.
+fn build_previous_pointer<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    generation: PointerValue<'ctx>,
+) -> PointerValue<'ctx> {
+    let i8_ptr_ptr_type = get_anchor_parameter_type(compiler).into_pointer_type();
+    compiler
+        .builder
+        .build_pointer_cast(generation, i8_ptr_ptr_type, "previous_ptr")
+        .unwrap()
+}
+
+///Ends the program when `generation` is null. The builder is left in the
+///block where it is not.
+fn build_generation_check<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    generation: PointerValue<'ctx>,
+    name: PointerValue<'ctx>,
+    message: &str,
+) {
+    let builder = compiler.builder;
+    let function = builder.get_insert_block().unwrap().get_parent().unwrap();
+    let missing_block = compiler.context.append_basic_block(function, "missing");
+    let found_block = compiler.context.append_basic_block(function, "found");
+
+    let is_missing = builder.build_is_null(generation, "is_missing").unwrap();
+    builder
+        .build_conditional_branch(is_missing, missing_block, found_block)
+        .unwrap();
+
+    builder.position_at_end(missing_block);
+    build_runtime_error(compiler, message, name);
+
+    builder.position_at_end(found_block);
+}
+
+///plick_controlled_push(anchor, generation): makes `generation` the newest one.
+fn add_push_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let i8_ptr_type = compiler.context.i8_type().ptr_type(AddressSpace::default());
+    let function = create_runtime_function(
+        compiler,
+        CONTROLLED_PUSH_FUNCTION_NAME,
+        &[
+            get_anchor_parameter_type(compiler).into(),
+            i8_ptr_type.into(),
+        ],
+    );
+    let anchor = function.get_first_param().unwrap().into_pointer_value();
+    let generation = function.get_nth_param(1).unwrap().into_pointer_value();
+    let builder = compiler.builder;
+
+    let previous = builder.build_load(anchor, "previous").unwrap();
+    let previous_ptr = build_previous_pointer(compiler, generation);
+    builder.build_store(previous_ptr, previous).unwrap();
+    builder.build_store(anchor, generation).unwrap();
+    builder.build_return(None).unwrap();
+}
+
+///plick_controlled_pop(anchor, name): frees the newest generation, the one
+///before it becomes the newest again.
+fn add_pop_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let i8_ptr_type = compiler.context.i8_type().ptr_type(AddressSpace::default());
+    let function = create_runtime_function(
+        compiler,
+        CONTROLLED_POP_FUNCTION_NAME,
+        &[
+            get_anchor_parameter_type(compiler).into(),
+            i8_ptr_type.into(),
+        ],
+    );
+    let anchor = function.get_first_param().unwrap().into_pointer_value();
+    let name = function.get_nth_param(1).unwrap().into_pointer_value();
+    let builder = compiler.builder;
+
+    let generation = builder
+        .build_load(anchor, "generation")
+        .unwrap()
+        .into_pointer_value();
+    build_generation_check(
+        compiler,
+        generation,
+        name,
+        "FREE of CONTROLLED variable %s, which has no generation\n",
+    );
+
+    let previous_ptr = build_previous_pointer(compiler, generation);
+    let previous = builder.build_load(previous_ptr, "previous").unwrap();
+    builder.build_store(anchor, previous).unwrap();
+    builder.build_free(generation).unwrap();
+    builder.build_return(None).unwrap();
+}
+
+///plick_controlled_generation(anchor, name): returns the newest generation,
+///ending the program when there is none.
+fn add_generation_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let i8_ptr_type = compiler.context.i8_type().ptr_type(AddressSpace::default());
+    let function_type = i8_ptr_type.fn_type(
+        &[
+            get_anchor_parameter_type(compiler).into(),
+            i8_ptr_type.into(),
+        ],
+        false,
+    );
+    let function = create_runtime_function_of_type(
+        compiler,
+        CONTROLLED_GENERATION_FUNCTION_NAME,
+        function_type,
+    );
+    let anchor = function.get_first_param().unwrap().into_pointer_value();
+    let name = function.get_nth_param(1).unwrap().into_pointer_value();
+    let builder = compiler.builder;
+
+    let generation = builder
+        .build_load(anchor, "generation")
+        .unwrap()
+        .into_pointer_value();
+    build_generation_check(
+        compiler,
+        generation,
+        name,
+        "CONTROLLED variable %s was referenced, but it has no generation\n",
+    );
+    builder.build_return(Some(&generation)).unwrap();
+}
+
+///plick_controlled_allocation(anchor): counts the generations.
+fn add_allocation_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let i8_ptr_type = compiler.context.i8_type().ptr_type(AddressSpace::default());
+    let i32_type = compiler.context.i32_type();
+    let function_type = i32_type.fn_type(&[get_anchor_parameter_type(compiler).into()], false);
+    let function = create_runtime_function_of_type(
+        compiler,
+        CONTROLLED_ALLOCATION_FUNCTION_NAME,
+        function_type,
+    );
+    let anchor = function.get_first_param().unwrap().into_pointer_value();
+    let builder = compiler.builder;
+    let entry_block = builder.get_insert_block().unwrap();
+
+    let loop_block = compiler.context.append_basic_block(function, "count_loop");
+    let next_block = compiler.context.append_basic_block(function, "count_next");
+    let done_block = compiler.context.append_basic_block(function, "count_done");
+
+    let newest = builder.build_load(anchor, "newest").unwrap();
+    builder.build_unconditional_branch(loop_block).unwrap();
+
+    builder.position_at_end(loop_block);
+    let generation = builder.build_phi(i8_ptr_type, "generation").unwrap();
+    let count = builder.build_phi(i32_type, "count").unwrap();
+    generation.add_incoming(&[(&newest, entry_block)]);
+    count.add_incoming(&[(&i32_type.const_zero(), entry_block)]);
+    let generation_value = generation.as_basic_value().into_pointer_value();
+    let count_value = count.as_basic_value().into_int_value();
+    let is_done = builder.build_is_null(generation_value, "is_done").unwrap();
+    builder
+        .build_conditional_branch(is_done, done_block, next_block)
+        .unwrap();
+
+    builder.position_at_end(next_block);
+    let previous_ptr = build_previous_pointer(compiler, generation_value);
+    let previous = builder.build_load(previous_ptr, "previous").unwrap();
+    let next_count = builder
+        .build_int_add(count_value, i32_type.const_int(1, false), "next_count")
+        .unwrap();
+    generation.add_incoming(&[(&previous, next_block)]);
+    count.add_incoming(&[(&next_count, next_block)]);
+    builder.build_unconditional_branch(loop_block).unwrap();
+
+    builder.position_at_end(done_block);
+    builder.build_return(Some(&count_value)).unwrap();
+}
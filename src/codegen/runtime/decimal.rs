@@ -0,0 +1,503 @@
+use inkwell::{
+    types::{BasicMetadataTypeEnum, BasicType, StructType},
+    values::{FunctionValue, IntValue, PointerValue},
+    AddressSpace, IntPredicate,
+};
+
+use super::stream::{build_printf, create_runtime_function, create_runtime_function_of_type};
+use crate::{
+    codegen::codegen::Compiler,
+    types::fixed_decimal::{
+        const_fixed,
+        packed::{PACKED_ALTERNATE_MINUS_SIGN, PACKED_LENGTH, PACKED_MINUS_SIGN, PACKED_PLUS_SIGN},
+    },
+};
+
+pub const FIXED_ADD_FUNCTION_NAME: &str = "plick_fixed_add";
+pub const FIXED_MULTIPLY_FUNCTION_NAME: &str = "plick_fixed_multiply";
+pub const FIXED_DIVIDE_FUNCTION_NAME: &str = "plick_fixed_divide";
+pub const RAISE_FIXEDOVERFLOW_FUNCTION_NAME: &str = "plick_raise_fixedoverflow";
+pub const RAISE_ZERODIVIDE_FUNCTION_NAME: &str = "plick_raise_zerodivide";
+pub const FIXED_TO_PACKED_FUNCTION_NAME: &str = "plick_fixed_to_packed";
+pub const PACKED_TO_FIXED_FUNCTION_NAME: &str = "plick_packed_to_fixed";
+
+///The arithmetic functions return the result and one of these.
+pub const DECIMAL_OK: u64 = 0;
+pub const DECIMAL_FIXEDOVERFLOW: u64 = 1;
+pub const DECIMAL_ZERODIVIDE: u64 = 2;
+
+///The field of the result of an arithmetic function holding the scaled
+///value, and the one holding the status.
+pub const SCALED_FIELD: u32 = 0;
+pub const STATUS_FIELD: u32 = 1;
+
+pub fn add_decimal_runtime<'a, 'ctx>(compiler: &mut Compiler<'a, 'ctx>) {
+    let current_bb = compiler.builder.get_insert_block().unwrap();
+
+    add_fixed_add_function(compiler);
+    add_fixed_multiply_function(compiler);
+    add_fixed_divide_function(compiler);
+    add_raise_function(
+        compiler,
+        RAISE_FIXEDOVERFLOW_FUNCTION_NAME,
+        "FIXEDOVERFLOW condition raised\n",
+    );
+    add_raise_function(
+        compiler,
+        RAISE_ZERODIVIDE_FUNCTION_NAME,
+        "ZERODIVIDE condition raised\n",
+    );
+    add_fixed_to_packed_function(compiler);
+    add_packed_to_fixed_function(compiler);
+
+    compiler.builder.position_at_end(current_bb);
+}
+
+///The result of an arithmetic function: the scaled value and a status.
+pub fn get_scaled_result_type<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) -> StructType<'ctx> {
+    compiler.context.struct_type(
+        &[
+            compiler.type_module.fixed_type.as_basic_type_enum(),
+            compiler.context.i8_type().as_basic_type_enum(),
+        ],
+        false,
+    )
+}
+
+///Returns the magnitude of `value` and whether it is negative.
+fn build_magnitude<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    value: IntValue<'ctx>,
+) -> (IntValue<'ctx>, IntValue<'ctx>) {
+    let builder = compiler.builder;
+    let zero = value.get_type().const_zero();
+    let is_negative = builder
+        .build_int_compare(IntPredicate::SLT, value, zero, "is_negative")
+        .unwrap();
+    let negated = builder.build_int_sub(zero, value, "negated").unwrap();
+    let magnitude = builder
+        .build_select(is_negative, negated, value, "magnitude")
+        .unwrap()
+        .into_int_value();
+    (magnitude, is_negative)
+}
+
+///`status`, or FIXEDOVERFLOW when `condition` holds.
+fn build_overflow_status<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    condition: IntValue<'ctx>,
+    status: IntValue<'ctx>,
+) -> IntValue<'ctx> {
+    compiler
+        .builder
+        .build_select(
+            condition,
+            compiler
+                .context
+                .i8_type()
+                .const_int(DECIMAL_FIXEDOVERFLOW, false),
+            status,
+            "status",
+        )
+        .unwrap()
+        .into_int_value()
+}
+
+///`status`, or FIXEDOVERFLOW when `magnitude` isn't below `limit`, 10 to
+///the power of the precision of the result.
+fn build_limit_status<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    magnitude: IntValue<'ctx>,
+    limit: IntValue<'ctx>,
+    status: IntValue<'ctx>,
+) -> IntValue<'ctx> {
+    let is_overflow = compiler
+        .builder
+        .build_int_compare(IntPredicate::UGE, magnitude, limit, "is_overflow")
+        .unwrap();
+    build_overflow_status(compiler, is_overflow, status)
+}
+
+///Gives `magnitude` the sign, and returns it with `status`.
+fn build_return_scaled<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    magnitude: IntValue<'ctx>,
+    is_negative: IntValue<'ctx>,
+    status: IntValue<'ctx>,
+) {
+    let builder = compiler.builder;
+    let negated = builder
+        .build_int_sub(magnitude.get_type().const_zero(), magnitude, "negated")
+        .unwrap();
+    let scaled = builder
+        .build_select(is_negative, negated, magnitude, "scaled")
+        .unwrap();
+
+    let result_type = get_scaled_result_type(compiler);
+    let result = builder
+        .build_insert_value(result_type.get_undef(), scaled, SCALED_FIELD, "result")
+        .unwrap();
+    let result = builder
+        .build_insert_value(result, status, STATUS_FIELD, "result")
+        .unwrap();
+    builder
+        .build_return(Some(&result.into_struct_value()))
+        .unwrap();
+}
+
+fn create_arithmetic_function<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    name: &str,
+    param_types: &[BasicMetadataTypeEnum<'ctx>],
+) -> FunctionValue<'ctx> {
+    let function_type = get_scaled_result_type(compiler).fn_type(param_types, false);
+    create_runtime_function_of_type(compiler, name, function_type)
+}
+
+///plick_fixed_add(a, b, limit): a + b, of operands already counting in
+///units of the same digit. Subtracting is adding the negated value.
+fn add_fixed_add_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let scaled_type = compiler.type_module.fixed_type;
+    let function = create_arithmetic_function(
+        compiler,
+        FIXED_ADD_FUNCTION_NAME,
+        &[scaled_type.into(), scaled_type.into(), scaled_type.into()],
+    );
+    let lhs = function.get_nth_param(0).unwrap().into_int_value();
+    let rhs = function.get_nth_param(1).unwrap().into_int_value();
+    let limit = function.get_nth_param(2).unwrap().into_int_value();
+
+    //aligned operands have at most ALIGNMENT_DIGIT_COUNT digits, so the sum
+    //can't wrap
+    let sum = compiler.builder.build_int_add(lhs, rhs, "sum").unwrap();
+    let (magnitude, is_negative) = build_magnitude(compiler, sum);
+    let status = compiler.context.i8_type().const_int(DECIMAL_OK, false);
+    let status = build_limit_status(compiler, magnitude, limit, status);
+    build_return_scaled(compiler, magnitude, is_negative, status);
+}
+
+///plick_fixed_multiply(a, b, limit): a * b, counting in units of the
+///product of the units of a and b.
+fn add_fixed_multiply_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let builder = compiler.builder;
+    let scaled_type = compiler.type_module.fixed_type;
+    let function = create_arithmetic_function(
+        compiler,
+        FIXED_MULTIPLY_FUNCTION_NAME,
+        &[scaled_type.into(), scaled_type.into(), scaled_type.into()],
+    );
+    let (lhs, lhs_is_negative) = build_magnitude(
+        compiler,
+        function.get_nth_param(0).unwrap().into_int_value(),
+    );
+    let (rhs, rhs_is_negative) = build_magnitude(
+        compiler,
+        function.get_nth_param(1).unwrap().into_int_value(),
+    );
+    let limit = function.get_nth_param(2).unwrap().into_int_value();
+    let is_negative = builder
+        .build_xor(lhs_is_negative, rhs_is_negative, "is_negative")
+        .unwrap();
+
+    //the product wraps when a is larger than the largest value over b
+    let is_zero = builder
+        .build_int_compare(IntPredicate::EQ, rhs, scaled_type.const_zero(), "is_zero")
+        .unwrap();
+    let divisor = builder
+        .build_select(is_zero, scaled_type.const_int(1, false), rhs, "divisor")
+        .unwrap()
+        .into_int_value();
+    let largest_lhs = builder
+        .build_int_unsigned_div(const_fixed(scaled_type, i128::MAX), divisor, "largest_lhs")
+        .unwrap();
+    let is_wrapping = builder
+        .build_int_compare(IntPredicate::UGT, lhs, largest_lhs, "is_wrapping")
+        .unwrap();
+
+    let product = builder.build_int_mul(lhs, rhs, "product").unwrap();
+    let status = compiler.context.i8_type().const_int(DECIMAL_OK, false);
+    let status = build_overflow_status(compiler, is_wrapping, status);
+    let status = build_limit_status(compiler, product, limit, status);
+    build_return_scaled(compiler, product, is_negative, status);
+}
+
+///plick_fixed_divide(a, b, digits, whole_limit): a / b, with `digits` more
+///digits after the point than a over b has, worked out by long division,
+///which cuts off the ones after them. The whole quotient of a over b has to
+///be below whole_limit.
+fn add_fixed_divide_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let builder = compiler.builder;
+    let i8_type = compiler.context.i8_type();
+    let i32_type = compiler.context.i32_type();
+    let scaled_type = compiler.type_module.fixed_type;
+    let function = create_arithmetic_function(
+        compiler,
+        FIXED_DIVIDE_FUNCTION_NAME,
+        &[
+            scaled_type.into(),
+            scaled_type.into(),
+            i32_type.into(),
+            scaled_type.into(),
+        ],
+    );
+    let (lhs, lhs_is_negative) = build_magnitude(
+        compiler,
+        function.get_nth_param(0).unwrap().into_int_value(),
+    );
+    let (rhs, rhs_is_negative) = build_magnitude(
+        compiler,
+        function.get_nth_param(1).unwrap().into_int_value(),
+    );
+    let digits = function.get_nth_param(2).unwrap().into_int_value();
+    let whole_limit = function.get_nth_param(3).unwrap().into_int_value();
+    let is_negative = builder
+        .build_xor(lhs_is_negative, rhs_is_negative, "is_negative")
+        .unwrap();
+
+    //dividing by one instead of zero keeps the division defined
+    let is_zero_divide = builder
+        .build_int_compare(
+            IntPredicate::EQ,
+            rhs,
+            scaled_type.const_zero(),
+            "is_zero_divide",
+        )
+        .unwrap();
+    let divisor = builder
+        .build_select(
+            is_zero_divide,
+            scaled_type.const_int(1, false),
+            rhs,
+            "divisor",
+        )
+        .unwrap()
+        .into_int_value();
+
+    let whole_quotient = builder
+        .build_int_unsigned_div(lhs, divisor, "whole_quotient")
+        .unwrap();
+    let whole_remainder = builder
+        .build_int_unsigned_rem(lhs, divisor, "remainder")
+        .unwrap();
+    let status = i8_type.const_int(DECIMAL_OK, false);
+    let status = build_limit_status(compiler, whole_quotient, whole_limit, status);
+    let status = builder
+        .build_select(
+            is_zero_divide,
+            i8_type.const_int(DECIMAL_ZERODIVIDE, false),
+            status,
+            "status",
+        )
+        .unwrap()
+        .into_int_value();
+
+    let entry_block = builder.get_insert_block().unwrap();
+    let loop_block = compiler.context.append_basic_block(function, "digit_loop");
+    let digit_block = compiler.context.append_basic_block(function, "next_digit");
+    let done_block = compiler.context.append_basic_block(function, "done");
+    builder.build_unconditional_branch(loop_block).unwrap();
+
+    builder.position_at_end(loop_block);
+    let index = builder.build_phi(i32_type, "index").unwrap();
+    let quotient = builder.build_phi(scaled_type, "quotient").unwrap();
+    let remainder = builder.build_phi(scaled_type, "remainder").unwrap();
+    index.add_incoming(&[(&i32_type.const_zero(), entry_block)]);
+    quotient.add_incoming(&[(&whole_quotient, entry_block)]);
+    remainder.add_incoming(&[(&whole_remainder, entry_block)]);
+    let index_value = index.as_basic_value().into_int_value();
+    let quotient_value = quotient.as_basic_value().into_int_value();
+    let remainder_value = remainder.as_basic_value().into_int_value();
+    let is_done = builder
+        .build_int_compare(IntPredicate::SGE, index_value, digits, "is_done")
+        .unwrap();
+    builder
+        .build_conditional_branch(is_done, done_block, digit_block)
+        .unwrap();
+
+    builder.position_at_end(digit_block);
+    let ten = scaled_type.const_int(10, false);
+    let dividend = builder
+        .build_int_mul(remainder_value, ten, "dividend")
+        .unwrap();
+    let digit = builder
+        .build_int_unsigned_div(dividend, divisor, "digit")
+        .unwrap();
+    let next_remainder = builder
+        .build_int_unsigned_rem(dividend, divisor, "remainder")
+        .unwrap();
+    let shifted = builder
+        .build_int_mul(quotient_value, ten, "shifted")
+        .unwrap();
+    let next_quotient = builder.build_int_add(shifted, digit, "quotient").unwrap();
+    let next_index = builder
+        .build_int_add(index_value, i32_type.const_int(1, false), "next_index")
+        .unwrap();
+    index.add_incoming(&[(&next_index, digit_block)]);
+    quotient.add_incoming(&[(&next_quotient, digit_block)]);
+    remainder.add_incoming(&[(&next_remainder, digit_block)]);
+    builder.build_unconditional_branch(loop_block).unwrap();
+
+    builder.position_at_end(done_block);
+    build_return_scaled(compiler, quotient_value, is_negative, status);
+}
+
+///What happens when a condition is raised where it has no ON-unit.
+pub(super) fn add_raise_function<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    name: &str,
+    message: &str,
+) {
+    create_runtime_function(compiler, name, &[]);
+
+    build_printf(compiler, message, &[]);
+    compiler
+        .builder
+        .build_call(
+            compiler.module.get_function("exit").unwrap(),
+            &[compiler.context.i32_type().const_int(1, false).into()],
+            "exit",
+        )
+        .unwrap();
+    compiler.builder.build_unreachable().unwrap();
+}
+
+fn get_packed_byte_pointer<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    packed: PointerValue<'ctx>,
+    index: u32,
+) -> PointerValue<'ctx> {
+    let index = compiler.context.i64_type().const_int(index as u64, false);
+    unsafe {
+        compiler
+            .builder
+            .build_gep(packed, &[index], "packed_byte")
+            .unwrap()
+    }
+}
+
+///plick_fixed_to_packed(value, packed): writes the PACKED_LENGTH bytes of
+///`value` in packed decimal, like types::fixed_decimal::packed::scaled_to_packed.
+fn add_fixed_to_packed_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let builder = compiler.builder;
+    let i8_type = compiler.context.i8_type();
+    let function = create_runtime_function(
+        compiler,
+        FIXED_TO_PACKED_FUNCTION_NAME,
+        &[
+            compiler.type_module.fixed_type.into(),
+            i8_type.ptr_type(AddressSpace::default()).into(),
+        ],
+    );
+    let (mut magnitude, is_negative) = build_magnitude(
+        compiler,
+        function.get_nth_param(0).unwrap().into_int_value(),
+    );
+    let packed = function.get_nth_param(1).unwrap().into_pointer_value();
+
+    let scaled_type = magnitude.get_type();
+    let ten = scaled_type.const_int(10, false);
+    let mut next_digit = || {
+        let digit = builder
+            .build_int_unsigned_rem(magnitude, ten, "digit")
+            .unwrap();
+        magnitude = builder
+            .build_int_unsigned_div(magnitude, ten, "magnitude")
+            .unwrap();
+        builder.build_int_truncate(digit, i8_type, "digit").unwrap()
+    };
+
+    let mut low_half = builder
+        .build_select(
+            is_negative,
+            i8_type.const_int(PACKED_MINUS_SIGN as u64, false),
+            i8_type.const_int(PACKED_PLUS_SIGN as u64, false),
+            "sign",
+        )
+        .unwrap()
+        .into_int_value();
+    for index in (0..PACKED_LENGTH).rev() {
+        let high_half = builder
+            .build_left_shift(next_digit(), i8_type.const_int(4, false), "high_half")
+            .unwrap();
+        let byte = builder.build_or(high_half, low_half, "byte").unwrap();
+        let byte_pointer = get_packed_byte_pointer(compiler, packed, index);
+        builder.build_store(byte_pointer, byte).unwrap();
+        low_half = next_digit();
+    }
+
+    builder.build_return(None).unwrap();
+}
+
+///plick_packed_to_fixed(packed): the value of PACKED_LENGTH bytes of packed
+///decimal, like types::fixed_decimal::packed::packed_to_scaled.
+fn add_packed_to_fixed_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let builder = compiler.builder;
+    let i8_type = compiler.context.i8_type();
+    let scaled_type = compiler.type_module.fixed_type;
+    let param_types: [BasicMetadataTypeEnum<'ctx>; 1] =
+        [i8_type.ptr_type(AddressSpace::default()).into()];
+    let function = create_runtime_function_of_type(
+        compiler,
+        PACKED_TO_FIXED_FUNCTION_NAME,
+        scaled_type.fn_type(&param_types, false),
+    );
+    let packed = function.get_nth_param(0).unwrap().into_pointer_value();
+
+    let ten = scaled_type.const_int(10, false);
+    let add_digit = |magnitude: IntValue<'ctx>, digit: IntValue<'ctx>| {
+        let digit = builder
+            .build_int_z_extend(digit, scaled_type, "digit")
+            .unwrap();
+        let shifted = builder.build_int_mul(magnitude, ten, "shifted").unwrap();
+        builder.build_int_add(shifted, digit, "magnitude").unwrap()
+    };
+
+    let mut magnitude = scaled_type.const_zero();
+    let mut sign = i8_type.const_zero();
+    for index in 0..PACKED_LENGTH {
+        let byte_pointer = get_packed_byte_pointer(compiler, packed, index);
+        let byte = builder
+            .build_load(byte_pointer, "byte")
+            .unwrap()
+            .into_int_value();
+        let high_half = builder
+            .build_right_shift(byte, i8_type.const_int(4, false), false, "high_half")
+            .unwrap();
+        let low_half = builder
+            .build_and(byte, i8_type.const_int(0xF, false), "low_half")
+            .unwrap();
+
+        magnitude = add_digit(magnitude, high_half);
+        match index == PACKED_LENGTH - 1 {
+            true => sign = low_half,
+            false => magnitude = add_digit(magnitude, low_half),
+        }
+    }
+
+    let is_minus = builder
+        .build_int_compare(
+            IntPredicate::EQ,
+            sign,
+            i8_type.const_int(PACKED_MINUS_SIGN as u64, false),
+            "is_minus",
+        )
+        .unwrap();
+    let is_alternate_minus = builder
+        .build_int_compare(
+            IntPredicate::EQ,
+            sign,
+            i8_type.const_int(PACKED_ALTERNATE_MINUS_SIGN as u64, false),
+            "is_alternate_minus",
+        )
+        .unwrap();
+    let is_negative = builder
+        .build_or(is_minus, is_alternate_minus, "is_negative")
+        .unwrap();
+    let negated = builder
+        .build_int_sub(scaled_type.const_zero(), magnitude, "negated")
+        .unwrap();
+    let scaled = builder
+        .build_select(is_negative, negated, magnitude, "scaled")
+        .unwrap();
+    builder.build_return(Some(&scaled)).unwrap();
+}
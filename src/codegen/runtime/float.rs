@@ -0,0 +1,381 @@
+use inkwell::{
+    types::BasicMetadataTypeEnum,
+    values::{BasicMetadataValueEnum, FloatValue, FunctionValue, IntValue},
+    AddressSpace, FloatPredicate, IntPredicate,
+};
+
+use super::stream::{create_runtime_function, create_runtime_function_of_type};
+use crate::codegen::codegen::Compiler;
+
+pub const EXTENDED_POWER_OF_TEN_FUNCTION_NAME: &str = "plick_extended_power_of_ten";
+pub const EXTENDED_TO_STRING_FUNCTION_NAME: &str = "plick_extended_to_string";
+
+///The bits of a 128 bit float below its exponent, and the bias of the
+///exponent.
+const EXTENDED_SIGNIFICAND_BITS: u64 = 112;
+const EXTENDED_EXPONENT_MASK: u64 = 0x7FFF;
+const EXTENDED_EXPONENT_BIAS: u64 = 16383;
+
+///log10(2) times 2^18, which turns a binary exponent into a decimal one.
+const LOG10_2_SCALED: u64 = 78913;
+const LOG10_2_SHIFT: u64 = 18;
+
+///The digits after the first one are printed in two pieces, the low one
+///this many digits long, as printf prints 64 bits at most.
+const LOW_DIGIT_COUNT: u32 = 16;
+
+///plick_extended_to_string, generated the first time it is needed. The
+///128 bit arithmetic it does needs the support library of the C compiler,
+///so modules not printing extended values don't link against it.
+pub fn get_extended_to_string_function<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+) -> FunctionValue<'ctx> {
+    if let Some(function) = compiler
+        .module
+        .get_function(EXTENDED_TO_STRING_FUNCTION_NAME)
+    {
+        return function;
+    }
+    //the runtime has no debug info of its own
+    let current_bb = compiler.builder.get_insert_block().unwrap();
+    let debug_location = compiler.builder.get_current_debug_location();
+    compiler.builder.unset_current_debug_location();
+
+    add_extended_power_of_ten_function(compiler);
+    add_extended_to_string_function(compiler);
+
+    compiler.builder.position_at_end(current_bb);
+    if let Some(location) = debug_location {
+        compiler.builder.set_current_debug_location(location);
+    }
+    compiler
+        .module
+        .get_function(EXTENDED_TO_STRING_FUNCTION_NAME)
+        .unwrap()
+}
+
+///plick_extended_power_of_ten(exponent): 10 to the power of a non-negative
+///`exponent`, as a 128 bit float.
+fn add_extended_power_of_ten_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let builder = compiler.builder;
+    let i32_type = compiler.context.i32_type();
+    let f128_type = compiler.context.f128_type();
+    let param_types: [BasicMetadataTypeEnum<'ctx>; 1] = [i32_type.into()];
+    let function = create_runtime_function_of_type(
+        compiler,
+        EXTENDED_POWER_OF_TEN_FUNCTION_NAME,
+        f128_type.fn_type(&param_types, false),
+    );
+    let exponent = function.get_nth_param(0).unwrap().into_int_value();
+    let entry_block = builder.get_insert_block().unwrap();
+
+    let loop_block = compiler.context.append_basic_block(function, "power_loop");
+    let square_block = compiler
+        .context
+        .append_basic_block(function, "power_square");
+    let done_block = compiler.context.append_basic_block(function, "power_done");
+    builder.build_unconditional_branch(loop_block).unwrap();
+
+    //squares the base for each bit of the exponent, multiplying the ones set
+    builder.position_at_end(loop_block);
+    let remaining = builder.build_phi(i32_type, "remaining").unwrap();
+    let base = builder.build_phi(f128_type, "base").unwrap();
+    let power = builder.build_phi(f128_type, "power").unwrap();
+    remaining.add_incoming(&[(&exponent, entry_block)]);
+    base.add_incoming(&[(&f128_type.const_float(10.0), entry_block)]);
+    power.add_incoming(&[(&f128_type.const_float(1.0), entry_block)]);
+    let remaining_value = remaining.as_basic_value().into_int_value();
+    let base_value = base.as_basic_value().into_float_value();
+    let power_value = power.as_basic_value().into_float_value();
+    let is_done = builder
+        .build_int_compare(
+            IntPredicate::SLE,
+            remaining_value,
+            i32_type.const_zero(),
+            "is_done",
+        )
+        .unwrap();
+    builder
+        .build_conditional_branch(is_done, done_block, square_block)
+        .unwrap();
+
+    builder.position_at_end(square_block);
+    let one = i32_type.const_int(1, false);
+    let bit = builder.build_and(remaining_value, one, "bit").unwrap();
+    let is_bit_set = builder
+        .build_int_compare(IntPredicate::NE, bit, i32_type.const_zero(), "is_bit_set")
+        .unwrap();
+    let multiplied = builder
+        .build_float_mul(power_value, base_value, "multiplied")
+        .unwrap();
+    let next_power = builder
+        .build_select(is_bit_set, multiplied, power_value, "next_power")
+        .unwrap();
+    let next_base = builder
+        .build_float_mul(base_value, base_value, "next_base")
+        .unwrap();
+    let next_remaining = builder
+        .build_right_shift(remaining_value, one, false, "next_remaining")
+        .unwrap();
+    remaining.add_incoming(&[(&next_remaining, square_block)]);
+    base.add_incoming(&[(&next_base, square_block)]);
+    power.add_incoming(&[(&next_power, square_block)]);
+    builder.build_unconditional_branch(loop_block).unwrap();
+
+    builder.position_at_end(done_block);
+    builder.build_return(Some(&power_value)).unwrap();
+}
+
+///plick_extended_to_string(value, digits, buffer): writes a 128 bit float
+///with `digits` significant digits, like printf's %E does for a double.
+///`digits` is more than LOW_DIGIT_COUNT.
+fn add_extended_to_string_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let builder = compiler.builder;
+    let i32_type = compiler.context.i32_type();
+    let i64_type = compiler.context.i64_type();
+    let i128_type = compiler.context.i128_type();
+    let f128_type = compiler.context.f128_type();
+    let function = create_runtime_function(
+        compiler,
+        EXTENDED_TO_STRING_FUNCTION_NAME,
+        &[
+            f128_type.into(),
+            i32_type.into(),
+            compiler
+                .context
+                .i8_type()
+                .ptr_type(AddressSpace::default())
+                .into(),
+        ],
+    );
+    let value = function.get_nth_param(0).unwrap().into_float_value();
+    let digits = function.get_nth_param(1).unwrap().into_int_value();
+    let buffer = function.get_nth_param(2).unwrap().into_pointer_value();
+
+    let power_of_ten = |exponent: IntValue<'ctx>| -> FloatValue<'ctx> {
+        builder
+            .build_call(
+                compiler
+                    .module
+                    .get_function(EXTENDED_POWER_OF_TEN_FUNCTION_NAME)
+                    .unwrap(),
+                &[exponent.into()],
+                "power_of_ten",
+            )
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_float_value()
+    };
+    let integer_power_of_ten = |exponent: IntValue<'ctx>| -> IntValue<'ctx> {
+        builder
+            .build_float_to_unsigned_int(power_of_ten(exponent), i128_type, "integer_power")
+            .unwrap()
+    };
+
+    let zero = f128_type.const_zero();
+    let is_negative = builder
+        .build_float_compare(FloatPredicate::OLT, value, zero, "is_negative")
+        .unwrap();
+    let negated = builder.build_float_neg(value, "negated").unwrap();
+    let magnitude = builder
+        .build_select(is_negative, negated, value, "magnitude")
+        .unwrap()
+        .into_float_value();
+    let is_zero = builder
+        .build_float_compare(FloatPredicate::OEQ, magnitude, zero, "is_zero")
+        .unwrap();
+
+    //the decimal exponent from the binary one, which is the exponent of the
+    //value or one less
+    let bits = builder
+        .build_bitcast(magnitude, i128_type, "bits")
+        .unwrap()
+        .into_int_value();
+    let biased = builder
+        .build_right_shift(
+            bits,
+            i128_type.const_int(EXTENDED_SIGNIFICAND_BITS, false),
+            false,
+            "biased",
+        )
+        .unwrap();
+    let biased = builder
+        .build_and(
+            biased,
+            i128_type.const_int(EXTENDED_EXPONENT_MASK, false),
+            "biased",
+        )
+        .unwrap();
+    let biased = builder
+        .build_int_truncate(biased, i32_type, "biased")
+        .unwrap();
+    let binary_exponent = builder
+        .build_int_sub(
+            biased,
+            i32_type.const_int(EXTENDED_EXPONENT_BIAS, false),
+            "binary_exponent",
+        )
+        .unwrap();
+    let exponent = builder
+        .build_int_mul(
+            binary_exponent,
+            i32_type.const_int(LOG10_2_SCALED, false),
+            "exponent",
+        )
+        .unwrap();
+    let exponent = builder
+        .build_right_shift(
+            exponent,
+            i32_type.const_int(LOG10_2_SHIFT, false),
+            true,
+            "exponent",
+        )
+        .unwrap();
+    let exponent = builder
+        .build_select(is_zero, i32_type.const_zero(), exponent, "exponent")
+        .unwrap()
+        .into_int_value();
+
+    //scales the value to an integer of `digits` digits, or one more when the
+    //exponent is one less than the value's. The power is split in two so
+    //neither half overflows for the smallest values.
+    let one = i32_type.const_int(1, false);
+    let after_point_digits = builder
+        .build_int_sub(digits, one, "after_point_digits")
+        .unwrap();
+    let shift = builder
+        .build_int_sub(after_point_digits, exponent, "shift")
+        .unwrap();
+    let is_scaled_down = builder
+        .build_int_compare(IntPredicate::SLT, shift, i32_type.const_zero(), "is_down")
+        .unwrap();
+    let negated_shift = builder.build_int_neg(shift, "negated_shift").unwrap();
+    let shift_magnitude = builder
+        .build_select(is_scaled_down, negated_shift, shift, "shift_magnitude")
+        .unwrap()
+        .into_int_value();
+    let first_half = builder
+        .build_right_shift(shift_magnitude, one, false, "first_half")
+        .unwrap();
+    let second_half = builder
+        .build_int_sub(shift_magnitude, first_half, "second_half")
+        .unwrap();
+    let first_power = power_of_ten(first_half);
+    let second_power = power_of_ten(second_half);
+    let scaled_up = builder
+        .build_float_mul(magnitude, first_power, "scaled_up")
+        .unwrap();
+    let scaled_up = builder
+        .build_float_mul(scaled_up, second_power, "scaled_up")
+        .unwrap();
+    let scaled_down = builder
+        .build_float_div(magnitude, first_power, "scaled_down")
+        .unwrap();
+    let scaled_down = builder
+        .build_float_div(scaled_down, second_power, "scaled_down")
+        .unwrap();
+    let scaled = builder
+        .build_select(is_scaled_down, scaled_down, scaled_up, "scaled")
+        .unwrap()
+        .into_float_value();
+    let rounded = builder
+        .build_float_add(scaled, f128_type.const_float(0.5), "rounded")
+        .unwrap();
+    let significand = builder
+        .build_float_to_unsigned_int(rounded, i128_type, "significand")
+        .unwrap();
+
+    //drops the extra digit, then the one rounding can carry into
+    let limit = integer_power_of_ten(digits);
+    let ten = i128_type.const_int(10, false);
+    let five = i128_type.const_int(5, false);
+    let mut significand = significand;
+    let mut exponent = exponent;
+    for _ in 0..2 {
+        let is_too_long = builder
+            .build_int_compare(IntPredicate::UGE, significand, limit, "is_too_long")
+            .unwrap();
+        let shortened = builder
+            .build_int_add(significand, five, "shortened")
+            .unwrap();
+        let shortened = builder
+            .build_int_unsigned_div(shortened, ten, "shortened")
+            .unwrap();
+        significand = builder
+            .build_select(is_too_long, shortened, significand, "significand")
+            .unwrap()
+            .into_int_value();
+        let next_exponent = builder.build_int_add(exponent, one, "exponent").unwrap();
+        exponent = builder
+            .build_select(is_too_long, next_exponent, exponent, "exponent")
+            .unwrap()
+            .into_int_value();
+    }
+
+    let after_point_divisor = integer_power_of_ten(after_point_digits);
+    let first_digit = builder
+        .build_int_unsigned_div(significand, after_point_divisor, "first_digit")
+        .unwrap();
+    let after_point = builder
+        .build_int_unsigned_rem(significand, after_point_divisor, "after_point")
+        .unwrap();
+    let low_divisor = i128_type.const_int(10u64.pow(LOW_DIGIT_COUNT), false);
+    let after_point_high = builder
+        .build_int_unsigned_div(after_point, low_divisor, "after_point_high")
+        .unwrap();
+    let after_point_low = builder
+        .build_int_unsigned_rem(after_point, low_divisor, "after_point_low")
+        .unwrap();
+    let high_digit_count = builder
+        .build_int_sub(
+            digits,
+            i32_type.const_int(LOW_DIGIT_COUNT as u64 + 1, false),
+            "high_digit_count",
+        )
+        .unwrap();
+
+    let as_u64 = |value: IntValue<'ctx>| -> BasicMetadataValueEnum<'ctx> {
+        builder
+            .build_int_truncate(value, i64_type, "printed_digits")
+            .unwrap()
+            .into()
+    };
+    let minus = builder
+        .build_global_string_ptr("-", "extended_minus")
+        .unwrap()
+        .as_pointer_value();
+    let no_sign = builder
+        .build_global_string_ptr("", "extended_no_sign")
+        .unwrap()
+        .as_pointer_value();
+    let sign = builder
+        .build_select(is_negative, minus, no_sign, "sign")
+        .unwrap();
+    let format_string = builder
+        .build_global_string_ptr(
+            &format!("%s%llu.%.*llu%0{}lluE%+03d", LOW_DIGIT_COUNT),
+            "extended_format",
+        )
+        .unwrap()
+        .as_pointer_value();
+    builder
+        .build_call(
+            compiler.module.get_function("sprintf").unwrap(),
+            &[
+                buffer.into(),
+                format_string.into(),
+                sign.into(),
+                as_u64(first_digit),
+                high_digit_count.into(),
+                as_u64(after_point_high),
+                as_u64(after_point_low),
+                exponent.into(),
+            ],
+            "extended_to_string",
+        )
+        .unwrap();
+
+    builder.build_return(None).unwrap();
+}
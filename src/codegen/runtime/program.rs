@@ -0,0 +1,72 @@
+use inkwell::module::Linkage;
+use inkwell::values::{IntValue, PointerValue};
+use inkwell::AddressSpace;
+
+use crate::codegen::{codegen::Compiler, utils::get_current_function};
+
+///The i32 main returns, and the program ends with on STOP and EXIT.
+pub const RETURN_CODE_GLOBAL_NAME: &str = "plick_return_code";
+pub const MAIN_FUNCTION_NAME: &str = "main";
+
+///Returns the global holding the return code. Every unit using it defines it
+///as a common symbol starting out as 0, so units compiled into separate objects
+///share the one return code when they are linked.
+pub fn get_or_add_return_code<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) -> PointerValue<'ctx> {
+    if let Some(return_code) = compiler.module.get_global(RETURN_CODE_GLOBAL_NAME) {
+        return return_code.as_pointer_value();
+    }
+
+    let i32_type = compiler.context.i32_type();
+    let return_code = compiler.module.add_global(
+        i32_type,
+        Some(AddressSpace::default()),
+        RETURN_CODE_GLOBAL_NAME,
+    );
+    return_code.set_linkage(Linkage::Common);
+    return_code.set_initializer(&i32_type.const_zero());
+    return_code.as_pointer_value()
+}
+
+pub fn build_load_return_code<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) -> IntValue<'ctx> {
+    compiler
+        .builder
+        .build_load(get_or_add_return_code(compiler), "return_code")
+        .unwrap()
+        .into_int_value()
+}
+
+///PLIRETC(n)
+pub fn build_store_return_code<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    return_code: IntValue<'ctx>,
+) {
+    compiler
+        .builder
+        .build_store(get_or_add_return_code(compiler), return_code)
+        .unwrap();
+}
+
+///STOP; and EXIT; end the program from any procedure, with the return code
+///set so far. exit flushes the files that are still open.
+pub fn build_stop<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let return_code = build_load_return_code(compiler);
+    compiler
+        .builder
+        .build_call(
+            compiler.module.get_function("exit").unwrap(),
+            &[return_code.into()],
+            "exit",
+        )
+        .unwrap();
+    compiler.builder.build_unreachable().unwrap();
+
+    //the statements after it can't be reached, but still need a block
+    let unreachable_block = compiler
+        .context
+        .append_basic_block(get_current_function(compiler), "afterstop");
+    compiler.builder.position_at_end(unreachable_block);
+}
+
+pub fn is_main_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) -> bool {
+    get_current_function(compiler).get_name().to_bytes() == MAIN_FUNCTION_NAME.as_bytes()
+}
@@ -0,0 +1,738 @@
+use std::{mem::size_of, os::raw::c_long};
+
+use inkwell::{
+    basic_block::BasicBlock,
+    context::Context,
+    types::IntType,
+    values::{BasicMetadataValueEnum, BasicValueEnum, FunctionValue, IntValue, PointerValue},
+    AddressSpace, IntPredicate,
+};
+
+use super::stream::{
+    self, build_has_flag, build_runtime_error, create_runtime_function,
+    create_runtime_function_of_type, get_file_parameter_type, load_attributes, load_handle,
+    load_name,
+};
+use crate::codegen::codegen::Compiler;
+
+///The longest key a KEYED file can hold. Longer keys are cut short.
+pub const KEY_LENGTH: u64 = 32;
+const SLOT_HEADER_SIZE: u64 = KEY_LENGTH + 1;
+
+pub const RECORD_READ_FUNCTION_NAME: &str = "plick_record_read";
+pub const RECORD_READ_KEY_FUNCTION_NAME: &str = "plick_record_read_key";
+pub const RECORD_WRITE_FUNCTION_NAME: &str = "plick_record_write";
+pub const RECORD_REWRITE_FUNCTION_NAME: &str = "plick_record_rewrite";
+pub const RECORD_DELETE_FUNCTION_NAME: &str = "plick_record_delete";
+pub const RAISE_KEY_FUNCTION_NAME: &str = "plick_raise_key";
+const RECORD_FIND_FUNCTION_NAME: &str = "plick_record_find";
+
+///The record functions return one of these.
+pub const RECORD_OK: u64 = 0;
+pub const RECORD_END_OF_FILE: u64 = 1;
+pub const RECORD_KEY_CONDITION: u64 = 2;
+
+///The C function that moves the position of a file. fseek takes its offset as
+///a long, which is only 32 bits on Windows, so there _fseeki64 is used.
+#[cfg(windows)]
+pub const SEEK_FUNCTION_NAME: &str = "_fseeki64";
+#[cfg(not(windows))]
+pub const SEEK_FUNCTION_NAME: &str = "fseek";
+
+const SEEK_SET: u64 = 0;
+const SEEK_CUR: u64 = 1;
+const SEEK_END: u64 = 2;
+
+///The type of the offset `SEEK_FUNCTION_NAME` takes: a 64 bit __int64 on
+///Windows, and a C long everywhere else.
+pub fn get_seek_offset_type(context: &Context) -> IntType<'_> {
+    if cfg!(windows) {
+        context.i64_type()
+    } else {
+        context.custom_width_int_type(8 * size_of::<c_long>() as u32)
+    }
+}
+
+pub fn add_record_runtime<'a, 'ctx>(compiler: &mut Compiler<'a, 'ctx>) {
+    let current_bb = compiler.builder.get_insert_block().unwrap();
+
+    add_record_find_function(compiler);
+    add_record_read_function(compiler);
+    add_record_read_key_function(compiler);
+    add_record_write_function(compiler);
+    add_record_rewrite_function(compiler);
+    add_record_delete_function(compiler);
+    add_raise_key_function(compiler);
+
+    compiler.builder.position_at_end(current_bb);
+}
+
+fn call_runtime<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    function_name: &str,
+    args: &[BasicMetadataValueEnum<'ctx>],
+) -> Option<BasicValueEnum<'ctx>> {
+    compiler
+        .builder
+        .build_call(
+            compiler.module.get_function(function_name).unwrap(),
+            args,
+            function_name,
+        )
+        .unwrap()
+        .try_as_basic_value()
+        .left()
+}
+
+fn build_fseek<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    handle: PointerValue<'ctx>,
+    offset: IntValue<'ctx>,
+    origin: u64,
+) {
+    let offset = compiler
+        .builder
+        .build_int_cast_sign_flag(
+            offset,
+            get_seek_offset_type(compiler.context),
+            true,
+            "offset",
+        )
+        .unwrap();
+    let origin = compiler.context.i32_type().const_int(origin, false);
+    call_runtime(
+        compiler,
+        SEEK_FUNCTION_NAME,
+        &[handle.into(), offset.into(), origin.into()],
+    );
+}
+
+///Reads or writes one item of `size` bytes, returning the number of items transferred.
+fn build_transfer<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    function_name: &str,
+    buffer: PointerValue<'ctx>,
+    size: IntValue<'ctx>,
+    handle: PointerValue<'ctx>,
+) -> IntValue<'ctx> {
+    let one = compiler.context.i64_type().const_int(1, false);
+    call_runtime(
+        compiler,
+        function_name,
+        &[buffer.into(), size.into(), one.into(), handle.into()],
+    )
+    .unwrap()
+    .into_int_value()
+}
+
+fn build_is_zero<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    value: IntValue<'ctx>,
+    name: &str,
+) -> IntValue<'ctx> {
+    compiler
+        .builder
+        .build_int_compare(IntPredicate::EQ, value, value.get_type().const_zero(), name)
+        .unwrap()
+}
+
+fn get_byte_pointer<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    buffer: PointerValue<'ctx>,
+    index: IntValue<'ctx>,
+) -> PointerValue<'ctx> {
+    unsafe {
+        compiler
+            .builder
+            .build_gep(buffer, &[index], "byte_ptr")
+            .unwrap()
+    }
+}
+
+///Allocates room for a slot header, followed by a zero so the key in it is always terminated.
+fn build_slot_header_buffer<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) -> PointerValue<'ctx> {
+    let i8_type = compiler.context.i8_type();
+    let i64_type = compiler.context.i64_type();
+    let buffer = compiler
+        .builder
+        .build_alloca(
+            i8_type.array_type(SLOT_HEADER_SIZE as u32 + 1),
+            "slot_header",
+        )
+        .unwrap();
+    let buffer = compiler
+        .builder
+        .build_bitcast(
+            buffer,
+            i8_type.ptr_type(AddressSpace::default()),
+            "slot_header_ptr",
+        )
+        .unwrap()
+        .into_pointer_value();
+
+    let terminator_ptr = get_byte_pointer(
+        compiler,
+        buffer,
+        i64_type.const_int(SLOT_HEADER_SIZE, false),
+    );
+    compiler
+        .builder
+        .build_store(terminator_ptr, i8_type.const_zero())
+        .unwrap();
+
+    buffer
+}
+
+fn get_key_pointer<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    slot_header: PointerValue<'ctx>,
+) -> PointerValue<'ctx> {
+    get_byte_pointer(
+        compiler,
+        slot_header,
+        compiler.context.i64_type().const_int(1, false),
+    )
+}
+
+fn build_return_status<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>, status: u64) {
+    let status = compiler.context.i32_type().const_int(status, false);
+    compiler.builder.build_return(Some(&status)).unwrap();
+}
+
+fn append_blocks<'a, 'ctx, const N: usize>(
+    compiler: &Compiler<'a, 'ctx>,
+    function: FunctionValue<'ctx>,
+    names: [&str; N],
+) -> [BasicBlock<'ctx>; N] {
+    names.map(|name| compiler.context.append_basic_block(function, name))
+}
+
+///plick_record_find(file, key, size): moves to the record stored under `key`,
+///returning 1 if there is one and 0 otherwise.
+fn add_record_find_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let i8_ptr_type = compiler.context.i8_type().ptr_type(AddressSpace::default());
+    let i32_type = compiler.context.i32_type();
+    let i64_type = compiler.context.i64_type();
+    let function_type = i32_type.fn_type(
+        &[
+            get_file_parameter_type(compiler),
+            i8_ptr_type.into(),
+            i64_type.into(),
+        ],
+        false,
+    );
+    let function =
+        create_runtime_function_of_type(compiler, RECORD_FIND_FUNCTION_NAME, function_type);
+    let file = function.get_first_param().unwrap().into_pointer_value();
+    let key = function.get_nth_param(1).unwrap().into_pointer_value();
+    let size = function.get_nth_param(2).unwrap().into_int_value();
+    let builder = compiler.builder;
+
+    let [loop_block, check_block, skip_block, found_block, not_found_block] = append_blocks(
+        compiler,
+        function,
+        ["find_loop", "find_check", "find_skip", "found", "not_found"],
+    );
+
+    let handle = load_handle(compiler, file);
+    let slot_header = build_slot_header_buffer(compiler);
+    build_fseek(compiler, handle, i64_type.const_zero(), SEEK_SET);
+    builder.build_unconditional_branch(loop_block).unwrap();
+
+    builder.position_at_end(loop_block);
+    let header_size = i64_type.const_int(SLOT_HEADER_SIZE, false);
+    let read_count = build_transfer(compiler, "fread", slot_header, header_size, handle);
+    let is_end_of_file = build_is_zero(compiler, read_count, "is_end_of_file");
+    builder
+        .build_conditional_branch(is_end_of_file, not_found_block, check_block)
+        .unwrap();
+
+    builder.position_at_end(check_block);
+    let in_use = builder
+        .build_load(slot_header, "in_use")
+        .unwrap()
+        .into_int_value();
+    let is_deleted = build_is_zero(compiler, in_use, "is_deleted");
+    let key_length = i64_type.const_int(KEY_LENGTH, false);
+    let comparison = call_runtime(
+        compiler,
+        "strncmp",
+        &[
+            get_key_pointer(compiler, slot_header).into(),
+            key.into(),
+            key_length.into(),
+        ],
+    )
+    .unwrap()
+    .into_int_value();
+    let is_same_key = build_is_zero(compiler, comparison, "is_same_key");
+    let is_live = builder.build_not(is_deleted, "is_live").unwrap();
+    let is_match = builder.build_and(is_live, is_same_key, "is_match").unwrap();
+    builder
+        .build_conditional_branch(is_match, found_block, skip_block)
+        .unwrap();
+
+    builder.position_at_end(skip_block);
+    build_fseek(compiler, handle, size, SEEK_CUR);
+    builder.build_unconditional_branch(loop_block).unwrap();
+
+    builder.position_at_end(found_block);
+    builder
+        .build_return(Some(&i32_type.const_int(1, false)))
+        .unwrap();
+
+    builder.position_at_end(not_found_block);
+    builder.build_return(Some(&i32_type.const_zero())).unwrap();
+}
+
+///plick_record_read(file, record, size, keyto, keyto_length): reads the next record.
+///For KEYED files, deleted records are skipped, and the key is copied into keyto
+///unless it is null.
+fn add_record_read_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let i8_ptr_type = compiler.context.i8_type().ptr_type(AddressSpace::default());
+    let i32_type = compiler.context.i32_type();
+    let i64_type = compiler.context.i64_type();
+    let function_type = i32_type.fn_type(
+        &[
+            get_file_parameter_type(compiler),
+            i8_ptr_type.into(),
+            i64_type.into(),
+            i8_ptr_type.into(),
+            i64_type.into(),
+        ],
+        false,
+    );
+    let function =
+        create_runtime_function_of_type(compiler, RECORD_READ_FUNCTION_NAME, function_type);
+    let file = function.get_first_param().unwrap().into_pointer_value();
+    let record = function.get_nth_param(1).unwrap().into_pointer_value();
+    let size = function.get_nth_param(2).unwrap().into_int_value();
+    let keyto = function.get_nth_param(3).unwrap().into_pointer_value();
+    let keyto_length = function.get_nth_param(4).unwrap().into_int_value();
+    let builder = compiler.builder;
+
+    store_record_size(compiler, file, size);
+
+    let [plain_block, keyed_loop_block, keyed_check_block, keyed_skip_block, keyed_read_block, keyto_block, ok_block, end_of_file_block] =
+        append_blocks(
+            compiler,
+            function,
+            [
+                "plain_read",
+                "keyed_loop",
+                "keyed_check",
+                "keyed_skip",
+                "keyed_read",
+                "copy_key",
+                "read_ok",
+                "read_end_of_file",
+            ],
+        );
+
+    let handle = load_handle(compiler, file);
+    let slot_header = build_slot_header_buffer(compiler);
+    let attributes = load_attributes(compiler, file);
+    let is_keyed = build_has_flag(compiler, attributes, stream::KEYED_FLAG, "is_keyed");
+    builder
+        .build_conditional_branch(is_keyed, keyed_loop_block, plain_block)
+        .unwrap();
+
+    builder.position_at_end(plain_block);
+    let read_count = build_transfer(compiler, "fread", record, size, handle);
+    let is_end_of_file = build_is_zero(compiler, read_count, "is_end_of_file");
+    builder
+        .build_conditional_branch(is_end_of_file, end_of_file_block, ok_block)
+        .unwrap();
+
+    builder.position_at_end(keyed_loop_block);
+    let header_size = i64_type.const_int(SLOT_HEADER_SIZE, false);
+    let read_count = build_transfer(compiler, "fread", slot_header, header_size, handle);
+    let is_end_of_file = build_is_zero(compiler, read_count, "is_end_of_file");
+    builder
+        .build_conditional_branch(is_end_of_file, end_of_file_block, keyed_check_block)
+        .unwrap();
+
+    builder.position_at_end(keyed_check_block);
+    let in_use = builder
+        .build_load(slot_header, "in_use")
+        .unwrap()
+        .into_int_value();
+    let is_deleted = build_is_zero(compiler, in_use, "is_deleted");
+    builder
+        .build_conditional_branch(is_deleted, keyed_skip_block, keyed_read_block)
+        .unwrap();
+
+    builder.position_at_end(keyed_skip_block);
+    build_fseek(compiler, handle, size, SEEK_CUR);
+    builder
+        .build_unconditional_branch(keyed_loop_block)
+        .unwrap();
+
+    builder.position_at_end(keyed_read_block);
+    let read_count = build_transfer(compiler, "fread", record, size, handle);
+    let is_end_of_file = build_is_zero(compiler, read_count, "is_end_of_file");
+    let has_no_keyto = builder.build_is_null(keyto, "has_no_keyto").unwrap();
+    let skips_key = builder
+        .build_or(is_end_of_file, has_no_keyto, "skips_key")
+        .unwrap();
+    builder
+        .build_conditional_branch(skips_key, ok_block, keyto_block)
+        .unwrap();
+
+    builder.position_at_end(keyto_block);
+    call_runtime(
+        compiler,
+        "strncpy",
+        &[
+            keyto.into(),
+            get_key_pointer(compiler, slot_header).into(),
+            keyto_length.into(),
+        ],
+    );
+    let keyto_end = get_byte_pointer(compiler, keyto, keyto_length);
+    builder
+        .build_store(keyto_end, compiler.context.i8_type().const_zero())
+        .unwrap();
+    builder.build_unconditional_branch(ok_block).unwrap();
+
+    builder.position_at_end(ok_block);
+    build_return_status(compiler, RECORD_OK);
+
+    builder.position_at_end(end_of_file_block);
+    build_return_status(compiler, RECORD_END_OF_FILE);
+}
+
+///plick_record_read_key(file, record, size, key): reads the record stored under `key`.
+fn add_record_read_key_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let i8_ptr_type = compiler.context.i8_type().ptr_type(AddressSpace::default());
+    let i32_type = compiler.context.i32_type();
+    let i64_type = compiler.context.i64_type();
+    let function_type = i32_type.fn_type(
+        &[
+            get_file_parameter_type(compiler),
+            i8_ptr_type.into(),
+            i64_type.into(),
+            i8_ptr_type.into(),
+        ],
+        false,
+    );
+    let function =
+        create_runtime_function_of_type(compiler, RECORD_READ_KEY_FUNCTION_NAME, function_type);
+    let file = function.get_first_param().unwrap().into_pointer_value();
+    let record = function.get_nth_param(1).unwrap().into_pointer_value();
+    let size = function.get_nth_param(2).unwrap().into_int_value();
+    let key = function.get_nth_param(3).unwrap().into_pointer_value();
+    let builder = compiler.builder;
+
+    store_record_size(compiler, file, size);
+
+    let [find_block, read_block, key_condition_block] =
+        append_blocks(compiler, function, ["find", "read", "key_condition"]);
+
+    let attributes = load_attributes(compiler, file);
+    let is_keyed = build_has_flag(compiler, attributes, stream::KEYED_FLAG, "is_keyed");
+    builder
+        .build_conditional_branch(is_keyed, find_block, key_condition_block)
+        .unwrap();
+
+    builder.position_at_end(find_block);
+    let found = build_find_call(compiler, file, key, size);
+    builder
+        .build_conditional_branch(found, read_block, key_condition_block)
+        .unwrap();
+
+    builder.position_at_end(read_block);
+    let handle = load_handle(compiler, file);
+    build_transfer(compiler, "fread", record, size, handle);
+    build_return_status(compiler, RECORD_OK);
+
+    builder.position_at_end(key_condition_block);
+    build_return_status(compiler, RECORD_KEY_CONDITION);
+}
+
+///Returns true if the record stored under `key` was found.
+fn build_find_call<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    file: PointerValue<'ctx>,
+    key: PointerValue<'ctx>,
+    size: IntValue<'ctx>,
+) -> IntValue<'ctx> {
+    let found = call_runtime(
+        compiler,
+        RECORD_FIND_FUNCTION_NAME,
+        &[file.into(), key.into(), size.into()],
+    )
+    .unwrap()
+    .into_int_value();
+    let is_not_found = build_is_zero(compiler, found, "is_not_found");
+    compiler.builder.build_not(is_not_found, "found").unwrap()
+}
+
+///plick_record_write(file, record, size, key): adds a record to the end of the file.
+///KEYED files raise KEY instead when `key` is already in use.
+fn add_record_write_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let i8_type = compiler.context.i8_type();
+    let i8_ptr_type = i8_type.ptr_type(AddressSpace::default());
+    let i32_type = compiler.context.i32_type();
+    let i64_type = compiler.context.i64_type();
+    let function_type = i32_type.fn_type(
+        &[
+            get_file_parameter_type(compiler),
+            i8_ptr_type.into(),
+            i64_type.into(),
+            i8_ptr_type.into(),
+        ],
+        false,
+    );
+    let function =
+        create_runtime_function_of_type(compiler, RECORD_WRITE_FUNCTION_NAME, function_type);
+    let file = function.get_first_param().unwrap().into_pointer_value();
+    let record = function.get_nth_param(1).unwrap().into_pointer_value();
+    let size = function.get_nth_param(2).unwrap().into_int_value();
+    let key = function.get_nth_param(3).unwrap().into_pointer_value();
+    let builder = compiler.builder;
+
+    store_record_size(compiler, file, size);
+
+    let [plain_block, keyed_block, append_block, key_condition_block] = append_blocks(
+        compiler,
+        function,
+        ["plain_write", "keyed_write", "append", "key_condition"],
+    );
+
+    let slot_header = build_slot_header_buffer(compiler);
+    let attributes = load_attributes(compiler, file);
+    let is_keyed = build_has_flag(compiler, attributes, stream::KEYED_FLAG, "is_keyed");
+    builder
+        .build_conditional_branch(is_keyed, keyed_block, plain_block)
+        .unwrap();
+
+    builder.position_at_end(plain_block);
+    let handle = load_handle(compiler, file);
+    build_transfer(compiler, "fwrite", record, size, handle);
+    build_return_status(compiler, RECORD_OK);
+
+    builder.position_at_end(keyed_block);
+    let empty_key = builder
+        .build_global_string_ptr("", "empty_key")
+        .unwrap()
+        .as_pointer_value();
+    let has_no_key = builder.build_is_null(key, "has_no_key").unwrap();
+    let key = builder
+        .build_select(has_no_key, empty_key, key, "key")
+        .unwrap()
+        .into_pointer_value();
+    let found = build_find_call(compiler, file, key, size);
+    builder
+        .build_conditional_branch(found, key_condition_block, append_block)
+        .unwrap();
+
+    builder.position_at_end(append_block);
+    let handle = load_handle(compiler, file);
+    builder
+        .build_store(slot_header, i8_type.const_int(1, false))
+        .unwrap();
+    call_runtime(
+        compiler,
+        "strncpy",
+        &[
+            get_key_pointer(compiler, slot_header).into(),
+            key.into(),
+            i64_type.const_int(KEY_LENGTH, false).into(),
+        ],
+    );
+    build_fseek(compiler, handle, i64_type.const_zero(), SEEK_END);
+    let header_size = i64_type.const_int(SLOT_HEADER_SIZE, false);
+    build_transfer(compiler, "fwrite", slot_header, header_size, handle);
+    build_transfer(compiler, "fwrite", record, size, handle);
+    build_fseek(compiler, handle, i64_type.const_zero(), SEEK_CUR);
+    build_return_status(compiler, RECORD_OK);
+
+    builder.position_at_end(key_condition_block);
+    build_return_status(compiler, RECORD_KEY_CONDITION);
+}
+
+///plick_record_rewrite(file, record, size, key): replaces the record stored under `key`,
+///or the record read last when `key` is null.
+fn add_record_rewrite_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let i8_ptr_type = compiler.context.i8_type().ptr_type(AddressSpace::default());
+    let i32_type = compiler.context.i32_type();
+    let i64_type = compiler.context.i64_type();
+    let function_type = i32_type.fn_type(
+        &[
+            get_file_parameter_type(compiler),
+            i8_ptr_type.into(),
+            i64_type.into(),
+            i8_ptr_type.into(),
+        ],
+        false,
+    );
+    let function =
+        create_runtime_function_of_type(compiler, RECORD_REWRITE_FUNCTION_NAME, function_type);
+    let file = function.get_first_param().unwrap().into_pointer_value();
+    let record = function.get_nth_param(1).unwrap().into_pointer_value();
+    let size = function.get_nth_param(2).unwrap().into_int_value();
+    let key = function.get_nth_param(3).unwrap().into_pointer_value();
+    let builder = compiler.builder;
+
+    store_record_size(compiler, file, size);
+
+    let [by_key_block, find_block, last_record_block, write_block, key_condition_block] =
+        append_blocks(
+            compiler,
+            function,
+            [
+                "rewrite_by_key",
+                "find",
+                "rewrite_last_record",
+                "rewrite",
+                "key_condition",
+            ],
+        );
+
+    let has_no_key = builder.build_is_null(key, "has_no_key").unwrap();
+    builder
+        .build_conditional_branch(has_no_key, last_record_block, by_key_block)
+        .unwrap();
+
+    builder.position_at_end(by_key_block);
+    let attributes = load_attributes(compiler, file);
+    let is_keyed = build_has_flag(compiler, attributes, stream::KEYED_FLAG, "is_keyed");
+    builder
+        .build_conditional_branch(is_keyed, find_block, key_condition_block)
+        .unwrap();
+
+    builder.position_at_end(find_block);
+    let found = build_find_call(compiler, file, key, size);
+    builder
+        .build_conditional_branch(found, write_block, key_condition_block)
+        .unwrap();
+
+    builder.position_at_end(last_record_block);
+    let handle = load_handle(compiler, file);
+    let back_one_record = builder.build_int_neg(size, "back_one_record").unwrap();
+    build_fseek(compiler, handle, back_one_record, SEEK_CUR);
+    builder.build_unconditional_branch(write_block).unwrap();
+
+    builder.position_at_end(write_block);
+    let handle = load_handle(compiler, file);
+    build_transfer(compiler, "fwrite", record, size, handle);
+    build_fseek(compiler, handle, i64_type.const_zero(), SEEK_CUR);
+    build_return_status(compiler, RECORD_OK);
+
+    builder.position_at_end(key_condition_block);
+    build_return_status(compiler, RECORD_KEY_CONDITION);
+}
+
+///plick_record_delete(file, key): marks the record stored under `key` as deleted,
+///or the record read last when `key` is null. Only KEYED files can have records deleted.
+///The records are assumed to be the size of the last one read or written.
+fn add_record_delete_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let i8_type = compiler.context.i8_type();
+    let i8_ptr_type = i8_type.ptr_type(AddressSpace::default());
+    let i32_type = compiler.context.i32_type();
+    let i64_type = compiler.context.i64_type();
+    let function_type = i32_type.fn_type(
+        &[get_file_parameter_type(compiler), i8_ptr_type.into()],
+        false,
+    );
+    let function =
+        create_runtime_function_of_type(compiler, RECORD_DELETE_FUNCTION_NAME, function_type);
+    let file = function.get_first_param().unwrap().into_pointer_value();
+    let key = function.get_nth_param(1).unwrap().into_pointer_value();
+    let builder = compiler.builder;
+
+    let [not_keyed_block, keyed_block, find_block, found_block, last_record_block, mark_block, key_condition_block] =
+        append_blocks(
+            compiler,
+            function,
+            [
+                "not_keyed",
+                "keyed_delete",
+                "find",
+                "found",
+                "delete_last_record",
+                "mark_deleted",
+                "key_condition",
+            ],
+        );
+
+    let slot_header = build_slot_header_buffer(compiler);
+    let size = load_record_size(compiler, file);
+    let attributes = load_attributes(compiler, file);
+    let is_keyed = build_has_flag(compiler, attributes, stream::KEYED_FLAG, "is_keyed");
+    builder
+        .build_conditional_branch(is_keyed, keyed_block, not_keyed_block)
+        .unwrap();
+
+    builder.position_at_end(not_keyed_block);
+    build_runtime_error(
+        compiler,
+        "ERROR condition raised: DELETE needs a KEYED file, %s is not\n",
+        load_name(compiler, file),
+    );
+
+    builder.position_at_end(keyed_block);
+    let has_no_key = builder.build_is_null(key, "has_no_key").unwrap();
+    builder
+        .build_conditional_branch(has_no_key, last_record_block, find_block)
+        .unwrap();
+
+    builder.position_at_end(find_block);
+    let found = build_find_call(compiler, file, key, size);
+    builder
+        .build_conditional_branch(found, found_block, key_condition_block)
+        .unwrap();
+
+    //after a find, the file is at the start of the record, just past its slot header
+    builder.position_at_end(found_block);
+    let handle = load_handle(compiler, file);
+    let back_one_header = i64_type.const_int(SLOT_HEADER_SIZE, false).const_neg();
+    build_fseek(compiler, handle, back_one_header, SEEK_CUR);
+    builder.build_unconditional_branch(mark_block).unwrap();
+
+    builder.position_at_end(last_record_block);
+    let handle = load_handle(compiler, file);
+    let slot_size = builder
+        .build_int_add(
+            size,
+            i64_type.const_int(SLOT_HEADER_SIZE, false),
+            "slot_size",
+        )
+        .unwrap();
+    let back_one_slot = builder.build_int_neg(slot_size, "back_one_slot").unwrap();
+    build_fseek(compiler, handle, back_one_slot, SEEK_CUR);
+    builder.build_unconditional_branch(mark_block).unwrap();
+
+    builder.position_at_end(mark_block);
+    let handle = load_handle(compiler, file);
+    builder
+        .build_store(slot_header, i8_type.const_zero())
+        .unwrap();
+    build_transfer(
+        compiler,
+        "fwrite",
+        slot_header,
+        i64_type.const_int(1, false),
+        handle,
+    );
+    build_fseek(compiler, handle, i64_type.const_zero(), SEEK_CUR);
+    build_return_status(compiler, RECORD_OK);
+
+    builder.position_at_end(key_condition_block);
+    build_return_status(compiler, RECORD_KEY_CONDITION);
+}
+
+///plick_raise_key(file): what happens when a keyed operation fails on a file
+///that has no ON KEY unit.
+fn add_raise_key_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let function = create_runtime_function(
+        compiler,
+        RAISE_KEY_FUNCTION_NAME,
+        &[get_file_parameter_type(compiler)],
+    );
+    let file = function.get_first_param().unwrap().into_pointer_value();
+    let name = load_name(compiler, file);
+
+    build_runtime_error(compiler, "KEY condition raised on file %s\n", name);
+}
@@ -0,0 +1,1111 @@
+use inkwell::{
+    basic_block::BasicBlock,
+    context::Context,
+    module::Linkage,
+    types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum, FunctionType, StructType},
+    values::{BasicMetadataValueEnum, FunctionValue, IntValue, PointerValue},
+    AddressSpace, IntPredicate,
+};
+
+use crate::ast::FileAttribute;
+use crate::codegen::codegen::Compiler;
+
+///Stream output is positioned on tab stops every TAB_WIDTH columns,
+///so list items start at columns 1, 25, 49, ...
+pub const TAB_WIDTH: u64 = 24;
+///The number of columns on a line of a print file.
+pub const LINE_SIZE: u64 = 120;
+
+///The file PUT writes to when no FILE option is given.
+pub const DEFAULT_OUTPUT_FILE: &str = "SYSPRINT";
+///The file GET reads from when no FILE option is given.
+pub const DEFAULT_INPUT_FILE: &str = "SYSIN";
+
+pub const PUT_ITEM_FUNCTION_NAME: &str = "plick_put_item";
+pub const PUT_SKIP_FUNCTION_NAME: &str = "plick_put_skip";
+pub const PUT_PAGE_FUNCTION_NAME: &str = "plick_put_page";
+pub const PUT_LINE_FUNCTION_NAME: &str = "plick_put_line";
+pub const OPEN_FUNCTION_NAME: &str = "plick_open";
+pub const CLOSE_FUNCTION_NAME: &str = "plick_close";
+pub const READ_ITEM_FUNCTION_NAME: &str = "plick_read_item";
+pub const RAISE_ENDFILE_FUNCTION_NAME: &str = "plick_raise_endfile";
+
+pub const INPUT_FLAG: u64 = 1;
+pub const OUTPUT_FLAG: u64 = 1 << 1;
+pub const STREAM_FLAG: u64 = 1 << 2;
+pub const PRINT_FLAG: u64 = 1 << 3;
+pub const RECORD_FLAG: u64 = 1 << 4;
+pub const SEQUENTIAL_FLAG: u64 = 1 << 5;
+pub const DIRECT_FLAG: u64 = 1 << 6;
+pub const KEYED_FLAG: u64 = 1 << 7;
+pub const UPDATE_FLAG: u64 = 1 << 8;
+///Set on SYSIN and SYSPRINT, which use stdin and stdout unless they are opened with a TITLE.
+const STANDARD_FILE_FLAG: u64 = 1 << 16;
+const OPEN_FLAG: u64 = 1 << 17;
+
+///The value scanf returns when it reaches the end of its input.
+pub const END_OF_FILE: i32 = -1;
+
+const COLUMN_FIELD: u32 = 0;
+const LINE_FIELD: u32 = 1;
+const HANDLE_FIELD: u32 = 2;
+const ATTRIBUTES_FIELD: u32 = 3;
+const NAME_FIELD: u32 = 4;
+const RECORD_SIZE_FIELD: u32 = 5;
+const DECLARED_ATTRIBUTES_FIELD: u32 = 6;
+
+const NEWLINE_ASCII_CODE: u64 = 10;
+
+///Gets the name of the global holding the state of the stream file called `file_name`.
+pub fn get_file_global_name(file_name: &str) -> String {
+    format!("plick_file_{}", file_name)
+}
+
+///Converts declared or opened file attributes into the flags kept by the runtime.
+pub fn get_attribute_flags(attributes: &[FileAttribute]) -> u64 {
+    attributes
+        .iter()
+        .map(|attribute| match attribute {
+            FileAttribute::Stream => STREAM_FLAG,
+            FileAttribute::Input => INPUT_FLAG,
+            FileAttribute::Output => OUTPUT_FLAG,
+            FileAttribute::Print => PRINT_FLAG | OUTPUT_FLAG,
+            FileAttribute::Record => RECORD_FLAG,
+            FileAttribute::Sequential => SEQUENTIAL_FLAG,
+            FileAttribute::Direct => DIRECT_FLAG | KEYED_FLAG,
+            FileAttribute::Keyed => KEYED_FLAG,
+            FileAttribute::Update => UPDATE_FLAG,
+        })
+        .fold(0, |flags, flag| flags | flag)
+}
+
+///The state the runtime keeps for every file.
+///Field 0 is the number of characters already written on the current line,
+///field 1 is the current line number of the current page, starting at 1,
+///field 2 is the FILE* the file was opened as, or null when it is not open
+///or is using stdin/stdout,
+///field 3 holds the attribute flags,
+///field 4 is the name of the file, which is also its default title,
+///field 5 is the size of the last record read or written, which DELETE
+///uses to step over the records of a KEYED file,
+///field 6 holds the declared attribute flags, which field 3 goes back to on CLOSE.
+pub fn get_stream_file_type<'ctx>(ctx: &'ctx Context) -> StructType<'ctx> {
+    let field_types: Vec<BasicTypeEnum> = vec![
+        ctx.i32_type().as_basic_type_enum(),
+        ctx.i32_type().as_basic_type_enum(),
+        ctx.i8_type()
+            .ptr_type(AddressSpace::default())
+            .as_basic_type_enum(),
+        ctx.i32_type().as_basic_type_enum(),
+        ctx.i8_type()
+            .ptr_type(AddressSpace::default())
+            .as_basic_type_enum(),
+        ctx.i64_type().as_basic_type_enum(),
+        ctx.i32_type().as_basic_type_enum(),
+    ];
+
+    let packed = false;
+    ctx.struct_type(&field_types, packed)
+}
+
+pub fn add_stream_runtime<'a, 'ctx>(compiler: &mut Compiler<'a, 'ctx>) {
+    let current_bb = compiler.builder.get_insert_block().unwrap();
+
+    let standard_flags = STANDARD_FILE_FLAG | OPEN_FLAG | STREAM_FLAG;
+    add_stream_file_global(
+        compiler,
+        DEFAULT_OUTPUT_FILE,
+        standard_flags | OUTPUT_FLAG | PRINT_FLAG,
+    );
+    add_stream_file_global(compiler, DEFAULT_INPUT_FILE, standard_flags | INPUT_FLAG);
+
+    add_put_page_function(compiler);
+    add_put_skip_function(compiler);
+    add_put_line_function(compiler);
+    add_put_item_function(compiler);
+    add_open_function(compiler);
+    add_close_function(compiler);
+    add_read_item_function(compiler);
+    add_raise_endfile_function(compiler);
+
+    compiler.builder.position_at_end(current_bb);
+}
+
+///Adds the global holding the state of a declared file. Files start out closed.
+pub fn add_declared_file<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    file_name: &str,
+    attributes: &[FileAttribute],
+) -> PointerValue<'ctx> {
+    add_stream_file_global(compiler, file_name, get_attribute_flags(attributes))
+}
+
+fn add_stream_file_global<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    file_name: &str,
+    attribute_flags: u64,
+) -> PointerValue<'ctx> {
+    let i8_ptr_type = compiler.context.i8_type().ptr_type(AddressSpace::default());
+    let i32_type = compiler.context.i32_type();
+
+    let name_value = compiler.context.const_string(file_name.as_bytes(), true);
+    let name_global = compiler.module.add_global(
+        name_value.get_type(),
+        Some(AddressSpace::default()),
+        &format!("plick_file_name_{}", file_name),
+    );
+    name_global.set_initializer(&name_value);
+    name_global.set_constant(true);
+    name_global.set_linkage(Linkage::Internal);
+
+    let file_type = compiler.type_module.stream_file_type;
+    let global = compiler.module.add_global(
+        file_type,
+        Some(AddressSpace::default()),
+        &get_file_global_name(file_name),
+    );
+
+    let initial_state = file_type.const_named_struct(&[
+        i32_type.const_zero().into(),
+        i32_type.const_int(1, false).into(),
+        i8_ptr_type.const_null().into(),
+        i32_type.const_int(attribute_flags, false).into(),
+        name_global
+            .as_pointer_value()
+            .const_cast(i8_ptr_type)
+            .into(),
+        compiler.context.i64_type().const_zero().into(),
+        i32_type.const_int(attribute_flags, false).into(),
+    ]);
+    global.set_initializer(&initial_state);
+    global.set_linkage(Linkage::Internal);
+
+    global.as_pointer_value()
+}
+
+impl<'a, 'ctx> Compiler<'a, 'ctx> {
+    ///Returns a pointer to the state of the stream file called `file_name`, if it exists.
+    pub fn get_stream_file(&self, file_name: &str) -> Option<PointerValue<'ctx>> {
+        self.module
+            .get_global(&get_file_global_name(file_name))
+            .map(|global| global.as_pointer_value())
+    }
+
+    ///Like get_stream_file, but reports an error when the file was never declared.
+    pub fn get_declared_stream_file(&self, file_name: &str) -> Option<PointerValue<'ctx>> {
+        let file = self.get_stream_file(file_name);
+        if let None = file {
+            self.error_module.store_msg_from_number(&["13", file_name]);
+        }
+        file
+    }
+
+    ///Opens `file` unless it already is. With no title, the name of the file is used.
+    pub fn build_open_call(
+        &self,
+        file: PointerValue<'ctx>,
+        title: Option<PointerValue<'ctx>>,
+        attribute_flags: u64,
+    ) {
+        let i8_ptr_type = self.context.i8_type().ptr_type(AddressSpace::default());
+        let title = title.unwrap_or(i8_ptr_type.const_null());
+        let attribute_flags = self.context.i32_type().const_int(attribute_flags, false);
+
+        self.builder
+            .build_call(
+                self.module.get_function(OPEN_FUNCTION_NAME).unwrap(),
+                &[file.into(), title.into(), attribute_flags.into()],
+                "open_file",
+            )
+            .unwrap();
+    }
+}
+
+pub fn create_runtime_function<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    name: &str,
+    param_types: &[BasicMetadataTypeEnum<'ctx>],
+) -> FunctionValue<'ctx> {
+    let function_type = compiler.context.void_type().fn_type(param_types, false);
+    create_runtime_function_of_type(compiler, name, function_type)
+}
+
+pub fn create_runtime_function_of_type<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    name: &str,
+    function_type: FunctionType<'ctx>,
+) -> FunctionValue<'ctx> {
+    let function = compiler
+        .module
+        .add_function(name, function_type, Some(Linkage::Internal));
+
+    let entry = compiler.context.append_basic_block(function, "entry");
+    compiler.builder.position_at_end(entry);
+
+    function
+}
+
+pub fn get_file_parameter_type<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+) -> BasicMetadataTypeEnum<'ctx> {
+    compiler
+        .type_module
+        .stream_file_type
+        .ptr_type(AddressSpace::default())
+        .into()
+}
+
+///Prints to stdout.
+pub fn build_printf<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    format_string: &str,
+    args: &[BasicMetadataValueEnum<'ctx>],
+) {
+    let format_string_ptr = compiler
+        .builder
+        .build_global_string_ptr(format_string, "runtime_format")
+        .unwrap()
+        .as_pointer_value();
+
+    let mut printf_args: Vec<BasicMetadataValueEnum<'ctx>> = vec![format_string_ptr.into()];
+    printf_args.extend_from_slice(args);
+
+    compiler
+        .builder
+        .build_call(
+            compiler.module.get_function("printf").unwrap(),
+            &printf_args[..],
+            "runtime_printf",
+        )
+        .unwrap();
+}
+
+///Prints to `file`, going through printf when the file has no handle of its own.
+fn build_file_printf<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    file: PointerValue<'ctx>,
+    format_string: &str,
+    args: &[BasicMetadataValueEnum<'ctx>],
+) {
+    let builder = compiler.builder;
+    let function = builder.get_insert_block().unwrap().get_parent().unwrap();
+
+    let stdout_block = compiler.context.append_basic_block(function, "to_stdout");
+    let handle_block = compiler.context.append_basic_block(function, "to_handle");
+    let printed_block = compiler.context.append_basic_block(function, "printed");
+
+    let handle = load_handle(compiler, file);
+    let is_standard = builder.build_is_null(handle, "is_standard").unwrap();
+    builder
+        .build_conditional_branch(is_standard, stdout_block, handle_block)
+        .unwrap();
+
+    builder.position_at_end(stdout_block);
+    build_printf(compiler, format_string, args);
+    builder.build_unconditional_branch(printed_block).unwrap();
+
+    builder.position_at_end(handle_block);
+    let format_string_ptr = builder
+        .build_global_string_ptr(format_string, "runtime_format")
+        .unwrap()
+        .as_pointer_value();
+    let mut fprintf_args: Vec<BasicMetadataValueEnum<'ctx>> =
+        vec![handle.into(), format_string_ptr.into()];
+    fprintf_args.extend_from_slice(args);
+    builder
+        .build_call(
+            compiler.module.get_function("fprintf").unwrap(),
+            &fprintf_args[..],
+            "runtime_fprintf",
+        )
+        .unwrap();
+    builder.build_unconditional_branch(printed_block).unwrap();
+
+    builder.position_at_end(printed_block);
+}
+
+fn get_field_pointers<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    file: PointerValue<'ctx>,
+) -> (PointerValue<'ctx>, PointerValue<'ctx>) {
+    let column_ptr = compiler
+        .builder
+        .build_struct_gep(file, COLUMN_FIELD, "column_ptr")
+        .unwrap();
+    let line_ptr = compiler
+        .builder
+        .build_struct_gep(file, LINE_FIELD, "line_ptr")
+        .unwrap();
+
+    (column_ptr, line_ptr)
+}
+
+pub fn load_handle<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    file: PointerValue<'ctx>,
+) -> PointerValue<'ctx> {
+    let handle_ptr = compiler
+        .builder
+        .build_struct_gep(file, HANDLE_FIELD, "handle_ptr")
+        .unwrap();
+    compiler
+        .builder
+        .build_load(handle_ptr, "handle")
+        .unwrap()
+        .into_pointer_value()
+}
+
+pub fn load_attributes<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    file: PointerValue<'ctx>,
+) -> IntValue<'ctx> {
+    let attributes_ptr = compiler
+        .builder
+        .build_struct_gep(file, ATTRIBUTES_FIELD, "attributes_ptr")
+        .unwrap();
+    load_int(compiler, attributes_ptr, "attributes")
+}
+
+pub fn load_name<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    file: PointerValue<'ctx>,
+) -> PointerValue<'ctx> {
+    let name_ptr = compiler
+        .builder
+        .build_struct_gep(file, NAME_FIELD, "name_ptr")
+        .unwrap();
+    compiler
+        .builder
+        .build_load(name_ptr, "name")
+        .unwrap()
+        .into_pointer_value()
+}
+
+pub fn load_record_size<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    file: PointerValue<'ctx>,
+) -> IntValue<'ctx> {
+    let record_size_ptr = compiler
+        .builder
+        .build_struct_gep(file, RECORD_SIZE_FIELD, "record_size_ptr")
+        .unwrap();
+    load_int(compiler, record_size_ptr, "record_size")
+}
+
+pub fn store_record_size<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    file: PointerValue<'ctx>,
+    record_size: IntValue<'ctx>,
+) {
+    let record_size_ptr = compiler
+        .builder
+        .build_struct_gep(file, RECORD_SIZE_FIELD, "record_size_ptr")
+        .unwrap();
+    compiler
+        .builder
+        .build_store(record_size_ptr, record_size)
+        .unwrap();
+}
+
+pub fn load_int<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    ptr: PointerValue<'ctx>,
+    name: &str,
+) -> IntValue<'ctx> {
+    compiler
+        .builder
+        .build_load(ptr, name)
+        .unwrap()
+        .into_int_value()
+}
+
+///Returns true if any of `flags` is set in `attributes`.
+pub fn build_has_flag<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    attributes: IntValue<'ctx>,
+    flags: u64,
+    name: &str,
+) -> IntValue<'ctx> {
+    let i32_type = compiler.context.i32_type();
+    let masked = compiler
+        .builder
+        .build_and(attributes, i32_type.const_int(flags, false), "masked")
+        .unwrap();
+    compiler
+        .builder
+        .build_int_compare(IntPredicate::NE, masked, i32_type.const_zero(), name)
+        .unwrap()
+}
+
+///Prints `message` with the C string `argument` substituted in, then ends the program.
+pub fn build_runtime_error<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    message: &str,
+    argument: PointerValue<'ctx>,
+) {
+    build_printf(compiler, message, &[argument.into()]);
+    compiler
+        .builder
+        .build_call(
+            compiler.module.get_function("exit").unwrap(),
+            &[compiler.context.i32_type().const_int(1, false).into()],
+            "exit",
+        )
+        .unwrap();
+    compiler.builder.build_unreachable().unwrap();
+}
+
+///plick_put_page(file): starts a new page.
+fn add_put_page_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let function = create_runtime_function(
+        compiler,
+        PUT_PAGE_FUNCTION_NAME,
+        &[get_file_parameter_type(compiler)],
+    );
+    let file = function.get_first_param().unwrap().into_pointer_value();
+    let (column_ptr, line_ptr) = get_field_pointers(compiler, file);
+    let i32_type = compiler.context.i32_type();
+
+    build_file_printf(compiler, file, "\x0C", &[]);
+    compiler
+        .builder
+        .build_store(column_ptr, i32_type.const_zero())
+        .unwrap();
+    compiler
+        .builder
+        .build_store(line_ptr, i32_type.const_int(1, false))
+        .unwrap();
+    compiler.builder.build_return(None).unwrap();
+}
+
+///plick_put_skip(file, n): moves down n lines, or back to the start of the line if n is 0.
+fn add_put_skip_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let i32_type = compiler.context.i32_type();
+    let function = create_runtime_function(
+        compiler,
+        PUT_SKIP_FUNCTION_NAME,
+        &[get_file_parameter_type(compiler), i32_type.into()],
+    );
+    let file = function.get_first_param().unwrap().into_pointer_value();
+    let lines_to_skip = function.get_nth_param(1).unwrap().into_int_value();
+    let builder = compiler.builder;
+
+    let (column_ptr, line_ptr) = get_field_pointers(compiler, file);
+    let counter_ptr = builder.build_alloca(i32_type, "skip_counter").unwrap();
+    builder
+        .build_store(counter_ptr, i32_type.const_zero())
+        .unwrap();
+
+    let carriage_return_block = compiler
+        .context
+        .append_basic_block(function, "carriage_return");
+    let loop_condition_block = compiler
+        .context
+        .append_basic_block(function, "skip_condition");
+    let loop_body_block = compiler.context.append_basic_block(function, "skip_body");
+    let done_block = compiler.context.append_basic_block(function, "skip_done");
+
+    let is_zero = builder
+        .build_int_compare(
+            IntPredicate::SLE,
+            lines_to_skip,
+            i32_type.const_zero(),
+            "is_skip_zero",
+        )
+        .unwrap();
+    builder
+        .build_conditional_branch(is_zero, carriage_return_block, loop_condition_block)
+        .unwrap();
+
+    builder.position_at_end(carriage_return_block);
+    build_file_printf(compiler, file, "\r", &[]);
+    builder
+        .build_store(column_ptr, i32_type.const_zero())
+        .unwrap();
+    builder.build_return(None).unwrap();
+
+    builder.position_at_end(loop_condition_block);
+    let counter = load_int(compiler, counter_ptr, "counter");
+    let keep_skipping = builder
+        .build_int_compare(IntPredicate::SLT, counter, lines_to_skip, "keep_skipping")
+        .unwrap();
+    builder
+        .build_conditional_branch(keep_skipping, loop_body_block, done_block)
+        .unwrap();
+
+    builder.position_at_end(loop_body_block);
+    build_file_printf(compiler, file, "\n", &[]);
+    let next_counter = builder
+        .build_int_add(counter, i32_type.const_int(1, false), "next_counter")
+        .unwrap();
+    builder.build_store(counter_ptr, next_counter).unwrap();
+    builder
+        .build_unconditional_branch(loop_condition_block)
+        .unwrap();
+
+    builder.position_at_end(done_block);
+    let line = load_int(compiler, line_ptr, "line");
+    let new_line = builder
+        .build_int_add(line, lines_to_skip, "new_line")
+        .unwrap();
+    builder.build_store(line_ptr, new_line).unwrap();
+    builder
+        .build_store(column_ptr, i32_type.const_zero())
+        .unwrap();
+    builder.build_return(None).unwrap();
+}
+
+///plick_put_line(file, n): moves down to line n, starting a new page
+///if line n has already been written to.
+fn add_put_line_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let i32_type = compiler.context.i32_type();
+    let function = create_runtime_function(
+        compiler,
+        PUT_LINE_FUNCTION_NAME,
+        &[get_file_parameter_type(compiler), i32_type.into()],
+    );
+    let file = function.get_first_param().unwrap().into_pointer_value();
+    let target_line = function.get_nth_param(1).unwrap().into_int_value();
+    let builder = compiler.builder;
+
+    let (column_ptr, line_ptr) = get_field_pointers(compiler, file);
+
+    let new_page_block = compiler.context.append_basic_block(function, "new_page");
+    let move_down_block = compiler.context.append_basic_block(function, "move_down");
+    let skip_block = compiler.context.append_basic_block(function, "skip_lines");
+    let done_block = compiler.context.append_basic_block(function, "line_done");
+
+    let line = load_int(compiler, line_ptr, "line");
+    let column = load_int(compiler, column_ptr, "column");
+    let is_past_line = builder
+        .build_int_compare(IntPredicate::SGT, line, target_line, "is_past_line")
+        .unwrap();
+    let is_on_line = builder
+        .build_int_compare(IntPredicate::EQ, line, target_line, "is_on_line")
+        .unwrap();
+    let has_written = builder
+        .build_int_compare(
+            IntPredicate::SGT,
+            column,
+            i32_type.const_zero(),
+            "has_written",
+        )
+        .unwrap();
+    let is_on_used_line = builder
+        .build_and(is_on_line, has_written, "is_on_used_line")
+        .unwrap();
+    let needs_new_page = builder
+        .build_or(is_past_line, is_on_used_line, "needs_new_page")
+        .unwrap();
+    builder
+        .build_conditional_branch(needs_new_page, new_page_block, move_down_block)
+        .unwrap();
+
+    builder.position_at_end(new_page_block);
+    builder
+        .build_call(
+            compiler
+                .module
+                .get_function(PUT_PAGE_FUNCTION_NAME)
+                .unwrap(),
+            &[file.into()],
+            "new_page",
+        )
+        .unwrap();
+    builder.build_unconditional_branch(move_down_block).unwrap();
+
+    builder.position_at_end(move_down_block);
+    let line = load_int(compiler, line_ptr, "line");
+    let lines_to_skip = builder
+        .build_int_sub(target_line, line, "lines_to_skip")
+        .unwrap();
+    let should_skip = builder
+        .build_int_compare(
+            IntPredicate::SGT,
+            lines_to_skip,
+            i32_type.const_zero(),
+            "should_skip",
+        )
+        .unwrap();
+    builder
+        .build_conditional_branch(should_skip, skip_block, done_block)
+        .unwrap();
+
+    builder.position_at_end(skip_block);
+    builder
+        .build_call(
+            compiler
+                .module
+                .get_function(PUT_SKIP_FUNCTION_NAME)
+                .unwrap(),
+            &[file.into(), lines_to_skip.into()],
+            "skip_to_line",
+        )
+        .unwrap();
+    builder.build_unconditional_branch(done_block).unwrap();
+
+    builder.position_at_end(done_block);
+    builder.build_return(None).unwrap();
+}
+
+///plick_put_item(file, string): writes one list-directed item.
+///Unless the line is empty, the item is moved to the next tab stop first,
+///and to a new line when no tab stop is left on the current line.
+fn add_put_item_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let i8_type = compiler.context.i8_type();
+    let i32_type = compiler.context.i32_type();
+    let function = create_runtime_function(
+        compiler,
+        PUT_ITEM_FUNCTION_NAME,
+        &[
+            get_file_parameter_type(compiler),
+            i8_type.ptr_type(AddressSpace::default()).into(),
+        ],
+    );
+    let file = function.get_first_param().unwrap().into_pointer_value();
+    let item = function.get_nth_param(1).unwrap().into_pointer_value();
+    let builder = compiler.builder;
+
+    let (column_ptr, line_ptr) = get_field_pointers(compiler, file);
+    let index_ptr = builder.build_alloca(i32_type, "char_index").unwrap();
+    builder
+        .build_store(index_ptr, i32_type.const_zero())
+        .unwrap();
+
+    let append_block =
+        |name: &str| -> BasicBlock<'ctx> { compiler.context.append_basic_block(function, name) };
+    let tab_block = append_block("tab");
+    let wrap_block = append_block("wrap");
+    let pad_block = append_block("pad");
+    let write_block = append_block("write");
+    let count_condition_block = append_block("count_condition");
+    let count_body_block = append_block("count_body");
+    let done_block = append_block("item_done");
+
+    let column = load_int(compiler, column_ptr, "column");
+    let is_line_empty = builder
+        .build_int_compare(
+            IntPredicate::EQ,
+            column,
+            i32_type.const_zero(),
+            "is_line_empty",
+        )
+        .unwrap();
+    builder
+        .build_conditional_branch(is_line_empty, write_block, tab_block)
+        .unwrap();
+
+    builder.position_at_end(tab_block);
+    let tab_width = i32_type.const_int(TAB_WIDTH, false);
+    let tab_index = builder
+        .build_int_signed_div(column, tab_width, "tab_index")
+        .unwrap();
+    let next_tab_index = builder
+        .build_int_add(tab_index, i32_type.const_int(1, false), "next_tab_index")
+        .unwrap();
+    let next_tab = builder
+        .build_int_mul(next_tab_index, tab_width, "next_tab")
+        .unwrap();
+    let is_past_line_end = builder
+        .build_int_compare(
+            IntPredicate::SGE,
+            next_tab,
+            i32_type.const_int(LINE_SIZE, false),
+            "is_past_line_end",
+        )
+        .unwrap();
+    builder
+        .build_conditional_branch(is_past_line_end, wrap_block, pad_block)
+        .unwrap();
+
+    builder.position_at_end(wrap_block);
+    builder
+        .build_call(
+            compiler
+                .module
+                .get_function(PUT_SKIP_FUNCTION_NAME)
+                .unwrap(),
+            &[file.into(), i32_type.const_int(1, false).into()],
+            "wrap_line",
+        )
+        .unwrap();
+    builder.build_unconditional_branch(write_block).unwrap();
+
+    builder.position_at_end(pad_block);
+    let padding = builder.build_int_sub(next_tab, column, "padding").unwrap();
+    let empty_string = builder
+        .build_global_string_ptr("", "empty_string")
+        .unwrap()
+        .as_pointer_value();
+    build_file_printf(
+        compiler,
+        file,
+        "%*s",
+        &[padding.into(), empty_string.into()],
+    );
+    builder.build_store(column_ptr, next_tab).unwrap();
+    builder.build_unconditional_branch(write_block).unwrap();
+
+    builder.position_at_end(write_block);
+    build_file_printf(compiler, file, "%s", &[item.into()]);
+    builder
+        .build_unconditional_branch(count_condition_block)
+        .unwrap();
+
+    //walk the written string to keep the column and line up to date
+    builder.position_at_end(count_condition_block);
+    let index = load_int(compiler, index_ptr, "index");
+    let char_ptr = unsafe { builder.build_gep(item, &[index], "char_ptr").unwrap() };
+    let current_char = load_int(compiler, char_ptr, "current_char");
+    let is_end_of_string = builder
+        .build_int_compare(
+            IntPredicate::EQ,
+            current_char,
+            i8_type.const_zero(),
+            "is_end_of_string",
+        )
+        .unwrap();
+    builder
+        .build_conditional_branch(is_end_of_string, done_block, count_body_block)
+        .unwrap();
+
+    builder.position_at_end(count_body_block);
+    let is_newline = builder
+        .build_int_compare(
+            IntPredicate::EQ,
+            current_char,
+            i8_type.const_int(NEWLINE_ASCII_CODE, false),
+            "is_newline",
+        )
+        .unwrap();
+    let column = load_int(compiler, column_ptr, "column");
+    let line = load_int(compiler, line_ptr, "line");
+    let next_column = builder
+        .build_int_add(column, i32_type.const_int(1, false), "next_column")
+        .unwrap();
+    let next_line = builder
+        .build_int_add(line, i32_type.const_int(1, false), "next_line")
+        .unwrap();
+    let new_column = builder
+        .build_select(is_newline, i32_type.const_zero(), next_column, "new_column")
+        .unwrap();
+    let new_line = builder
+        .build_select(is_newline, next_line, line, "new_line")
+        .unwrap();
+    builder.build_store(column_ptr, new_column).unwrap();
+    builder.build_store(line_ptr, new_line).unwrap();
+    let next_index = builder
+        .build_int_add(index, i32_type.const_int(1, false), "next_index")
+        .unwrap();
+    builder.build_store(index_ptr, next_index).unwrap();
+    builder
+        .build_unconditional_branch(count_condition_block)
+        .unwrap();
+
+    builder.position_at_end(done_block);
+    builder.build_return(None).unwrap();
+}
+
+///plick_open(file, title, attributes): opens the file with the given attributes added to
+///its declared ones. A null title means the name of the file is used as its title.
+///Files without INPUT or OUTPUT are opened for INPUT.
+fn add_open_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let i8_ptr_type = compiler.context.i8_type().ptr_type(AddressSpace::default());
+    let i32_type = compiler.context.i32_type();
+    let function = create_runtime_function(
+        compiler,
+        OPEN_FUNCTION_NAME,
+        &[
+            get_file_parameter_type(compiler),
+            i8_ptr_type.into(),
+            i32_type.into(),
+        ],
+    );
+    let file = function.get_first_param().unwrap().into_pointer_value();
+    let title = function.get_nth_param(1).unwrap().into_pointer_value();
+    let opened_attributes = function.get_nth_param(2).unwrap().into_int_value();
+    let builder = compiler.builder;
+
+    let (column_ptr, line_ptr) = get_field_pointers(compiler, file);
+    let attributes_ptr = builder
+        .build_struct_gep(file, ATTRIBUTES_FIELD, "attributes_ptr")
+        .unwrap();
+    let handle_ptr = builder
+        .build_struct_gep(file, HANDLE_FIELD, "handle_ptr")
+        .unwrap();
+
+    let open_block = compiler.context.append_basic_block(function, "open");
+    let fopen_block = compiler.context.append_basic_block(function, "fopen");
+    let failed_block = compiler.context.append_basic_block(function, "open_failed");
+    let mark_open_block = compiler.context.append_basic_block(function, "mark_open");
+    let done_block = compiler.context.append_basic_block(function, "open_done");
+
+    let attributes = load_int(compiler, attributes_ptr, "attributes");
+    let is_open = build_has_flag(compiler, attributes, OPEN_FLAG, "is_open");
+    builder
+        .build_conditional_branch(is_open, done_block, open_block)
+        .unwrap();
+
+    builder.position_at_end(open_block);
+    let attributes = builder
+        .build_or(attributes, opened_attributes, "merged_attributes")
+        .unwrap();
+    let has_direction = build_has_flag(
+        compiler,
+        attributes,
+        INPUT_FLAG | OUTPUT_FLAG | UPDATE_FLAG,
+        "has_direction",
+    );
+    let attributes_as_input = builder
+        .build_or(
+            attributes,
+            i32_type.const_int(INPUT_FLAG, false),
+            "attributes_as_input",
+        )
+        .unwrap();
+    let attributes = builder
+        .build_select(has_direction, attributes, attributes_as_input, "attributes")
+        .unwrap()
+        .into_int_value();
+    builder
+        .build_store(column_ptr, i32_type.const_zero())
+        .unwrap();
+    builder
+        .build_store(line_ptr, i32_type.const_int(1, false))
+        .unwrap();
+
+    let is_standard = build_has_flag(compiler, attributes, STANDARD_FILE_FLAG, "is_standard");
+    let has_no_title = builder.build_is_null(title, "has_no_title").unwrap();
+    let uses_standard_stream = builder
+        .build_and(is_standard, has_no_title, "uses_standard_stream")
+        .unwrap();
+    builder
+        .build_conditional_branch(uses_standard_stream, mark_open_block, fopen_block)
+        .unwrap();
+
+    builder.position_at_end(fopen_block);
+    let name = load_name(compiler, file);
+    let title = builder
+        .build_select(has_no_title, name, title, "title")
+        .unwrap()
+        .into_pointer_value();
+    let mode = build_open_mode(compiler, attributes);
+    let handle = builder
+        .build_call(
+            compiler.module.get_function("fopen").unwrap(),
+            &[title.into(), mode.into()],
+            "fopen",
+        )
+        .unwrap()
+        .try_as_basic_value()
+        .left()
+        .unwrap()
+        .into_pointer_value();
+    builder.build_store(handle_ptr, handle).unwrap();
+    let has_failed = builder.build_is_null(handle, "has_failed").unwrap();
+    builder
+        .build_conditional_branch(has_failed, failed_block, mark_open_block)
+        .unwrap();
+
+    builder.position_at_end(failed_block);
+    build_runtime_error(
+        compiler,
+        "UNDEFINEDFILE condition raised: could not open file %s\n",
+        title,
+    );
+
+    builder.position_at_end(mark_open_block);
+    let open_attributes = builder
+        .build_or(
+            attributes,
+            i32_type.const_int(OPEN_FLAG, false),
+            "open_attributes",
+        )
+        .unwrap();
+    builder
+        .build_store(attributes_ptr, open_attributes)
+        .unwrap();
+    builder.build_unconditional_branch(done_block).unwrap();
+
+    builder.position_at_end(done_block);
+    builder.build_return(None).unwrap();
+}
+
+///Picks the fopen mode for a file with the given attributes.
+///Record files are binary, and KEYED ones have to be read while they are written.
+fn build_open_mode<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    attributes: IntValue<'ctx>,
+) -> PointerValue<'ctx> {
+    let builder = compiler.builder;
+    let is_output = build_has_flag(compiler, attributes, OUTPUT_FLAG, "is_output");
+    let is_record = build_has_flag(compiler, attributes, RECORD_FLAG, "is_record");
+    let is_keyed = build_has_flag(compiler, attributes, KEYED_FLAG, "is_keyed");
+    let is_update = build_has_flag(compiler, attributes, UPDATE_FLAG, "is_update");
+
+    let get_mode = |mode: &str| -> PointerValue<'ctx> {
+        builder
+            .build_global_string_ptr(mode, "open_mode")
+            .unwrap()
+            .as_pointer_value()
+    };
+    let select_mode = |condition: IntValue<'ctx>,
+                       then_mode: PointerValue<'ctx>,
+                       else_mode: PointerValue<'ctx>|
+     -> PointerValue<'ctx> {
+        builder
+            .build_select(condition, then_mode, else_mode, "mode")
+            .unwrap()
+            .into_pointer_value()
+    };
+
+    let stream_mode = select_mode(is_output, get_mode("w"), get_mode("r"));
+    let record_output_mode = select_mode(is_keyed, get_mode("w+b"), get_mode("wb"));
+    let record_input_mode = select_mode(is_update, get_mode("r+b"), get_mode("rb"));
+    let record_mode = select_mode(is_output, record_output_mode, record_input_mode);
+
+    select_mode(is_record, record_mode, stream_mode)
+}
+
+///plick_close(file): closes the file if it is open, dropping the attributes it was opened with.
+fn add_close_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let function = create_runtime_function(
+        compiler,
+        CLOSE_FUNCTION_NAME,
+        &[get_file_parameter_type(compiler)],
+    );
+    let file = function.get_first_param().unwrap().into_pointer_value();
+    let builder = compiler.builder;
+
+    let attributes_ptr = builder
+        .build_struct_gep(file, ATTRIBUTES_FIELD, "attributes_ptr")
+        .unwrap();
+    let handle_ptr = builder
+        .build_struct_gep(file, HANDLE_FIELD, "handle_ptr")
+        .unwrap();
+
+    let close_block = compiler.context.append_basic_block(function, "close");
+    let fclose_block = compiler.context.append_basic_block(function, "fclose");
+    let mark_closed_block = compiler.context.append_basic_block(function, "mark_closed");
+    let done_block = compiler.context.append_basic_block(function, "close_done");
+
+    let attributes = load_int(compiler, attributes_ptr, "attributes");
+    let is_open = build_has_flag(compiler, attributes, OPEN_FLAG, "is_open");
+    builder
+        .build_conditional_branch(is_open, close_block, done_block)
+        .unwrap();
+
+    builder.position_at_end(close_block);
+    let handle = load_handle(compiler, file);
+    let is_standard = builder.build_is_null(handle, "is_standard").unwrap();
+    builder
+        .build_conditional_branch(is_standard, mark_closed_block, fclose_block)
+        .unwrap();
+
+    builder.position_at_end(fclose_block);
+    builder
+        .build_call(
+            compiler.module.get_function("fclose").unwrap(),
+            &[handle.into()],
+            "fclose",
+        )
+        .unwrap();
+    builder
+        .build_store(handle_ptr, handle.get_type().const_null())
+        .unwrap();
+    builder
+        .build_unconditional_branch(mark_closed_block)
+        .unwrap();
+
+    //the attributes given when the file was opened only last until it is closed
+    builder.position_at_end(mark_closed_block);
+    let declared_attributes_ptr = builder
+        .build_struct_gep(file, DECLARED_ATTRIBUTES_FIELD, "declared_attributes_ptr")
+        .unwrap();
+    let declared_attributes = load_int(compiler, declared_attributes_ptr, "declared_attributes");
+    builder
+        .build_store(attributes_ptr, declared_attributes)
+        .unwrap();
+    builder.build_unconditional_branch(done_block).unwrap();
+
+    builder.position_at_end(done_block);
+    builder.build_return(None).unwrap();
+}
+
+///plick_read_item(file, format, target): reads one item into target with scanf or fscanf,
+///returning what they returned.
+fn add_read_item_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let i8_ptr_type = compiler.context.i8_type().ptr_type(AddressSpace::default());
+    let i32_type = compiler.context.i32_type();
+    let function_type = i32_type.fn_type(
+        &[
+            get_file_parameter_type(compiler),
+            i8_ptr_type.into(),
+            i8_ptr_type.into(),
+        ],
+        false,
+    );
+    let function =
+        create_runtime_function_of_type(compiler, READ_ITEM_FUNCTION_NAME, function_type);
+    let file = function.get_first_param().unwrap().into_pointer_value();
+    let format_string = function.get_nth_param(1).unwrap().into_pointer_value();
+    let target = function.get_nth_param(2).unwrap().into_pointer_value();
+    let builder = compiler.builder;
+
+    let stdin_block = compiler.context.append_basic_block(function, "from_stdin");
+    let handle_block = compiler.context.append_basic_block(function, "from_handle");
+    let done_block = compiler.context.append_basic_block(function, "read_done");
+
+    let handle = load_handle(compiler, file);
+    let is_standard = builder.build_is_null(handle, "is_standard").unwrap();
+    builder
+        .build_conditional_branch(is_standard, stdin_block, handle_block)
+        .unwrap();
+
+    builder.position_at_end(stdin_block);
+    let scanf_result = builder
+        .build_call(
+            compiler.module.get_function("scanf").unwrap(),
+            &[format_string.into(), target.into()],
+            "scanf",
+        )
+        .unwrap()
+        .try_as_basic_value()
+        .left()
+        .unwrap();
+    builder.build_unconditional_branch(done_block).unwrap();
+
+    builder.position_at_end(handle_block);
+    let fscanf_result = builder
+        .build_call(
+            compiler.module.get_function("fscanf").unwrap(),
+            &[handle.into(), format_string.into(), target.into()],
+            "fscanf",
+        )
+        .unwrap()
+        .try_as_basic_value()
+        .left()
+        .unwrap();
+    builder.build_unconditional_branch(done_block).unwrap();
+
+    builder.position_at_end(done_block);
+    let result = builder.build_phi(i32_type, "read_result").unwrap();
+    result.add_incoming(&[(&scanf_result, stdin_block), (&fscanf_result, handle_block)]);
+    builder
+        .build_return(Some(&result.as_basic_value()))
+        .unwrap();
+}
+
+///plick_raise_endfile(file): what happens when a GET reads past the end of a file
+///that has no ON ENDFILE unit.
+fn add_raise_endfile_function<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) {
+    let function = create_runtime_function(
+        compiler,
+        RAISE_ENDFILE_FUNCTION_NAME,
+        &[get_file_parameter_type(compiler)],
+    );
+    let file = function.get_first_param().unwrap().into_pointer_value();
+    let name = load_name(compiler, file);
+
+    build_runtime_error(compiler, "ENDFILE condition raised on file %s\n", name);
+}
@@ -1,10 +1,13 @@
-use std::cell::RefCell;
+use std::{cell::RefCell, collections::HashMap};
 
 use super::Config;
 
-use crate::codegen::codegen::Compiler;
+use crate::{ast::SourceLocation, codegen::codegen::Compiler};
 use inkwell::{
-    debug_info::{self, DICompileUnit, DILexicalBlock, DIScope, DebugInfoBuilder},
+    context::Context,
+    debug_info::{
+        self, AsDIScope, DICompileUnit, DILexicalBlock, DILocation, DIScope, DebugInfoBuilder,
+    },
     module::Module,
 };
 
@@ -14,7 +17,7 @@ use inkwell::{
 pub struct DebugController<'ctx> {
     pub builder: DebugInfoBuilder<'ctx>,
     pub compile_unit: DICompileUnit<'ctx>,
-    pub lexical_blocks: RefCell<Vec<DIScope<'ctx>>>,
+    pub lexical_blocks: RefCell<Vec<ProcedureScope<'ctx>>>,
 
     pub line_number: RefCell<u32>,
     pub column_number: RefCell<u32>,
@@ -24,6 +27,66 @@ pub struct DebugController<'ctx> {
     pub optimized: bool,
 }
 
+///The scope of a procedure being generated, and the lexical blocks its
+///statements from other files, spliced in by %INCLUDE, are described in.
+#[derive(Debug)]
+pub struct ProcedureScope<'ctx> {
+    pub scope: DIScope<'ctx>,
+    ///The file the procedure was written in, None for the unit itself.
+    pub file_name: Option<String>,
+    file_blocks: HashMap<String, DILexicalBlock<'ctx>>,
+}
+
+impl<'ctx> ProcedureScope<'ctx> {
+    pub fn new(scope: DIScope<'ctx>, file_name: Option<String>) -> ProcedureScope<'ctx> {
+        ProcedureScope {
+            scope,
+            file_name,
+            file_blocks: HashMap::new(),
+        }
+    }
+}
+
+impl<'ctx> DebugController<'ctx> {
+    ///The location of a statement written at `location` in the procedure being
+    ///generated. A statement from another file than the procedure's is put in a
+    ///lexical block of that file, so DWARF reports it in the file it is in.
+    pub fn create_statement_location(
+        &self,
+        context: &'ctx Context,
+        location: &SourceLocation,
+    ) -> Option<DILocation<'ctx>> {
+        let mut procedures = self.lexical_blocks.borrow_mut();
+        let procedure = procedures.last_mut()?;
+        let scope = if location.file_name == procedure.file_name {
+            procedure.scope
+        } else {
+            let file_name = location
+                .file_name
+                .clone()
+                .unwrap_or_else(|| self.filename.clone());
+            let parent = procedure.scope;
+            procedure
+                .file_blocks
+                .entry(file_name)
+                .or_insert_with_key(|file_name| {
+                    let file = self.builder.create_file(file_name, &self.directory);
+                    self.builder
+                        .create_lexical_block(parent, file, location.line_number, 0)
+                })
+                .as_debug_info_scope()
+        };
+
+        Some(self.builder.create_debug_location(
+            context,
+            location.line_number,
+            location.column_number,
+            scope,
+            None,
+        ))
+    }
+}
+
 pub fn setup_module_for_debugging<'a, 'ctx>(
     m: &'a Module<'ctx>,
     config: &Config,
@@ -45,5 +45,36 @@ create_errors! {
     E010: "Cannot find a block named '[0]'",
     E011: "Cannot define a block more than once: '[0]'",
     E012: "Variable '[0]' is undefined!",
+    E013: "File '[0]' has not been declared",
+    E014: "KEYTO variable '[0]' must be declared CHARACTER",
+    E015: "BASED variable '[0]' has no locator, qualify it with one as in P->[0]",
+    E016: "The locator of '[0]' must be a POINTER, found a '[1]'",
+    E017: "'[0]' is not BASED, so it can't be qualified by a locator",
+    E018: "Only BASED and CONTROLLED variables can be allocated or freed, '[0]' is neither",
+    E019: "SET can't be used when allocating CONTROLLED variable '[0]'",
+    E020: "INITIAL values of STATIC or EXTERNAL variable '[0]' must be constants of its type",
+    E021: "INITIAL of '[0]' has [1] values, but '[0]' only has room for [2]",
+    E022: "'[0]' is not an array, so it can't be subscripted",
+    E023: "Array '[0]' has to be subscripted, as in [0](1)",
+    E024: "EXTERNAL '[0]' is declared differently in [1] and [2]",
+    E025: "EXTERNAL '[0]' is defined in both [1] and [2]",
+    E026: "OPTIONS(C) entry '[0]' can't return a string",
+    E027: "Argument [0] of OPTIONS(C) entry '[1]' must be a [2]",
+    E028: "FIXED BINARY and CHAR(*) can only be used by OPTIONS(C) entries, '[0]' is a PL/1 procedure",
+    E029: "Procedure '[0]' can't be exported to C as '[1]', the name is already used",
+    E030: "Can't find '[0]', included on line [1] of [2]",
+    E031: "'[0]' includes itself",
+    E032: "Preprocessor error on line [0]: [1]",
+    E033: "Preprocessor value '[0]' on line [1] is not a number",
+    E034: "[0] [1]: '[1]' is not the label of a DO group containing it",
+    E035: "[0] has to be inside a DO group",
+    E036: "ENTRY variable '[0]' can only be AUTOMATIC",
+    E037: "'[0]' can't be called, it wasn't declared with the parameters of the procedures it holds",
+    E038: "No procedure of GENERIC '[0]' takes arguments ([1]), the candidates are: [2]",
+    E039: "Picture '[0]' is not valid: [1]",
+    E040: "FLOAT [0]([1]) can't be held, the precision has to be between 1 and [2]",
+    E041: "FIXED [0]([1],[2]) can't be held, the precision has to be between 1 and [3] and the scale between 0 and the precision",
+    E042: "INITIAL value of '[0]' has more digits before the point than it can hold",
+    E043: "A condition has to be a number, not a value of type '[0]'",
 
 }
@@ -0,0 +1,219 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    codegen::c_interface::ExportedProcedure,
+    types::{float_decimal::FloatFormat, Type},
+};
+
+///Generates the C header declaring the procedures exported by the compiled
+///units. Every PL/1 type gets a typedef laid out like the LLVM type plick
+///stores it as.
+pub fn generate_header(header_name: &str, procedures: &[ExportedProcedure]) -> String {
+    let guard = get_include_guard(header_name);
+    let mut lines: Vec<String> = vec![
+        format!("/* Generated by plick from {}. */", header_name),
+        format!("#ifndef {}", guard),
+        format!("#define {}", guard),
+        String::new(),
+        "#include <stdbool.h>".to_string(),
+        "#include <stdint.h>".to_string(),
+        String::new(),
+    ];
+
+    //every type is only declared once, in a stable order
+    let used_types: BTreeSet<String> = procedures
+        .iter()
+        .flat_map(|procedure| {
+            procedure
+                .parameters
+                .iter()
+                .map(|parameter| parameter._type)
+                .chain(std::iter::once(procedure.return_type))
+        })
+        //a complex number needs the typedef of its parts too
+        .flat_map(|_type| match _type.is_complex() {
+            true => vec![_type.get_real_type(), _type],
+            false => vec![_type],
+        })
+        .filter_map(get_typedef)
+        .collect();
+    for typedef in used_types {
+        lines.push(typedef);
+        lines.push(String::new());
+    }
+
+    for procedure in procedures {
+        lines.push(get_declaration(procedure));
+    }
+
+    lines.push(String::new());
+    lines.push(format!("#endif /* {} */", guard));
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn get_include_guard(header_name: &str) -> String {
+    let guard: String = header_name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_uppercase(),
+            false => '_',
+        })
+        .collect();
+    format!("PLICK_{}_H", guard)
+}
+
+///The C name of `_type`.
+pub fn get_c_type_name(_type: Type) -> String {
+    match _type {
+        Type::FixedDecimal(precision, scale) => {
+            format!("plick_fixed_decimal_{}_{}", precision, scale)
+        }
+        Type::Float(precision) => match FloatFormat::from_precision(precision) {
+            FloatFormat::Single => "plick_float_decimal_single".to_string(),
+            FloatFormat::Double => "plick_float_decimal".to_string(),
+            FloatFormat::Extended => "plick_float_decimal_extended".to_string(),
+        },
+        Type::Char(size) => format!("plick_char_{}", size),
+        //a pictured number is passed as its edited characters
+        Type::Picture(picture) => get_c_type_name(Type::Char(picture.get_length())),
+        Type::VaryingChar(max_length) => format!("plick_varying_char_{}", max_length),
+        //a procedure is passed to C as its address
+        Type::Pointer | Type::Entry => "void *".to_string(),
+        Type::FixedComplex => "plick_fixed_complex".to_string(),
+        Type::FloatComplex => "plick_float_complex".to_string(),
+        Type::Void => "void".to_string(),
+        Type::Label => panic!("Can't export a procedure using LABEL values to C!"),
+        Type::TBD => panic!("Can't export a procedure using TBD types to C!"),
+    }
+}
+
+fn get_typedef(_type: Type) -> Option<String> {
+    let name = get_c_type_name(_type);
+    match _type {
+        //the value is stored scaled to an integer
+        Type::FixedDecimal(_precision, scale) => Some(format!(
+            "/* The value times 10^{}. */\ntypedef __int128 {};",
+            scale, name
+        )),
+        Type::Float(precision) => {
+            Some(format!("typedef {} {};", get_c_float_name(precision), name))
+        }
+        //one more character for the NUL terminator
+        Type::Char(size) => Some(format!("typedef char {}[{}];", name, size + 1)),
+        Type::Picture(picture) => get_typedef(Type::Char(picture.get_length())),
+        Type::VaryingChar(max_length) => Some(format!(
+            "typedef struct {{\n    int32_t length;\n    char characters[{}];\n}} {};",
+            max_length + 1,
+            name
+        )),
+        //the typedefs of the parts sort before this one
+        Type::FixedComplex | Type::FloatComplex => Some(format!(
+            "typedef struct {{\n    {part} real;\n    {part} imaginary;\n}} {};",
+            name,
+            part = get_c_type_name(_type.get_real_type())
+        )),
+        Type::Pointer | Type::Label | Type::Entry | Type::Void | Type::TBD => None,
+    }
+}
+
+///The C type FLOAT DECIMAL(`precision`) keeps its value in.
+fn get_c_float_name(precision: u32) -> &'static str {
+    match FloatFormat::from_precision(precision) {
+        FloatFormat::Single => "float",
+        FloatFormat::Double => "double",
+        FloatFormat::Extended => "__float128",
+    }
+}
+
+fn get_declaration(procedure: &ExportedProcedure) -> String {
+    let mut parameters: Vec<String> = vec![];
+    if procedure.return_type != Type::Void {
+        parameters.push(get_pointer_parameter(
+            procedure.return_type,
+            "result",
+            false,
+        ));
+    }
+    for parameter in procedure.parameters.iter() {
+        parameters.push(get_pointer_parameter(
+            parameter._type,
+            &parameter.name.to_lowercase(),
+            true,
+        ));
+    }
+    if parameters.is_empty() {
+        parameters.push("void".to_string());
+    }
+
+    format!("void {}({});", procedure.c_name, parameters.join(", "))
+}
+
+fn get_pointer_parameter(_type: Type, name: &str, is_const: bool) -> String {
+    let c_type = get_c_type_name(_type);
+    let qualifier = if is_const { "const " } else { "" };
+    match c_type.ends_with('*') {
+        //a const pointer to a pointer keeps the pointer itself writable
+        true => format!(
+            "{}{}{}",
+            c_type,
+            if is_const { "const *" } else { "*" },
+            name
+        ),
+        false => format!("{}{} *{}", qualifier, c_type, name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::PrototypeArgument;
+
+    #[test]
+    fn declare_exported_procedures() {
+        let procedures = vec![
+            ExportedProcedure {
+                c_name: "add_one".to_string(),
+                parameters: vec![PrototypeArgument {
+                    name: "AMOUNT".to_string(),
+                    _type: Type::FixedDecimal(7, 2),
+                }],
+                return_type: Type::FixedDecimal(8, 2),
+            },
+            ExportedProcedure {
+                c_name: "greet".to_string(),
+                parameters: vec![PrototypeArgument {
+                    name: "NAME".to_string(),
+                    _type: Type::VaryingChar(10),
+                }],
+                return_type: Type::Void,
+            },
+        ];
+
+        let header = generate_header("adding", &procedures);
+
+        assert!(header.contains("#ifndef PLICK_ADDING_H"));
+        assert!(header
+            .contains("/* The value times 10^2. */\ntypedef __int128 plick_fixed_decimal_7_2;"));
+        assert!(header
+            .contains("/* The value times 10^2. */\ntypedef __int128 plick_fixed_decimal_8_2;"));
+        assert!(header.contains("    char characters[11];\n} plick_varying_char_10;"));
+        assert!(header.contains(
+            "void add_one(plick_fixed_decimal_8_2 *result, const plick_fixed_decimal_7_2 *amount);"
+        ));
+        assert!(header.contains("void greet(const plick_varying_char_10 *name);"));
+    }
+
+    #[test]
+    fn float_typedefs_hold_the_format_of_the_precision() {
+        assert_eq!(
+            Some("typedef float plick_float_decimal_single;".to_string()),
+            get_typedef(Type::Float(6))
+        );
+        assert_eq!("plick_float_decimal", get_c_type_name(Type::Float(16)));
+        assert_eq!(
+            "plick_float_decimal_extended",
+            get_c_type_name(Type::Float(33))
+        );
+    }
+}
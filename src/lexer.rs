@@ -1,4 +1,4 @@
-use crate::{ast::SourceLocation, debugger::DebugController};
+use crate::{ast::SourceLocation, debugger::DebugController, preprocessor::ExpandedSource};
 use log::{debug, error, info, log_enabled, trace, Level};
 
 pub fn get_token_list(compilable_file: &str) -> Vec<Token> {
@@ -36,6 +36,22 @@ impl<'a, 'b> TokenManager<'a, 'b> {
         result
     }
 
+    ///Tokenizes a unit after its %INCLUDEs were expanded. Source locations
+    ///still point at the file and line the tokens were written on.
+    pub fn new_expanded(source: &ExpandedSource) -> TokenManager {
+        let mut token_iter = TokenIterator::new(source.text.chars());
+        token_iter.expanded_source = Some(source);
+
+        let mut result = TokenManager {
+            current_token: None,
+            token_iter,
+        };
+
+        result.next_token();
+
+        result
+    }
+
     pub fn attach_debugger(&mut self, dbg: &'a DebugController<'b>) {
         self.token_iter.dbg_info = Some(dbg);
     }
@@ -53,8 +69,10 @@ impl<'a, 'b> TokenManager<'a, 'b> {
     }
 
     pub fn get_source_location(&self) -> SourceLocation {
+        let (file_name, line_number) = self.token_iter.get_original_line();
         SourceLocation {
-            line_number: self.token_iter.line_number,
+            file_name,
+            line_number,
             column_number: self.token_iter.column_number,
         }
     }
@@ -66,6 +84,8 @@ struct TokenIterator<'a, 'b> {
     pub dbg_info: Option<&'a DebugController<'b>>,
     pub line_number: u32,
     pub column_number: u32,
+    ///Maps the lines back to where they were before %INCLUDEs were expanded.
+    expanded_source: Option<&'a ExpandedSource>,
 }
 impl<'a, 'b> TokenIterator<'a, 'b> {
     fn new(char_iter: std::str::Chars<'_>) -> TokenIterator {
@@ -75,6 +95,18 @@ impl<'a, 'b> TokenIterator<'a, 'b> {
             line_number: 1,
             column_number: 0,
             dbg_info: None,
+            expanded_source: None,
+        }
+    }
+    ///The file (None for the unit itself) and line the current character was
+    ///written on.
+    fn get_original_line(&self) -> (Option<String>, u32) {
+        match self.expanded_source {
+            Some(source) => {
+                let line = source.get_original_line(self.line_number);
+                (line.file_name, line.line_number)
+            }
+            None => (None, self.line_number),
         }
     }
     fn get_next_char(&mut self) -> Option<char> {
@@ -87,14 +119,16 @@ impl<'a, 'b> TokenIterator<'a, 'b> {
         }
 
         if let Some(ref dbg) = self.dbg_info {
-            *dbg.line_number.borrow_mut() = self.line_number;
+            *dbg.line_number.borrow_mut() = self.get_original_line().1;
             *dbg.column_number.borrow_mut() = self.column_number;
         }
 
         self.next_char
     }
     fn is_character_special(ch: char) -> bool {
-        let special_chars = vec!['/', '(', ')', '\'', '+', '-', '*', ',', '=', ';', '<', '>'];
+        let special_chars = vec![
+            '/', '(', ')', '\'', '+', '-', '*', ',', '=', ';', '<', '>', '|',
+        ];
 
         if special_chars.contains(&ch) {
             true
@@ -140,6 +174,26 @@ impl<'a, 'b> TokenIterator<'a, 'b> {
         }
         Some(Token::MULTIPLY)
     }
+
+    fn process_bar(&mut self) -> Option<Token> {
+        let current_char = self.get_next_char()?;
+
+        if current_char == '|' {
+            self.get_next_char();
+            return Some(Token::CONCATENATE);
+        }
+        convert_string_to_token("|")
+    }
+
+    fn process_minus(&mut self) -> Option<Token> {
+        let current_char = self.get_next_char()?;
+
+        if current_char == '>' {
+            self.get_next_char();
+            return Some(Token::ARROW);
+        }
+        Some(Token::MINUS)
+    }
 }
 impl Iterator for TokenIterator<'_, '_> {
     type Item = Token;
@@ -175,6 +229,10 @@ impl Iterator for TokenIterator<'_, '_> {
                 }
             } else if is_special && current_character == '*' {
                 return Some(self.process_mult().unwrap());
+            } else if is_special && current_character == '|' {
+                return self.process_bar();
+            } else if is_special && current_character == '-' {
+                return self.process_minus();
             }
 
             //we have skipped over all the whitespace and are now building are buffer.
@@ -207,8 +265,8 @@ impl Iterator for TokenIterator<'_, '_> {
 }
 
 pub fn convert_string_to_token(input: &str) -> Option<Token> {
-    if let Ok(number) = input.parse() {
-        return Some(Token::NumVal(number));
+    if input.parse::<f64>().is_ok() {
+        return Some(Token::NumVal(input.to_string()));
     }
     if input.len() == 0 {
         return None;
@@ -240,14 +298,64 @@ pub fn convert_string_to_token(input: &str) -> Option<Token> {
         "DATA" => Token::DATA,
         "END" => Token::END,
         "WHILE" => Token::WHILE,
+        "TO" => Token::TO,
+        "BY" => Token::BY,
+        "LEAVE" => Token::LEAVE,
+        "ITERATE" => Token::ITERATE,
+        "STOP" | "EXIT" => Token::STOP,
         "LIST" => Token::LIST,
         "SKIP" => Token::SKIP,
-        "GO" => Token::GO,
+        "PAGE" => Token::PAGE,
+        "LINE" => Token::LINE,
+        "GO" | "GOTO" => Token::GO,
         "DECLARE" | "DCL" => Token::DECLARE,
         "CHARACTER" | "CHAR" => Token::CHARACTER,
+        "VARYING" | "VAR" => Token::VARYING,
+        "PICTURE" | "PIC" => Token::PICTURE,
+        "||" => Token::CONCATENATE,
         "OPTIONS" => Token::OPTIONS,
         "AND" | "&" => Token::AND,
         "NOT" => Token::NOT,
+        "FILE" => Token::FILE,
+        "STREAM" => Token::STREAM,
+        "INPUT" => Token::INPUT,
+        "OUTPUT" => Token::OUTPUT,
+        "PRINT" => Token::PRINT,
+        "OPEN" => Token::OPEN,
+        "CLOSE" => Token::CLOSE,
+        "TITLE" => Token::TITLE,
+        "ON" => Token::ON,
+        "ENDFILE" => Token::ENDFILE,
+        "RECORD" => Token::RECORD,
+        "SEQUENTIAL" => Token::SEQUENTIAL,
+        "DIRECT" => Token::DIRECT,
+        "KEYED" => Token::KEYED,
+        "UPDATE" => Token::UPDATE,
+        "READ" => Token::READ,
+        "WRITE" => Token::WRITE,
+        "REWRITE" => Token::REWRITE,
+        "DELETE" => Token::DELETE,
+        "INTO" => Token::INTO,
+        "FROM" => Token::FROM,
+        "KEY" => Token::KEY,
+        "KEYTO" => Token::KEYTO,
+        "KEYFROM" => Token::KEYFROM,
+        "POINTER" | "PTR" => Token::POINTER,
+        "BASED" => Token::BASED,
+        "CONTROLLED" | "CTL" => Token::CONTROLLED,
+        "STATIC" => Token::STATIC,
+        "AUTOMATIC" | "AUTO" => Token::AUTOMATIC,
+        "INITIAL" | "INIT" => Token::INITIAL,
+        "EXTERNAL" | "EXT" => Token::EXTERNAL,
+        "ENTRY" => Token::ENTRY,
+        "RETURNS" => Token::RETURNS,
+        "BINARY" | "BIN" => Token::BINARY,
+        "DECIMAL" | "DEC" => Token::DECIMAL,
+        "ALLOCATE" | "ALLOC" => Token::ALLOCATE,
+        "FREE" => Token::FREE,
+        "SET" => Token::SET,
+        "DEFAULT" | "DFT" => Token::DEFAULT,
+        "->" => Token::ARROW,
         _ => Token::Identifier(input.to_owned()),
     })
 }
@@ -271,12 +379,19 @@ pub enum Token {
     DIVIDE,
     EXPONENT,
     WHILE,
+    TO,
+    BY,
+    LEAVE,
+    ITERATE,
+    STOP,
     END,
     PUT,
     LESS_THAN,
     GREATER_THAN,
     LABEL(String),
     SKIP,
+    PAGE,
+    LINE,
     STRING(String),
     EQ,
     SEMICOLON,
@@ -291,7 +406,52 @@ pub enum Token {
     NOT,
     LIST,
     CHARACTER,
-    NumVal(f64),        // integer
+    VARYING,
+    PICTURE,
+    CONCATENATE,
+    FILE,
+    STREAM,
+    INPUT,
+    OUTPUT,
+    PRINT,
+    OPEN,
+    CLOSE,
+    TITLE,
+    ON,
+    ENDFILE,
+    RECORD,
+    SEQUENTIAL,
+    DIRECT,
+    KEYED,
+    UPDATE,
+    READ,
+    WRITE,
+    REWRITE,
+    DELETE,
+    INTO,
+    FROM,
+    KEY,
+    KEYTO,
+    KEYFROM,
+    POINTER,
+    BASED,
+    CONTROLLED,
+    STATIC,
+    AUTOMATIC,
+    INITIAL,
+    EXTERNAL,
+    ENTRY,
+    RETURNS,
+    BINARY,
+    DECIMAL,
+    ALLOCATE,
+    FREE,
+    SET,
+    DEFAULT,
+    ///The locator qualifier "->" as in P->X
+    ARROW,
+    ///A number as it was written, so no digits of it are lost
+    NumVal(String),
     Identifier(String), //an identifier / variable name
 }
 
@@ -316,7 +476,7 @@ mod tests {
         let output: Vec<Token> = vec![
             Token::Identifier("FLAG".to_string()),
             Token::EQ,
-            Token::NumVal(0.0),
+            Token::NumVal("0".to_string()),
             Token::SEMICOLON,
         ];
 
@@ -327,9 +487,37 @@ mod tests {
         initialize_test_logger();
         let input = "5*5;";
         let token_list: Vec<Token> = vec![
-            Token::NumVal(5.0),
+            Token::NumVal("5".to_string()),
             Token::MULTIPLY,
-            Token::NumVal(5.0),
+            Token::NumVal("5".to_string()),
+            Token::SEMICOLON,
+        ];
+        assert_eq!(get_token_list(input), token_list);
+    }
+    #[test]
+    fn lex_touching_concatenate() {
+        let input = "A||'B' || C;";
+        let token_list: Vec<Token> = vec![
+            Token::Identifier("A".to_string()),
+            Token::CONCATENATE,
+            Token::STRING("B".to_string()),
+            Token::CONCATENATE,
+            Token::Identifier("C".to_string()),
+            Token::SEMICOLON,
+        ];
+        assert_eq!(get_token_list(input), token_list);
+    }
+    #[test]
+    fn lex_locator_qualifier() {
+        let input = "P->X = X - 1;";
+        let token_list: Vec<Token> = vec![
+            Token::Identifier("P".to_string()),
+            Token::ARROW,
+            Token::Identifier("X".to_string()),
+            Token::EQ,
+            Token::Identifier("X".to_string()),
+            Token::MINUS,
+            Token::NumVal("1".to_string()),
             Token::SEMICOLON,
         ];
         assert_eq!(get_token_list(input), token_list);
@@ -347,7 +535,7 @@ mod tests {
             SEMICOLON,
             Identifier(String::from("FLAG")),
             EQ,
-            NumVal(0.0),
+            NumVal("0".to_string()),
             SEMICOLON,
             LABEL(String::from("LOOP")),
             DO,
@@ -355,7 +543,7 @@ mod tests {
             OPEN_PAREN,
             Identifier(String::from("FLAG")),
             EQ,
-            NumVal(0.0),
+            NumVal("0".to_string()),
             CLOSED_PAREN,
             SEMICOLON,
             PUT,
@@ -392,7 +580,7 @@ mod tests {
             SEMICOLON,
             Identifier(String::from("FLAG")),
             EQ,
-            NumVal(0.0),
+            NumVal("0".to_string()),
             SEMICOLON,
             LABEL(String::from("LOOP")),
             DO,
@@ -400,7 +588,7 @@ mod tests {
             OPEN_PAREN,
             Identifier(String::from("FLAG")),
             EQ,
-            NumVal(0.0),
+            NumVal("0".to_string()),
             CLOSED_PAREN,
             SEMICOLON,
             PUT,
@@ -424,7 +612,12 @@ mod tests {
     #[test]
     fn eq_with_statement() {
         let input = "/*A program to assign a variable*/A = 4;";
-        let output = vec![Identifier(String::from("A")), EQ, NumVal(4.0), SEMICOLON];
+        let output = vec![
+            Identifier(String::from("A")),
+            EQ,
+            NumVal("4".to_string()),
+            SEMICOLON,
+        ];
 
         assert_eq!(get_token_list(input), output);
     }
@@ -435,9 +628,9 @@ mod tests {
         let output = vec![
             Identifier(String::from("MIN")),
             OPEN_PAREN,
-            NumVal(2.0),
+            NumVal("2".to_string()),
             COMMA,
-            NumVal(3.0),
+            NumVal("3".to_string()),
             CLOSED_PAREN,
             SEMICOLON,
         ];
@@ -455,7 +648,7 @@ mod tests {
     #[test]
     fn test_touching_binary_lexing() {
         let input = "2+2";
-        let output = vec![NumVal(2.0), PLUS, NumVal(2.0)];
+        let output = vec![NumVal("2".to_string()), PLUS, NumVal("2".to_string())];
 
         assert_eq!(get_token_list(input), output);
     }
@@ -1,5 +1,6 @@
 #![allow(unused_imports, dead_code, unreachable_code)]
-use cli::Arguments;
+use cli::{Arguments, Emit};
+use codegen::c_interface::ExportedProcedure;
 use codegen::codegen::{CodeGenable, Compiler};
 use error::errors::CodegenError;
 use inkwell::builder::Builder;
@@ -37,23 +38,26 @@ pub mod cli;
 mod codegen;
 mod debugger;
 mod error;
+mod header;
 pub mod lexer;
+mod linker;
 pub mod parser;
 mod passes;
+mod preprocessor;
 pub mod types;
 
 fn drive_compilation<'a, 'ctx>(
     token_manager: &mut TokenManager,
     compiler: &mut Compiler<'a, 'ctx>,
 ) -> Result<(), String> {
-    compiler.initalize_main_function();
+    let parse_result = perform_parse_pass(token_manager);
+
+    compiler.initialize_unit_function(parse_result.unit_procedure.as_ref());
 
     prelude::add_extern_functions(compiler);
     //prelude::add_standard_library_to_module(compiler.module);
 
     unsafe {
-        let parse_result = perform_parse_pass(token_manager);
-
         let parse_result = parse_result
             .perform_type_pass()?
             .code_generation_pass(compiler)?;
@@ -82,97 +86,168 @@ pub fn initialize_logger() {
     env_logger::init();
 }
 pub fn compile_input(input: &str, config: Config) -> CompilationResults {
-    execute_compilation_actions(
-        input,
-        &config,
-        &mut |token_manager, compiler, target_machine| {
-            let compilation_result = drive_compilation(token_manager, compiler);
-
-            if let Err(_err_msg) = compilation_result {
-                return Err(_err_msg);
-            }
-
-            if let Some(dbg) = compiler.debug_controller {
-                dbg.builder.finalize();
-            }
-
-            //comment for finalize says call before verification
-            if config.print_ir {
-                println!("{}", compiler.module.print_to_string());
-            }
-
-            if config.write_ir_to_file {
-                output_module_as_ir_to_file(&compiler, target_machine, &config);
-            }
-
-            if config.verify {
-                verify_module(&compiler).map_err(|llvm_str| llvm_str.to_string())?;
-            }
-            if config.write_ir_to_file {
-            } else if config.dry_run {
-                output_module_to_memory_buffer(&compiler, target_machine);
-            } else {
-                output_module_to_file(compiler, &config, target_machine);
-            }
-
-            Ok(())
-        },
-    )
+    let unit_name = config.filename.clone();
+    compile_units(&[(&unit_name, input)], config)
 }
 
-pub fn execute_compilation_actions<
-    F: FnMut(&mut TokenManager, &mut Compiler, &TargetMachine) -> Result<(), String>,
->(
-    input: &str,
-    config: &Config,
-    closure: &mut F,
-) -> CompilationResults {
-    let filename = config.filename.clone();
+///Compiles several units, each given as its name and its text, into a single
+///object file. A name declared EXTERNAL, or as an ENTRY, in one unit refers to
+///the variable or procedure of that name in the others.
+pub fn compile_units(units: &[(&str, &str)], config: Config) -> CompilationResults {
+    if config.preprocess_only {
+        return print_preprocessed_units(units, &config);
+    }
 
     let target_machine = build_default_target_machine(&config);
     //create compiler dependencies
     let c = context::Context::create();
     let b = c.create_builder();
-    let m = c.create_module("globalMod");
+    let modules: Vec<Module> = units
+        .iter()
+        .map(|(unit_name, _input)| c.create_module(unit_name))
+        .collect();
+
+    let mut errors: Vec<CodegenError> = vec![];
+    let mut exported_procedures: Vec<ExportedProcedure> = vec![];
+    for (m, (unit_name, input)) in modules.iter().zip(units) {
+        errors.append(&mut compile_unit(
+            unit_name,
+            input,
+            &c,
+            &b,
+            m,
+            &config,
+            &mut exported_procedures,
+        ));
+    }
+    if errors.is_empty() {
+        errors = linker::check_external_declarations(&modules);
+    }
+    log::info!("number of errors: {}", errors.len());
+    if !errors.is_empty() {
+        return CompilationResults::new(false, errors);
+    }
+
+    let output_result = if config.emit_header {
+        output_header(&exported_procedures, &config)
+    } else {
+        linker::link_units(modules).and_then(|m| output_module(&m, &target_machine, &config))
+    };
+    if let Err(message) = output_result {
+        return CompilationResults::new(false, vec![CodegenError { message }]);
+    }
+
+    CompilationResults::new(true, vec![])
+}
+
+///Prints the units the way the compiler sees them, after the preprocessor
+///expanded their %INCLUDEs and macros.
+fn print_preprocessed_units(units: &[(&str, &str)], config: &Config) -> CompilationResults {
+    let mut errors: Vec<CodegenError> = vec![];
+    for (unit_name, input) in units {
+        match preprocessor::preprocess(input, unit_name, &config.include_directories) {
+            Ok(source) => println!("{}", source.text),
+            Err(message) => errors.push(CodegenError { message }),
+        }
+    }
+    CompilationResults::new(errors.is_empty(), errors)
+}
+
+fn compile_unit<'ctx>(
+    unit_name: &str,
+    input: &str,
+    c: &'ctx Context,
+    b: &Builder<'ctx>,
+    m: &Module<'ctx>,
+    config: &Config,
+    exported_procedures: &mut Vec<ExportedProcedure>,
+) -> Vec<CodegenError> {
+    let source = match preprocessor::preprocess(input, unit_name, &config.include_directories) {
+        Ok(source) => source,
+        Err(message) => return vec![CodegenError { message }],
+    };
 
     let mut optional_debugger: Option<&DebugController<'_>> = None;
     let debugger: DebugController;
 
     if config.debug_mode {
-        debugger = setup_module_for_debugging(&m, &config);
+        debugger = setup_module_for_debugging(m, config);
         optional_debugger = Some(&debugger);
     }
 
-    let mut compiler =
-        codegen::codegen::Compiler::new(&c, &b, &m, optional_debugger, config.error_test);
+    let mut compiler = codegen::codegen::Compiler::new(
+        c,
+        b,
+        m,
+        optional_debugger,
+        config.error_test,
+        config.packed_decimal,
+    );
 
-    let mut token_manager = lexer::TokenManager::new(input);
+    let mut token_manager = lexer::TokenManager::new_expanded(&source);
 
     if let Some(dbg) = optional_debugger {
         token_manager.attach_debugger(dbg);
     }
-    let closure_result = closure(&mut token_manager, &mut compiler, &target_machine);
-    log::info!(
-        "number of errors: {}",
-        compiler.error_module.get_number_of_errors()
-    );
 
-    if let Err(msg) = closure_result {
+    let compilation_result = drive_compilation(&mut token_manager, &mut compiler);
+
+    if let Err(msg) = compilation_result {
         compiler.error_module.store_error_msg(&msg);
+    } else if let Some(dbg) = compiler.debug_controller {
+        dbg.builder.finalize();
     }
+    exported_procedures.append(&mut compiler.exported_procedures.borrow_mut());
 
-    CompilationResults::new(
-        compiler.error_module.get_number_of_errors() == 0,
-        compiler.error_module.get_all_errors(),
-    )
+    compiler.error_module.get_all_errors()
 }
 
-fn output_module_to_memory_buffer(
-    compiler: &Compiler,
+///Writes the C header declaring the exported procedures, named after the
+///output file.
+fn output_header(procedures: &[ExportedProcedure], config: &Config) -> Result<(), String> {
+    let path = Path::new(&config.filename);
+    let header_name = path.file_stem().unwrap().to_str().unwrap();
+    let header = header::generate_header(header_name, procedures);
+    if config.print_ir {
+        println!("{}", header);
+    }
+    if config.dry_run {
+        return Ok(());
+    }
+
+    fs::write(path, header).map_err(|err| format!("Couldn't write {}: {}", config.filename, err))
+}
+
+fn output_module(
+    m: &Module,
     target_machine: &TargetMachine,
-) -> MemoryBuffer {
+    config: &Config,
+) -> Result<(), String> {
+    //comment for finalize says call before verification
+    if config.print_ir {
+        println!("{}", m.print_to_string());
+    }
+
+    if config.write_ir_to_file {
+        output_module_as_ir_to_file(m, target_machine, config);
+    }
+
+    if config.verify {
+        verify_module(m).map_err(|llvm_str| llvm_str.to_string())?;
+    }
+    if config.write_ir_to_file {
+    } else if config.dry_run {
+        output_module_to_memory_buffer(m, target_machine);
+    } else {
+        output_module_to_file(m, config, target_machine);
+    }
+
+    Ok(())
+}
+
+fn output_module_to_memory_buffer(m: &Module, target_machine: &TargetMachine) -> MemoryBuffer {
     let write_to_memory_result =
-        target_machine.write_to_memory_buffer(&compiler.module, inkwell::targets::FileType::Object);
+        target_machine.write_to_memory_buffer(m, inkwell::targets::FileType::Object);
     match write_to_memory_result {
         Ok(memory_buffer) => memory_buffer,
         Err(err_message) => {
@@ -183,13 +258,9 @@ fn output_module_to_memory_buffer(
         }
     }
 }
-fn output_module_as_ir_to_file(
-    compiler: &Compiler,
-    target_machine: &TargetMachine,
-    config: &Config,
-) {
+fn output_module_as_ir_to_file(m: &Module, target_machine: &TargetMachine, config: &Config) {
     let write_to_memory_result =
-        target_machine.write_to_memory_buffer(&compiler.module, inkwell::targets::FileType::Object);
+        target_machine.write_to_memory_buffer(m, inkwell::targets::FileType::Object);
 
     let x = match write_to_memory_result {
         Ok(memory_buffer) => (),
@@ -201,10 +272,10 @@ fn output_module_as_ir_to_file(
         }
     };
     let file_name = Path::new(&config.filename);
-    compiler.module.print_to_file(file_name).unwrap();
+    m.print_to_file(file_name).unwrap();
 }
-fn verify_module(compiler: &Compiler) -> Result<(), LLVMString> {
-    let module_verification_result = compiler.module.verify();
+fn verify_module(m: &Module) -> Result<(), LLVMString> {
+    let module_verification_result = m.verify();
 
     let x = match module_verification_result {
         Ok(()) => {
@@ -213,15 +284,15 @@ fn verify_module(compiler: &Compiler) -> Result<(), LLVMString> {
         Err(ref err_message) => {
             error!("Module verification failed:");
             error!("{}", err_message);
-            compiler.module.print_to_file("error_module").unwrap();
+            m.print_to_file("error_module").unwrap();
         }
     };
     module_verification_result
 }
 
-fn output_module_to_file(compiler: &Compiler, config: &Config, target_machine: &TargetMachine) {
+fn output_module_to_file(m: &Module, config: &Config, target_machine: &TargetMachine) {
     let write_to_file_result = target_machine.write_to_file(
-        &compiler.module,
+        m,
         inkwell::targets::FileType::Object,
         Path::new(&config.filename),
     );
@@ -281,8 +352,12 @@ pub struct Config {
     pub debug_mode: bool,
     pub print_ir: bool,
     pub write_ir_to_file: bool,
+    pub emit_header: bool, //if true, writes the C header of the exported procedures instead
+    pub include_directories: Vec<String>, //where %INCLUDE looks for files
+    pub preprocess_only: bool, //if true, prints the preprocessed source instead of compiling it
     pub dry_run: bool, //if true, won't save the compiled output to the disk - enable during testing
     pub error_test: bool, //if true, don't end the process after errors.
+    pub packed_decimal: bool, //if true, records hold FIXED DECIMAL in packed decimal
 }
 
 impl Default for Config {
@@ -294,8 +369,12 @@ impl Default for Config {
             verify: true,
             print_ir: false,
             write_ir_to_file: false,
+            emit_header: false,
+            include_directories: vec![],
+            preprocess_only: false,
             dry_run: false,
             error_test: false,
+            packed_decimal: false,
         }
     }
 }
@@ -307,6 +386,10 @@ impl From<Arguments> for Config {
         Config {
             filename,
             write_ir_to_file: value.save_as_ir,
+            emit_header: value.emit == Emit::Header,
+            include_directories: value.include_directories,
+            preprocess_only: value.preprocess_only,
+            packed_decimal: value.packed_decimal,
             ..default
         }
     }
@@ -336,13 +419,17 @@ pub fn initialize_test_logger() {
 }
 
 fn get_output_filename(arguments: &Arguments) -> String {
-    let path = Path::new(&arguments.path_to_file);
+    //the object file is named after the first unit
+    let path = Path::new(&arguments.paths_to_files[0]);
     let file_stem = path.file_stem().unwrap();
     let result = file_stem.to_str().unwrap().to_string();
     result + &get_output_extension(&arguments)
 }
 
 fn get_output_extension(arguments: &Arguments) -> String {
+    if arguments.emit == Emit::Header {
+        return ".h".to_string();
+    }
     if arguments.save_as_ir {
         return ".ll".to_string();
     }
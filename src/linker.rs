@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use inkwell::{
+    module::{Linkage, Module},
+    types::{AnyType, AnyTypeEnum, BasicTypeEnum},
+};
+
+use crate::error::{errors::CodegenError, get_error};
+
+///Where an external name was first seen, and what it was declared as.
+struct ExternalDeclaration<'ctx> {
+    unit_name: String,
+    _type: AnyTypeEnum<'ctx>,
+    is_defined: bool,
+}
+
+///Every unit declaring an external name has to declare it the same way, and
+///only one of them can define it.
+pub fn check_external_declarations(modules: &[Module]) -> Vec<CodegenError> {
+    let mut declarations: HashMap<String, ExternalDeclaration> = HashMap::new();
+    let mut errors: Vec<CodegenError> = vec![];
+
+    for m in modules {
+        let unit_name = m.get_name().to_str().unwrap().to_string();
+
+        for global in m.get_globals() {
+            if global.get_linkage() != Linkage::External {
+                continue;
+            }
+            let name = global.get_name().to_str().unwrap().to_string();
+            let _type = global.as_pointer_value().get_type().get_element_type();
+            let is_defined = global.get_initializer().is_some();
+            if let Err(error) =
+                record_declaration(&mut declarations, name, &unit_name, _type, is_defined)
+            {
+                errors.push(error);
+            }
+        }
+
+        for function in m.get_functions() {
+            if function.get_linkage() != Linkage::External {
+                continue;
+            }
+            let name = function.get_name().to_str().unwrap().to_string();
+            let _type = function.get_type().as_any_type_enum();
+            let is_defined = function.count_basic_blocks() > 0;
+            if let Err(error) =
+                record_declaration(&mut declarations, name, &unit_name, _type, is_defined)
+            {
+                errors.push(error);
+            }
+        }
+    }
+
+    errors
+}
+
+fn record_declaration<'ctx>(
+    declarations: &mut HashMap<String, ExternalDeclaration<'ctx>>,
+    name: String,
+    unit_name: &str,
+    _type: AnyTypeEnum<'ctx>,
+    is_defined: bool,
+) -> Result<(), CodegenError> {
+    match declarations.get_mut(&name) {
+        None => {
+            declarations.insert(
+                name,
+                ExternalDeclaration {
+                    unit_name: unit_name.to_string(),
+                    _type,
+                    is_defined,
+                },
+            );
+            Ok(())
+        }
+        Some(first) if first._type != _type => Err(CodegenError {
+            message: get_error(&["24", &name, &first.unit_name, unit_name]),
+        }),
+        Some(first) if first.is_defined && is_defined => Err(CodegenError {
+            message: get_error(&["25", &name, &first.unit_name, unit_name]),
+        }),
+        Some(first) => {
+            if is_defined {
+                first.is_defined = true;
+                first.unit_name = unit_name.to_string();
+            }
+            Ok(())
+        }
+    }
+}
+
+///Links the units into the first one. EXTERNAL variables that no unit gave
+///an INITIAL value are defined there as common symbols, starting out as zeros,
+///so objects compiled separately that share them still link together.
+pub fn link_units(modules: Vec<Module>) -> Result<Module, String> {
+    let mut units = modules.into_iter();
+    let linked = units
+        .next()
+        .ok_or("There are no units to link".to_string())?;
+    for unit in units {
+        linked
+            .link_in_module(unit)
+            .map_err(|message| message.to_string())?;
+    }
+
+    for global in linked.get_globals() {
+        if global.get_linkage() == Linkage::External && global.get_initializer().is_none() {
+            let _type = global.as_pointer_value().get_type().get_element_type();
+            let storage_type = BasicTypeEnum::try_from(_type)
+                .map_err(|_err| format!("EXTERNAL {:?} has no storage", global.get_name()))?;
+            global.set_linkage(Linkage::Common);
+            global.set_initializer(&storage_type.const_zero());
+        }
+    }
+
+    Ok(linked)
+}
+
+#[cfg(test)]
+mod tests {
+    use inkwell::{context::Context, AddressSpace};
+
+    use super::*;
+
+    #[test]
+    fn undefined_external_variables_are_common() {
+        let context = Context::create();
+        let first = context.create_module("FIRST");
+        let second = context.create_module("SECOND");
+        let i32_type = context.i32_type();
+        first.add_global(i32_type, Some(AddressSpace::default()), "SHARED");
+        second.add_global(i32_type, Some(AddressSpace::default()), "SHARED");
+        let defined = second.add_global(i32_type, Some(AddressSpace::default()), "DEFINED");
+        defined.set_initializer(&i32_type.const_int(5, false));
+
+        let linked = link_units(vec![first, second]).unwrap();
+
+        let shared = linked.get_global("SHARED").unwrap();
+        assert_eq!(Linkage::Common, shared.get_linkage());
+        assert!(shared.get_initializer().is_some());
+        let defined = linked.get_global("DEFINED").unwrap();
+        assert_eq!(Linkage::External, defined.get_linkage());
+    }
+}
@@ -1,7 +1,7 @@
 use std::{env, fs, process};
 
 use clap::Parser;
-use plick::{compile_input, initialize_logger, Config};
+use plick::{compile_units, initialize_logger, Config};
 
 fn main() {
     initialize_logger();
@@ -9,12 +9,25 @@ fn main() {
     let cli_arguments = parse_cli_arguments();
     log::info!("CLI arguments: {:#?}", &cli_arguments);
 
-    let file_to_compile_as_string = read_file_to_string(&cli_arguments.path_to_file);
-
-    let config = Config::from(cli_arguments);
-    let results = compile_input(&file_to_compile_as_string, config);
+    let files_to_compile_as_strings: Vec<String> = cli_arguments
+        .paths_to_files
+        .iter()
+        .map(|path_to_file| read_file_to_string(path_to_file))
+        .collect();
+    let units: Vec<(&str, &str)> = cli_arguments
+        .paths_to_files
+        .iter()
+        .zip(&files_to_compile_as_strings)
+        .map(|(path_to_file, file_text)| (path_to_file.as_str(), file_text.as_str()))
+        .collect();
+
+    let config = Config::from(cli_arguments.clone());
+    let results = compile_units(&units, config);
 
     if !results.was_successful {
+        for error in results.errors {
+            println!("{}", error);
+        }
         println!("Compilation failed!");
         process::exit(1);
     }
@@ -4,6 +4,16 @@ use crate::ast;
 use crate::ast::*;
 use crate::error;
 use crate::error::errors::ParseError;
+use crate::types::conversion::{
+    get_fixed_binary_type, Attributes, Base, DEFAULT_FIXED_BINARY_PRECISION,
+    MAX_FIXED_BINARY_PRECISION, MAX_FIXED_DECIMAL_PRECISION,
+};
+use crate::types::defaults::{DefaultRange, DefaultRule};
+use crate::types::fixed_decimal::DEFAULT_PRECISION;
+use crate::types::float_decimal::{
+    get_precision_of_binary_digits, DEFAULT_FLOAT_PRECISION, EXTENDED_PRECISION, MAX_BINARY_DIGITS,
+};
+use crate::types::picture::Picture;
 use crate::types::Type;
 use crate::{
     codegen::codegen::CodeGenable,
@@ -53,23 +63,14 @@ pub fn parse_constant_numeric<'a>(
     let minus_result = parse_token(token_manager, Token::MINUS);
     log::debug!("after trying to find minus!");
 
-    let mut is_negative = 1.0;
-    match minus_result {
-        Ok(thing) => {
-            is_negative = -1.0;
-        }
-        _ => (),
-    };
+    let is_negative = minus_result.is_ok();
 
-    if let Some(Token::NumVal(value)) = token_manager.current_token {
+    if let Some(Token::NumVal(written)) = token_manager.current_token.clone() {
         log::debug!("before numval next");
         token_manager.next_token();
         log::debug!("after numval next");
 
-        Ok(Expr::NumVal {
-            value: value * is_negative,
-            _type: Type::FixedDecimal,
-        })
+        Ok(Expr::new_written_numval(&written, is_negative))
     } else {
         Err(ParseError {
             message: "Failed to parse numeric!".to_owned(),
@@ -124,7 +125,8 @@ pub fn parse_declare(token_manager: &mut lexer::TokenManager) -> Result<Declare,
     log::info!("Parsing the declare!");
     token_manager.next_token();
     let new_variable_name;
-    let mut variable_type = Type::FixedDecimal;
+    //without a data attribute the variable gets the default type of its name
+    let mut variable_type = None;
 
     if let Some(Token::Identifier(ref name)) = token_manager.current_token {
         new_variable_name = name.clone();
@@ -135,14 +137,165 @@ pub fn parse_declare(token_manager: &mut lexer::TokenManager) -> Result<Declare,
     }
     token_manager.next_token();
 
+    let mut dimension = None;
+    if let Some(Token::OPEN_PAREN) = token_manager.current_token {
+        dimension = Some(parse_dimension(token_manager)?);
+    }
+
+    let mut storage_class = StorageClass::Automatic;
+    let mut initial = None;
+    parse_variable_attributes(token_manager, &mut storage_class, &mut initial)?;
+
+    match token_manager.current_token {
+        Some(Token::FIXED)
+        | Some(Token::FLOAT)
+        | Some(Token::POINTER)
+        | Some(Token::CHARACTER)
+        | Some(Token::PICTURE) => {
+            variable_type = Some(parse_data_type(token_manager)?);
+        }
+        Some(Token::Identifier(ref keyword))
+            if keyword == "LABEL" || is_complex_keyword(keyword) =>
+        {
+            variable_type = Some(parse_data_type(token_manager)?);
+        }
+        Some(Token::ENTRY) => {
+            let entry = parse_entry_attributes(token_manager, &mut storage_class)?;
+            return Ok(Declare {
+                var_name: new_variable_name,
+                attribute: None,
+                file_attributes: None,
+                storage_class,
+                dimension,
+                initial: None,
+                entry: Some(entry),
+                generic: None,
+            });
+        }
+        Some(Token::Identifier(ref keyword)) if keyword == "GENERIC" => {
+            let alternatives = parse_generic_alternatives(token_manager)?;
+            return Ok(Declare {
+                var_name: new_variable_name,
+                attribute: None,
+                file_attributes: None,
+                storage_class,
+                dimension: None,
+                initial: None,
+                entry: None,
+                generic: Some(alternatives),
+            });
+        }
+        Some(Token::FILE)
+        | Some(Token::STREAM)
+        | Some(Token::INPUT)
+        | Some(Token::OUTPUT)
+        | Some(Token::PRINT)
+        | Some(Token::RECORD)
+        | Some(Token::SEQUENTIAL)
+        | Some(Token::DIRECT)
+        | Some(Token::KEYED)
+        | Some(Token::UPDATE) => {
+            let file_attributes = parse_file_declaration_attributes(token_manager)?;
+            return Ok(Declare {
+                var_name: new_variable_name,
+                attribute: None,
+                file_attributes: Some(file_attributes),
+                storage_class,
+                dimension: None,
+                initial: None,
+                entry: None,
+                generic: None,
+            });
+        }
+        Some(Token::SEMICOLON) => (),
+        ref other => {
+            let message = format!("Could not parse declare statement {:#?}", other);
+            return Err(ParseError { message });
+        }
+    };
+
+    //the other attributes can come before or after the type
+    parse_variable_attributes(token_manager, &mut storage_class, &mut initial)?;
+
+    log::info!("Finish parsing declare");
+    Ok(Declare {
+        var_name: new_variable_name,
+        attribute: variable_type,
+        file_attributes: None,
+        storage_class,
+        dimension,
+        initial,
+        entry: None,
+        generic: None,
+    })
+}
+
+///COMPLEX, or its abbreviation CPLX. These are identifiers, since COMPLEX
+///is also the name of a builtin function.
+fn is_complex_keyword(keyword: &str) -> bool {
+    keyword == "COMPLEX" || keyword == "CPLX"
+}
+
+///Parses the COMPLEX that can follow FIXED or FLOAT, giving the complex type
+///with parts of `real_type` when it is there.
+fn parse_complex_attribute(token_manager: &mut lexer::TokenManager, real_type: Type) -> Type {
+    match token_manager.current_token {
+        Some(Token::Identifier(ref keyword)) if is_complex_keyword(keyword) => {
+            token_manager.next_token();
+            real_type.get_complex_type()
+        }
+        _ => real_type,
+    }
+}
+
+///Parses FIXED, FLOAT, POINTER, LABEL, PICTURE 'ZZ9' or CHARACTER(n) with an
+///optional VARYING. FIXED and FLOAT can be COMPLEX, written before or after
+///them, and a COMPLEX on its own is FLOAT.
+pub fn parse_data_type(token_manager: &mut lexer::TokenManager) -> Result<Type, ParseError> {
     match token_manager.current_token {
+        //a FIXED BINARY is held as the FIXED DECIMAL with room for its values
         Some(Token::FIXED) => {
-            variable_type = Type::FixedDecimal;
             token_manager.next_token();
+            let real_type = match parse_fixed_precision(token_manager)? {
+                (true, precision, 0) => get_fixed_binary_type(precision),
+                (true, precision, scale) => {
+                    Attributes::fixed(Base::Binary, precision, scale as i32)
+                        .to_base(Base::Decimal)
+                        .get_type()
+                }
+                (false, precision, scale) => Type::FixedDecimal(precision, scale),
+            };
+            Ok(parse_complex_attribute(token_manager, real_type))
         }
         Some(Token::FLOAT) => {
-            variable_type = Type::Float;
             token_manager.next_token();
+            let precision = parse_float_precision(token_manager)?;
+            Ok(parse_complex_attribute(
+                token_manager,
+                Type::Float(precision),
+            ))
+        }
+        Some(Token::Identifier(ref keyword)) if is_complex_keyword(keyword) => {
+            token_manager.next_token();
+            match token_manager.current_token {
+                Some(Token::FIXED) => {
+                    token_manager.next_token();
+                    Ok(Type::FixedComplex)
+                }
+                Some(Token::FLOAT) => {
+                    token_manager.next_token();
+                    Ok(Type::FloatComplex)
+                }
+                _ => Ok(Type::FloatComplex),
+            }
+        }
+        Some(Token::POINTER) => {
+            token_manager.next_token();
+            Ok(Type::Pointer)
+        }
+        Some(Token::Identifier(ref keyword)) if keyword == "LABEL" => {
+            token_manager.next_token();
+            Ok(Type::Label)
         }
         Some(Token::CHARACTER) => {
             parse_token(token_manager, Token::CHARACTER)?;
@@ -152,187 +305,719 @@ pub fn parse_declare(token_manager: &mut lexer::TokenManager) -> Result<Declare,
             parse_token(token_manager, Token::CLOSED_PAREN)?;
 
             let string_size = match numval {
-                Expr::NumVal { value, _type: _ } => value,
+                Expr::NumVal { value, .. } => value,
                 other => panic!("Expected numval, received {:#?}", other),
             };
 
             if string_size <= 0.0 {
                 panic!("character can't have a size below zero!");
             }
-            variable_type = Type::Char(string_size as u32);
+
+            if let Some(Token::VARYING) = token_manager.current_token {
+                parse_token(token_manager, Token::VARYING)?;
+                return Ok(Type::VaryingChar(string_size as u32));
+            }
+            Ok(Type::Char(string_size as u32))
+        }
+        Some(Token::PICTURE) => {
+            parse_token(token_manager, Token::PICTURE)?;
+            let pattern = match token_manager.current_token {
+                Some(Token::STRING(ref pattern)) => pattern.clone(),
+                ref other => {
+                    let source_loc = token_manager.get_source_location().to_string();
+                    let found = format!("{:?}", other);
+                    let message = get_error(&["1", "a picture string", &found, &source_loc]);
+                    return Err(ParseError { message });
+                }
+            };
+            token_manager.next_token();
+
+            let picture = Picture::parse(&pattern).map_err(|reason| ParseError {
+                message: get_error(&["39", &pattern, &reason]),
+            })?;
+            Ok(Type::Picture(picture))
         }
-        Some(Token::SEMICOLON) => variable_type = variable_type,
         ref other => {
-            let message = format!("Could not parse declare statement {:#?}", other);
-            return Err(ParseError { message });
+            let message = format!("Expected a data type, found {:#?}", other);
+            Err(ParseError { message })
         }
-    };
-
-    log::info!("Finish parsing declare");
-    Ok(Declare {
-        var_name: new_variable_name,
-        attribute: Some(variable_type),
-    })
+    }
 }
-//current token is the semicolon AFTER do
-pub fn parse_do_block(
+
+///Parses ENTRY(FIXED, CHAR(*)) with the RETURNS(...), OPTIONS(C), VARIABLE and
+///storage class attributes that can follow it, in any order.
+pub fn parse_entry_attributes(
     token_manager: &mut lexer::TokenManager,
-) -> Result<Vec<Statement>, ParseError> {
-    let mut statements: Vec<Statement> = vec![];
+    storage_class: &mut StorageClass,
+) -> Result<Entry, ParseError> {
+    parse_token(token_manager, Token::ENTRY)?;
+    let mut parameters = vec![];
+    if let Some(Token::OPEN_PAREN) = token_manager.current_token {
+        parse_token(token_manager, Token::OPEN_PAREN)?;
+        loop {
+            parameters.push(parse_descriptor(token_manager)?);
+            match token_manager.current_token {
+                Some(Token::COMMA) => parse_token(token_manager, Token::COMMA)?,
+                _ => break,
+            }
+        }
+        parse_token(token_manager, Token::CLOSED_PAREN)?;
+    }
 
-    //parse_token(token_manager,Token::DO)?;
-    parse_token(token_manager, Token::SEMICOLON)?;
+    let mut entry = Entry {
+        parameters,
+        returns: None,
+        is_c: false,
+        is_variable: false,
+    };
     loop {
-        if let Token::END = token_manager.current_token.as_mut().unwrap() {
-            parse_token(token_manager, Token::END)?;
-            parse_token(token_manager, Token::SEMICOLON)?;
-            break;
+        match token_manager.current_token {
+            Some(Token::RETURNS) => {
+                parse_token(token_manager, Token::RETURNS)?;
+                parse_token(token_manager, Token::OPEN_PAREN)?;
+                entry.returns = Some(parse_descriptor(token_manager)?);
+                parse_token(token_manager, Token::CLOSED_PAREN)?;
+            }
+            Some(Token::OPTIONS) => {
+                parse_c_option(token_manager)?;
+                entry.is_c = true;
+            }
+            Some(Token::Identifier(ref keyword)) if keyword == "VARIABLE" => {
+                token_manager.next_token();
+                entry.is_variable = true;
+            }
+            Some(Token::EXTERNAL) if *storage_class == StorageClass::Automatic => {
+                *storage_class = parse_storage_class(token_manager)?;
+            }
+            _ => return Ok(entry),
         }
-
-        let statement = parse_statement(token_manager)?;
-        statements.push(statement);
     }
-
-    Ok(statements)
 }
 
-//parses identifiers like variable names but also function calls
-pub fn parse_identifier<'a>(
-    token_manager: &'a mut lexer::TokenManager,
-) -> Result<Expr, ParseError> {
-    trace!("Parsing Identifier");
-    let identifier_string: String;
-    if let Some(Token::Identifier(ref val)) = token_manager.current_token {
-        identifier_string = val.clone();
-        trace!("The identifier string is: {}", identifier_string);
-    } else {
-        log::error!("Found token as {:#?}", token_manager.current_token);
-        let message = "failed to parse identifier!".to_string();
-        return Err(ParseError { message });
-    }
-    let mut args_list: Vec<Expr> = vec![];
-    token_manager.next_token(); // prime next token
-    if let Some(Token::OPEN_PAREN) = token_manager.current_token
-    // if this is a function call
-    {
-        trace!("Found an open parenthesis first!");
-        //function call here.
-        //now we loop through each expression in the arguments
-        let mut expecting_comma: bool = false; // expecting comma does not affect breaking
-        trace!("Turning expecting comma off!");
+///Parses GENERIC (CIRCLE WHEN (FLOAT), SQUARE WHEN (FIXED)).
+pub fn parse_generic_alternatives(
+    token_manager: &mut lexer::TokenManager,
+) -> Result<Vec<GenericAlternative>, ParseError> {
+    token_manager.next_token();
+    parse_token(token_manager, Token::OPEN_PAREN)?;
+    let mut alternatives = vec![];
+    loop {
+        let procedure_name = match token_manager.current_token {
+            Some(Token::Identifier(ref name)) => name.clone(),
+            ref other => {
+                let message = format!("Expected the name of a procedure, found {:#?}", other);
+                return Err(ParseError { message });
+            }
+        };
         token_manager.next_token();
-        loop {
-            trace!("Looping!");
-            if let Some(Token::CLOSED_PAREN) = token_manager.current_token {
-                trace!("found a closed parenthesis!");
-                token_manager.next_token(); // eat the next token, ready for next use
-                break;
-            } else if let Some(Token::COMMA) = token_manager.current_token {
-                if expecting_comma {
-                    trace!("Found comma at right place, continuing!");
-                    expecting_comma = false;
-                    token_manager.next_token(); // eat the token
-                } else {
-                    panic!("Expected an expression, found a comma!!");
-                }
-            } else if let Some(ref token) = token_manager.current_token {
-                //parse as expression
-                trace!("Found a token called {:#?}", *token);
-                let parsed_arg: Expr = parse_expression(token_manager)?;
-
-                args_list.push(parsed_arg);
 
-                expecting_comma = true;
-                trace!("turned expecting comma on!");
-            } else {
-                panic!("{:?}", token_manager.current_token);
+        match token_manager.current_token {
+            Some(Token::Identifier(ref keyword)) if keyword == "WHEN" => {
+                token_manager.next_token();
+            }
+            ref other => {
+                let message = format!("Expected WHEN, found {:#?}", other);
+                return Err(ParseError { message });
             }
         }
-        return Ok(Expr::Call {
-            fn_name: identifier_string,
-            args: args_list,
-            _type: Type::TBD,
-        });
-    } else {
-        return Ok(Expr::Variable {
-            name: identifier_string,
-            _type: Type::FixedDecimal,
+        parse_token(token_manager, Token::OPEN_PAREN)?;
+        let mut descriptors = vec![];
+        while token_manager.current_token != Some(Token::CLOSED_PAREN) {
+            descriptors.push(parse_descriptor(token_manager)?);
+            if let Some(Token::COMMA) = token_manager.current_token {
+                parse_token(token_manager, Token::COMMA)?;
+            }
+        }
+        parse_token(token_manager, Token::CLOSED_PAREN)?;
+        alternatives.push(GenericAlternative {
+            procedure_name,
+            descriptors,
         });
+
+        match token_manager.current_token {
+            Some(Token::COMMA) => parse_token(token_manager, Token::COMMA)?,
+            _ => break,
+        }
     }
+    parse_token(token_manager, Token::CLOSED_PAREN)?;
+    Ok(alternatives)
 }
 
-///the current token is a '(' / Token::OPEN_PAREN
-pub fn parse_parenthesis_expression(
-    token_manager: &mut lexer::TokenManager,
-) -> Result<Expr, ParseError> {
-    log::trace!("Calling parse_paren_expression");
+///Parses OPTIONS(C).
+fn parse_c_option(token_manager: &mut lexer::TokenManager) -> Result<(), ParseError> {
+    parse_token(token_manager, Token::OPTIONS)?;
     parse_token(token_manager, Token::OPEN_PAREN)?;
-
-    let result: Expr = parse_expression(token_manager)?;
-
-    parse_token(token_manager, Token::CLOSED_PAREN)?;
-
-    log::trace!("Exiting parse_paren_expression");
-    return Ok(result);
+    match token_manager.current_token {
+        Some(Token::Identifier(ref option)) if option == "C" => {
+            token_manager.next_token();
+        }
+        ref other => {
+            let message = format!("Expected the option C, found {:#?}", other);
+            return Err(ParseError { message });
+        }
+    }
+    parse_token(token_manager, Token::CLOSED_PAREN)
 }
 
-pub fn parse_expression<'a>(
-    token_manager: &'a mut lexer::TokenManager,
-) -> Result<Expr, ParseError> {
-    log::trace!("starting a parse_expression() ");
-
-    let left_handed_side = parse_primary_expression(token_manager)?;
-    let lhs_expr = left_handed_side.expression.clone();
-    let was_lhs_in_paren = left_handed_side.in_parenthesis;
-    log::trace!("left side: {:#?}", lhs_expr);
+///Parses the type of an entry parameter or result. Besides the data types,
+///these can be FIXED BINARY(p) and CHAR(*).
+pub fn parse_descriptor(token_manager: &mut lexer::TokenManager) -> Result<Descriptor, ParseError> {
+    match token_manager.current_token {
+        Some(Token::FIXED) => {
+            parse_token(token_manager, Token::FIXED)?;
+            match parse_fixed_precision(token_manager)? {
+                (true, precision, _scale) => Ok(Descriptor::FixedBinary(precision)),
+                (false, precision, scale) => {
+                    let _type = parse_complex_attribute(
+                        token_manager,
+                        Type::FixedDecimal(precision, scale),
+                    );
+                    Ok(Descriptor::Data(_type))
+                }
+            }
+        }
+        Some(Token::CHARACTER) => {
+            parse_token(token_manager, Token::CHARACTER)?;
+            parse_token(token_manager, Token::OPEN_PAREN)?;
+            if let Some(Token::MULTIPLY) = token_manager.current_token {
+                parse_token(token_manager, Token::MULTIPLY)?;
+                parse_token(token_manager, Token::CLOSED_PAREN)?;
+                if let Some(Token::VARYING) = token_manager.current_token {
+                    parse_token(token_manager, Token::VARYING)?;
+                }
+                return Ok(Descriptor::AnyLengthCharacter);
+            }
 
-    if let Expr::Variable { ref name, _type } = lhs_expr {
-        if let Some(Token::EQ) = token_manager.current_token {
-            log::trace!("EQUAL expression");
-            parse_token(token_manager, Token::EQ).expect("always true"); // eat the equal token
-            let expression_value = parse_expression(token_manager);
-            //return Expr::Assignment(name, expression_value);
-            return Ok(Expr::Assignment {
-                variable_name: name.clone(),
-                value: Box::new(expression_value.unwrap()),
-            });
+            let string_size = match parse_constant_numeric(token_manager)? {
+                Expr::NumVal { value, .. } => value as u32,
+                other => panic!("Expected numval, received {:#?}", other),
+            };
+            parse_token(token_manager, Token::CLOSED_PAREN)?;
+            if let Some(Token::VARYING) = token_manager.current_token {
+                parse_token(token_manager, Token::VARYING)?;
+                return Ok(Descriptor::Data(Type::VaryingChar(string_size)));
+            }
+            Ok(Descriptor::Data(Type::Char(string_size)))
         }
-    } else if let Expr::Char { value } = lhs_expr.clone() {
-        log::trace!("parsing a CHAR expression");
-        token_manager.next_token(); //eat the string token
-        return Ok(lhs_expr);
+        _ => Ok(Descriptor::Data(parse_data_type(token_manager)?)),
     }
-    match token_manager.current_token {
-        Some(Token::PLUS)
-        | Some(Token::MINUS)
-        | Some(Token::DIVIDE)
-        | Some(Token::AND)
-        | Some(Token::EXPONENT)
-        | Some(Token::MULTIPLY)
-        | Some(Token::GREATER_THAN)
-        | Some(Token::LESS_THAN) => {
-            let token_precedence =
-                get_binary_operator_precedence(token_manager.current_token.as_ref().unwrap());
+}
 
-            log::trace!(
-                "building recursive binary tree!: LHS = {:#?} CURRENT_TOKEN: {:#?}",
-                lhs_expr,
-                token_manager.current_token
-            );
-            build_recursive_binary_tree(token_manager, lhs_expr, token_precedence, was_lhs_in_paren)
+///Parses the DECIMAL or BINARY and the "(p)" or "(p,q)" that can follow
+///FIXED or FLOAT, each optional. Returns whether the base is BINARY, and p.
+fn parse_base_and_precision(
+    token_manager: &mut lexer::TokenManager,
+) -> Result<(bool, Option<u32>, i32), ParseError> {
+    let is_binary = match token_manager.current_token {
+        Some(Token::BINARY) => {
+            parse_token(token_manager, Token::BINARY)?;
+            true
         }
-        _ => Ok(lhs_expr),
+        Some(Token::DECIMAL) => {
+            parse_token(token_manager, Token::DECIMAL)?;
+            false
+        }
+        _ => false,
+    };
+
+    if token_manager.current_token != Some(Token::OPEN_PAREN) {
+        return Ok((is_binary, None, 0));
+    }
+    parse_token(token_manager, Token::OPEN_PAREN)?;
+    let digits = match parse_constant_numeric(token_manager)? {
+        Expr::NumVal { value, .. } => value,
+        other => panic!("Expected numval, received {:#?}", other),
+    };
+    //the scale factor only matters to FIXED
+    let mut scale = 0;
+    if let Some(Token::COMMA) = token_manager.current_token {
+        parse_token(token_manager, Token::COMMA)?;
+        scale = match parse_constant_numeric(token_manager)? {
+            Expr::NumVal { value, .. } => value as i32,
+            other => panic!("Expected numval, received {:#?}", other),
+        };
     }
+    parse_token(token_manager, Token::CLOSED_PAREN)?;
+
+    Ok((is_binary, Some(digits.max(0.0) as u32), scale))
 }
 
-pub fn build_recursive_binary_tree(
+///Parses the base, precision and scale of a FIXED, returning whether it is
+///BINARY, its precision in digits of that base and its scale. A FIXED
+///without a precision is FIXED DECIMAL(5,0) or FIXED BINARY(15,0).
+fn parse_fixed_precision(
     token_manager: &mut lexer::TokenManager,
-    lhs: Expr,
-    precendence: i32,
-    is_lhs_in_paren: bool,
-) -> Result<Expr, ParseError> {
-    //LHS has to be a binary node.
-    let operator_token: Token = token_manager.current_token.as_ref().unwrap().clone();
+) -> Result<(bool, u32, u32), ParseError> {
+    let (is_binary, digits, scale) = parse_base_and_precision(token_manager)?;
+    let (base, max_digits, default_digits) = match is_binary {
+        true => (
+            "BINARY",
+            MAX_FIXED_BINARY_PRECISION,
+            DEFAULT_FIXED_BINARY_PRECISION,
+        ),
+        false => ("DECIMAL", MAX_FIXED_DECIMAL_PRECISION, DEFAULT_PRECISION),
+    };
+    let digits = digits.unwrap_or(default_digits);
+
+    if digits == 0 || digits > max_digits || scale < 0 || scale > digits as i32 {
+        let message = get_error(&[
+            "41",
+            base,
+            &digits.to_string(),
+            &scale.to_string(),
+            &max_digits.to_string(),
+        ]);
+        return Err(ParseError { message });
+    }
+
+    Ok((is_binary, digits, scale as u32))
+}
+
+///Parses the base and precision of a FLOAT, returning its precision in
+///decimal digits. A FLOAT without a precision has DEFAULT_FLOAT_PRECISION.
+fn parse_float_precision(token_manager: &mut lexer::TokenManager) -> Result<u32, ParseError> {
+    let (is_binary, digits) = match parse_base_and_precision(token_manager)? {
+        (is_binary, Some(digits), _scale) => (is_binary, digits),
+        (_is_binary, None, _scale) => return Ok(DEFAULT_FLOAT_PRECISION),
+    };
+
+    let (base, max_digits) = match is_binary {
+        true => ("BINARY", MAX_BINARY_DIGITS),
+        false => ("DECIMAL", EXTENDED_PRECISION),
+    };
+    if digits == 0 || digits > max_digits {
+        let message = get_error(&["40", base, &digits.to_string(), &max_digits.to_string()]);
+        return Err(ParseError { message });
+    }
+
+    match is_binary {
+        true => Ok(get_precision_of_binary_digits(digits)),
+        false => Ok(digits),
+    }
+}
+
+///Parses the "(10)" of DCL A(10) FIXED; and returns the number of elements.
+pub fn parse_dimension(token_manager: &mut lexer::TokenManager) -> Result<u32, ParseError> {
+    parse_token(token_manager, Token::OPEN_PAREN)?;
+    let bound = match parse_constant_numeric(token_manager)? {
+        Expr::NumVal { value, .. } => value,
+        other => panic!("Expected numval, received {:#?}", other),
+    };
+    parse_token(token_manager, Token::CLOSED_PAREN)?;
+
+    if bound < 1.0 {
+        let message = format!("An array needs at least one element, found {}", bound);
+        return Err(ParseError { message });
+    }
+    Ok(bound as u32)
+}
+
+///Parses the storage class and INITIAL attributes of a variable, in any order.
+///Only the first storage class found is kept.
+pub fn parse_variable_attributes(
+    token_manager: &mut lexer::TokenManager,
+    storage_class: &mut StorageClass,
+    initial: &mut Option<Vec<Expr>>,
+) -> Result<(), ParseError> {
+    loop {
+        match token_manager.current_token {
+            Some(Token::INITIAL) => *initial = Some(parse_initial(token_manager)?),
+            Some(Token::BASED)
+            | Some(Token::CONTROLLED)
+            | Some(Token::STATIC)
+            | Some(Token::AUTOMATIC)
+            | Some(Token::EXTERNAL)
+                if *storage_class == StorageClass::Automatic =>
+            {
+                *storage_class = parse_storage_class(token_manager)?
+            }
+            _ => return Ok(()),
+        }
+    }
+}
+
+///INITIAL(1, 'A', (3)0) gives [1, 'A', 0, 0, 0].
+///The current token is INITIAL.
+pub fn parse_initial(token_manager: &mut lexer::TokenManager) -> Result<Vec<Expr>, ParseError> {
+    parse_token(token_manager, Token::INITIAL)?;
+    parse_initial_items(token_manager)
+}
+
+///Parses a parenthesized list of initial values, expanding iteration factors.
+fn parse_initial_items(token_manager: &mut lexer::TokenManager) -> Result<Vec<Expr>, ParseError> {
+    parse_token(token_manager, Token::OPEN_PAREN)?;
+    let mut values = vec![];
+    loop {
+        if let Some(Token::OPEN_PAREN) = token_manager.current_token {
+            //(n) item or (n)(item, item)
+            let repetitions = parse_dimension(token_manager)?;
+            let repeated = match token_manager.current_token {
+                Some(Token::OPEN_PAREN) => parse_initial_items(token_manager)?,
+                _ => vec![parse_expression(token_manager)?],
+            };
+            for _ in 0..repetitions {
+                values.extend(repeated.iter().cloned());
+            }
+        } else {
+            values.push(parse_expression(token_manager)?);
+        }
+
+        match token_manager.current_token {
+            Some(Token::COMMA) => parse_token(token_manager, Token::COMMA)?,
+            _ => break,
+        }
+    }
+    parse_token(token_manager, Token::CLOSED_PAREN)?;
+    Ok(values)
+}
+
+///Parses BASED, BASED(P), CONTROLLED, STATIC, AUTOMATIC or EXTERNAL if it is
+///the current token. Variables without a storage class attribute are AUTOMATIC.
+pub fn parse_storage_class(
+    token_manager: &mut lexer::TokenManager,
+) -> Result<StorageClass, ParseError> {
+    match token_manager.current_token {
+        Some(Token::CONTROLLED) => {
+            parse_token(token_manager, Token::CONTROLLED)?;
+            return Ok(StorageClass::Controlled);
+        }
+        Some(Token::STATIC) => {
+            parse_token(token_manager, Token::STATIC)?;
+            return Ok(StorageClass::Static);
+        }
+        Some(Token::AUTOMATIC) => {
+            parse_token(token_manager, Token::AUTOMATIC)?;
+            return Ok(StorageClass::Automatic);
+        }
+        Some(Token::EXTERNAL) => {
+            parse_token(token_manager, Token::EXTERNAL)?;
+            return Ok(StorageClass::External);
+        }
+        _ => (),
+    }
+    if let Some(Token::BASED) = token_manager.current_token {
+        parse_token(token_manager, Token::BASED)?;
+        let mut locator = None;
+        if let Some(Token::OPEN_PAREN) = token_manager.current_token {
+            locator = Some(parse_identifier_in_parens(token_manager, "a pointer name")?);
+        }
+        return Ok(StorageClass::Based { locator });
+    }
+    Ok(StorageClass::Automatic)
+}
+
+///Parses the attributes of a file declaration, which can come in any order
+///as long as one of them is FILE.
+///DCL OUTF FILE STREAM OUTPUT;
+pub fn parse_file_declaration_attributes(
+    token_manager: &mut lexer::TokenManager,
+) -> Result<Vec<FileAttribute>, ParseError> {
+    let mut found_file_attribute = false;
+    let mut file_attributes: Vec<FileAttribute> = vec![];
+
+    loop {
+        if let Some(Token::FILE) = token_manager.current_token {
+            parse_token(token_manager, Token::FILE)?;
+            found_file_attribute = true;
+        } else if let Some(attribute) = parse_file_attribute(token_manager) {
+            file_attributes.push(attribute);
+        } else {
+            break;
+        }
+    }
+
+    if !found_file_attribute {
+        let source_loc = token_manager.get_source_location().to_string();
+        let message = get_error(&["1", "FILE", "a file attribute", &source_loc]);
+        return Err(ParseError { message });
+    }
+
+    Ok(file_attributes)
+}
+
+///Eats the current token and returns it as a file attribute, if it is one.
+pub fn parse_file_attribute(token_manager: &mut lexer::TokenManager) -> Option<FileAttribute> {
+    let attribute = match token_manager.current_token {
+        Some(Token::STREAM) => FileAttribute::Stream,
+        Some(Token::INPUT) => FileAttribute::Input,
+        Some(Token::OUTPUT) => FileAttribute::Output,
+        Some(Token::PRINT) => FileAttribute::Print,
+        Some(Token::RECORD) => FileAttribute::Record,
+        Some(Token::SEQUENTIAL) => FileAttribute::Sequential,
+        Some(Token::DIRECT) => FileAttribute::Direct,
+        Some(Token::KEYED) => FileAttribute::Keyed,
+        Some(Token::UPDATE) => FileAttribute::Update,
+        _ => return None,
+    };
+    token_manager.next_token();
+    Some(attribute)
+}
+
+///Parses the FILE(...) option of an I/O statement and returns the name of the file.
+///The current token is FILE.
+pub fn parse_file_option(token_manager: &mut lexer::TokenManager) -> Result<String, ParseError> {
+    parse_token(token_manager, Token::FILE)?;
+    parse_identifier_in_parens(token_manager, "a file name")
+}
+
+///Parses "(NAME)" and returns NAME. `description` is what the error message
+///says was expected when there is no identifier between the parenthesis.
+pub fn parse_identifier_in_parens(
+    token_manager: &mut lexer::TokenManager,
+    description: &str,
+) -> Result<String, ParseError> {
+    parse_token(token_manager, Token::OPEN_PAREN)?;
+
+    let name: String;
+    if let Some(Token::Identifier(ref identifier)) = token_manager.current_token {
+        name = identifier.clone();
+    } else {
+        let source_loc = token_manager.get_source_location().to_string();
+        let message = get_error(&["1", description, "a non-identifier", &source_loc]);
+        return Err(ParseError { message });
+    }
+    token_manager.next_token();
+
+    parse_token(token_manager, Token::CLOSED_PAREN)?;
+    Ok(name)
+}
+
+//current token is the semicolon AFTER do
+pub fn parse_do_block(
+    token_manager: &mut lexer::TokenManager,
+) -> Result<Vec<Statement>, ParseError> {
+    let mut statements: Vec<Statement> = vec![];
+
+    //parse_token(token_manager,Token::DO)?;
+    parse_token(token_manager, Token::SEMICOLON)?;
+    loop {
+        if let Token::END = token_manager.current_token.as_mut().unwrap() {
+            parse_token(token_manager, Token::END)?;
+            //END OUTER; closes the group labeled OUTER
+            if let Some(Token::Identifier(_)) = token_manager.current_token {
+                token_manager.next_token();
+            }
+            parse_token(token_manager, Token::SEMICOLON)?;
+            break;
+        }
+
+        let statement = parse_statement(token_manager)?;
+        statements.push(statement);
+    }
+
+    Ok(statements)
+}
+
+///Parses a DO group that isn't part of an IF statement, starting at the DO.
+///DO;
+///DO WHILE (X < 10);
+///DO I = 1 TO 10 BY 2 WHILE (X > 0);
+pub fn parse_do(
+    token_manager: &mut lexer::TokenManager,
+    label: Option<String>,
+) -> Result<Do, ParseError> {
+    parse_token(token_manager, Token::DO)?;
+
+    let mut control = None;
+    if let Some(Token::Identifier(ref name)) = token_manager.current_token {
+        let variable_name = name.clone();
+        token_manager.next_token();
+        parse_token(token_manager, Token::EQ)?;
+        let start = parse_expression(token_manager)?;
+        parse_token(token_manager, Token::TO)?;
+        let end = parse_expression(token_manager)?;
+        let step = match parse_token(token_manager, Token::BY) {
+            Ok(()) => Some(parse_expression(token_manager)?),
+            Err(_) => None,
+        };
+        control = Some(DoControl {
+            variable_name,
+            start,
+            end,
+            step,
+        });
+    }
+
+    let while_condition = match parse_token(token_manager, Token::WHILE) {
+        Ok(()) => Some(parse_expression(token_manager)?),
+        Err(_) => None,
+    };
+
+    let statements = parse_do_block(token_manager)?;
+
+    Ok(Do {
+        label,
+        control,
+        while_condition,
+        statements,
+    })
+}
+
+///Parses the optional label of LEAVE and ITERATE, which is the current token.
+pub fn parse_loop_exit_label(
+    token_manager: &mut lexer::TokenManager,
+) -> Result<Option<String>, ParseError> {
+    token_manager.next_token();
+    let label = match token_manager.current_token {
+        Some(Token::Identifier(ref label)) => Some(label.clone()),
+        _ => None,
+    };
+    if label.is_some() {
+        token_manager.next_token();
+    }
+    Ok(label)
+}
+
+//parses identifiers like variable names but also function calls
+pub fn parse_identifier<'a>(
+    token_manager: &'a mut lexer::TokenManager,
+) -> Result<Expr, ParseError> {
+    trace!("Parsing Identifier");
+    let identifier_string: String;
+    if let Some(Token::Identifier(ref val)) = token_manager.current_token {
+        identifier_string = val.clone();
+        trace!("The identifier string is: {}", identifier_string);
+    } else {
+        log::error!("Found token as {:#?}", token_manager.current_token);
+        let message = "failed to parse identifier!".to_string();
+        return Err(ParseError { message });
+    }
+    let mut args_list: Vec<Expr> = vec![];
+    token_manager.next_token(); // prime next token
+    if let Some(Token::OPEN_PAREN) = token_manager.current_token
+    // if this is a function call
+    {
+        trace!("Found an open parenthesis first!");
+        //function call here.
+        //now we loop through each expression in the arguments
+        let mut expecting_comma: bool = false; // expecting comma does not affect breaking
+        trace!("Turning expecting comma off!");
+        token_manager.next_token();
+        loop {
+            trace!("Looping!");
+            if let Some(Token::CLOSED_PAREN) = token_manager.current_token {
+                trace!("found a closed parenthesis!");
+                token_manager.next_token(); // eat the next token, ready for next use
+                break;
+            } else if let Some(Token::COMMA) = token_manager.current_token {
+                if expecting_comma {
+                    trace!("Found comma at right place, continuing!");
+                    expecting_comma = false;
+                    token_manager.next_token(); // eat the token
+                } else {
+                    panic!("Expected an expression, found a comma!!");
+                }
+            } else if let Some(ref token) = token_manager.current_token {
+                //parse as expression
+                trace!("Found a token called {:#?}", *token);
+                let parsed_arg: Expr = parse_expression(token_manager)?;
+
+                args_list.push(parsed_arg);
+
+                expecting_comma = true;
+                trace!("turned expecting comma on!");
+            } else {
+                panic!("{:?}", token_manager.current_token);
+            }
+        }
+        return Ok(Expr::Call {
+            fn_name: identifier_string,
+            args: args_list,
+            _type: Type::TBD,
+        });
+    } else {
+        return Ok(Expr::Variable {
+            name: identifier_string,
+            _type: Type::DEFAULT_FIXED,
+        });
+    }
+}
+
+///the current token is a '(' / Token::OPEN_PAREN
+pub fn parse_parenthesis_expression(
+    token_manager: &mut lexer::TokenManager,
+) -> Result<Expr, ParseError> {
+    log::trace!("Calling parse_paren_expression");
+    parse_token(token_manager, Token::OPEN_PAREN)?;
+
+    let result: Expr = parse_expression(token_manager)?;
+
+    parse_token(token_manager, Token::CLOSED_PAREN)?;
+
+    log::trace!("Exiting parse_paren_expression");
+    return Ok(result);
+}
+
+pub fn parse_expression<'a>(
+    token_manager: &'a mut lexer::TokenManager,
+) -> Result<Expr, ParseError> {
+    log::trace!("starting a parse_expression() ");
+
+    let left_handed_side = parse_primary_expression(token_manager)?;
+    let lhs_expr = left_handed_side.expression.clone();
+    let was_lhs_in_paren = left_handed_side.in_parenthesis;
+    log::trace!("left side: {:#?}", lhs_expr);
+
+    let assignment_target = match lhs_expr {
+        Expr::Variable { ref name, _type } => Some((name.clone(), None, None)),
+        Expr::Qualified {
+            ref locator,
+            ref name,
+        } => Some((name.clone(), Some(locator.clone()), None)),
+        //an element of an array, A(I)
+        Expr::Call {
+            ref fn_name,
+            ref args,
+            _type,
+        } if args.len() == 1 => Some((fn_name.clone(), None, Some(Box::new(args[0].clone())))),
+        _ => None,
+    };
+    if let Some((name, locator, subscript)) = assignment_target {
+        if let Some(Token::EQ) = token_manager.current_token {
+            log::trace!("EQUAL expression");
+            parse_token(token_manager, Token::EQ).expect("always true"); // eat the equal token
+            let expression_value = parse_expression(token_manager);
+            //return Expr::Assignment(name, expression_value);
+            return Ok(Expr::Assignment {
+                variable_name: name,
+                locator,
+                subscript,
+                value: Box::new(expression_value.unwrap()),
+            });
+        }
+    }
+    match token_manager.current_token {
+        Some(Token::CONCATENATE)
+        | Some(Token::PLUS)
+        | Some(Token::MINUS)
+        | Some(Token::DIVIDE)
+        | Some(Token::AND)
+        | Some(Token::EXPONENT)
+        | Some(Token::MULTIPLY)
+        | Some(Token::GREATER_THAN)
+        | Some(Token::LESS_THAN) => {
+            let token_precedence =
+                get_binary_operator_precedence(token_manager.current_token.as_ref().unwrap());
+
+            log::trace!(
+                "building recursive binary tree!: LHS = {:#?} CURRENT_TOKEN: {:#?}",
+                lhs_expr,
+                token_manager.current_token
+            );
+            build_recursive_binary_tree(token_manager, lhs_expr, token_precedence, was_lhs_in_paren)
+        }
+        _ => Ok(lhs_expr),
+    }
+}
+
+pub fn build_recursive_binary_tree(
+    token_manager: &mut lexer::TokenManager,
+    lhs: Expr,
+    precendence: i32,
+    is_lhs_in_paren: bool,
+) -> Result<Expr, ParseError> {
+    //LHS has to be a binary node.
+    let operator_token: Token = token_manager.current_token.as_ref().unwrap().clone();
     trace!("Operator in this: {:#?}", operator_token);
     let tok = token_manager.next_token();
     trace!("Skipped {:#?}", tok);
@@ -421,7 +1106,15 @@ pub fn parse_primary_expression(
         }
         Token::Identifier(the_identifier) => {
             log::trace!("running identifier code for {}", the_identifier);
-            if the_identifier.ends_with("E") {
+            if let Some(magnitude) = get_imaginary_magnitude(the_identifier) {
+                token_manager.next_token();
+                //3.5I is the complex number COMPLEX(0, 3.5)
+                Expr::Call {
+                    fn_name: "COMPLEX".to_string(),
+                    args: vec![Expr::new_numval(0.0), Expr::new_numval(magnitude)],
+                    _type: Type::TBD,
+                }
+            } else if the_identifier.ends_with("E") {
                 let mut the_identifier = the_identifier.clone();
                 the_identifier.pop();
 
@@ -434,14 +1127,18 @@ pub fn parse_primary_expression(
                 }
             } else {
                 log::trace!("The current token is: {:#?}", token_manager.current_token);
-                parse_identifier(token_manager)?
+                let identifier = parse_identifier(token_manager)?;
+                parse_locator_qualifiers(token_manager, identifier)?
             }
         }
         Token::NumVal(_) => parse_constant_numeric(token_manager)?,
         Token::NOT => parse_constant_numeric(token_manager)?,
-        Token::STRING(value) => Expr::Char {
-            value: value.clone(),
-        },
+        Token::STRING(value) => {
+            log::trace!("parsing a CHAR expression");
+            let value = value.clone();
+            token_manager.next_token(); //eat the string token
+            Expr::Char { value }
+        }
         other => {
             return Err(ParseError {
                 message: format!("Can't parse top level token type {:?}", other),
@@ -454,6 +1151,42 @@ pub fn parse_primary_expression(
         was_in_parenthesis,
     ))
 }
+///The magnitude of an imaginary constant, a number followed by I like 3.5I.
+fn get_imaginary_magnitude(identifier: &str) -> Option<f64> {
+    let number = identifier.strip_suffix('I')?;
+    //INF and NAN parse as numbers, but INFI and NANI are names
+    if !number.starts_with(|character: char| character.is_ascii_digit() || character == '.') {
+        return None;
+    }
+    number.parse().ok()
+}
+
+///Wraps `locator` for every "->NAME" that follows it, so P->Q->X
+///becomes X qualified by Q qualified by P.
+pub fn parse_locator_qualifiers(
+    token_manager: &mut lexer::TokenManager,
+    mut locator: Expr,
+) -> Result<Expr, ParseError> {
+    while let Some(Token::ARROW) = token_manager.current_token {
+        parse_token(token_manager, Token::ARROW)?;
+        let name = match token_manager.current_token {
+            Some(Token::Identifier(ref name)) => name.clone(),
+            _ => {
+                let source_loc = token_manager.get_source_location().to_string();
+                let message =
+                    get_error(&["1", "a BASED variable", "a non-identifier", &source_loc]);
+                return Err(ParseError { message });
+            }
+        };
+        token_manager.next_token();
+        locator = Expr::Qualified {
+            locator: Box::new(locator),
+            name,
+        };
+    }
+    Ok(locator)
+}
+
 fn is_token_infix_operator(current_token: Token) -> bool {
     match current_token {
         Token::NOT => true,
@@ -482,6 +1215,7 @@ pub fn get_binary_operator_precedence(token: &lexer::Token) -> i32 {
         Token::MULTIPLY => 40,
         Token::EXPONENT => 80,
         Token::AND => 4,
+        Token::CONCATENATE => 12,
         Token::DIVIDE => 40,
         Token::LESS_THAN => 8,
         Token::GREATER_THAN => 8,
@@ -491,6 +1225,7 @@ pub fn get_binary_operator_precedence(token: &lexer::Token) -> i32 {
 
 //The token is currently PROCEDURE
 //CALC: PROCEDURE(A,B,C); // we are just parsing this part.
+//CALC: PROCEDURE(A,B,C) OPTIONS(C); // or EXTERNAL, to call it from elsewhere
 //      RETURN(A+B+C);
 //      END;
 pub fn parse_function_prototype(
@@ -506,10 +1241,12 @@ pub fn parse_function_prototype(
     parse_token(token_manager, Token::OPEN_PAREN)?;
     let mut expecting_comma = false;
     let mut args_list: Vec<String> = vec![];
+    let mut linkage = ProcedureLinkage::Internal;
     loop {
         if let Some(Token::CLOSED_PAREN) = token_manager.current_token {
             parse_token(token_manager, Token::CLOSED_PAREN)?;
 
+            linkage = parse_procedure_linkage(token_manager)?;
             parse_token(token_manager, Token::SEMICOLON)?;
             break;
         } else if let Some(Token::COMMA) = token_manager.current_token {
@@ -539,77 +1276,513 @@ pub fn parse_function_prototype(
                 return Err(ParseError { message });
             }
 
-            args_list.push(arg_name);
+            args_list.push(arg_name);
+
+            expecting_comma = true;
+            trace!("turned expecting comma on!");
+        } else {
+            let message = format!("{:?}", token_manager.current_token).to_string();
+            return Err(ParseError { message });
+        }
+    }
+
+    Ok(Prototype {
+        fn_name: label_name,
+        args: args_list,
+        source_loc,
+        linkage,
+    })
+}
+
+fn parse_procedure_linkage(
+    token_manager: &mut lexer::TokenManager,
+) -> Result<ProcedureLinkage, ParseError> {
+    match token_manager.current_token {
+        Some(Token::EXTERNAL) => {
+            parse_token(token_manager, Token::EXTERNAL)?;
+            Ok(ProcedureLinkage::External)
+        }
+        Some(Token::OPTIONS) => {
+            parse_c_option(token_manager)?;
+            Ok(ProcedureLinkage::C)
+        }
+        _ => Ok(ProcedureLinkage::Internal),
+    }
+}
+
+pub fn parse_arguments_in_parens(
+    token_manager: &mut lexer::TokenManager,
+) -> Result<Vec<Expr>, ParseError> {
+    parse_token(token_manager, Token::OPEN_PAREN)?;
+    let mut expecting_comma = false;
+    let mut args_list: Vec<Expr> = vec![];
+    loop {
+        let source_loc = token_manager.get_source_location();
+        if let Some(Token::CLOSED_PAREN) = token_manager.current_token {
+            parse_token(token_manager, Token::CLOSED_PAREN)?;
+
+            //parse_token(token_manager, Token::SEMICOLON)?;
+            break;
+        } else if let Some(Token::COMMA) = token_manager.current_token {
+            if expecting_comma {
+                expecting_comma = false;
+                parse_token(token_manager, Token::COMMA)?;
+            } else {
+                let message = format!("Expected an expression, found a comma at {}", source_loc);
+
+                return Err(ParseError { message });
+            }
+        } else if let Some(ref token) = token_manager.current_token {
+            //
+            //START OF CALLBACK
+            trace!("Found a token called {:#?}", *token);
+            let parsed_arg: Expr = parse_expression(token_manager)?;
+
+            //let arg_name: String;
+            //if let Expr::Variable { name, _type } = parsed_arg {
+            //    arg_name = name.clone();
+            //} else {
+            //    return Err(format!("Expected variable in function prototype, found _").to_string());
+            //}
+
+            args_list.push(parsed_arg);
+            //END OF CALLBACK SHIT
+
+            expecting_comma = true;
+            trace!("turned expecting comma on!");
+        } else {
+            let message = format!("{:?}", token_manager.current_token).to_string();
+            return Err(ParseError { message });
+        }
+    }
+    Ok(args_list)
+}
+
+pub fn parse_put(token_manager: &mut lexer::TokenManager) -> Result<Put, ParseError> {
+    parse_token(token_manager, Token::PUT)?;
+
+    let mut file: Option<String> = None;
+    let mut messages_to_print = IOList { items: vec![] };
+    let mut options: Vec<PutOption> = vec![];
+
+    loop {
+        match token_manager.current_token {
+            Some(Token::FILE) => {
+                file = Some(parse_file_option(token_manager)?);
+            }
+            Some(Token::LIST) => {
+                messages_to_print = *IOList::parse_from_tokens(token_manager)?;
+            }
+            Some(Token::SKIP) => {
+                parse_token(token_manager, Token::SKIP)?;
+                let mut lines_to_skip = Expr::new_numval(1.0);
+                if let Some(Token::OPEN_PAREN) = token_manager.current_token {
+                    lines_to_skip = parse_parenthesis_expression(token_manager)?;
+                }
+                options.push(PutOption::Skip(lines_to_skip));
+            }
+            Some(Token::PAGE) => {
+                parse_token(token_manager, Token::PAGE)?;
+                options.push(PutOption::Page);
+            }
+            Some(Token::LINE) => {
+                parse_token(token_manager, Token::LINE)?;
+                let line_number = parse_parenthesis_expression(token_manager)?;
+                options.push(PutOption::Line(line_number));
+            }
+            _ => break,
+        }
+    }
+
+    Ok(Put {
+        file,
+        messages_to_print,
+        options,
+    })
+}
+
+pub fn parse_get(token_manager: &mut lexer::TokenManager) -> Result<Get, ParseError> {
+    parse_token(token_manager, Token::GET)?;
+
+    let mut file: Option<String> = None;
+    if let Some(Token::FILE) = token_manager.current_token {
+        file = Some(parse_file_option(token_manager)?);
+    }
+
+    let list_to_get = *IOList::parse_from_tokens(token_manager)?;
+
+    Ok(Get { file, list_to_get })
+}
+
+///OPEN FILE(OUTF) STREAM OUTPUT TITLE('out.txt'), FILE(INF);
+pub fn parse_open(token_manager: &mut lexer::TokenManager) -> Result<Open, ParseError> {
+    parse_token(token_manager, Token::OPEN)?;
+
+    let mut files: Vec<OpenFile> = vec![];
+    loop {
+        let file_name = parse_file_option(token_manager)?;
+        let mut attributes: Vec<FileAttribute> = vec![];
+        let mut title: Option<Expr> = None;
+
+        loop {
+            if let Some(Token::TITLE) = token_manager.current_token {
+                parse_token(token_manager, Token::TITLE)?;
+                title = Some(parse_parenthesis_expression(token_manager)?);
+            } else if let Some(attribute) = parse_file_attribute(token_manager) {
+                attributes.push(attribute);
+            } else {
+                break;
+            }
+        }
+
+        files.push(OpenFile {
+            file_name,
+            attributes,
+            title,
+        });
+
+        if let Err(_) = parse_token(token_manager, Token::COMMA) {
+            break;
+        }
+    }
+
+    Ok(Open { files })
+}
+
+///CLOSE FILE(OUTF), FILE(INF);
+pub fn parse_close(token_manager: &mut lexer::TokenManager) -> Result<Close, ParseError> {
+    parse_token(token_manager, Token::CLOSE)?;
+
+    let mut file_names: Vec<String> = vec![];
+    loop {
+        file_names.push(parse_file_option(token_manager)?);
+
+        if let Err(_) = parse_token(token_manager, Token::COMMA) {
+            break;
+        }
+    }
+
+    Ok(Close { file_names })
+}
+
+///ON ENDFILE(INF) GO END_OF_INPUT;
+///The on-unit is a single statement, which eats its own semicolon.
+pub fn parse_on(token_manager: &mut lexer::TokenManager) -> Result<On, ParseError> {
+    parse_token(token_manager, Token::ON)?;
+
+    let condition = match token_manager.current_token {
+        Some(Token::ENDFILE) => {
+            token_manager.next_token();
+            Condition::EndFile(parse_identifier_in_parens(token_manager, "a file name")?)
+        }
+        Some(Token::KEY) => {
+            token_manager.next_token();
+            Condition::Key(parse_identifier_in_parens(token_manager, "a file name")?)
+        }
+        Some(Token::Identifier(ref name)) if name == "FIXEDOVERFLOW" || name == "FOFL" => {
+            token_manager.next_token();
+            Condition::FixedOverflow
+        }
+        Some(Token::Identifier(ref name)) if name == "ZERODIVIDE" || name == "ZDIV" => {
+            token_manager.next_token();
+            Condition::ZeroDivide
+        }
+        Some(Token::Identifier(ref name)) if name == "CONVERSION" || name == "CONV" => {
+            token_manager.next_token();
+            Condition::Conversion
+        }
+        ref other => {
+            let source_loc = token_manager.get_source_location().to_string();
+            let found = format!("{:?}", other);
+            let message = get_error(&["1", "a condition", &found, &source_loc]);
+            return Err(ParseError { message });
+        }
+    };
+
+    let on_unit = Box::new(parse_statement(token_manager)?);
+
+    Ok(On { condition, on_unit })
+}
+
+///Returns an error saying `option` is missing from a `statement` statement,
+///unless it was found.
+fn require_option<T>(
+    token_manager: &lexer::TokenManager,
+    option: Option<T>,
+    statement: &str,
+    option_name: &str,
+) -> Result<T, ParseError> {
+    match option {
+        Some(value) => Ok(value),
+        None => {
+            let source_loc = token_manager.get_source_location().to_string();
+            let expected = format!("{} in the {} statement", option_name, statement);
+            let message = get_error(&["1", &expected, "the end of the statement", &source_loc]);
+            Err(ParseError { message })
+        }
+    }
+}
+
+///READ FILE(F) INTO(X) KEY('K') KEYTO(Y);
+///The options can come in any order. FILE and INTO are required.
+pub fn parse_read(token_manager: &mut lexer::TokenManager) -> Result<Read, ParseError> {
+    parse_token(token_manager, Token::READ)?;
+
+    let mut file_name: Option<String> = None;
+    let mut into: Option<String> = None;
+    let mut key: Option<Expr> = None;
+    let mut keyto: Option<String> = None;
+
+    loop {
+        match token_manager.current_token {
+            Some(Token::FILE) => file_name = Some(parse_file_option(token_manager)?),
+            Some(Token::INTO) => {
+                parse_token(token_manager, Token::INTO)?;
+                into = Some(parse_identifier_in_parens(
+                    token_manager,
+                    "a variable name",
+                )?);
+            }
+            Some(Token::KEY) => {
+                parse_token(token_manager, Token::KEY)?;
+                key = Some(parse_parenthesis_expression(token_manager)?);
+            }
+            Some(Token::KEYTO) => {
+                parse_token(token_manager, Token::KEYTO)?;
+                keyto = Some(parse_identifier_in_parens(
+                    token_manager,
+                    "a variable name",
+                )?);
+            }
+            _ => break,
+        }
+    }
+
+    Ok(Read {
+        file_name: require_option(token_manager, file_name, "READ", "FILE")?,
+        into: require_option(token_manager, into, "READ", "INTO")?,
+        key,
+        keyto,
+    })
+}
+
+///WRITE FILE(F) FROM(X) KEYFROM('K');
+pub fn parse_write(token_manager: &mut lexer::TokenManager) -> Result<Write, ParseError> {
+    parse_token(token_manager, Token::WRITE)?;
+
+    let mut file_name: Option<String> = None;
+    let mut from: Option<String> = None;
+    let mut keyfrom: Option<Expr> = None;
+
+    loop {
+        match token_manager.current_token {
+            Some(Token::FILE) => file_name = Some(parse_file_option(token_manager)?),
+            Some(Token::FROM) => {
+                parse_token(token_manager, Token::FROM)?;
+                from = Some(parse_identifier_in_parens(
+                    token_manager,
+                    "a variable name",
+                )?);
+            }
+            Some(Token::KEYFROM) => {
+                parse_token(token_manager, Token::KEYFROM)?;
+                keyfrom = Some(parse_parenthesis_expression(token_manager)?);
+            }
+            _ => break,
+        }
+    }
+
+    Ok(Write {
+        file_name: require_option(token_manager, file_name, "WRITE", "FILE")?,
+        from: require_option(token_manager, from, "WRITE", "FROM")?,
+        keyfrom,
+    })
+}
 
-            expecting_comma = true;
-            trace!("turned expecting comma on!");
-        } else {
-            let message = format!("{:?}", token_manager.current_token).to_string();
-            return Err(ParseError { message });
+///REWRITE FILE(F) FROM(X) KEY('K');
+pub fn parse_rewrite(token_manager: &mut lexer::TokenManager) -> Result<Rewrite, ParseError> {
+    parse_token(token_manager, Token::REWRITE)?;
+
+    let mut file_name: Option<String> = None;
+    let mut from: Option<String> = None;
+    let mut key: Option<Expr> = None;
+
+    loop {
+        match token_manager.current_token {
+            Some(Token::FILE) => file_name = Some(parse_file_option(token_manager)?),
+            Some(Token::FROM) => {
+                parse_token(token_manager, Token::FROM)?;
+                from = Some(parse_identifier_in_parens(
+                    token_manager,
+                    "a variable name",
+                )?);
+            }
+            Some(Token::KEY) => {
+                parse_token(token_manager, Token::KEY)?;
+                key = Some(parse_parenthesis_expression(token_manager)?);
+            }
+            _ => break,
         }
     }
 
-    Ok(Prototype {
-        fn_name: label_name,
-        args: args_list,
-        source_loc,
+    Ok(Rewrite {
+        file_name: require_option(token_manager, file_name, "REWRITE", "FILE")?,
+        from: require_option(token_manager, from, "REWRITE", "FROM")?,
+        key,
     })
 }
 
-pub fn parse_arguments_in_parens(
-    token_manager: &mut lexer::TokenManager,
-) -> Result<Vec<Expr>, ParseError> {
-    parse_token(token_manager, Token::OPEN_PAREN)?;
-    let mut expecting_comma = false;
-    let mut args_list: Vec<Expr> = vec![];
-    loop {
-        let source_loc = token_manager.get_source_location();
-        if let Some(Token::CLOSED_PAREN) = token_manager.current_token {
-            parse_token(token_manager, Token::CLOSED_PAREN)?;
+///DELETE FILE(F) KEY('K');
+pub fn parse_delete(token_manager: &mut lexer::TokenManager) -> Result<Delete, ParseError> {
+    parse_token(token_manager, Token::DELETE)?;
 
-            //parse_token(token_manager, Token::SEMICOLON)?;
-            break;
-        } else if let Some(Token::COMMA) = token_manager.current_token {
-            if expecting_comma {
-                expecting_comma = false;
-                parse_token(token_manager, Token::COMMA)?;
-            } else {
-                let message = format!("Expected an expression, found a comma at {}", source_loc);
+    let mut file_name: Option<String> = None;
+    let mut key: Option<Expr> = None;
 
-                return Err(ParseError { message });
+    loop {
+        match token_manager.current_token {
+            Some(Token::FILE) => file_name = Some(parse_file_option(token_manager)?),
+            Some(Token::KEY) => {
+                parse_token(token_manager, Token::KEY)?;
+                key = Some(parse_parenthesis_expression(token_manager)?);
             }
-        } else if let Some(ref token) = token_manager.current_token {
-            //
-            //START OF CALLBACK
-            trace!("Found a token called {:#?}", *token);
-            let parsed_arg: Expr = parse_expression(token_manager)?;
+            _ => break,
+        }
+    }
 
-            //let arg_name: String;
-            //if let Expr::Variable { name, _type } = parsed_arg {
-            //    arg_name = name.clone();
-            //} else {
-            //    return Err(format!("Expected variable in function prototype, found _").to_string());
-            //}
+    Ok(Delete {
+        file_name: require_option(token_manager, file_name, "DELETE", "FILE")?,
+        key,
+    })
+}
 
-            args_list.push(parsed_arg);
-            //END OF CALLBACK SHIT
+///ALLOCATE X; or ALLOCATE X SET(P);
+pub fn parse_allocate(token_manager: &mut lexer::TokenManager) -> Result<Allocate, ParseError> {
+    parse_token(token_manager, Token::ALLOCATE)?;
 
-            expecting_comma = true;
-            trace!("turned expecting comma on!");
-        } else {
-            let message = format!("{:?}", token_manager.current_token).to_string();
+    let var_name = match token_manager.current_token {
+        Some(Token::Identifier(ref name)) => name.clone(),
+        _ => {
+            let source_loc = token_manager.get_source_location().to_string();
+            let message = get_error(&["1", "a BASED variable", "a non-identifier", &source_loc]);
+            return Err(ParseError { message });
+        }
+    };
+    token_manager.next_token();
+
+    let mut set = None;
+    if let Some(Token::SET) = token_manager.current_token {
+        parse_token(token_manager, Token::SET)?;
+        set = Some(parse_identifier_in_parens(token_manager, "a pointer name")?);
+    }
+
+    Ok(Allocate { var_name, set })
+}
+
+///DEFAULT RANGE(A:H) FLOAT; or DEFAULT RANGE(*) FIXED BINARY;
+pub fn parse_default(token_manager: &mut lexer::TokenManager) -> Result<DefaultRule, ParseError> {
+    parse_token(token_manager, Token::DEFAULT)?;
+    match token_manager.current_token {
+        Some(Token::Identifier(ref keyword)) if keyword == "RANGE" => {
+            token_manager.next_token();
+        }
+        ref other => {
+            let message = format!("Expected RANGE after DEFAULT, found {:#?}", other);
             return Err(ParseError { message });
         }
     }
-    Ok(args_list)
+
+    parse_token(token_manager, Token::OPEN_PAREN)?;
+    let range = parse_default_range(token_manager)?;
+    parse_token(token_manager, Token::CLOSED_PAREN)?;
+
+    let _type = match parse_descriptor(token_manager)? {
+        ast::Descriptor::Data(_type) => _type,
+        ast::Descriptor::FixedBinary(precision) => get_fixed_binary_type(precision),
+        ast::Descriptor::AnyLengthCharacter => {
+            let message = "A DEFAULT statement can't use CHAR(*)".to_string();
+            return Err(ParseError { message });
+        }
+    };
+
+    Ok(DefaultRule { range, _type })
 }
 
-pub fn parse_put(token_manager: &mut lexer::TokenManager) -> Result<Put, ParseError> {
-    parse_token(token_manager, Token::PUT)?;
+///Parses the "*", "A" or "A:H" of RANGE(...). "A:" is read as a label.
+fn parse_default_range(
+    token_manager: &mut lexer::TokenManager,
+) -> Result<DefaultRange, ParseError> {
+    if let Some(Token::MULTIPLY) = token_manager.current_token {
+        parse_token(token_manager, Token::MULTIPLY)?;
+        return Ok(DefaultRange::All);
+    }
+
+    let first = match token_manager.current_token.clone() {
+        Some(Token::LABEL(letter)) => {
+            token_manager.next_token();
+            parse_range_letter(&letter)?
+        }
+        Some(Token::Identifier(letter)) => {
+            token_manager.next_token();
+            let first = parse_range_letter(&letter)?;
+            match token_manager.current_token {
+                //a space before the colon leaves it as an empty label
+                Some(Token::LABEL(ref empty)) if empty.is_empty() => {
+                    token_manager.next_token();
+                }
+                _ => return Ok(DefaultRange::Letters(first, first)),
+            }
+            first
+        }
+        ref other => {
+            let message = format!("Expected a letter in RANGE, found {:#?}", other);
+            return Err(ParseError { message });
+        }
+    };
+
+    match token_manager.current_token.clone() {
+        Some(Token::Identifier(letter)) => {
+            token_manager.next_token();
+            Ok(DefaultRange::Letters(first, parse_range_letter(&letter)?))
+        }
+        ref other => {
+            let message = format!("Expected a letter in RANGE, found {:#?}", other);
+            Err(ParseError { message })
+        }
+    }
+}
 
-    let messages_to_print = *IOList::parse_from_tokens(token_manager)?;
+fn parse_range_letter(letter: &str) -> Result<char, ParseError> {
+    let mut chars = letter.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), None) if letter.is_ascii_alphabetic() => Ok(letter.to_ascii_uppercase()),
+        _ => {
+            let message = format!("Expected a single letter in RANGE, found {}", letter);
+            Err(ParseError { message })
+        }
+    }
+}
 
-    Ok(Put { messages_to_print })
+///FREE X; or FREE P->X;
+pub fn parse_free(token_manager: &mut lexer::TokenManager) -> Result<Free, ParseError> {
+    parse_token(token_manager, Token::FREE)?;
+
+    match parse_primary_expression(token_manager)?.expression {
+        Expr::Variable { name, _type } => Ok(Free {
+            var_name: name,
+            locator: None,
+        }),
+        Expr::Qualified { locator, name } => Ok(Free {
+            var_name: name,
+            locator: Some(*locator),
+        }),
+        other => {
+            let source_loc = token_manager.get_source_location().to_string();
+            let found = format!("{:?}", other);
+            let message = get_error(&["1", "a BASED variable", &found, &source_loc]);
+            Err(ParseError { message })
+        }
+    }
 }
 
 pub fn parse_function(
@@ -659,6 +1832,7 @@ pub fn parse_statement(token_manager: &mut lexer::TokenManager) -> Result<Statem
     debug!("Calling parse statement!");
     let mut command: Command = Command::Empty;
     let mut label: Option<String> = None;
+    let source_loc = token_manager.get_source_location();
     while let Some(ref token) = token_manager.current_token {
         log::debug!("Token at beginning of statement: {:#?}", &token);
         match token {
@@ -674,64 +1848,221 @@ pub fn parse_statement(token_manager: &mut lexer::TokenManager) -> Result<Statem
                 match command {
                     Command::Empty => (),
                     other_command => {
-                        let message = get_error(&["4", "LABEL", &other_command.to_string()]);
+                        let message = get_error(&["4", "LABEL", &other_command.to_string()]);
+                        return Err(ParseError { message });
+                    }
+                }
+
+                label = Some(label_string.to_string()); //store the fact something
+                token_manager.next_token(); //is labelled
+            }
+            Token::PUT => {
+                match command {
+                    Command::Empty => command = Command::PUT(parse_put(token_manager)?),
+                    other_command => {
+                        let message = get_error(&["4", "PUT", &other_command.to_string()]);
+                        return Err(ParseError { message });
+                    }
+                }
+                parse_token(token_manager, Token::SEMICOLON)?;
+                break;
+            }
+            Token::GO => {
+                match command {
+                    Command::Empty => {
+                        command = Command::GO(*(ast::Go::parse_from_tokens(token_manager).unwrap()))
+                    }
+                    other_command => {
+                        let message = get_error(&["4", "PUT", &other_command.to_string()]);
+                        return Err(ParseError { message });
+                    }
+                }
+                parse_token(token_manager, Token::SEMICOLON)?;
+                break;
+            }
+            //            Token::PUT => {
+            //                match command {
+            //                    Command::Empty => command = Command::PUT(parse_put(token_manager)?),
+            //                    other_command => {
+            //                        let message = get_error(&["4", "PUT", &other_command.to_string()]);
+            //                        return Err(ParseError { message });
+            //                    }
+            //                }
+            //
+            //                parse_token(token_manager, Token::SEMICOLON)?;
+            //                break;
+            //            }
+            Token::GET => {
+                match command {
+                    Command::Empty => command = Command::GET(parse_get(token_manager)?),
+                    other_command => {
+                        let message = get_error(&["4", "PUT", &other_command.to_string()]);
+                        return Err(ParseError { message });
+                    }
+                }
+
+                parse_token(token_manager, Token::SEMICOLON)?;
+                break;
+            }
+            Token::OPEN => {
+                match command {
+                    Command::Empty => command = Command::OPEN(parse_open(token_manager)?),
+                    other_command => {
+                        let message = get_error(&["4", "OPEN", &other_command.to_string()]);
+                        return Err(ParseError { message });
+                    }
+                }
+                parse_token(token_manager, Token::SEMICOLON)?;
+                break;
+            }
+            Token::CLOSE => {
+                match command {
+                    Command::Empty => command = Command::CLOSE(parse_close(token_manager)?),
+                    other_command => {
+                        let message = get_error(&["4", "CLOSE", &other_command.to_string()]);
+                        return Err(ParseError { message });
+                    }
+                }
+                parse_token(token_manager, Token::SEMICOLON)?;
+                break;
+            }
+            Token::READ => {
+                match command {
+                    Command::Empty => command = Command::READ(parse_read(token_manager)?),
+                    other_command => {
+                        let message = get_error(&["4", "READ", &other_command.to_string()]);
+                        return Err(ParseError { message });
+                    }
+                }
+                parse_token(token_manager, Token::SEMICOLON)?;
+                break;
+            }
+            Token::WRITE => {
+                match command {
+                    Command::Empty => command = Command::WRITE(parse_write(token_manager)?),
+                    other_command => {
+                        let message = get_error(&["4", "WRITE", &other_command.to_string()]);
+                        return Err(ParseError { message });
+                    }
+                }
+                parse_token(token_manager, Token::SEMICOLON)?;
+                break;
+            }
+            Token::ALLOCATE => {
+                match command {
+                    Command::Empty => command = Command::ALLOCATE(parse_allocate(token_manager)?),
+                    other_command => {
+                        let message = get_error(&["4", "ALLOCATE", &other_command.to_string()]);
+                        return Err(ParseError { message });
+                    }
+                }
+                parse_token(token_manager, Token::SEMICOLON)?;
+                break;
+            }
+            Token::FREE => {
+                match command {
+                    Command::Empty => command = Command::FREE(parse_free(token_manager)?),
+                    other_command => {
+                        let message = get_error(&["4", "FREE", &other_command.to_string()]);
+                        return Err(ParseError { message });
+                    }
+                }
+                parse_token(token_manager, Token::SEMICOLON)?;
+                break;
+            }
+            Token::DEFAULT => {
+                match command {
+                    Command::Empty => command = Command::DEFAULT(parse_default(token_manager)?),
+                    other_command => {
+                        let message = get_error(&["4", "DEFAULT", &other_command.to_string()]);
+                        return Err(ParseError { message });
+                    }
+                }
+                parse_token(token_manager, Token::SEMICOLON)?;
+                break;
+            }
+            Token::DO => {
+                match command {
+                    Command::Empty => {
+                        command = Command::DO(parse_do(token_manager, label.clone())?)
+                    }
+                    other_command => {
+                        let message = get_error(&["4", "DO", &other_command.to_string()]);
+                        return Err(ParseError { message });
+                    }
+                }
+                break;
+            }
+            Token::LEAVE => {
+                match command {
+                    Command::Empty => {
+                        let label = parse_loop_exit_label(token_manager)?;
+                        command = Command::LEAVE(Leave { label });
+                    }
+                    other_command => {
+                        let message = get_error(&["4", "LEAVE", &other_command.to_string()]);
+                        return Err(ParseError { message });
+                    }
+                }
+                parse_token(token_manager, Token::SEMICOLON)?;
+                break;
+            }
+            Token::ITERATE => {
+                match command {
+                    Command::Empty => {
+                        let label = parse_loop_exit_label(token_manager)?;
+                        command = Command::ITERATE(Iterate { label });
+                    }
+                    other_command => {
+                        let message = get_error(&["4", "ITERATE", &other_command.to_string()]);
+                        return Err(ParseError { message });
+                    }
+                }
+                parse_token(token_manager, Token::SEMICOLON)?;
+                break;
+            }
+            Token::STOP => {
+                match command {
+                    Command::Empty => command = Command::STOP,
+                    other_command => {
+                        let message = get_error(&["4", "STOP", &other_command.to_string()]);
                         return Err(ParseError { message });
                     }
                 }
-
-                label = Some(label_string.to_string()); //store the fact something
-                token_manager.next_token(); //is labelled
+                token_manager.next_token();
+                parse_token(token_manager, Token::SEMICOLON)?;
+                break;
             }
-            Token::PUT => {
+            Token::REWRITE => {
                 match command {
-                    Command::Empty => command = Command::PUT(parse_put(token_manager)?),
+                    Command::Empty => command = Command::REWRITE(parse_rewrite(token_manager)?),
                     other_command => {
-                        let message = get_error(&["4", "PUT", &other_command.to_string()]);
+                        let message = get_error(&["4", "REWRITE", &other_command.to_string()]);
                         return Err(ParseError { message });
                     }
                 }
                 parse_token(token_manager, Token::SEMICOLON)?;
                 break;
             }
-            Token::GO => {
+            Token::DELETE => {
                 match command {
-                    Command::Empty => {
-                        command = Command::GO(*(ast::Go::parse_from_tokens(token_manager).unwrap()))
-                    }
+                    Command::Empty => command = Command::DELETE(parse_delete(token_manager)?),
                     other_command => {
-                        let message = get_error(&["4", "PUT", &other_command.to_string()]);
+                        let message = get_error(&["4", "DELETE", &other_command.to_string()]);
                         return Err(ParseError { message });
                     }
                 }
                 parse_token(token_manager, Token::SEMICOLON)?;
                 break;
             }
-            //            Token::PUT => {
-            //                match command {
-            //                    Command::Empty => command = Command::PUT(parse_put(token_manager)?),
-            //                    other_command => {
-            //                        let message = get_error(&["4", "PUT", &other_command.to_string()]);
-            //                        return Err(ParseError { message });
-            //                    }
-            //                }
-            //
-            //                parse_token(token_manager, Token::SEMICOLON)?;
-            //                break;
-            //            }
-            Token::GET => {
+            Token::ON => {
                 match command {
-                    Command::Empty => {
-                        parse_token(token_manager, Token::GET)?;
-                        let list_to_get = *IOList::parse_from_tokens(token_manager).unwrap();
-                        command = Command::GET(Get { list_to_get })
-                    }
+                    Command::Empty => command = Command::ON(parse_on(token_manager)?),
                     other_command => {
-                        let message = get_error(&["4", "PUT", &other_command.to_string()]);
+                        let message = get_error(&["4", "ON", &other_command.to_string()]);
                         return Err(ParseError { message });
                     }
                 }
-
-                parse_token(token_manager, Token::SEMICOLON)?;
                 break;
             }
             Token::PROCEDURE => {
@@ -747,6 +2078,7 @@ pub fn parse_statement(token_manager: &mut lexer::TokenManager) -> Result<Statem
                 return Ok(Statement {
                     label: Some(fn_name),
                     command: Command::FunctionDec(result),
+                    source_loc,
                 });
             }
             Token::END => {
@@ -796,7 +2128,11 @@ pub fn parse_statement(token_manager: &mut lexer::TokenManager) -> Result<Statem
                         }
                     }
                     token_manager.next_token();
-                    return Ok(Statement { label, command });
+                    return Ok(Statement {
+                        label,
+                        command,
+                        source_loc,
+                    });
                 }
                 match command {
                     Command::Empty => command = Command::RETURN(parse_expression(token_manager)?),
@@ -816,11 +2152,15 @@ pub fn parse_statement(token_manager: &mut lexer::TokenManager) -> Result<Statem
                 let new_command;
                 if let Expr::Assignment {
                     variable_name,
+                    locator,
+                    subscript,
                     value,
                 } = expr
                 {
                     let assn = Assignment {
                         var_name: variable_name,
+                        locator: locator.map(|locator| *locator),
+                        subscript: subscript.map(|subscript| *subscript),
                         value: *value,
                     };
                     new_command = Command::Assignment(assn);
@@ -839,7 +2179,11 @@ pub fn parse_statement(token_manager: &mut lexer::TokenManager) -> Result<Statem
         }
     } // end while loop
 
-    Ok(Statement { label, command })
+    Ok(Statement {
+        label,
+        command,
+        source_loc,
+    })
 }
 
 pub fn parse_float_const(token_manager: &mut lexer::TokenManager) -> Result<Expr, ParseError> {
@@ -874,7 +2218,7 @@ pub fn parse_float_const(token_manager: &mut lexer::TokenManager) -> Result<Expr
 
         token_manager.next_token();
         let exponent_expr = parse_constant_numeric(token_manager)?;
-        if let Expr::NumVal { value, _type } = exponent_expr {
+        if let Expr::NumVal { value, _type, .. } = exponent_expr {
             let exponent = value as i32 * exp_sign;
             final_float = float_constant.powi(exponent);
         } else {
@@ -890,19 +2234,23 @@ pub fn parse_float_const(token_manager: &mut lexer::TokenManager) -> Result<Expr
 
     Ok(Expr::NumVal {
         value: final_float,
-        _type: Type::Float,
+        _type: Type::DEFAULT_FLOAT,
+        digits: None,
     })
 }
 
 ///parses the beginning of a PL/1 Program.
 ///They look like this:
 /// ANY_LABEL_HERE : PROCDURE OPTIONS (MAIN);
-pub fn parse_opening(token_manager: &mut lexer::TokenManager) -> Result<(), ParseError> {
-    if let Some(Token::LABEL(_)) = token_manager.current_token {
-        token_manager.next_token();
+///Parses the procedure a compilation unit begins with. Without OPTIONS(MAIN),
+///it is an external procedure that other units can call.
+pub fn parse_opening(token_manager: &mut lexer::TokenManager) -> Result<UnitProcedure, ParseError> {
+    let name = if let Some(Token::LABEL(ref label)) = token_manager.current_token {
+        label.clone()
     } else {
         panic!("Program not beginning with a label!");
-    }
+    };
+    token_manager.next_token();
     if let Some(Token::PROCEDURE) = token_manager.current_token {
         token_manager.next_token();
     } else {
@@ -911,7 +2259,11 @@ pub fn parse_opening(token_manager: &mut lexer::TokenManager) -> Result<(), Pars
     if let Some(Token::OPTIONS) = token_manager.current_token {
         token_manager.next_token();
     } else {
-        panic!("Program missing OPTIONS attribute on main procedure!");
+        parse_token(token_manager, Token::SEMICOLON)?;
+        return Ok(UnitProcedure {
+            name,
+            is_main: false,
+        });
     }
     if let Some(Token::OPEN_PAREN) = token_manager.current_token {
         token_manager.next_token();
@@ -934,7 +2286,10 @@ pub fn parse_opening(token_manager: &mut lexer::TokenManager) -> Result<(), Pars
     }
     parse_token(token_manager, Token::SEMICOLON)?;
 
-    Ok(())
+    Ok(UnitProcedure {
+        name,
+        is_main: true,
+    })
 }
 
 // TRAITS ////////////////
@@ -956,21 +2311,31 @@ impl Parseable for ast::IOList {
 impl Parseable for ast::Go {
     fn parse_from_tokens(token_manager: &mut lexer::TokenManager) -> Result<Box<Self>, ParseError> {
         parse_token(token_manager, Token::GO)?;
+        //GO TO L; and GO L; are the same
+        if let Some(Token::TO) = token_manager.current_token {
+            parse_token(token_manager, Token::TO)?;
+        }
 
         //TODO: Implement a "Parse Raw Word To String" function
         let exp = parse_identifier(token_manager)?;
-        if let Expr::Variable {
-            _type: _,
-            name: nam,
-        } = exp
-        {
-            Ok(Box::new(Go {
+        match exp {
+            Expr::Variable {
+                _type: _,
+                name: nam,
+            } => Ok(Box::new(Go {
                 label_to_go_to: nam,
-            }))
-        } else {
-            panic!(
+                subscript: None,
+            })),
+            //GO TO CASE(I); goes to the label held by an element of a LABEL array
+            Expr::Call {
+                fn_name, mut args, ..
+            } if args.len() == 1 => Ok(Box::new(Go {
+                label_to_go_to: fn_name,
+                subscript: Some(args.remove(0)),
+            })),
+            _ => panic!(
                 "Expected some string 'identifier' type after GO command, found something else!"
-            );
+            ),
         }
     }
 }
@@ -994,12 +2359,12 @@ mod tests {
 
         let lhsvar = Expr::Variable {
             name: String::from("x"),
-            _type: Type::FixedDecimal,
+            _type: Type::DEFAULT_FIXED,
         };
 
         let rhsvar = Expr::Variable {
             name: String::from("y"),
-            _type: Type::FixedDecimal,
+            _type: Type::DEFAULT_FIXED,
         };
 
         let _test = Expr::Binary {
@@ -1010,11 +2375,11 @@ mod tests {
 
         let lhsvar = Expr::Variable {
             name: String::from("x"),
-            _type: Type::FixedDecimal,
+            _type: Type::DEFAULT_FIXED,
         };
         if let Expr::Variable {
             name,
-            _type: Type::FixedDecimal,
+            _type: Type::FixedDecimal(..),
         } = lhsvar
         {
             assert_eq!(name, "x");
@@ -1029,7 +2394,7 @@ mod tests {
 
         let result: Expr = parse_constant_numeric(&mut tok_man)?;
 
-        if let Expr::NumVal { value, _type } = result {
+        if let Expr::NumVal { value, _type, .. } = result {
             assert_eq!(4.0, value);
 
             Ok(())
@@ -1044,9 +2409,9 @@ mod tests {
 
         let result: Expr = parse_float_const(&mut tok_man)?;
 
-        if let Expr::NumVal { value, _type } = result {
+        if let Expr::NumVal { value, _type, .. } = result {
             assert_eq!(4.0, value);
-            assert_eq!(Type::Float, _type);
+            assert_eq!(Type::DEFAULT_FLOAT, _type);
 
             Ok(())
         } else {
@@ -1064,6 +2429,32 @@ mod tests {
         dbg!("{:#?}", &list);
     }
 
+    #[test]
+    fn parse_put_options() -> Result<(), ParseError> {
+        let mut tok_man = TokenManager::new("PUT PAGE SKIP(2) LIST(A,B) LINE(4) SKIP;");
+
+        let put = parse_put(&mut tok_man)?;
+
+        assert_eq!(put.messages_to_print.items.len(), 2);
+        assert_eq!(put.options.len(), 4);
+
+        let expected_values = [None, Some(2.0), Some(4.0), Some(1.0)];
+        for (option, expected_value) in put.options.iter().zip(expected_values) {
+            let found_value = match option {
+                PutOption::Page => None,
+                PutOption::Skip(Expr::NumVal { value, .. }) => Some(*value),
+                PutOption::Line(Expr::NumVal { value, .. }) => Some(*value),
+                other => panic!("Unexpected PUT option {:#?}", other),
+            };
+            assert_eq!(expected_value, found_value);
+        }
+        assert!(matches!(put.options[1], PutOption::Skip(_)));
+        assert!(matches!(put.options[2], PutOption::Line(_)));
+
+        assert_eq!(Some(Token::SEMICOLON), tok_man.current_token);
+        Ok(())
+    }
+
     #[test]
     fn parse_get() {
         let mut tok_man = TokenManager::new("GET LIST(A,B,C);");
@@ -1081,6 +2472,331 @@ mod tests {
         };
     }
 
+    #[test]
+    fn parse_file_declare_and_open() -> Result<(), ParseError> {
+        let mut tok_man = TokenManager::new(
+            "DCL OUTF STREAM FILE OUTPUT; OPEN FILE(OUTF) TITLE('OUT.TXT') PRINT, FILE(INF);",
+        );
+
+        let declare = parse_statement(&mut tok_man)?;
+        match declare.command {
+            Command::Declare(declare) => {
+                assert_eq!("OUTF", declare.var_name);
+                assert_eq!(
+                    Some(vec![FileAttribute::Stream, FileAttribute::Output]),
+                    declare.file_attributes
+                );
+            }
+            other => panic!("Expected a DECLARE, received a {:#?}", other),
+        }
+
+        let open = parse_statement(&mut tok_man)?;
+        match open.command {
+            Command::OPEN(open) => {
+                assert_eq!(2, open.files.len());
+                assert_eq!("OUTF", open.files[0].file_name);
+                assert_eq!(vec![FileAttribute::Print], open.files[0].attributes);
+                assert!(matches!(open.files[0].title, Some(Expr::Char { .. })));
+                assert_eq!("INF", open.files[1].file_name);
+                assert!(open.files[1].title.is_none());
+            }
+            other => panic!("Expected an OPEN, received a {:#?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parse_on_endfile() -> Result<(), ParseError> {
+        let mut tok_man = TokenManager::new("ON ENDFILE(INF) GO DONE; GET FILE(INF) LIST(A);");
+
+        let on = parse_statement(&mut tok_man)?;
+        match on.command {
+            Command::ON(on) => {
+                assert_eq!(Condition::EndFile("INF".to_string()), on.condition);
+                assert!(matches!(on.on_unit.command, Command::GO(_)));
+            }
+            other => panic!("Expected an ON, received a {:#?}", other),
+        }
+
+        let get = parse_statement(&mut tok_man)?;
+        match get.command {
+            Command::GET(get) => assert_eq!(Some("INF".to_string()), get.file),
+            other => panic!("Expected a GET, received a {:#?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parse_on_arithmetic_conditions() -> Result<(), ParseError> {
+        let mut tok_man =
+            TokenManager::new("ON FIXEDOVERFLOW GO BIG; ON ZDIV PUT LIST('ZERO'); ON CONV GO BAD;");
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::ON(on) => assert_eq!(Condition::FixedOverflow, on.condition),
+            other => panic!("Expected an ON, received a {:#?}", other),
+        }
+        match parse_statement(&mut tok_man)?.command {
+            Command::ON(on) => {
+                assert_eq!(Condition::ZeroDivide, on.condition);
+                assert!(matches!(on.on_unit.command, Command::PUT(_)));
+            }
+            other => panic!("Expected an ON, received a {:#?}", other),
+        }
+        match parse_statement(&mut tok_man)?.command {
+            Command::ON(on) => assert_eq!(Condition::Conversion, on.condition),
+            other => panic!("Expected an ON, received a {:#?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parse_record_statements() -> Result<(), ParseError> {
+        let mut tok_man = TokenManager::new(
+            "DCL ACCOUNTS FILE RECORD KEYED UPDATE; \
+             READ INTO(ACCOUNT) FILE(ACCOUNTS) KEY('ABC') KEYTO(NAME); \
+             WRITE FILE(ACCOUNTS) FROM(ACCOUNT) KEYFROM(NAME); \
+             REWRITE FILE(ACCOUNTS) FROM(ACCOUNT); \
+             DELETE FILE(ACCOUNTS) KEY('ABC'); \
+             ON KEY(ACCOUNTS) GO MISSING;",
+        );
+
+        let declare = parse_statement(&mut tok_man)?;
+        match declare.command {
+            Command::Declare(declare) => assert_eq!(
+                Some(vec![
+                    FileAttribute::Record,
+                    FileAttribute::Keyed,
+                    FileAttribute::Update
+                ]),
+                declare.file_attributes
+            ),
+            other => panic!("Expected a DECLARE, received a {:#?}", other),
+        }
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::READ(read) => {
+                assert_eq!("ACCOUNTS", read.file_name);
+                assert_eq!("ACCOUNT", read.into);
+                assert!(matches!(read.key, Some(Expr::Char { .. })));
+                assert_eq!(Some("NAME".to_string()), read.keyto);
+            }
+            other => panic!("Expected a READ, received a {:#?}", other),
+        }
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::WRITE(write) => {
+                assert_eq!("ACCOUNTS", write.file_name);
+                assert_eq!("ACCOUNT", write.from);
+                assert!(matches!(write.keyfrom, Some(Expr::Variable { .. })));
+            }
+            other => panic!("Expected a WRITE, received a {:#?}", other),
+        }
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::REWRITE(rewrite) => assert!(rewrite.key.is_none()),
+            other => panic!("Expected a REWRITE, received a {:#?}", other),
+        }
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::DELETE(delete) => assert!(delete.key.is_some()),
+            other => panic!("Expected a DELETE, received a {:#?}", other),
+        }
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::ON(on) => assert_eq!(Condition::Key("ACCOUNTS".to_string()), on.condition),
+            other => panic!("Expected an ON, received a {:#?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn read_without_into_is_an_error() {
+        let mut tok_man = TokenManager::new("READ FILE(ACCOUNTS);");
+        assert!(parse_statement(&mut tok_man).is_err());
+    }
+
+    #[test]
+    fn parse_picture_declares() -> Result<(), ParseError> {
+        let mut tok_man = TokenManager::new("DCL AMT PIC '$$$,$$9V.99'; DCL N PICTURE 'ZZZ9';");
+
+        for expected in ["$$$,$$9V.99", "ZZZ9"] {
+            match parse_statement(&mut tok_man)?.command {
+                Command::Declare(declare) => {
+                    let picture = Picture::parse(expected).unwrap();
+                    assert_eq!(Some(Type::Picture(picture)), declare.attribute)
+                }
+                other => panic!("Expected a DECLARE, received a {:#?}", other),
+            }
+        }
+
+        let mut tok_man = TokenManager::new("DCL AMT PIC '99X';");
+        assert!(parse_statement(&mut tok_man).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_bases_and_precisions() -> Result<(), ParseError> {
+        let mut tok_man = TokenManager::new(
+            "DCL A FLOAT DECIMAL(6); DCL B FLOAT DEC(33); DCL C FLOAT BINARY(53); DCL D FIXED DECIMAL(7,2); DCL E FLOAT;",
+        );
+
+        for expected in [
+            Type::Float(6),
+            Type::Float(33),
+            Type::Float(16),
+            Type::FixedDecimal(7, 2),
+            Type::DEFAULT_FLOAT,
+        ] {
+            match parse_statement(&mut tok_man)?.command {
+                Command::Declare(declare) => assert_eq!(Some(expected), declare.attribute),
+                other => panic!("Expected a DECLARE, received a {:#?}", other),
+            }
+        }
+
+        let mut tok_man = TokenManager::new("DCL X FLOAT DECIMAL(34);");
+        assert!(parse_statement(&mut tok_man).is_err());
+        let mut tok_man = TokenManager::new("DCL X FIXED DECIMAL(32);");
+        assert!(parse_statement(&mut tok_man).is_err());
+        let mut tok_man = TokenManager::new("DCL X FIXED DECIMAL(5,6);");
+        assert!(parse_statement(&mut tok_man).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_complex_declares_and_imaginary_constants() -> Result<(), ParseError> {
+        let mut tok_man = TokenManager::new(
+            "DCL A FLOAT COMPLEX; DCL B FIXED CPLX; DCL C COMPLEX FIXED; DCL D COMPLEX; Z = 3.5I;",
+        );
+
+        for expected in [
+            Type::FloatComplex,
+            Type::FixedComplex,
+            Type::FixedComplex,
+            Type::FloatComplex,
+        ] {
+            match parse_statement(&mut tok_man)?.command {
+                Command::Declare(declare) => assert_eq!(Some(expected), declare.attribute),
+                other => panic!("Expected a DECLARE, received a {:#?}", other),
+            }
+        }
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::Assignment(assignment) => match assignment.value {
+                Expr::Call { fn_name, args, .. } => {
+                    assert_eq!("COMPLEX", fn_name);
+                    assert!(matches!(
+                        args.as_slice(),
+                        [Expr::NumVal { value: real, .. }, Expr::NumVal { value: imaginary, .. }]
+                            if *real == 0.0 && *imaginary == 3.5
+                    ));
+                }
+                other => panic!("Expected a call to COMPLEX, received a {:#?}", other),
+            },
+            other => panic!("Expected an assignment, received a {:#?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parse_varying_declare_and_concatenation() -> Result<(), ParseError> {
+        let mut tok_man = TokenManager::new("DCL NAME CHAR(20) VARYING; NAME = 'MR ' || NAME;");
+
+        let declare = parse_statement(&mut tok_man)?;
+        match declare.command {
+            Command::Declare(declare) => {
+                assert_eq!(Some(Type::VaryingChar(20)), declare.attribute)
+            }
+            other => panic!("Expected a DECLARE, received a {:#?}", other),
+        }
+
+        let assignment = parse_statement(&mut tok_man)?;
+        match assignment.command {
+            Command::Assignment(assignment) => match assignment.value {
+                Expr::Binary {
+                    operator: Token::CONCATENATE,
+                    left,
+                    right,
+                } => {
+                    assert!(matches!(*left, Expr::Char { .. }));
+                    assert!(matches!(*right, Expr::Variable { .. }));
+                }
+                other => panic!("Expected a concatenation, received a {:#?}", other),
+            },
+            other => panic!("Expected an assignment, received a {:#?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parse_based_and_controlled_variables() -> Result<(), ParseError> {
+        let mut tok_man = TokenManager::new(
+            "DCL NODE BASED(P) FIXED; \
+             DCL NAME CHAR(5) BASED; \
+             DCL DEPTH CTL FIXED; \
+             ALLOCATE NAME SET(Q); \
+             P->Q->NAME = 'A'; \
+             FREE P->NODE;",
+        );
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::Declare(declare) => {
+                assert_eq!(Some(Type::DEFAULT_FIXED), declare.attribute);
+                assert_eq!(
+                    StorageClass::Based {
+                        locator: Some("P".to_string())
+                    },
+                    declare.storage_class
+                );
+            }
+            other => panic!("Expected a DECLARE, received a {:#?}", other),
+        }
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::Declare(declare) => {
+                assert_eq!(StorageClass::Based { locator: None }, declare.storage_class)
+            }
+            other => panic!("Expected a DECLARE, received a {:#?}", other),
+        }
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::Declare(declare) => {
+                assert_eq!(StorageClass::Controlled, declare.storage_class)
+            }
+            other => panic!("Expected a DECLARE, received a {:#?}", other),
+        }
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::ALLOCATE(allocate) => {
+                assert_eq!("NAME", allocate.var_name);
+                assert_eq!(Some("Q".to_string()), allocate.set);
+            }
+            other => panic!("Expected an ALLOCATE, received a {:#?}", other),
+        }
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::Assignment(assignment) => {
+                assert_eq!("NAME", assignment.var_name);
+                match assignment.locator {
+                    Some(Expr::Qualified { locator, name }) => {
+                        assert_eq!("Q", name);
+                        assert!(matches!(*locator, Expr::Variable { .. }));
+                    }
+                    other => panic!("Expected a qualified locator, received a {:#?}", other),
+                }
+            }
+            other => panic!("Expected an assignment, received a {:#?}", other),
+        }
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::FREE(free) => {
+                assert_eq!("NODE", free.var_name);
+                assert!(free.locator.is_some());
+            }
+            other => panic!("Expected a FREE, received a {:#?}", other),
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_parsing_identifier() -> Result<(), ParseError> {
         let mut tok_man = TokenManager::new("MIN(2,3);");
@@ -1094,7 +2810,7 @@ mod tests {
             assert_eq!(fn_name, "MIN");
             assert_eq!(args.len(), 2);
 
-            if let Expr::NumVal { value, _type } = args[0] {
+            if let Expr::NumVal { value, _type, .. } = args[0] {
                 assert_eq!(value, 2.0);
             } else {
                 panic!("args[0] was not type numval");
@@ -1113,7 +2829,7 @@ mod tests {
 
         let result: Expr = parse_parenthesis_expression(&mut tok_man)?;
 
-        if let Expr::NumVal { value, _type } = result {
+        if let Expr::NumVal { value, _type, .. } = result {
             assert_eq!(25665.0, value);
             Ok(())
         } else {
@@ -1135,7 +2851,7 @@ mod tests {
 
         let result = parse_expression(&mut tok_man)?;
         tok_man.next_token();
-        if let Expr::NumVal { value, _type } = result {
+        if let Expr::NumVal { value, _type, .. } = result {
             assert_eq!(value, 2.0);
         } else {
             panic!("Not a numval 2!");
@@ -1154,7 +2870,7 @@ mod tests {
 
         if let Expr::Variable {
             name,
-            _type: Type::FixedDecimal,
+            _type: Type::FixedDecimal(..),
         } = result
         {
             assert_eq!("FLAG", name);
@@ -1165,7 +2881,7 @@ mod tests {
         let result = parse_expression(&mut tok_man)?;
         tok_man.next_token();
 
-        if let Expr::NumVal { value, _type } = result {
+        if let Expr::NumVal { value, _type, .. } = result {
             assert_eq!(4.0, value);
 
             Ok(())
@@ -1262,7 +2978,7 @@ mod tests {
         let decl = parse_declare(&mut token_manager)?;
 
         assert_eq!(decl.var_name, "x");
-        assert_eq!(decl.attribute, Some(Type::FixedDecimal));
+        assert_eq!(decl.attribute, Some(Type::DEFAULT_FIXED));
 
         //make sure declare sets up parsing for next line
         parse_statement(&mut token_manager)?;
@@ -1295,4 +3011,281 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn parse_static_arrays_with_initial_values() -> Result<(), ParseError> {
+        let mut tok_man = TokenManager::new(
+            "DCL TABLE(5) FIXED STATIC INITIAL(1, (2)0, (1)(8, 9)); \
+             TABLE(2) = 3;",
+        );
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::Declare(declare) => {
+                assert_eq!(Some(5), declare.dimension);
+                assert_eq!(StorageClass::Static, declare.storage_class);
+                let values: Vec<f64> = declare
+                    .initial
+                    .unwrap()
+                    .into_iter()
+                    .map(|value| match value {
+                        Expr::NumVal { value, .. } => value,
+                        other => panic!("Expected a number, received {:#?}", other),
+                    })
+                    .collect();
+                assert_eq!(vec![1.0, 0.0, 0.0, 8.0, 9.0], values);
+            }
+            other => panic!("Expected a DECLARE, received a {:#?}", other),
+        }
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::Assignment(assignment) => {
+                assert_eq!("TABLE", assignment.var_name);
+                assert!(assignment.subscript.is_some());
+            }
+            other => panic!("Expected an assignment, received a {:#?}", other),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_external_declarations() -> Result<(), ParseError> {
+        let mut tok_man = TokenManager::new(
+            "COUNTING: PROCEDURE; \
+             DCL COUNTER FIXED EXT; \
+             DCL BUMP ENTRY(FIXED, CHAR(3) VARYING) EXTERNAL;",
+        );
+
+        let unit_procedure = parse_opening(&mut tok_man)?;
+        assert_eq!("COUNTING", unit_procedure.name);
+        assert!(!unit_procedure.is_main);
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::Declare(declare) => {
+                assert_eq!(StorageClass::External, declare.storage_class);
+                assert_eq!(None, declare.entry);
+            }
+            other => panic!("Expected a DECLARE, received a {:#?}", other),
+        }
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::Declare(declare) => {
+                assert_eq!(StorageClass::External, declare.storage_class);
+                let entry = declare.entry.unwrap();
+                assert_eq!(
+                    vec![
+                        Descriptor::Data(Type::DEFAULT_FIXED),
+                        Descriptor::Data(Type::VaryingChar(3))
+                    ],
+                    entry.parameters
+                );
+                assert!(!entry.is_c);
+            }
+            other => panic!("Expected a DECLARE, received a {:#?}", other),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_options_c_entry() -> Result<(), ParseError> {
+        let mut tok_man = TokenManager::new(
+            "DCL STRLEN ENTRY(CHAR(*), FIXED BIN(15), FIXED DEC) RETURNS(FIXED BIN(31)) OPTIONS(C);",
+        );
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::Declare(declare) => {
+                let entry = declare.entry.unwrap();
+                assert_eq!(
+                    vec![
+                        Descriptor::AnyLengthCharacter,
+                        Descriptor::FixedBinary(15),
+                        Descriptor::Data(Type::DEFAULT_FIXED)
+                    ],
+                    entry.parameters
+                );
+                assert_eq!(Some(Descriptor::FixedBinary(31)), entry.returns);
+                assert!(entry.is_c);
+            }
+            other => panic!("Expected a DECLARE, received a {:#?}", other),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_exported_procedures() -> Result<(), ParseError> {
+        let mut token_manager = TokenManager::new("PROCEDURE(A) OPTIONS(C);");
+        let prototype = parse_function_prototype(&mut token_manager, String::from("CALC"))?;
+        assert_eq!(ProcedureLinkage::C, prototype.linkage);
+
+        let mut token_manager = TokenManager::new("PROCEDURE(A, B) EXTERNAL;");
+        let prototype = parse_function_prototype(&mut token_manager, String::from("CALC"))?;
+        assert_eq!(ProcedureLinkage::External, prototype.linkage);
+
+        let mut token_manager = TokenManager::new("PROCEDURE(A);");
+        let prototype = parse_function_prototype(&mut token_manager, String::from("CALC"))?;
+        assert_eq!(ProcedureLinkage::Internal, prototype.linkage);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_default_statements() -> Result<(), ParseError> {
+        let mut tok_man =
+            TokenManager::new("DEFAULT RANGE(A:H) FLOAT; DFT RANGE(*) FIXED BIN; DCL X STATIC;");
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::DEFAULT(rule) => {
+                assert_eq!(DefaultRange::Letters('A', 'H'), rule.range);
+                assert_eq!(Type::DEFAULT_FLOAT, rule._type);
+            }
+            other => panic!("Expected a DEFAULT, received a {:#?}", other),
+        }
+        match parse_statement(&mut tok_man)?.command {
+            Command::DEFAULT(rule) => {
+                assert_eq!(DefaultRange::All, rule.range);
+                assert_eq!(get_fixed_binary_type(15), rule._type);
+            }
+            other => panic!("Expected a DEFAULT, received a {:#?}", other),
+        }
+        //the type is left to the defaults
+        match parse_statement(&mut tok_man)?.command {
+            Command::Declare(declare) => assert_eq!(None, declare.attribute),
+            other => panic!("Expected a DECLARE, received a {:#?}", other),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_do_groups() -> Result<(), ParseError> {
+        let mut tok_man = TokenManager::new(
+            "OUTER: DO I = 1 TO N BY 2 WHILE (X > 0); LEAVE OUTER; ITERATE; END OUTER;",
+        );
+
+        let statement = parse_statement(&mut tok_man)?;
+        assert_eq!(Some("OUTER".to_string()), statement.label);
+        let do_group = match statement.command {
+            Command::DO(do_group) => do_group,
+            other => panic!("Expected a DO, received a {:#?}", other),
+        };
+        assert_eq!(Some("OUTER".to_string()), do_group.label);
+        assert_eq!("I", do_group.control.unwrap().variable_name);
+        assert!(do_group.while_condition.is_some());
+        match &do_group.statements[0].command {
+            Command::LEAVE(leave) => assert_eq!(Some("OUTER".to_string()), leave.label),
+            other => panic!("Expected a LEAVE, received a {:#?}", other),
+        }
+        match &do_group.statements[1].command {
+            Command::ITERATE(iterate) => assert_eq!(None, iterate.label),
+            other => panic!("Expected an ITERATE, received a {:#?}", other),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_stop_and_exit() -> Result<(), ParseError> {
+        let mut tok_man = TokenManager::new("STOP; EXIT;");
+
+        assert!(matches!(
+            parse_statement(&mut tok_man)?.command,
+            Command::STOP
+        ));
+        assert!(matches!(
+            parse_statement(&mut tok_man)?.command,
+            Command::STOP
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_label_variables_and_go_to() -> Result<(), ParseError> {
+        let mut tok_man = TokenManager::new("DCL CASE(3) LABEL; GO TO CASE(I); GOTO DONE;");
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::Declare(declare) => {
+                assert_eq!(Some(Type::Label), declare.attribute);
+                assert_eq!(Some(3), declare.dimension);
+            }
+            other => panic!("Expected a DECLARE, received a {:#?}", other),
+        }
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::GO(go) => {
+                assert_eq!("CASE", go.label_to_go_to);
+                assert!(
+                    matches!(go.subscript, Some(Expr::Variable { ref name, .. }) if name == "I")
+                );
+            }
+            other => panic!("Expected a GO TO, received a {:#?}", other),
+        }
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::GO(go) => {
+                assert_eq!("DONE", go.label_to_go_to);
+                assert!(go.subscript.is_none());
+            }
+            other => panic!("Expected a GO TO, received a {:#?}", other),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_entry_variables() -> Result<(), ParseError> {
+        let mut tok_man =
+            TokenManager::new("DCL F ENTRY(FLOAT) RETURNS(FLOAT) VARIABLE; DCL G ENTRY(FIXED);");
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::Declare(declare) => {
+                let entry = declare.entry.unwrap();
+                assert_eq!(
+                    vec![Descriptor::Data(Type::DEFAULT_FLOAT)],
+                    entry.parameters
+                );
+                assert_eq!(Some(Descriptor::Data(Type::DEFAULT_FLOAT)), entry.returns);
+                assert!(entry.is_variable);
+            }
+            other => panic!("Expected a DECLARE, received a {:#?}", other),
+        }
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::Declare(declare) => assert!(!declare.entry.unwrap().is_variable),
+            other => panic!("Expected a DECLARE, received a {:#?}", other),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_generic_declarations() -> Result<(), ParseError> {
+        let mut tok_man = TokenManager::new(
+            "DCL AREA GENERIC (CIRCLE WHEN (FLOAT), RECTANGLE WHEN (FIXED, FIXED));",
+        );
+
+        match parse_statement(&mut tok_man)?.command {
+            Command::Declare(declare) => assert_eq!(
+                Some(vec![
+                    GenericAlternative {
+                        procedure_name: "CIRCLE".to_string(),
+                        descriptors: vec![Descriptor::Data(Type::DEFAULT_FLOAT)],
+                    },
+                    GenericAlternative {
+                        procedure_name: "RECTANGLE".to_string(),
+                        descriptors: vec![
+                            Descriptor::Data(Type::DEFAULT_FIXED),
+                            Descriptor::Data(Type::DEFAULT_FIXED)
+                        ],
+                    },
+                ]),
+                declare.generic
+            ),
+            other => panic!("Expected a DECLARE, received a {:#?}", other),
+        }
+
+        Ok(())
+    }
 }
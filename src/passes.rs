@@ -1,9 +1,10 @@
 use std::{collections::HashMap, error::Error, hash::Hash, process};
 
 use crate::{
-    ast::{Command, Statement},
+    ast::{Command, Statement, UnitProcedure},
     codegen::{
         codegen::{CodeGenable, Compiler},
+        runtime::program,
         utils,
     },
     error::errors::CodegenError,
@@ -12,7 +13,11 @@ use crate::{
     types::Type,
 };
 
+mod type_pass;
+
 pub struct PassResult {
+    ///None when the opening procedure statement could not be parsed.
+    pub unit_procedure: Option<UnitProcedure>,
     statements: Vec<Statement>,
     function_return_types: HashMap<String, Type>,
     pub found_errors: Vec<Box<dyn Error>>,
@@ -27,9 +32,13 @@ pub fn perform_parse_pass(token_manager: &mut TokenManager) -> PassResult {
     let mut function_return_types: HashMap<String, Type> = HashMap::new();
     let mut found_errors: Vec<Box<dyn Error>> = vec![];
 
-    if let Err(parse_error) = result {
-        found_errors.push(Box::new(parse_error));
-    }
+    let unit_procedure = match result {
+        Ok(unit_procedure) => Some(unit_procedure),
+        Err(parse_error) => {
+            found_errors.push(Box::new(parse_error));
+            None
+        }
+    };
 
     while let Some(ref token) = token_manager.current_token {
         log::trace!("Cycling to token: {:#?}", token);
@@ -71,6 +80,7 @@ pub fn perform_parse_pass(token_manager: &mut TokenManager) -> PassResult {
         found_errors.push(Box::new(err));
     }
     let output = PassResult {
+        unit_procedure,
         statements,
         function_return_types,
         found_errors,
@@ -80,20 +90,23 @@ pub fn perform_parse_pass(token_manager: &mut TokenManager) -> PassResult {
 }
 
 impl PassResult {
-    ///Reserved function for pre-codegen type inference
+    ///Works out the types of the arguments of calls before codegen, to
+    ///replace the calls of GENERIC names with calls of the procedures chosen
+    ///for them.
     pub fn perform_type_pass(mut self) -> Result<PassResult, String> {
-        let mut annotated_statements: Vec<Statement> = vec![];
-        for i in &self.statements {
-            let statement_clone = i.clone();
+        let scope = type_pass::Scope::new_block(None, &self.statements, &[]);
+        let mut errors: Vec<String> = vec![];
+        scope.resolve_statements(&mut self.statements, &mut errors);
 
-            annotated_statements.push(statement_clone);
+        if !errors.is_empty() {
+            return Err(errors.join("\n"));
         }
-
-        self.statements = annotated_statements;
         Ok(self)
     }
 
     pub unsafe fn code_generation_pass(mut self, compiler: &mut Compiler) -> Result<Self, String> {
+        compiler.apply_default_statements(&self.statements);
+        compiler.declare_label_constants(&self.statements);
         for i in &self.statements {
             i.clone().codegen(compiler);
         }
@@ -104,12 +117,24 @@ impl PassResult {
             .unwrap()
             .get_terminator()
         {
-            let _build_return_result = compiler
-                .builder
-                .build_return(None)
-                .map_err(|_err| "Error in code generation pass")?;
+            //the procedure of a unit without OPTIONS(MAIN) returns a value like any other
+            let return_type = utils::get_current_function(compiler)
+                .get_type()
+                .get_return_type();
+            let _build_return_result = match return_type {
+                Some(_) if program::is_main_function(compiler) => {
+                    let return_code = program::build_load_return_code(compiler);
+                    compiler.builder.build_return(Some(&return_code))
+                }
+                Some(return_type) => compiler
+                    .builder
+                    .build_return(Some(&return_type.const_zero())),
+                None => compiler.builder.build_return(None),
+            }
+            .map_err(|_err| "Error in code generation pass")?;
         }
 
+        compiler.finish_label_dispatch();
         compiler.verify_no_placeholder_blocks_exist();
 
         let mut mapped_vec: Vec<Box<dyn Error>> = compiler
@@ -0,0 +1,591 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ast::{Command, Declare, Descriptor, Expr, GenericAlternative, PutOption, Statement},
+    codegen::{builtins, c_interface},
+    error::get_error,
+    lexer::Token,
+    types::{
+        conversion::get_conversion, defaults::DefaultRules, fixed_decimal::DEFAULT_PRECISION,
+        float_decimal::DEFAULT_FLOAT_PRECISION, get_binary_type, label::get_statement_labels, Type,
+    },
+};
+
+///What the type pass knows about the names of a block, which it uses to find
+///the types of expressions before any code is generated.
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+    ///The declared variables and the parameters. Like in codegen, the ones
+    ///of the blocks containing a procedure are not known inside it.
+    variables: HashMap<String, Type>,
+    arrays: HashSet<String>,
+    ///The result types of the names declared with ENTRY.
+    entries: HashMap<String, Type>,
+    procedures: HashSet<String>,
+    labels: HashSet<String>,
+    generics: HashMap<String, Vec<GenericAlternative>>,
+    defaults: DefaultRules,
+}
+
+impl Scope {
+    ///The scope of a block, knowing the procedures, GENERIC names and
+    ///DEFAULT rules of the blocks containing it.
+    pub fn new_block(
+        parent: Option<&Scope>,
+        statements: &[Statement],
+        parameters: &[String],
+    ) -> Scope {
+        let mut scope = match parent {
+            Some(parent) => Scope {
+                entries: parent.entries.clone(),
+                procedures: parent.procedures.clone(),
+                generics: parent.generics.clone(),
+                defaults: parent.defaults.clone(),
+                ..Scope::default()
+            },
+            None => Scope::default(),
+        };
+        for statement in statements {
+            if let Command::DEFAULT(ref rule) = statement.command {
+                scope.defaults.add(rule.clone());
+            }
+        }
+        //parameters not declared in the body are undeclared names
+        for parameter in parameters {
+            let _type = scope.defaults.get_type(parameter);
+            scope.variables.insert(parameter.clone(), _type);
+        }
+        scope.labels = get_statement_labels(statements).into_iter().collect();
+        scope.collect_declarations(statements);
+        scope
+    }
+
+    fn collect_declarations(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            match statement.command {
+                Command::Declare(ref declare) => self.declare(declare),
+                Command::FunctionDec(ref function) => {
+                    self.procedures.insert(function.prototype.fn_name.clone());
+                }
+                Command::IF(ref if_statement) => {
+                    self.collect_declarations(&if_statement.then_statements);
+                    if let Some(ref else_statements) = if_statement.else_statements {
+                        self.collect_declarations(else_statements);
+                    }
+                }
+                Command::DO(ref do_group) => self.collect_declarations(&do_group.statements),
+                Command::ON(ref on) => {
+                    self.collect_declarations(std::slice::from_ref(on.on_unit.as_ref()))
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn declare(&mut self, declare: &Declare) {
+        let name = declare.var_name.clone();
+        if let Some(ref alternatives) = declare.generic {
+            self.generics.insert(name, alternatives.clone());
+            return;
+        }
+        if let Some(ref entry) = declare.entry {
+            let result_type = match (entry.is_c, entry.returns) {
+                (true, _) => c_interface::get_result_type(entry),
+                (false, Some(Descriptor::Data(_type))) => _type,
+                (false, _) => self.defaults.get_type(&name),
+            };
+            self.entries.insert(name.clone(), result_type);
+            //an ENTRY parameter holds a procedure like an ENTRY variable does
+            if entry.is_variable || self.variables.contains_key(&name) {
+                self.variables.insert(name, Type::Entry);
+            } else {
+                self.procedures.insert(name);
+            }
+            return;
+        }
+        if declare.file_attributes.is_some() {
+            return;
+        }
+        let _type = declare
+            .attribute
+            .unwrap_or_else(|| self.defaults.get_type(&name));
+        if declare.dimension.is_some() {
+            self.arrays.insert(name.clone());
+        }
+        self.variables.insert(name, _type);
+    }
+
+    ///Replaces the calls of GENERIC names in the statements with calls of
+    ///the procedures chosen for their arguments. The calls no procedure was
+    ///chosen for are described in `errors`.
+    pub fn resolve_statements(&self, statements: &mut [Statement], errors: &mut Vec<String>) {
+        for statement in statements {
+            self.resolve_statement(statement, errors);
+        }
+    }
+
+    fn resolve_statement(&self, statement: &mut Statement, errors: &mut Vec<String>) {
+        match statement.command {
+            Command::PUT(ref mut put) => {
+                self.resolve_expressions(&mut put.messages_to_print.items, errors);
+                for option in put.options.iter_mut() {
+                    match option {
+                        PutOption::Line(expr) | PutOption::Skip(expr) => {
+                            self.resolve_expression(expr, errors)
+                        }
+                        PutOption::Page => (),
+                    }
+                }
+            }
+            Command::GET(ref mut get) => {
+                self.resolve_expressions(&mut get.list_to_get.items, errors)
+            }
+            Command::GO(ref mut go) => self.resolve_optional_expression(&mut go.subscript, errors),
+            Command::IF(ref mut if_statement) => {
+                self.resolve_expression(&mut if_statement.conditional, errors);
+                self.resolve_statements(&mut if_statement.then_statements, errors);
+                if let Some(ref mut else_statements) = if_statement.else_statements {
+                    self.resolve_statements(else_statements, errors);
+                }
+            }
+            Command::Declare(ref mut declare) => {
+                if let Some(ref mut initial) = declare.initial {
+                    self.resolve_expressions(initial, errors);
+                }
+            }
+            Command::Assignment(ref mut assignment) => {
+                self.resolve_optional_expression(&mut assignment.locator, errors);
+                self.resolve_optional_expression(&mut assignment.subscript, errors);
+                self.resolve_expression(&mut assignment.value, errors);
+            }
+            Command::FunctionDec(ref mut function) => {
+                let scope = Scope::new_block(
+                    Some(self),
+                    &function.body_statements,
+                    &function.prototype.args,
+                );
+                scope.resolve_statements(&mut function.body_statements, errors);
+                scope.resolve_optional_expression(&mut function.return_value, errors);
+            }
+            Command::EXPR(ref mut expr) | Command::RETURN(ref mut expr) => {
+                self.resolve_expression(expr, errors)
+            }
+            Command::OPEN(ref mut open) => {
+                for file in open.files.iter_mut() {
+                    self.resolve_optional_expression(&mut file.title, errors);
+                }
+            }
+            Command::ON(ref mut on) => self.resolve_statement(&mut on.on_unit, errors),
+            Command::READ(ref mut read) => self.resolve_optional_expression(&mut read.key, errors),
+            Command::WRITE(ref mut write) => {
+                self.resolve_optional_expression(&mut write.keyfrom, errors)
+            }
+            Command::REWRITE(ref mut rewrite) => {
+                self.resolve_optional_expression(&mut rewrite.key, errors)
+            }
+            Command::DELETE(ref mut delete) => {
+                self.resolve_optional_expression(&mut delete.key, errors)
+            }
+            Command::FREE(ref mut free) => {
+                self.resolve_optional_expression(&mut free.locator, errors)
+            }
+            Command::DO(ref mut do_group) => {
+                if let Some(ref mut control) = do_group.control {
+                    self.resolve_expression(&mut control.start, errors);
+                    self.resolve_expression(&mut control.end, errors);
+                    self.resolve_optional_expression(&mut control.step, errors);
+                }
+                self.resolve_optional_expression(&mut do_group.while_condition, errors);
+                self.resolve_statements(&mut do_group.statements, errors);
+            }
+            _ => (),
+        }
+    }
+
+    fn resolve_expressions(&self, exprs: &mut [Expr], errors: &mut Vec<String>) {
+        for expr in exprs {
+            self.resolve_expression(expr, errors);
+        }
+    }
+
+    fn resolve_optional_expression(&self, expr: &mut Option<Expr>, errors: &mut Vec<String>) {
+        if let Some(expr) = expr {
+            self.resolve_expression(expr, errors);
+        }
+    }
+
+    fn resolve_expression(&self, expr: &mut Expr, errors: &mut Vec<String>) {
+        match expr {
+            Expr::Call { fn_name, args, .. } => {
+                self.resolve_expressions(args, errors);
+                let alternatives = match self.generics.get(fn_name.as_str()) {
+                    Some(alternatives) => alternatives,
+                    None => return,
+                };
+                let argument_types: Vec<Type> = args.iter().map(|arg| self.get_type(arg)).collect();
+                match find_alternative(alternatives, &argument_types) {
+                    Some(alternative) => *fn_name = alternative.procedure_name.clone(),
+                    None => errors.push(get_error(&[
+                        "38",
+                        fn_name.as_str(),
+                        &describe_types(&argument_types),
+                        &describe_alternatives(alternatives),
+                    ])),
+                }
+            }
+            Expr::Assignment {
+                locator,
+                subscript,
+                value,
+                ..
+            } => {
+                if let Some(locator) = locator {
+                    self.resolve_expression(locator, errors);
+                }
+                if let Some(subscript) = subscript {
+                    self.resolve_expression(subscript, errors);
+                }
+                self.resolve_expression(value, errors);
+            }
+            Expr::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                self.resolve_expression(left, errors);
+                self.resolve_expression(right, errors);
+                self.insert_conversions(operator, left, right);
+                //operands of unknown types were described already, or are
+                //left to codegen
+                let is_known =
+                    self.get_type(left) != Type::TBD && self.get_type(right) != Type::TBD;
+                if let (true, Err(message)) = (
+                    is_known,
+                    self.get_binary_expression_type(operator, left, right),
+                ) {
+                    errors.push(message);
+                }
+            }
+            Expr::Infix { operand, .. } | Expr::Conversion { operand, .. } => {
+                self.resolve_expression(operand, errors)
+            }
+            Expr::Qualified { locator, .. } => self.resolve_expression(locator, errors),
+            Expr::NumVal { .. } | Expr::Char { .. } | Expr::Variable { .. } => (),
+        }
+    }
+
+    ///Puts the operands of `left operator right` that PL/I converts before
+    ///the operator works on them in explicit conversions.
+    fn insert_conversions(&self, operator: &Token, left: &mut Expr, right: &mut Expr) {
+        let left_type = self.get_type(left);
+        let right_type = self.get_type(right);
+        if let Some(conversion) = get_conversion(operator, left_type, right_type) {
+            convert_operand(left, left_type, conversion.left_type);
+            convert_operand(right, right_type, conversion.right_type);
+        }
+    }
+
+    ///The type of an expression, worked out the way codegen does.
+    fn get_type(&self, expr: &Expr) -> Type {
+        match expr {
+            Expr::Variable { name, .. } => {
+                if let Some(_type) = self.variables.get(name) {
+                    return *_type;
+                }
+                if self.labels.contains(name) {
+                    return Type::Label;
+                }
+                if self.procedures.contains(name) {
+                    return Type::Entry;
+                }
+                self.defaults.get_type(name)
+            }
+            Expr::NumVal { _type, .. } => *_type,
+            Expr::Char { value } => Type::Char(value.len() as u32),
+            Expr::Infix { operand, .. } => self.get_type(operand).get_computational_type(),
+            Expr::Qualified { name, .. } => self.variables.get(name).copied().unwrap_or(Type::TBD),
+            Expr::Assignment { .. } => Type::TRUTH_VALUE,
+            Expr::Conversion { to, .. } => *to,
+            Expr::Call { fn_name, args, .. } => {
+                let argument_types: Vec<Type> = args.iter().map(|arg| self.get_type(arg)).collect();
+                if let Some(builtin_type) = builtins::get_builtin_type(fn_name, &argument_types) {
+                    return builtin_type;
+                }
+                if let Some(alternatives) = self.generics.get(fn_name) {
+                    return match find_alternative(alternatives, &argument_types) {
+                        Some(alternative) => self.get_result_type(&alternative.procedure_name),
+                        None => Type::TBD,
+                    };
+                }
+                if self.arrays.contains(fn_name) {
+                    return self.variables[fn_name];
+                }
+                self.get_result_type(fn_name)
+            }
+            Expr::Binary {
+                operator,
+                left,
+                right,
+            } => self
+                .get_binary_type(operator, left, right)
+                .unwrap_or(Type::TBD),
+        }
+    }
+
+    ///The type of `left operator right`, or the error describing operands
+    ///that can't be mixed, like a LABEL and a number.
+    fn get_binary_expression_type(
+        &self,
+        operator: &Token,
+        left: &Expr,
+        right: &Expr,
+    ) -> Result<Type, String> {
+        let left_type = self.get_type(left);
+        let right_type = self.get_type(right);
+        if let Token::CONCATENATE = operator {
+            let max_length = left_type.get_character_length().unwrap_or(0)
+                + right_type.get_character_length().unwrap_or(0);
+            return Ok(Type::VaryingChar(max_length));
+        }
+        //comparisons of strings, pointers and complex numbers are numbers
+        if left_type.is_character()
+            || right_type.is_character()
+            || left_type == Type::Pointer
+            || right_type == Type::Pointer
+            || (*operator == Token::EQ && (left_type.is_complex() || right_type.is_complex()))
+        {
+            return Ok(Type::TRUTH_VALUE);
+        }
+        get_binary_type(operator, &left_type, &right_type)
+    }
+
+    ///The type of the result of calling the procedure, or the procedure held
+    ///by the ENTRY variable, `name`.
+    fn get_result_type(&self, name: &str) -> Type {
+        match self.entries.get(name) {
+            Some(result_type) => *result_type,
+            None => self.defaults.get_type(name),
+        }
+    }
+}
+
+///Wraps `operand` in a conversion to `to`, unless it already is a number of
+///that type.
+fn convert_operand(operand: &mut Expr, from: Type, to: Type) {
+    if from.get_computational_type() == to {
+        return;
+    }
+    let unconverted = std::mem::replace(operand, Expr::new_numval(0.0));
+    *operand = Expr::Conversion {
+        operand: Box::new(unconverted),
+        to,
+    };
+}
+
+///The first of the procedures whose WHEN(...) has the types of the arguments.
+fn find_alternative<'a>(
+    alternatives: &'a [GenericAlternative],
+    argument_types: &[Type],
+) -> Option<&'a GenericAlternative> {
+    alternatives.iter().find(|alternative| {
+        alternative.descriptors.len() == argument_types.len()
+            && alternative
+                .descriptors
+                .iter()
+                .zip(argument_types)
+                .all(|(descriptor, argument_type)| is_matched_by(*descriptor, *argument_type))
+    })
+}
+
+///Strings match whatever their length, FIXED matches every FIXED DECIMAL and
+///FLOAT every FLOAT, everything else has to be of the same type, so FIXED and
+///FLOAT arguments pick different procedures.
+fn is_matched_by(descriptor: Descriptor, argument_type: Type) -> bool {
+    match descriptor {
+        Descriptor::Data(_type) if _type.is_character() => argument_type.is_character(),
+        Descriptor::Data(Type::FixedDecimal(..)) | Descriptor::FixedBinary(_) => {
+            matches!(argument_type, Type::FixedDecimal(..))
+        }
+        Descriptor::Data(Type::Float(_)) => matches!(argument_type, Type::Float(_)),
+        Descriptor::Data(_type) => _type == argument_type,
+        Descriptor::AnyLengthCharacter => argument_type.is_character(),
+    }
+}
+
+fn describe_type(_type: Type) -> String {
+    match _type {
+        Type::FixedDecimal(precision, 0) if precision == DEFAULT_PRECISION => "FIXED".to_string(),
+        Type::FixedDecimal(precision, scale) => {
+            format!("FIXED DECIMAL({},{})", precision, scale)
+        }
+        Type::Float(precision) if precision == DEFAULT_FLOAT_PRECISION => "FLOAT".to_string(),
+        Type::Float(precision) => format!("FLOAT DECIMAL({})", precision),
+        Type::Char(size) => format!("CHAR({})", size),
+        Type::VaryingChar(max_length) => format!("CHAR({}) VARYING", max_length),
+        Type::Pointer => "POINTER".to_string(),
+        Type::Label => "LABEL".to_string(),
+        Type::Entry => "ENTRY".to_string(),
+        Type::Picture(picture) => format!("PICTURE '{}'", picture),
+        Type::FixedComplex => "FIXED COMPLEX".to_string(),
+        Type::FloatComplex => "FLOAT COMPLEX".to_string(),
+        Type::Void | Type::TBD => "unknown".to_string(),
+    }
+}
+
+fn describe_types(types: &[Type]) -> String {
+    let descriptions: Vec<String> = types.iter().map(|_type| describe_type(*_type)).collect();
+    descriptions.join(", ")
+}
+
+///CIRCLE WHEN (FLOAT), SQUARE WHEN (FIXED)
+fn describe_alternatives(alternatives: &[GenericAlternative]) -> String {
+    let descriptions: Vec<String> = alternatives
+        .iter()
+        .map(|alternative| {
+            let descriptors: Vec<String> = alternative
+                .descriptors
+                .iter()
+                .map(|descriptor| match descriptor {
+                    Descriptor::Data(_type) => describe_type(*_type),
+                    Descriptor::FixedBinary(precision) => format!("FIXED BINARY({})", precision),
+                    Descriptor::AnyLengthCharacter => "CHAR(*)".to_string(),
+                })
+                .collect();
+            format!(
+                "{} WHEN ({})",
+                alternative.procedure_name,
+                descriptors.join(", ")
+            )
+        })
+        .collect();
+    descriptions.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::TokenManager;
+    use crate::parser::parse_statement;
+
+    fn resolve(input: &str) -> (Vec<Statement>, Vec<String>) {
+        let mut token_manager = TokenManager::new(input);
+        let mut statements = vec![];
+        while token_manager.current_token.is_some() {
+            statements.push(parse_statement(&mut token_manager).unwrap());
+        }
+        let scope = Scope::new_block(None, &statements, &[]);
+        let mut errors = vec![];
+        scope.resolve_statements(&mut statements, &mut errors);
+        (statements, errors)
+    }
+
+    fn get_called_name(statement: &Statement) -> &str {
+        match statement.command {
+            Command::Assignment(ref assignment) => match assignment.value {
+                Expr::Call { ref fn_name, .. } => fn_name,
+                ref other => panic!("Expected a call, found {:#?}", other),
+            },
+            ref other => panic!("Expected an assignment, found {:#?}", other),
+        }
+    }
+
+    #[test]
+    fn generic_calls_go_to_the_procedure_for_their_arguments() {
+        let (statements, errors) = resolve(
+            "DCL AREA GENERIC (CIRCLE WHEN (FLOAT), SQUARE WHEN (FIXED)); \
+             DCL R FLOAT; \
+             DCL SIDE FIXED; \
+             X = AREA(R); \
+             Y = AREA(SIDE); \
+             Z = AREA(2);",
+        );
+
+        assert!(errors.is_empty());
+        assert_eq!("CIRCLE", get_called_name(&statements[3]));
+        assert_eq!("SQUARE", get_called_name(&statements[4]));
+        assert_eq!("SQUARE", get_called_name(&statements[5]));
+    }
+
+    #[test]
+    fn unmatched_generic_calls_list_the_candidates() {
+        let (_statements, errors) = resolve(
+            "DCL AREA GENERIC (CIRCLE WHEN (FLOAT), SQUARE WHEN (FIXED)); \
+             X = AREA('WIDE');",
+        );
+
+        assert_eq!(1, errors.len());
+        assert!(errors[0].contains("E038"));
+        assert!(errors[0].contains("CIRCLE WHEN (FLOAT), SQUARE WHEN (FIXED)"));
+    }
+
+    #[test]
+    fn parameters_get_their_default_type_before_their_declaration() {
+        let mut token_manager =
+            TokenManager::new("DEFAULT RANGE(F) FLOAT DECIMAL(16); DCL AMOUNT FIXED;");
+        let mut statements = vec![];
+        while token_manager.current_token.is_some() {
+            statements.push(parse_statement(&mut token_manager).unwrap());
+        }
+        let parameters = ["AMOUNT".to_string(), "FACTOR".to_string(), "N".to_string()];
+        let scope = Scope::new_block(None, &statements, &parameters);
+
+        assert_eq!(Some(&Type::DEFAULT_FIXED), scope.variables.get("AMOUNT"));
+        assert_eq!(Some(&Type::Float(16)), scope.variables.get("FACTOR"));
+        assert_eq!(Some(&Type::FixedDecimal(6, 0)), scope.variables.get("N"));
+    }
+
+    #[test]
+    fn operands_that_cant_be_mixed_are_described() {
+        let (_statements, errors) = resolve(
+            "DCL L LABEL; \
+             DCL N FIXED; \
+             X = L + N;",
+        );
+
+        assert_eq!(1, errors.len());
+        assert!(errors[0].contains("E005"));
+    }
+
+    #[test]
+    fn mixed_operands_are_converted() {
+        let (statements, errors) = resolve(
+            "DCL R FLOAT; \
+             DCL N FIXED; \
+             X = R + N; \
+             Y = N + '5'; \
+             Z = N + 1;",
+        );
+        assert!(errors.is_empty());
+
+        let get_operands = |statement: &Statement| match statement.command {
+            Command::Assignment(ref assignment) => match assignment.value {
+                Expr::Binary {
+                    ref left,
+                    ref right,
+                    ..
+                } => (*left.clone(), *right.clone()),
+                ref other => panic!("Expected a binary expression, found {:#?}", other),
+            },
+            ref other => panic!("Expected an assignment, found {:#?}", other),
+        };
+
+        match get_operands(&statements[2]) {
+            (Expr::Variable { .. }, Expr::Conversion { operand, to }) => {
+                assert!(matches!(*operand, Expr::Variable { ref name, .. } if name == "N"));
+                assert_eq!(Type::DEFAULT_FLOAT, to);
+            }
+            other => panic!("Expected N converted to FLOAT, found {:#?}", other),
+        }
+        match get_operands(&statements[3]) {
+            (Expr::Variable { .. }, Expr::Conversion { operand, to }) => {
+                assert!(matches!(*operand, Expr::Char { .. }));
+                assert_eq!(Type::FixedDecimal(31, 0), to);
+            }
+            other => panic!("Expected '5' converted to FIXED, found {:#?}", other),
+        }
+        assert!(matches!(
+            get_operands(&statements[4]),
+            (Expr::Variable { .. }, Expr::NumVal { .. })
+        ));
+    }
+}
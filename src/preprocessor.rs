@@ -0,0 +1,380 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::error::get_error;
+
+mod macros;
+
+///Where a line of the expanded source came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLine {
+    ///The included file the line is in, None for the unit being compiled.
+    pub file_name: Option<String>,
+    pub line_number: u32,
+}
+
+///The source of a unit, with every %INCLUDE replaced by the text it includes.
+#[derive(Debug, Clone)]
+pub struct ExpandedSource {
+    pub text: String,
+    ///The original location of every line of `text`.
+    pub lines: Vec<SourceLine>,
+}
+
+impl ExpandedSource {
+    ///The original location of the line `line_number` of the expanded text,
+    ///counting from 1.
+    pub fn get_original_line(&self, line_number: u32) -> SourceLine {
+        let index = (line_number as usize).saturating_sub(1);
+        match self.lines.get(index).or(self.lines.last()) {
+            Some(line) => line.clone(),
+            None => SourceLine {
+                file_name: None,
+                line_number,
+            },
+        }
+    }
+}
+
+///Builds the expanded source a character at a time, remembering where every
+///line of it came from.
+struct SourceBuilder {
+    source: ExpandedSource,
+    at_line_start: bool,
+}
+
+impl SourceBuilder {
+    fn new() -> Self {
+        SourceBuilder {
+            source: ExpandedSource {
+                text: String::new(),
+                lines: vec![],
+            },
+            at_line_start: true,
+        }
+    }
+
+    fn push_char(&mut self, ch: char, origin: &SourceLine) {
+        if self.at_line_start {
+            self.source.lines.push(origin.clone());
+            self.at_line_start = false;
+        }
+        self.source.text.push(ch);
+        if ch == '\n' {
+            self.at_line_start = true;
+        }
+    }
+
+    fn end_line(&mut self, origin: &SourceLine) {
+        if !self.at_line_start {
+            self.push_char('\n', origin);
+        }
+    }
+
+    fn finish(self) -> ExpandedSource {
+        self.source
+    }
+}
+
+///Expands the %INCLUDEs of a unit, then runs the macro preprocessor over it.
+pub fn preprocess(
+    text: &str,
+    unit_path: &str,
+    include_directories: &[String],
+) -> Result<ExpandedSource, String> {
+    let included = expand_includes(text, unit_path, include_directories)?;
+    macros::expand_macros(&included)
+}
+
+///What %INCLUDE names: a member found in the include directories, or a path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum IncludeTarget {
+    Member(String),
+    Path(String),
+}
+
+///Splices the files included by %INCLUDE NAME; and %INCLUDE 'path'; into the
+///source of the unit at `unit_path`. They are looked for next to the file
+///including them, then in `include_directories`.
+pub fn expand_includes(
+    text: &str,
+    unit_path: &str,
+    include_directories: &[String],
+) -> Result<ExpandedSource, String> {
+    let mut expander = IncludeExpander {
+        include_directories,
+        output: SourceBuilder::new(),
+        included_files: vec![],
+    };
+    let directory = get_directory(Path::new(unit_path));
+    expander.expand(text, None, &directory)?;
+    Ok(expander.output.finish())
+}
+
+struct IncludeExpander<'a> {
+    include_directories: &'a [String],
+    output: SourceBuilder,
+    ///The files being included, to stop a file from including itself.
+    included_files: Vec<PathBuf>,
+}
+
+impl<'a> IncludeExpander<'a> {
+    fn push_char(&mut self, ch: char, origin: &SourceLine) {
+        self.output.push_char(ch, origin);
+    }
+
+    fn end_line(&mut self, origin: &SourceLine) {
+        self.output.end_line(origin);
+    }
+
+    fn expand(
+        &mut self,
+        text: &str,
+        file_name: Option<String>,
+        directory: &Path,
+    ) -> Result<(), String> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut origin = SourceLine {
+            file_name,
+            line_number: 1,
+        };
+        let mut column: usize = 0;
+        let mut index = 0;
+        let mut in_string = false;
+        let mut in_comment = false;
+
+        while index < chars.len() {
+            let ch = chars[index];
+            let next = chars.get(index + 1).copied();
+
+            if !in_string && !in_comment && ch == '%' {
+                if let Some((target, end)) = parse_include(&chars, index, &origin)? {
+                    //the directive may span lines, the text after it keeps its column
+                    for consumed in &chars[index..end] {
+                        if *consumed == '\n' {
+                            origin.line_number += 1;
+                            column = 0;
+                        } else {
+                            column += 1;
+                        }
+                    }
+                    self.end_line(&origin);
+                    self.include(&target, directory, &origin)?;
+                    for _ in 0..column {
+                        self.push_char(' ', &origin);
+                    }
+                    index = end;
+                    continue;
+                }
+            }
+
+            if in_comment {
+                if ch == '*' && next == Some('/') {
+                    in_comment = false;
+                    self.push_char(ch, &origin);
+                    self.push_char('/', &origin);
+                    column += 2;
+                    index += 2;
+                    continue;
+                }
+            } else if ch == '\'' {
+                in_string = !in_string;
+            } else if !in_string && ch == '/' && next == Some('*') {
+                in_comment = true;
+                self.push_char(ch, &origin);
+                self.push_char('*', &origin);
+                column += 2;
+                index += 2;
+                continue;
+            }
+
+            self.push_char(ch, &origin);
+            if ch == '\n' {
+                origin.line_number += 1;
+                column = 0;
+            } else {
+                column += 1;
+            }
+            index += 1;
+        }
+
+        Ok(())
+    }
+
+    fn include(
+        &mut self,
+        target: &IncludeTarget,
+        directory: &Path,
+        origin: &SourceLine,
+    ) -> Result<(), String> {
+        let path = self.find_included_file(target, directory).ok_or_else(|| {
+            let name = match target {
+                IncludeTarget::Member(name) | IncludeTarget::Path(name) => name,
+            };
+            get_error(&[
+                "30",
+                name,
+                &origin.line_number.to_string(),
+                origin.file_name.as_deref().unwrap_or("the unit"),
+            ])
+        })?;
+
+        let identity = fs::canonicalize(&path).unwrap_or(path.clone());
+        let file_name = path.display().to_string();
+        if self.included_files.contains(&identity) {
+            return Err(get_error(&["31", &file_name]));
+        }
+        let text = fs::read_to_string(&path)
+            .map_err(|err| format!("Couldn't read {}: {}", file_name, err))?;
+
+        self.included_files.push(identity);
+        self.expand(&text, Some(file_name.clone()), &get_directory(&path))?;
+        self.included_files.pop();
+
+        //the included text ends where its last line does
+        let last_line = SourceLine {
+            file_name: Some(file_name),
+            line_number: text.lines().count().max(1) as u32,
+        };
+        self.end_line(&last_line);
+        Ok(())
+    }
+
+    fn find_included_file(&self, target: &IncludeTarget, directory: &Path) -> Option<PathBuf> {
+        let file_names: Vec<String> = match target {
+            IncludeTarget::Path(path) => vec![path.clone()],
+            IncludeTarget::Member(name) => {
+                let mut file_names = vec![];
+                for member in [name.clone(), name.to_lowercase()] {
+                    for extension in ["", ".pli", ".inc"] {
+                        file_names.push(member.clone() + extension);
+                    }
+                }
+                file_names
+            }
+        };
+
+        let directories = std::iter::once(directory.to_path_buf())
+            .chain(self.include_directories.iter().map(PathBuf::from));
+        for directory in directories {
+            for file_name in file_names.iter() {
+                let path = directory.join(file_name);
+                if path.is_file() {
+                    return Some(path);
+                }
+            }
+        }
+        None
+    }
+}
+
+fn get_directory(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(parent) => parent.to_path_buf(),
+        None => PathBuf::new(),
+    }
+}
+
+///Parses %INCLUDE NAME; or %INCLUDE 'path'; starting at the %, returning
+///what it includes and where the directive ends. Any other % is left alone.
+fn parse_include(
+    chars: &[char],
+    start: usize,
+    origin: &SourceLine,
+) -> Result<Option<(IncludeTarget, usize)>, String> {
+    let skip_whitespace = |mut index: usize| {
+        while index < chars.len() && chars[index].is_whitespace() {
+            index += 1;
+        }
+        index
+    };
+
+    let mut index = skip_whitespace(start + 1);
+    let keyword: String = chars[index..]
+        .iter()
+        .take_while(|ch| ch.is_ascii_alphabetic())
+        .collect();
+    if !keyword.eq_ignore_ascii_case("INCLUDE") {
+        return Ok(None);
+    }
+    index = skip_whitespace(index + keyword.len());
+
+    let target = if chars.get(index) == Some(&'\'') {
+        let path: String = chars[index + 1..]
+            .iter()
+            .take_while(|ch| **ch != '\'')
+            .collect();
+        index += path.len() + 2;
+        IncludeTarget::Path(path)
+    } else {
+        let name: String = chars[index..]
+            .iter()
+            .take_while(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '#' | '@' | '$'))
+            .collect();
+        index += name.len();
+        IncludeTarget::Member(name)
+    };
+
+    index = skip_whitespace(index);
+    match (&target, chars.get(index)) {
+        (IncludeTarget::Member(name), _) if name.is_empty() => {}
+        (_, Some(';')) => return Ok(Some((target, index + 1))),
+        _ => {}
+    }
+
+    Err(format!(
+        "Expected %INCLUDE NAME; or %INCLUDE 'path'; on line {}",
+        origin.line_number
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::{Token, TokenManager};
+
+    #[test]
+    fn included_lines_keep_their_locations() -> Result<(), String> {
+        let input = "HELLO: PROCEDURE OPTIONS (MAIN);
+%INCLUDE 'copybooks/totals.pli'; X = 1;
+END;";
+
+        let source = expand_includes(input, "./test_pli_files/hello.pli", &[])?;
+        let included_file = Some("./test_pli_files/copybooks/totals.pli".to_string());
+
+        assert_eq!(None, source.get_original_line(1).file_name);
+        assert_eq!(included_file, source.get_original_line(2).file_name);
+        assert_eq!(1, source.get_original_line(2).line_number);
+        assert_eq!(2, source.get_original_line(3).line_number);
+        //the rest of the line with the directive is padded to its column
+        let rest_of_line = " ".repeat("%INCLUDE 'copybooks/totals.pli';".len()) + " X = 1;";
+        assert_eq!(None, source.get_original_line(4).file_name);
+        assert_eq!(2, source.get_original_line(4).line_number);
+        assert_eq!(3, source.get_original_line(5).line_number);
+        assert_eq!(rest_of_line, source.text.lines().nth(3).unwrap());
+
+        let mut token_manager = TokenManager::new_expanded(&source);
+        while token_manager.current_token != Some(Token::Identifier("X".to_string())) {
+            token_manager.next_token();
+        }
+        let location = token_manager.get_source_location();
+        assert_eq!(None, location.file_name);
+        assert_eq!(2, location.line_number);
+        Ok(())
+    }
+
+    #[test]
+    fn members_are_found_in_include_directories() -> Result<(), String> {
+        let input = "%INCLUDE TOTALS;";
+        let include_directories = vec!["./test_pli_files/copybooks".to_string()];
+
+        let source = expand_includes(input, "MAIN_UNIT", &include_directories)?;
+        assert!(source.text.contains("DCL TOTAL FIXED"));
+
+        let error = expand_includes(input, "MAIN_UNIT", &[]).unwrap_err();
+        assert!(error.contains("E030"));
+        Ok(())
+    }
+}
@@ -0,0 +1,1236 @@
+use std::{collections::HashMap, rc::Rc};
+
+use super::{ExpandedSource, SourceBuilder, SourceLine};
+use crate::error::get_error;
+
+///How many times a %DO loop may run before the preprocessor gives up on it.
+const MAX_LOOP_ITERATIONS: usize = 100_000;
+///How deeply replacements and procedure calls may nest.
+const MAX_EXPANSION_DEPTH: usize = 64;
+
+///The value of a preprocessor variable.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Fixed(i64),
+    Character(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ValueType {
+    Fixed,
+    Character,
+}
+
+impl Value {
+    fn new_of_type(value_type: ValueType) -> Value {
+        match value_type {
+            ValueType::Fixed => Value::Fixed(0),
+            ValueType::Character => Value::Character(String::new()),
+        }
+    }
+
+    ///The text the value replaces a name with.
+    fn get_text(&self) -> String {
+        match self {
+            Value::Fixed(number) => number.to_string(),
+            Value::Character(text) => text.clone(),
+        }
+    }
+
+    fn get_type(&self) -> ValueType {
+        match self {
+            Value::Fixed(_) => ValueType::Fixed,
+            Value::Character(_) => ValueType::Character,
+        }
+    }
+
+    fn to_fixed(&self, origin: &SourceLine) -> Result<i64, String> {
+        match self {
+            Value::Fixed(number) => Ok(*number),
+            Value::Character(text) => text
+                .trim()
+                .parse::<i64>()
+                .map_err(|_err| get_error(&["33", text, &describe_line(origin)])),
+        }
+    }
+
+    fn convert(self, value_type: ValueType, origin: &SourceLine) -> Result<Value, String> {
+        match value_type {
+            ValueType::Fixed => Ok(Value::Fixed(self.to_fixed(origin)?)),
+            ValueType::Character => Ok(Value::Character(self.get_text())),
+        }
+    }
+
+    fn is_true(&self, origin: &SourceLine) -> Result<bool, String> {
+        Ok(self.to_fixed(origin)? != 0)
+    }
+}
+
+fn describe_line(origin: &SourceLine) -> String {
+    match origin.file_name {
+        Some(ref file_name) => format!("{} of {}", origin.line_number, file_name),
+        None => origin.line_number.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum MacroToken {
+    Identifier(String),
+    Number(i64),
+    Str(String),
+    Symbol(&'static str),
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Value(Value),
+    Variable(String),
+    Call(String, Vec<Expr>),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone)]
+struct DoControl {
+    variable: String,
+    from: Expr,
+    to: Expr,
+    by: Option<Expr>,
+}
+
+///A preprocessor procedure, which is invoked wherever its name appears in
+///the source and replaced by what it returns.
+#[derive(Debug)]
+struct Procedure {
+    name: String,
+    parameters: Vec<String>,
+    returns: ValueType,
+    body: Vec<Node>,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    ///Source text, from one character position of the input to another.
+    Text(usize, usize),
+    Declare(Vec<(String, ValueType)>, usize),
+    Assign(String, Expr, usize),
+    Replace(String, Expr, usize),
+    If(Expr, Vec<Node>, Vec<Node>, usize),
+    Do(Option<DoControl>, Vec<Node>, usize),
+    Return(Expr, usize),
+    Procedure(Rc<Procedure>),
+}
+
+///Runs the macro preprocessor over the source: the % statements are
+///executed, and the names of preprocessor variables and procedures in the
+///rest of the text are replaced by their values.
+pub fn expand_macros(source: &ExpandedSource) -> Result<ExpandedSource, String> {
+    let mut parser = MacroParser::new(source);
+    let (nodes, ended_by_end) = parser.parse_text()?;
+    if ended_by_end {
+        return Err(parser.error(parser.position, "%END without %DO"));
+    }
+
+    let mut interpreter = Interpreter {
+        parser: &parser,
+        globals: HashMap::new(),
+        procedures: HashMap::new(),
+        output: SourceBuilder::new(),
+        depth: 0,
+    };
+    let mut locals = None;
+    interpreter.run(&nodes, &mut locals)?;
+    Ok(interpreter.output.finish())
+}
+
+struct MacroParser<'s> {
+    source: &'s ExpandedSource,
+    chars: Vec<char>,
+    ///The index of the line of `source` every character is on.
+    line_indices: Vec<usize>,
+    position: usize,
+}
+
+impl<'s> MacroParser<'s> {
+    fn new(source: &'s ExpandedSource) -> Self {
+        let chars: Vec<char> = source.text.chars().collect();
+        let mut line_indices = Vec::with_capacity(chars.len());
+        let mut line_index = 0;
+        for ch in chars.iter() {
+            line_indices.push(line_index);
+            if *ch == '\n' {
+                line_index += 1;
+            }
+        }
+        MacroParser {
+            source,
+            chars,
+            line_indices,
+            position: 0,
+        }
+    }
+
+    fn get_origin(&self, position: usize) -> SourceLine {
+        let line_index = match self.line_indices.get(position) {
+            Some(line_index) => *line_index,
+            None => self.line_indices.last().copied().unwrap_or(0),
+        };
+        self.source.get_original_line(line_index as u32 + 1)
+    }
+
+    fn error(&self, position: usize, message: &str) -> String {
+        get_error(&["32", &describe_line(&self.get_origin(position)), message])
+    }
+
+    ///Parses source text and the % statements in it, up to a %END or the end
+    ///of the source. Returns whether it stopped at a %END.
+    fn parse_text(&mut self) -> Result<(Vec<Node>, bool), String> {
+        let mut nodes = vec![];
+        let mut start = self.position;
+        let mut in_string = false;
+        let mut in_comment = false;
+
+        while self.position < self.chars.len() {
+            let ch = self.chars[self.position];
+            let next = self.chars.get(self.position + 1).copied();
+            if in_comment {
+                if ch == '*' && next == Some('/') {
+                    in_comment = false;
+                    self.position += 1;
+                }
+            } else if ch == '\'' {
+                in_string = !in_string;
+            } else if !in_string && ch == '/' && next == Some('*') {
+                in_comment = true;
+                self.position += 1;
+            } else if !in_string && ch == '%' {
+                if start < self.position {
+                    nodes.push(Node::Text(start, self.position));
+                }
+                self.position += 1;
+                match self.parse_percent_statement()? {
+                    Parsed::Node(node) => nodes.push(node),
+                    Parsed::Nothing => {}
+                    Parsed::End => return Ok((nodes, true)),
+                }
+                start = self.position;
+                continue;
+            }
+            self.position += 1;
+        }
+
+        if start < self.position {
+            nodes.push(Node::Text(start, self.position));
+        }
+        Ok((nodes, false))
+    }
+
+    ///Parses the statement after a %.
+    fn parse_percent_statement(&mut self) -> Result<Parsed, String> {
+        let statement_position = self.position;
+        let keyword = match self.next_token()? {
+            Some(MacroToken::Identifier(keyword)) => keyword,
+            other => {
+                let message = format!("Expected a statement after %, found {:?}", other);
+                return Err(self.error(statement_position, &message));
+            }
+        };
+
+        let node = match keyword.as_str() {
+            "DECLARE" | "DCL" => self.parse_declare(statement_position)?,
+            "REPLACE" => {
+                let name = self.parse_identifier()?;
+                self.expect_keyword("BY")?;
+                let value = self.parse_expression()?;
+                self.expect_symbol(";")?;
+                Node::Replace(name, value, statement_position)
+            }
+            "IF" => {
+                let condition = self.parse_expression()?;
+                self.expect_symbol("%")?;
+                self.expect_keyword("THEN")?;
+                let then_clause = self.parse_clause()?;
+                let else_clause = match self.try_keyword_after_percent("ELSE")? {
+                    true => self.parse_clause()?,
+                    false => vec![],
+                };
+                Node::If(condition, then_clause, else_clause, statement_position)
+            }
+            "DO" => {
+                let control = self.parse_do_control()?;
+                let (body, ended_by_end) = self.parse_text()?;
+                if !ended_by_end {
+                    return Err(self.error(statement_position, "%DO without %END"));
+                }
+                Node::Do(control, body, statement_position)
+            }
+            "END" => {
+                self.expect_symbol(";")?;
+                return Ok(Parsed::End);
+            }
+            //listing control has nothing to do here
+            "PAGE" | "SKIP" | "PRINT" | "NOPRINT" | "ACTIVATE" | "DEACTIVATE" => {
+                self.skip_past_semicolon()?;
+                return Ok(Parsed::Nothing);
+            }
+            _ => match self.next_token()? {
+                Some(MacroToken::Symbol(":")) => self.parse_procedure(keyword)?,
+                Some(MacroToken::Symbol("=")) => {
+                    let value = self.parse_expression()?;
+                    self.expect_symbol(";")?;
+                    Node::Assign(keyword, value, statement_position)
+                }
+                _ => {
+                    let message = format!("Unknown statement %{}", keyword);
+                    return Err(self.error(statement_position, &message));
+                }
+            },
+        };
+        Ok(Parsed::Node(node))
+    }
+
+    ///Parses what %THEN and %ELSE run, a single % statement.
+    fn parse_clause(&mut self) -> Result<Vec<Node>, String> {
+        let position = self.position;
+        self.expect_symbol("%")?;
+        match self.parse_percent_statement()? {
+            Parsed::Node(node) => Ok(vec![node]),
+            Parsed::Nothing => Ok(vec![]),
+            Parsed::End => Err(self.error(position, "%END without %DO")),
+        }
+    }
+
+    ///Consumes % and `keyword` when they come next.
+    fn try_keyword_after_percent(&mut self, keyword: &str) -> Result<bool, String> {
+        let position = self.position;
+        if self.next_token()? == Some(MacroToken::Symbol("%"))
+            && self.next_token()? == Some(MacroToken::Identifier(keyword.to_string()))
+        {
+            return Ok(true);
+        }
+        self.position = position;
+        Ok(false)
+    }
+
+    fn try_keyword(&mut self, keyword: &str) -> Result<bool, String> {
+        let position = self.position;
+        if self.next_token()? == Some(MacroToken::Identifier(keyword.to_string())) {
+            return Ok(true);
+        }
+        self.position = position;
+        Ok(false)
+    }
+
+    //DCL A FIXED, (B, C) CHARACTER;
+    fn parse_declare(&mut self, position: usize) -> Result<Node, String> {
+        let mut declarations = vec![];
+        loop {
+            let mut names = vec![];
+            if self.try_symbol("(")? {
+                loop {
+                    names.push(self.parse_identifier()?);
+                    if !self.try_symbol(",")? {
+                        break;
+                    }
+                }
+                self.expect_symbol(")")?;
+            } else {
+                names.push(self.parse_identifier()?);
+            }
+
+            let value_type = self.parse_value_type()?;
+            for name in names {
+                declarations.push((name, value_type));
+            }
+
+            if !self.try_symbol(",")? {
+                break;
+            }
+        }
+        self.expect_symbol(";")?;
+        Ok(Node::Declare(declarations, position))
+    }
+
+    fn parse_value_type(&mut self) -> Result<ValueType, String> {
+        let position = self.position;
+        match self.parse_identifier()?.as_str() {
+            "FIXED" => Ok(ValueType::Fixed),
+            "CHARACTER" | "CHAR" => Ok(ValueType::Character),
+            other => {
+                let message = format!("Expected FIXED or CHARACTER, found {}", other);
+                Err(self.error(position, &message))
+            }
+        }
+    }
+
+    //DO; or DO I = 1 TO 10 BY 2;
+    fn parse_do_control(&mut self) -> Result<Option<DoControl>, String> {
+        if self.try_symbol(";")? {
+            return Ok(None);
+        }
+        let variable = self.parse_identifier()?;
+        self.expect_symbol("=")?;
+        let from = self.parse_expression()?;
+        self.expect_keyword("TO")?;
+        let to = self.parse_expression()?;
+        let by = match self.try_keyword("BY")? {
+            true => Some(self.parse_expression()?),
+            false => None,
+        };
+        self.expect_symbol(";")?;
+        Ok(Some(DoControl {
+            variable,
+            from,
+            to,
+            by,
+        }))
+    }
+
+    //%NAME: PROCEDURE(A, B) RETURNS(CHARACTER); ... %END;
+    fn parse_procedure(&mut self, name: String) -> Result<Node, String> {
+        let position = self.position;
+        match self.parse_identifier()?.as_str() {
+            "PROCEDURE" | "PROC" => {}
+            other => {
+                let message = format!("Expected PROCEDURE after %{}:, found {}", name, other);
+                return Err(self.error(position, &message));
+            }
+        }
+
+        let mut parameters = vec![];
+        if self.try_symbol("(")? && !self.try_symbol(")")? {
+            loop {
+                parameters.push(self.parse_identifier()?);
+                if !self.try_symbol(",")? {
+                    break;
+                }
+            }
+            self.expect_symbol(")")?;
+        }
+        let mut returns = ValueType::Character;
+        if self.try_keyword("RETURNS")? {
+            self.expect_symbol("(")?;
+            returns = self.parse_value_type()?;
+            self.expect_symbol(")")?;
+        }
+        self.expect_symbol(";")?;
+
+        let body = self.parse_procedure_body()?;
+        Ok(Node::Procedure(Rc::new(Procedure {
+            name,
+            parameters,
+            returns,
+            body,
+        })))
+    }
+
+    ///Parses the statements of a procedure or a DO group in it, up to its END.
+    fn parse_procedure_body(&mut self) -> Result<Vec<Node>, String> {
+        let mut body = vec![];
+        while let Some(node) = self.parse_procedure_statement()? {
+            body.push(node);
+        }
+        Ok(body)
+    }
+
+    ///Parses a statement of a procedure, where the % can be left out. Returns
+    ///None at the END of the procedure or DO group.
+    fn parse_procedure_statement(&mut self) -> Result<Option<Node>, String> {
+        self.try_symbol("%")?;
+        let statement_position = self.position;
+        let keyword = match self.next_token()? {
+            Some(MacroToken::Identifier(keyword)) => keyword,
+            other => {
+                let message = format!("Expected a statement, found {:?}", other);
+                return Err(self.error(statement_position, &message));
+            }
+        };
+
+        let node = match keyword.as_str() {
+            "DECLARE" | "DCL" => self.parse_declare(statement_position)?,
+            "IF" => {
+                let condition = self.parse_expression()?;
+                self.expect_keyword("THEN")?;
+                let then_clause = self.parse_nested_statement(statement_position)?;
+                let position = self.position;
+                self.try_symbol("%")?;
+                let else_clause = match self.try_keyword("ELSE")? {
+                    true => self.parse_nested_statement(statement_position)?,
+                    false => {
+                        self.position = position;
+                        vec![]
+                    }
+                };
+                Node::If(condition, then_clause, else_clause, statement_position)
+            }
+            "DO" => {
+                let control = self.parse_do_control()?;
+                let body = self.parse_procedure_body()?;
+                Node::Do(control, body, statement_position)
+            }
+            "END" => {
+                self.expect_symbol(";")?;
+                return Ok(None);
+            }
+            "RETURN" => {
+                self.expect_symbol("(")?;
+                let value = self.parse_expression()?;
+                self.expect_symbol(")")?;
+                self.expect_symbol(";")?;
+                Node::Return(value, statement_position)
+            }
+            _ => {
+                self.expect_symbol("=")?;
+                let value = self.parse_expression()?;
+                self.expect_symbol(";")?;
+                Node::Assign(keyword, value, statement_position)
+            }
+        };
+        Ok(Some(node))
+    }
+
+    fn parse_nested_statement(&mut self, position: usize) -> Result<Vec<Node>, String> {
+        match self.parse_procedure_statement()? {
+            Some(node) => Ok(vec![node]),
+            None => Err(self.error(position, "END without DO")),
+        }
+    }
+
+    fn parse_expression(&mut self) -> Result<Expr, String> {
+        self.parse_binary_level(0)
+    }
+
+    ///Parses the binary operators from `level` up, the lowest levels binding
+    ///the loosest.
+    fn parse_binary_level(&mut self, level: usize) -> Result<Expr, String> {
+        const LEVELS: [&[&str]; 6] = [
+            &["|"],
+            &["&"],
+            &["=", "^=", "<", ">", "<=", ">="],
+            &["||"],
+            &["+", "-"],
+            &["*", "/"],
+        ];
+        if level == LEVELS.len() {
+            return self.parse_unary();
+        }
+
+        let mut left = self.parse_binary_level(level + 1)?;
+        loop {
+            let position = self.position;
+            match self.next_token()? {
+                Some(MacroToken::Symbol(symbol)) if LEVELS[level].contains(&symbol) => {
+                    let right = self.parse_binary_level(level + 1)?;
+                    left = Expr::Binary(symbol, Box::new(left), Box::new(right));
+                }
+                _ => {
+                    self.position = position;
+                    return Ok(left);
+                }
+            }
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        let position = self.position;
+        match self.next_token()? {
+            Some(MacroToken::Symbol(symbol)) if matches!(symbol, "-" | "+" | "^") => {
+                Ok(Expr::Unary(symbol, Box::new(self.parse_unary()?)))
+            }
+            Some(MacroToken::Symbol("(")) => {
+                let expression = self.parse_expression()?;
+                self.expect_symbol(")")?;
+                Ok(expression)
+            }
+            Some(MacroToken::Number(number)) => Ok(Expr::Value(Value::Fixed(number))),
+            Some(MacroToken::Str(text)) => Ok(Expr::Value(Value::Character(text))),
+            Some(MacroToken::Identifier(name)) => {
+                if !self.try_symbol("(")? {
+                    return Ok(Expr::Variable(name));
+                }
+                let mut args = vec![];
+                if !self.try_symbol(")")? {
+                    loop {
+                        args.push(self.parse_expression()?);
+                        if !self.try_symbol(",")? {
+                            break;
+                        }
+                    }
+                    self.expect_symbol(")")?;
+                }
+                Ok(Expr::Call(name, args))
+            }
+            other => {
+                let message = format!("Expected an expression, found {:?}", other);
+                Err(self.error(position, &message))
+            }
+        }
+    }
+
+    fn parse_identifier(&mut self) -> Result<String, String> {
+        let position = self.position;
+        match self.next_token()? {
+            Some(MacroToken::Identifier(name)) => Ok(name),
+            other => {
+                let message = format!("Expected a name, found {:?}", other);
+                Err(self.error(position, &message))
+            }
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), String> {
+        let position = self.position;
+        if !self.try_keyword(keyword)? {
+            let message = format!("Expected {}", keyword);
+            return Err(self.error(position, &message));
+        }
+        Ok(())
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), String> {
+        let position = self.position;
+        if !self.try_symbol(symbol)? {
+            let message = format!("Expected {}", symbol);
+            return Err(self.error(position, &message));
+        }
+        Ok(())
+    }
+
+    fn try_symbol(&mut self, symbol: &str) -> Result<bool, String> {
+        let position = self.position;
+        match self.next_token()? {
+            Some(MacroToken::Symbol(found)) if found == symbol => Ok(true),
+            _ => {
+                self.position = position;
+                Ok(false)
+            }
+        }
+    }
+
+    fn skip_past_semicolon(&mut self) -> Result<(), String> {
+        while let Some(token) = self.next_token()? {
+            if token == MacroToken::Symbol(";") {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while self.position < self.chars.len() {
+            let ch = self.chars[self.position];
+            if ch.is_whitespace() {
+                self.position += 1;
+            } else if ch == '/' && self.chars.get(self.position + 1) == Some(&'*') {
+                self.position += 2;
+                while self.position < self.chars.len()
+                    && !(self.chars[self.position] == '*'
+                        && self.chars.get(self.position + 1) == Some(&'/'))
+                {
+                    self.position += 1;
+                }
+                self.position += 2;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<MacroToken>, String> {
+        self.skip_whitespace_and_comments();
+        let start = self.position;
+        let ch = match self.chars.get(start) {
+            Some(ch) => *ch,
+            None => return Ok(None),
+        };
+
+        if is_identifier_start(ch) {
+            let name = self.take_while(is_identifier_char);
+            return Ok(Some(MacroToken::Identifier(name.to_uppercase())));
+        }
+        if ch.is_ascii_digit() {
+            let digits = self.take_while(|ch| ch.is_ascii_digit());
+            let number = digits
+                .parse::<i64>()
+                .map_err(|_err| self.error(start, "The number is too large"))?;
+            return Ok(Some(MacroToken::Number(number)));
+        }
+        if ch == '\'' {
+            self.position += 1;
+            let text = self.take_while(|ch| ch != '\'');
+            if self.position >= self.chars.len() {
+                return Err(self.error(start, "The string is never closed"));
+            }
+            self.position += 1;
+            return Ok(Some(MacroToken::Str(text)));
+        }
+
+        const SYMBOLS: [&str; 20] = [
+            "||", "^=", "<=", ">=", "%", ";", "(", ")", ",", "=", "+", "-", "*", "/", "|", "&",
+            "^", "<", ">", ":",
+        ];
+        let next = self.chars.get(start + 1).copied().unwrap_or(' ');
+        //¬ is another way to write ^
+        let pair: String = [ch, next].iter().collect::<String>().replace('¬', "^");
+        let single = pair.chars().next().unwrap().to_string();
+        for symbol in SYMBOLS {
+            if symbol.len() == 2 && symbol == pair {
+                self.position += 2;
+                return Ok(Some(MacroToken::Symbol(symbol)));
+            }
+        }
+        for symbol in SYMBOLS {
+            if symbol == single {
+                self.position += 1;
+                return Ok(Some(MacroToken::Symbol(symbol)));
+            }
+        }
+
+        let message = format!("Unexpected character {}", ch);
+        Err(self.error(start, &message))
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let start = self.position;
+        while self.position < self.chars.len() && predicate(self.chars[self.position]) {
+            self.position += 1;
+        }
+        self.chars[start..self.position].iter().collect()
+    }
+}
+
+enum Parsed {
+    Node(Node),
+    ///A statement that does nothing, like %PAGE.
+    Nothing,
+    End,
+}
+
+fn is_identifier_start(ch: char) -> bool {
+    ch.is_ascii_alphabetic() || matches!(ch, '_' | '#' | '@' | '$')
+}
+
+fn is_identifier_char(ch: char) -> bool {
+    is_identifier_start(ch) || ch.is_ascii_digit()
+}
+
+enum Flow {
+    Normal,
+    Return(Value),
+}
+
+struct Interpreter<'p, 's> {
+    parser: &'p MacroParser<'s>,
+    globals: HashMap<String, Value>,
+    procedures: HashMap<String, Rc<Procedure>>,
+    output: SourceBuilder,
+    depth: usize,
+}
+
+impl<'p, 's> Interpreter<'p, 's> {
+    ///Runs the nodes, with the variables of the procedure being run if there
+    ///is one.
+    fn run(
+        &mut self,
+        nodes: &[Node],
+        locals: &mut Option<HashMap<String, Value>>,
+    ) -> Result<Flow, String> {
+        for node in nodes {
+            match node {
+                Node::Text(start, end) => {
+                    let text: Vec<(char, usize)> = (*start..*end)
+                        .map(|position| (self.parser.chars[position], position))
+                        .collect();
+                    let mut active = vec![];
+                    for (ch, position) in self.expand(&text, &mut active)? {
+                        let origin = self.parser.get_origin(position);
+                        self.output.push_char(ch, &origin);
+                    }
+                }
+                Node::Declare(declarations, position) => {
+                    let variables = match locals {
+                        Some(ref mut locals) => locals,
+                        None => &mut self.globals,
+                    };
+                    for (name, value_type) in declarations {
+                        //a parameter keeps its value, converted to its type
+                        let value = match variables.remove(name) {
+                            Some(value) => {
+                                let origin = self.parser.get_origin(*position);
+                                value.convert(*value_type, &origin)?
+                            }
+                            None => Value::new_of_type(*value_type),
+                        };
+                        variables.insert(name.clone(), value);
+                    }
+                }
+                Node::Assign(name, value, position) | Node::Replace(name, value, position) => {
+                    let value = self.evaluate(value, locals, *position)?;
+                    self.assign(name, value, locals, *position)?;
+                }
+                Node::If(condition, then_clause, else_clause, position) => {
+                    let origin = self.parser.get_origin(*position);
+                    let clause = match self
+                        .evaluate(condition, locals, *position)?
+                        .is_true(&origin)?
+                    {
+                        true => then_clause,
+                        false => else_clause,
+                    };
+                    if let Flow::Return(value) = self.run(clause, locals)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+                Node::Do(None, body, _position) => {
+                    if let Flow::Return(value) = self.run(body, locals)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+                Node::Do(Some(control), body, position) => {
+                    if let Flow::Return(value) = self.run_loop(control, body, locals, *position)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+                Node::Return(value, position) => {
+                    if locals.is_none() {
+                        return Err(self
+                            .parser
+                            .error(*position, "RETURN outside of a procedure"));
+                    }
+                    return Ok(Flow::Return(self.evaluate(value, locals, *position)?));
+                }
+                Node::Procedure(procedure) => {
+                    self.procedures
+                        .insert(procedure.name.clone(), procedure.clone());
+                }
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn run_loop(
+        &mut self,
+        control: &DoControl,
+        body: &[Node],
+        locals: &mut Option<HashMap<String, Value>>,
+        position: usize,
+    ) -> Result<Flow, String> {
+        let origin = self.parser.get_origin(position);
+        let from = self.evaluate(&control.from, locals, position)?;
+        self.assign(&control.variable, from, locals, position)?;
+        let step = match control.by {
+            Some(ref by) => self.evaluate(by, locals, position)?.to_fixed(&origin)?,
+            None => 1,
+        };
+        if step == 0 {
+            return Err(self.parser.error(position, "The %DO loop steps BY 0"));
+        }
+
+        for _iteration in 0..MAX_LOOP_ITERATIONS {
+            let to = self
+                .evaluate(&control.to, locals, position)?
+                .to_fixed(&origin)?;
+            let current = self
+                .lookup(&control.variable, locals)
+                .unwrap_or(Value::Fixed(0))
+                .to_fixed(&origin)?;
+            let is_done = if step > 0 { current > to } else { current < to };
+            if is_done {
+                return Ok(Flow::Normal);
+            }
+
+            if let Flow::Return(value) = self.run(body, locals)? {
+                return Ok(Flow::Return(value));
+            }
+
+            let current = self
+                .lookup(&control.variable, locals)
+                .unwrap_or(Value::Fixed(0))
+                .to_fixed(&origin)?;
+            self.assign(
+                &control.variable,
+                Value::Fixed(current + step),
+                locals,
+                position,
+            )?;
+        }
+        let message = format!("The %DO loop ran more than {} times", MAX_LOOP_ITERATIONS);
+        Err(self.parser.error(position, &message))
+    }
+
+    fn lookup(&self, name: &str, locals: &Option<HashMap<String, Value>>) -> Option<Value> {
+        if let Some(ref locals) = locals {
+            if let Some(value) = locals.get(name) {
+                return Some(value.clone());
+            }
+        }
+        self.globals.get(name).cloned()
+    }
+
+    ///Assigns a variable, converting the value to its type. A variable that
+    ///wasn't declared takes the type of the value.
+    fn assign(
+        &mut self,
+        name: &str,
+        value: Value,
+        locals: &mut Option<HashMap<String, Value>>,
+        position: usize,
+    ) -> Result<(), String> {
+        let origin = self.parser.get_origin(position);
+        let variables = match locals {
+            Some(ref mut locals) if locals.contains_key(name) => locals,
+            Some(ref mut locals) if !self.globals.contains_key(name) => locals,
+            _ => &mut self.globals,
+        };
+        let value = match variables.get(name) {
+            Some(old_value) => value.convert(old_value.get_type(), &origin)?,
+            None => value,
+        };
+        variables.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    fn evaluate(
+        &mut self,
+        expression: &Expr,
+        locals: &Option<HashMap<String, Value>>,
+        position: usize,
+    ) -> Result<Value, String> {
+        let origin = self.parser.get_origin(position);
+        match expression {
+            Expr::Value(value) => Ok(value.clone()),
+            Expr::Variable(name) => match self.lookup(name, locals) {
+                Some(value) => Ok(value),
+                None if self.procedures.contains_key(name) => self.call(name, vec![], position),
+                None => {
+                    let message = format!("{} is not a preprocessor variable", name);
+                    Err(self.parser.error(position, &message))
+                }
+            },
+            Expr::Call(name, args) => {
+                let mut values = vec![];
+                for arg in args {
+                    values.push(self.evaluate(arg, locals, position)?);
+                }
+                self.call(name, values, position)
+            }
+            Expr::Unary(operator, operand) => {
+                let operand = self.evaluate(operand, locals, position)?;
+                match *operator {
+                    "-" => Ok(Value::Fixed(-operand.to_fixed(&origin)?)),
+                    "^" => Ok(Value::Fixed(!operand.is_true(&origin)? as i64)),
+                    _plus => Ok(Value::Fixed(operand.to_fixed(&origin)?)),
+                }
+            }
+            Expr::Binary(operator, left, right) => {
+                let left = self.evaluate(left, locals, position)?;
+                let right = self.evaluate(right, locals, position)?;
+                self.evaluate_binary(operator, left, right, position)
+            }
+        }
+    }
+
+    fn evaluate_binary(
+        &self,
+        operator: &str,
+        left: Value,
+        right: Value,
+        position: usize,
+    ) -> Result<Value, String> {
+        let origin = self.parser.get_origin(position);
+        if operator == "||" {
+            return Ok(Value::Character(left.get_text() + &right.get_text()));
+        }
+        if let "&" | "|" = operator {
+            let (left, right) = (left.is_true(&origin)?, right.is_true(&origin)?);
+            let result = if operator == "&" {
+                left && right
+            } else {
+                left || right
+            };
+            return Ok(Value::Fixed(result as i64));
+        }
+        if let "=" | "^=" | "<" | ">" | "<=" | ">=" = operator {
+            //strings are compared as strings, unless both are numbers
+            let ordering = match (&left, &right) {
+                (Value::Character(left), Value::Character(right)) => left.cmp(right),
+                _ => match (left.to_fixed(&origin), right.to_fixed(&origin)) {
+                    (Ok(left), Ok(right)) => left.cmp(&right),
+                    _ => left.get_text().cmp(&right.get_text()),
+                },
+            };
+            let result = match operator {
+                "=" => ordering.is_eq(),
+                "^=" => ordering.is_ne(),
+                "<" => ordering.is_lt(),
+                ">" => ordering.is_gt(),
+                "<=" => ordering.is_le(),
+                _greater_or_equal => ordering.is_ge(),
+            };
+            return Ok(Value::Fixed(result as i64));
+        }
+
+        let (left, right) = (left.to_fixed(&origin)?, right.to_fixed(&origin)?);
+        let result = match operator {
+            "+" => left.checked_add(right),
+            "-" => left.checked_sub(right),
+            "*" => left.checked_mul(right),
+            _divide => left.checked_div(right),
+        };
+        result
+            .map(Value::Fixed)
+            .ok_or_else(|| self.parser.error(position, "The arithmetic overflows"))
+    }
+
+    fn call(&mut self, name: &str, args: Vec<Value>, position: usize) -> Result<Value, String> {
+        let procedure = match self.procedures.get(name) {
+            Some(procedure) => procedure.clone(),
+            None => {
+                let message = format!("{} is not a preprocessor procedure", name);
+                return Err(self.parser.error(position, &message));
+            }
+        };
+        if args.len() != procedure.parameters.len() {
+            let message = format!(
+                "{} takes {} arguments, it was given {}",
+                name,
+                procedure.parameters.len(),
+                args.len()
+            );
+            return Err(self.parser.error(position, &message));
+        }
+        if self.depth >= MAX_EXPANSION_DEPTH {
+            let message = format!("The calls of {} nest too deeply", name);
+            return Err(self.parser.error(position, &message));
+        }
+
+        let mut locals = Some(
+            procedure
+                .parameters
+                .iter()
+                .cloned()
+                .zip(args)
+                .collect::<HashMap<String, Value>>(),
+        );
+        self.depth += 1;
+        let flow = self.run(&procedure.body, &mut locals);
+        self.depth -= 1;
+
+        let origin = self.parser.get_origin(position);
+        match flow? {
+            Flow::Return(value) => value.convert(procedure.returns, &origin),
+            Flow::Normal => Ok(Value::new_of_type(procedure.returns)),
+        }
+    }
+
+    ///Replaces the preprocessor variables and procedure calls in the text.
+    ///What they are replaced by is scanned again, except for the names
+    ///already being replaced in `active`.
+    fn expand(
+        &mut self,
+        text: &[(char, usize)],
+        active: &mut Vec<String>,
+    ) -> Result<Vec<(char, usize)>, String> {
+        let mut result = vec![];
+        let mut index = 0;
+        let mut in_string = false;
+        let mut in_comment = false;
+
+        while index < text.len() {
+            let (ch, position) = text[index];
+            let next = text.get(index + 1).map(|(ch, _position)| *ch);
+            if in_comment || in_string || !is_identifier_start(ch) {
+                if in_comment && ch == '*' && next == Some('/') {
+                    in_comment = false;
+                    result.push(text[index]);
+                    index += 1;
+                } else if !in_comment && ch == '\'' {
+                    in_string = !in_string;
+                } else if !in_comment && !in_string && ch == '/' && next == Some('*') {
+                    in_comment = true;
+                }
+                result.push(text[index]);
+                index += 1;
+                continue;
+            }
+
+            let mut end = index;
+            while end < text.len() && is_identifier_char(text[end].0) {
+                end += 1;
+            }
+            let name: String = text[index..end]
+                .iter()
+                .map(|(ch, _position)| ch.to_ascii_uppercase())
+                .collect();
+
+            let replacement = if active.contains(&name) {
+                None
+            } else if let Some(value) = self.globals.get(&name) {
+                Some(value.get_text())
+            } else if self.procedures.contains_key(&name) {
+                let (args, args_end) = self.expand_call_arguments(text, end, active)?;
+                end = args_end;
+                Some(self.call(&name, args, position)?.get_text())
+            } else {
+                None
+            };
+
+            match replacement {
+                Some(replacement) => {
+                    if active.len() >= MAX_EXPANSION_DEPTH {
+                        let message = format!("The replacement of {} nests too deeply", name);
+                        return Err(self.parser.error(position, &message));
+                    }
+                    let replacement: Vec<(char, usize)> =
+                        replacement.chars().map(|ch| (ch, position)).collect();
+                    active.push(name);
+                    result.append(&mut self.expand(&replacement, active)?);
+                    active.pop();
+                }
+                None => result.extend_from_slice(&text[index..end]),
+            }
+            index = end;
+        }
+
+        Ok(result)
+    }
+
+    ///Reads the arguments of a procedure invoked in the text, the text
+    ///between the parentheses after its name split at the commas. Returns
+    ///them expanded, and where they end.
+    fn expand_call_arguments(
+        &mut self,
+        text: &[(char, usize)],
+        start: usize,
+        active: &mut Vec<String>,
+    ) -> Result<(Vec<Value>, usize), String> {
+        let mut index = start;
+        while index < text.len() && text[index].0.is_whitespace() {
+            index += 1;
+        }
+        if index >= text.len() || text[index].0 != '(' {
+            return Ok((vec![], start));
+        }
+
+        let mut args: Vec<Vec<(char, usize)>> = vec![vec![]];
+        let mut depth = 0;
+        let mut in_string = false;
+        index += 1;
+        loop {
+            let (ch, position) = match text.get(index) {
+                Some(character) => *character,
+                None => {
+                    let position = text[start.min(text.len() - 1)].1;
+                    return Err(self
+                        .parser
+                        .error(position, "The arguments are never closed"));
+                }
+            };
+            index += 1;
+            match ch {
+                '\'' => in_string = !in_string,
+                '(' if !in_string => depth += 1,
+                ')' if !in_string && depth == 0 => break,
+                ')' if !in_string => depth -= 1,
+                ',' if !in_string && depth == 0 => {
+                    args.push(vec![]);
+                    continue;
+                }
+                _ => {}
+            }
+            args.last_mut().unwrap().push((ch, position));
+        }
+        if args.len() == 1 && args[0].iter().all(|(ch, _position)| ch.is_whitespace()) {
+            args.clear();
+        }
+
+        let mut values = vec![];
+        for arg in args {
+            let expanded: String = self
+                .expand(&arg, active)?
+                .into_iter()
+                .map(|(ch, _position)| ch)
+                .collect();
+            values.push(Value::Character(expanded.trim().to_string()));
+        }
+        Ok((values, index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(text: &str) -> Result<String, String> {
+        let source = ExpandedSource {
+            text: text.to_string(),
+            lines: vec![],
+        };
+        Ok(expand_macros(&source)?.text)
+    }
+
+    fn squeeze(text: &str) -> String {
+        text.split_whitespace().collect::<Vec<&str>>().join(" ")
+    }
+
+    #[test]
+    fn replace_and_assign_variables() -> Result<(), String> {
+        let text = "%REPLACE LIMIT BY 10;
+%DCL (COUNT, TOTAL) FIXED, NAME CHAR;
+%COUNT = LIMIT * 2 + 1;
+%NAME = 'ROW' || COUNT;
+X = LIMIT + COUNT; Y = 'LIMIT'; NAME = 0; /* LIMIT */";
+
+        assert_eq!(
+            "X = 10 + 21; Y = 'LIMIT'; ROW21 = 0; /* LIMIT */",
+            squeeze(&expand(text)?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn conditional_compilation() -> Result<(), String> {
+        let text = "%DCL DEBUG FIXED;
+%DEBUG = 1;
+%IF DEBUG = 1 %THEN %DO;
+PUT LIST('DEBUG');
+%END;
+%ELSE %DO;
+PUT LIST('RELEASE');
+%END;
+%IF DEBUG ^= 1 %THEN %DEBUG = 5;";
+
+        assert_eq!("PUT LIST('DEBUG');", squeeze(&expand(text)?));
+        Ok(())
+    }
+
+    #[test]
+    fn loops_repeat_their_text() -> Result<(), String> {
+        let text = "%DCL I FIXED;
+%DO I = 1 TO 5 BY 2;
+A(I) = I;
+%END;";
+
+        assert_eq!("A(1) = 1; A(3) = 3; A(5) = 5;", squeeze(&expand(text)?));
+        Ok(())
+    }
+
+    #[test]
+    fn procedures_replace_their_invocations() -> Result<(), String> {
+        let text = "%SQUARE: PROCEDURE(X) RETURNS(CHAR);
+    DCL X FIXED;
+    IF X < 0 THEN RETURN('(' || -X || ')**2');
+    RETURN(X * X);
+%END;
+A = SQUARE(3) + SQUARE(-2);";
+
+        assert_eq!("A = 9 + (2)**2;", squeeze(&expand(text)?));
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_statements_are_errors() {
+        let error = expand("%FOO;").unwrap_err();
+        assert!(error.contains("E032"), "{}", error);
+    }
+}
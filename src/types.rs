@@ -5,35 +5,57 @@ use std::{
 
 use inkwell::{
     context::Context,
-    types::{AnyType, AnyTypeEnum, ArrayType, BasicType, BasicTypeEnum, StructType},
-    values::{FloatValue, IntValue, PointerValue, StructValue},
+    types::{
+        AnyType, AnyTypeEnum, ArrayType, BasicType, BasicTypeEnum, FloatType, IntType, PointerType,
+        StructType,
+    },
+    values::{AnyValue, FloatValue, IntValue, PointerValue},
+    AddressSpace,
 };
 use log::debug;
 
 use crate::{
-    codegen::codegen::Compiler, error::get_error, types::float_decimal::PLIFloatDecimalValue,
+    codegen::{codegen::Compiler, runtime::stream},
+    error::get_error,
+    lexer::Token,
+    types::{
+        conversion::{
+            get_common_type, get_conversion, get_fixed_binary_type, DEFAULT_FIXED_BINARY_PRECISION,
+        },
+        float_decimal::{FloatFormat, PLIFloatDecimalValue, DEFAULT_FLOAT_PRECISION},
+        picture::Picture,
+        traits::get_mathable_type,
+    },
 };
 
-use self::fixed_decimal::{generate_fixed_decimal_code, FixedValue};
+use self::fixed_decimal::{
+    generate_fixed_decimal_code, FixedValue, DEFAULT_PRECISION, MAX_PRECISION,
+};
 
 pub mod character;
+pub mod complex;
+pub mod conversion;
+pub mod defaults;
+pub mod entry;
 /// Holds all type data
 pub mod fixed_decimal;
 pub mod float_decimal;
+pub mod label;
+pub mod picture;
 pub mod traits;
 const SIZE_OF_STRINGS: u32 = 255;
 
 //DCL (A,B,C,D,E) FIXED(3);
 
-// a FIXED DECIMAL is a i16
-// a FLOAT DECIMAL is a floating point i16 number
-// FIXED DECIMAL (3) or (3,0) means a number with 3 digits before period i.e 100
-// FIXED DECIMAL (3.1) means a number with 3 digits before period, one after i.e 100.1
+// FIXED DECIMAL (3) or (3,0) means a number with 3 digits, none after the point i.e 100
+// FIXED DECIMAL (3,1) means a number with 3 digits, one after the point i.e 10.1
 
 #[derive(Clone, Debug, Copy, PartialEq, PartialOrd)]
 pub enum Type {
-    FixedDecimal,
-    Float,
+    ///FIXED DECIMAL(p,q), with p digits, q of them after the point.
+    FixedDecimal(u32, u32),
+    ///FLOAT DECIMAL(p), with its precision in decimal digits.
+    Float(u32),
     /// Not a type: just represents something
     /// whose type has to be determined later.
     TBD,
@@ -41,6 +63,87 @@ pub enum Type {
     Void,
     ///The string type
     Char(u32),
+    ///A string of up to the given number of characters, which keeps track of
+    ///how many it currently holds.
+    VaryingChar(u32),
+    ///Holds the address of some storage, usually of a BASED variable.
+    Pointer,
+    ///Holds a statement label, for GO TO to go to.
+    Label,
+    ///Holds the address of a procedure, which is called through it.
+    Entry,
+    ///A number kept as the characters its picture edits it into.
+    Picture(Picture),
+    ///A complex number whose real and imaginary parts are each FIXED DECIMAL.
+    FixedComplex,
+    ///A complex number whose real and imaginary parts are each FLOAT.
+    FloatComplex,
+}
+
+impl Type {
+    ///FIXED DECIMAL declared without a precision.
+    pub const DEFAULT_FIXED: Type = Type::FixedDecimal(DEFAULT_PRECISION, 0);
+    ///FLOAT DECIMAL declared without a precision.
+    pub const DEFAULT_FLOAT: Type = Type::Float(DEFAULT_FLOAT_PRECISION);
+    ///The 1 or 0 of a comparison.
+    pub const TRUTH_VALUE: Type = Type::FixedDecimal(1, 0);
+
+    pub fn is_character(&self) -> bool {
+        matches!(self, Type::Char(_) | Type::VaryingChar(_))
+    }
+
+    pub fn is_arithmetic(&self) -> bool {
+        matches!(
+            self,
+            Type::FixedDecimal(..)
+                | Type::Float(_)
+                | Type::Picture(_)
+                | Type::FixedComplex
+                | Type::FloatComplex
+        )
+    }
+
+    pub fn is_complex(&self) -> bool {
+        matches!(self, Type::FixedComplex | Type::FloatComplex)
+    }
+
+    ///The type of the real and imaginary parts of a complex number. A real
+    ///number is its own real part.
+    pub fn get_real_type(&self) -> Type {
+        match self {
+            Type::FixedComplex => Type::FixedDecimal(MAX_PRECISION, complex::FIXED_PART_SCALE),
+            Type::FloatComplex => Type::DEFAULT_FLOAT,
+            other => other.get_computational_type(),
+        }
+    }
+
+    ///The complex type whose parts have the type of this number.
+    pub fn get_complex_type(&self) -> Type {
+        match self.get_real_type() {
+            Type::FixedDecimal(..) => Type::FixedComplex,
+            Type::Float(_) => Type::FloatComplex,
+            other => other,
+        }
+    }
+
+    ///The type a value of this type takes part in arithmetic as. A pictured
+    ///number is the FIXED DECIMAL its picture edits.
+    pub fn get_computational_type(&self) -> Type {
+        match self {
+            Type::Picture(picture) => {
+                Type::FixedDecimal(picture.get_digit_count(), picture.get_scale())
+            }
+            other => *other,
+        }
+    }
+
+    ///The declared length of a fixed string, or the maximum length of a varying one.
+    pub fn get_character_length(&self) -> Option<u32> {
+        match self {
+            Type::Char(length) | Type::VaryingChar(length) => Some(*length),
+            _ => None,
+        }
+    }
 }
 
 impl Display for Type {
@@ -63,11 +166,13 @@ pub enum FixedRadix {
 
 #[derive(Debug, Clone)]
 pub struct TypeModule<'ctx> {
-    pub fixed_type: StructType<'ctx>,
+    pub fixed_type: IntType<'ctx>,
+    pub stream_file_type: StructType<'ctx>,
 }
 
 ///Takes two input types, and determines what the output type should be.
-///Useful for binary expressions.
+///Useful for binary expressions. Numbers of different types are converted by
+///the rules in `conversion`.
 pub fn resolve_types(type_one: &Type, type_two: &Type) -> Result<Type, String> {
     if *type_one == Type::TBD
         || *type_two == Type::TBD
@@ -81,15 +186,55 @@ pub fn resolve_types(type_one: &Type, type_two: &Type) -> Result<Type, String> {
         ]));
     }
 
+    let type_one = &type_one.get_computational_type();
+    let type_two = &type_two.get_computational_type();
     if *type_one == *type_two {
         return Ok(type_one.clone());
     }
 
-    //by this point we have one fixed decimal and one float.
-    Ok(Type::FixedDecimal)
+    //a pointer, label or entry can't be mixed with anything but one of its kind
+    if matches!(type_one, Type::Pointer | Type::Label | Type::Entry)
+        || matches!(type_two, Type::Pointer | Type::Label | Type::Entry)
+    {
+        return Err(get_error(&[
+            "5",
+            &type_one.to_string(),
+            &type_two.to_string(),
+        ]));
+    }
+
+    //a complex number mixed with any other number is complex, with parts of
+    //the type their real parts resolve to
+    if type_one.is_complex() || type_two.is_complex() {
+        let part_type = resolve_types(&type_one.get_real_type(), &type_two.get_real_type())?;
+        return Ok(part_type.get_complex_type());
+    }
+
+    //numbers of different scales or precisions, or strings read as numbers
+    get_common_type(*type_one, *type_two)
+        .ok_or_else(|| get_error(&["5", &type_one.to_string(), &type_two.to_string()]))
 }
 
+///The type of `left operator right`, the result the conversion rules give
+///when they apply, and the type both operands resolve to otherwise.
+pub fn get_binary_type(operator: &Token, left: &Type, right: &Type) -> Result<Type, String> {
+    match get_conversion(operator, *left, *right) {
+        Some(conversion) => Ok(conversion.result_type),
+        None => resolve_types(left, right),
+    }
+}
+
+///Character strings match each other whatever their lengths, and numbers
+///match each other whatever their types, since they are converted on
+///assignment.
 pub fn do_types_match(_type1: &Type, _type2: &Type) -> bool {
+    if _type1.is_character() && _type2.is_character() {
+        return true;
+    }
+    if _type1.is_arithmetic() && _type2.is_arithmetic() {
+        return true;
+    }
+
     let lhs: Discriminant<Type> = discriminant(_type1);
     let rhs: Discriminant<Type> = discriminant(_type2);
 
@@ -100,6 +245,7 @@ impl<'ctx> TypeModule<'ctx> {
     pub fn new(ctx: &'ctx Context) -> Self {
         TypeModule {
             fixed_type: fixed_decimal::get_fixed_type(ctx),
+            stream_file_type: stream::get_stream_file_type(ctx),
         }
     }
 }
@@ -111,18 +257,119 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
         character::get_character_type(self.context, size)
     }
 
-    pub fn gen_const_fixed_decimal(&self, value: f64) -> StructValue<'ctx> {
-        let struc: StructValue<'ctx> =
-            generate_fixed_decimal_code(self.context, self.type_module.fixed_type, value).into();
+    pub fn get_varying_character_type(&self, max_length: u32) -> StructType<'ctx> {
+        character::get_varying_character_type(self.context, max_length)
+    }
+
+    ///Every pointer is an i8*, it is cast to the type of the BASED variable
+    ///it is used to reach.
+    pub fn get_pointer_type(&self) -> PointerType<'ctx> {
+        self.context.i8_type().ptr_type(AddressSpace::default())
+    }
+
+    ///Converts a number from one arithmetic type to another, going through a
+    ///float of the format the arithmetic is done in.
+    pub unsafe fn convert_arithmetic_value(
+        &self,
+        value: Box<dyn AnyValue<'ctx> + 'ctx>,
+        from: Type,
+        to: Type,
+    ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, String> {
+        if from == to {
+            return Ok(value);
+        }
+        if let (
+            Type::FixedDecimal(from_precision, from_scale),
+            Type::FixedDecimal(precision, scale),
+        ) = (from, to)
+        {
+            let fixed_value = FixedValue::new(
+                value.as_any_value_enum().into_int_value(),
+                from_precision,
+                from_scale,
+            );
+            return Ok(Box::new(
+                self.generate_fixed_conversion(&fixed_value, precision, scale)
+                    .value,
+            ));
+        }
+        //a real number becomes a complex one with no imaginary part, and a
+        //complex number becomes a real one by losing it
+        if to.is_complex() {
+            let (real, imaginary) = self.get_complex_parts(value, from)?;
+            return Ok(Box::new(self.create_complex_value(real, imaginary, to)?));
+        }
+        let value_as_float = get_mathable_type(value, from)?
+            .convert_to_float_of_type(self, self.get_working_float_type(to));
+        self.create_arithmetic_value(&value_as_float, to)
+    }
+
+    ///The format arithmetic giving a value of `_type` is done in, a double
+    ///unless it is a FLOAT held in a wider one.
+    pub fn get_working_float_type(&self, _type: Type) -> FloatType<'ctx> {
+        match _type {
+            Type::Float(precision)
+                if FloatFormat::from_precision(precision) == FloatFormat::Extended =>
+            {
+                FloatFormat::Extended.get_float_type(self.context)
+            }
+            _ => self.context.f64_type(),
+        }
+    }
+
+    ///Stores a computed float as a value of the arithmetic type `to`. Only a
+    ///FLOAT can be given one wider than a double.
+    pub unsafe fn create_arithmetic_value(
+        &self,
+        value: &FloatValue<'ctx>,
+        to: Type,
+    ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, String> {
+        match to {
+            Type::FixedDecimal(precision, scale) => Ok(Box::new(
+                self.float_value_to_fixed_decimal(*value, precision, scale)
+                    .value,
+            )),
+            Type::Float(precision) => Ok(Box::new(
+                PLIFloatDecimalValue::from_float(*value, precision, self).value,
+            )),
+            Type::Picture(picture) => Ok(Box::new(self.edit_into_picture(*value, picture))),
+            Type::FixedComplex | Type::FloatComplex => {
+                let imaginary = self.context.f64_type().const_zero();
+                Ok(Box::new(self.create_complex_value(*value, imaginary, to)?))
+            }
+            other => Err(format!("Can't convert a number into type {}", other)),
+        }
+    }
 
-        struc
+    ///The FIXED DECIMAL(precision, scale) constant of `digits`, like -123.45,
+    ///or None when it has too many digits before the point to be held.
+    pub fn gen_const_fixed_decimal(
+        &self,
+        digits: &str,
+        precision: u32,
+        scale: u32,
+    ) -> Option<IntValue<'ctx>> {
+        generate_fixed_decimal_code(self.type_module.fixed_type, digits, precision, scale)
+            .map(|fixed_value| fixed_value.value)
     }
 
-    pub fn convert_plick_type_to_llvm_basic_type(&'a self, _type: Type) -> BasicTypeEnum<'ctx> {
+    pub fn convert_plick_type_to_llvm_basic_type(&self, _type: Type) -> BasicTypeEnum<'ctx> {
         let result = match _type {
-            Type::FixedDecimal => self.type_module.fixed_type.as_basic_type_enum(),
+            Type::FixedDecimal(..) => self.type_module.fixed_type.as_basic_type_enum(),
             Type::Char(size) => self.get_character_type(size).as_basic_type_enum(),
-            Type::Float => PLIFloatDecimalValue::get_llvm_basic_type(self),
+            Type::VaryingChar(max_length) => self
+                .get_varying_character_type(max_length)
+                .as_basic_type_enum(),
+            Type::Float(precision) => PLIFloatDecimalValue::get_llvm_basic_type(self, precision),
+            Type::Pointer => self.get_pointer_type().as_basic_type_enum(),
+            Type::Label => label::get_label_type(self.context).as_basic_type_enum(),
+            Type::Entry => self.get_pointer_type().as_basic_type_enum(),
+            Type::Picture(picture) => self
+                .get_character_type(picture.get_length())
+                .as_basic_type_enum(),
+            Type::FixedComplex | Type::FloatComplex => {
+                self.get_complex_struct_type(_type).as_basic_type_enum()
+            }
             Type::Void => panic!("Can't convert void type to basic type enum!"),
             Type::TBD => panic!("Can't convert TBD type to basic type enum!"),
         };
@@ -131,11 +378,35 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
 
         result
     }
-    pub fn convert_plick_type_to_llvm_any_type(&'a self, _type: Type) -> AnyTypeEnum<'ctx> {
+    ///The type of the storage of a variable, an array of `_type` when the
+    ///variable is declared with a dimension.
+    pub fn get_storage_type(&self, _type: Type, dimension: Option<u32>) -> BasicTypeEnum<'ctx> {
+        let element_type = self.convert_plick_type_to_llvm_basic_type(_type);
+        match dimension {
+            Some(element_count) => element_type.array_type(element_count).as_basic_type_enum(),
+            None => element_type,
+        }
+    }
+
+    pub fn convert_plick_type_to_llvm_any_type(&self, _type: Type) -> AnyTypeEnum<'ctx> {
         match _type {
-            Type::FixedDecimal => self.type_module.fixed_type.as_any_type_enum(),
+            Type::FixedDecimal(..) => self.type_module.fixed_type.as_any_type_enum(),
             Type::Char(size) => self.get_character_type(size).as_any_type_enum(),
-            Type::Float => todo!("implement float type"),
+            Type::VaryingChar(max_length) => self
+                .get_varying_character_type(max_length)
+                .as_any_type_enum(),
+            Type::Float(precision) => {
+                PLIFloatDecimalValue::get_llvm_basic_type(self, precision).as_any_type_enum()
+            }
+            Type::Pointer => self.get_pointer_type().as_any_type_enum(),
+            Type::Label => label::get_label_type(self.context).as_any_type_enum(),
+            Type::Entry => self.get_pointer_type().as_any_type_enum(),
+            Type::Picture(picture) => self
+                .get_character_type(picture.get_length())
+                .as_any_type_enum(),
+            Type::FixedComplex | Type::FloatComplex => {
+                self.get_complex_struct_type(_type).as_any_type_enum()
+            }
             Type::Void => self.context.void_type().as_any_type_enum(),
             Type::TBD => panic!("Can't convert TBD type to any type enum!"),
         }
@@ -152,18 +423,18 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
 ///   2. Because the default attributes for function names do not allow us to return a result that
 ///      is FIXED DECIMAL or FLOAT DECIMAL(16), for example, we have another method of specifying
 ///      the attributes of a returned value. This is accomplished through the RETURNS keyword.
+///
+///The same defaults apply to variables that are never declared. A FIXED BINARY(15) is held as
+///the FIXED DECIMAL with room for its values.
 pub fn infer_pli_type_via_name(name_of_pli_object: &str) -> Type {
     let first_letter_of_func = name_of_pli_object
         .chars()
         .next()
         .unwrap()
-        .to_ascii_lowercase();
+        .to_ascii_uppercase();
 
-    if 105 < first_letter_of_func as u32 {
-        return Type::FixedDecimal;
-    } else {
-        return Type::FixedDecimal;
+    match first_letter_of_func {
+        'I'..='N' => get_fixed_binary_type(DEFAULT_FIXED_BINARY_PRECISION),
+        _ => Type::DEFAULT_FLOAT,
     }
-
-    todo!("Make it so all functions don't return just a fixed decimal by default!")
 }
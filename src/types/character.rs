@@ -1,13 +1,21 @@
 use inkwell::{
     module::Linkage,
-    types::{ArrayType, BasicMetadataTypeEnum, BasicTypeEnum, FunctionType, StructType},
-    values::{ArrayValue, BasicMetadataValueEnum, BasicValueEnum, IntValue, PointerValue},
-    AddressSpace,
+    types::{ArrayType, BasicMetadataTypeEnum, BasicType, BasicTypeEnum, FunctionType, StructType},
+    values::{
+        AnyValue, ArrayValue, BasicMetadataValueEnum, BasicValueEnum, IntValue, PointerValue,
+        StructValue,
+    },
+    AddressSpace, IntPredicate,
 };
 
 use crate::codegen::codegen::Compiler;
 
-use super::{traits::Puttable, SIZE_OF_STRINGS};
+use super::{traits::Puttable, Type, SIZE_OF_STRINGS};
+
+///Field 0 of a VARYING string is its current length, field 1 holds its characters.
+///The characters are always followed by a terminator, so they can be printed like a CHAR.
+const LENGTH_FIELD: u32 = 0;
+const DATA_FIELD: u32 = 1;
 
 ///Represents a CHAR PL/1 value.
 ///A string is just an array of characters (which are i8 integers for ASCII)
@@ -38,6 +46,62 @@ pub fn get_character_type<'ctx>(
     char_array
 }
 
+pub fn get_varying_character_type<'ctx>(
+    ctx: &'ctx inkwell::context::Context,
+    max_length: u32,
+) -> StructType<'ctx> {
+    let field_types: Vec<BasicTypeEnum> = vec![
+        ctx.i32_type().as_basic_type_enum(),
+        get_character_type(ctx, max_length).as_basic_type_enum(),
+    ];
+
+    let packed = false;
+    ctx.struct_type(&field_types, packed)
+}
+
+///A constant VARYING string, cut short to `max_length` characters.
+pub fn generate_constant_varying_string<'ctx>(
+    ctx: &'ctx inkwell::context::Context,
+    value: &str,
+    max_length: u32,
+) -> StructValue<'ctx> {
+    let characters: Vec<IntValue> = value
+        .bytes()
+        .take(max_length as usize)
+        .map(|character| ctx.i8_type().const_int(character as u64, false))
+        .collect();
+    let length = characters.len() as u64;
+
+    let mut data = characters;
+    //everything after the characters is zero, which also terminates them
+    data.resize((max_length + 1) as usize, ctx.i8_type().const_zero());
+
+    get_varying_character_type(ctx, max_length).const_named_struct(&[
+        ctx.i32_type().const_int(length, false).into(),
+        ctx.i8_type().const_array(&data[..]).into(),
+    ])
+}
+
+///Represents a CHAR VARYING PL/1 value.
+#[derive(Debug)]
+pub struct VaryingCharValue<'ctx> {
+    pub value: StructValue<'ctx>,
+}
+
+impl<'ctx> VaryingCharValue<'ctx> {
+    pub fn new(value: StructValue<'ctx>) -> VaryingCharValue<'ctx> {
+        VaryingCharValue { value }
+    }
+}
+
+///The characters of a string and how many of them there are.
+///For a CHAR value, the characters end at its terminator.
+#[derive(Debug, Clone, Copy)]
+pub struct StringView<'ctx> {
+    pub characters: PointerValue<'ctx>,
+    pub length: IntValue<'ctx>,
+}
+
 ///Converts a constant string into a charvalue
 pub fn generate_character_code<'ctx>(
     ctx: &'ctx inkwell::context::Context,
@@ -118,11 +182,13 @@ impl<'a, 'ctx> Puttable<'a, 'ctx> for CharValue<'ctx> {
         &self,
         compiler: &'a Compiler<'a, 'ctx>,
     ) -> PointerValue<'ctx> {
-        let string_array = self.value;
-        let const_string = self.value.get_string_constant().unwrap();
         let allocd_string = compiler
             .builder
-            .build_global_string_ptr(const_string.to_str().unwrap(), "char_const")
+            .build_alloca(self.value.get_type(), "char_to_print")
+            .unwrap();
+        compiler
+            .builder
+            .build_store(allocd_string, self.value)
             .unwrap();
 
         let bitc: BasicValueEnum<'ctx> = compiler
@@ -137,3 +203,277 @@ impl<'a, 'ctx> Puttable<'a, 'ctx> for CharValue<'ctx> {
         bitc.into_pointer_value()
     }
 }
+
+impl<'a, 'ctx> Puttable<'a, 'ctx> for VaryingCharValue<'ctx> {
+    unsafe fn print_object(&self, compiler: &'a Compiler<'a, 'ctx>) {
+        let string_ptr = self.get_pointer_to_printable_string(compiler);
+
+        compiler
+            .builder
+            .build_call(
+                compiler.get_function("printf").unwrap(),
+                &[string_ptr.into()],
+                "printing_varying_char_from_puttable",
+            )
+            .unwrap();
+    }
+    fn get_pointer_to_printable_string(
+        &self,
+        compiler: &'a Compiler<'a, 'ctx>,
+    ) -> PointerValue<'ctx> {
+        let allocd_string = compiler
+            .builder
+            .build_alloca(self.value.get_type(), "varying_char_to_print")
+            .unwrap();
+        compiler
+            .builder
+            .build_store(allocd_string, self.value)
+            .unwrap();
+
+        compiler.get_varying_characters_pointer(allocd_string)
+    }
+}
+
+impl<'a, 'ctx> Compiler<'a, 'ctx> {
+    ///Returns an i8* to the characters of the VARYING string `string_ptr` points to.
+    pub fn get_varying_characters_pointer(
+        &self,
+        string_ptr: PointerValue<'ctx>,
+    ) -> PointerValue<'ctx> {
+        let data_ptr = self
+            .builder
+            .build_struct_gep(string_ptr, DATA_FIELD, "varying_data_ptr")
+            .unwrap();
+        self.builder
+            .build_bitcast(
+                data_ptr,
+                self.context.i8_type().ptr_type(AddressSpace::default()),
+                "varying_characters",
+            )
+            .unwrap()
+            .into_pointer_value()
+    }
+
+    ///Gets the characters of a CHAR or VARYING value of the given type.
+    pub fn generate_string_view(
+        &self,
+        value: Box<dyn AnyValue<'ctx> + 'ctx>,
+        _type: Type,
+    ) -> Result<StringView<'ctx>, String> {
+        match _type {
+            Type::Char(_size) => {
+                let char_array = value.as_any_value_enum().into_array_value();
+                let allocd_string = self
+                    .builder
+                    .build_alloca(char_array.get_type(), "char_view")
+                    .unwrap();
+                self.builder.build_store(allocd_string, char_array).unwrap();
+
+                let characters = self
+                    .builder
+                    .build_bitcast(
+                        allocd_string,
+                        self.context.i8_type().ptr_type(AddressSpace::default()),
+                        "char_characters",
+                    )
+                    .unwrap()
+                    .into_pointer_value();
+
+                Ok(StringView {
+                    characters,
+                    length: self.build_string_length(characters),
+                })
+            }
+            Type::VaryingChar(_max_length) => {
+                let varying_struct = value.as_any_value_enum().into_struct_value();
+                let allocd_string = self
+                    .builder
+                    .build_alloca(varying_struct.get_type(), "varying_view")
+                    .unwrap();
+                self.builder
+                    .build_store(allocd_string, varying_struct)
+                    .unwrap();
+
+                let length = self
+                    .builder
+                    .build_extract_value(varying_struct, LENGTH_FIELD, "varying_length")
+                    .unwrap()
+                    .into_int_value();
+
+                Ok(StringView {
+                    characters: self.get_varying_characters_pointer(allocd_string),
+                    length,
+                })
+            }
+            other => Err(format!("Type {} is not a string", other)),
+        }
+    }
+
+    ///Returns the number of characters before the terminator, as an i32.
+    pub fn build_string_length(&self, characters: PointerValue<'ctx>) -> IntValue<'ctx> {
+        let length = self
+            .builder
+            .build_call(
+                self.module.get_function("strlen").unwrap(),
+                &[characters.into()],
+                "strlen",
+            )
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_int_value();
+
+        self.builder
+            .build_int_truncate(length, self.context.i32_type(), "string_length")
+            .unwrap()
+    }
+
+    ///Copies `length` characters to `destination` and terminates them.
+    fn build_copy_characters(
+        &self,
+        destination: PointerValue<'ctx>,
+        source: PointerValue<'ctx>,
+        length: IntValue<'ctx>,
+    ) {
+        let length_as_i64 = self
+            .builder
+            .build_int_z_extend(length, self.context.i64_type(), "copy_length")
+            .unwrap();
+        self.builder
+            .build_memcpy(destination, 1, source, 1, length_as_i64)
+            .unwrap();
+
+        let terminator_ptr = unsafe {
+            self.builder
+                .build_gep(destination, &[length], "terminator_ptr")
+                .unwrap()
+        };
+        self.builder
+            .build_store(terminator_ptr, self.context.i8_type().const_zero())
+            .unwrap();
+    }
+
+    ///Stores a string into a CHAR or VARYING variable, keeping as many characters
+    ///as the variable can hold.
+    pub fn store_string(
+        &self,
+        target_ptr: PointerValue<'ctx>,
+        target_type: Type,
+        string: StringView<'ctx>,
+    ) {
+        let i32_type = self.context.i32_type();
+        let max_length =
+            i32_type.const_int(target_type.get_character_length().unwrap() as u64, false);
+        let is_too_long = self
+            .builder
+            .build_int_compare(IntPredicate::UGT, string.length, max_length, "is_too_long")
+            .unwrap();
+        let length = self
+            .builder
+            .build_select(is_too_long, max_length, string.length, "stored_length")
+            .unwrap()
+            .into_int_value();
+
+        match target_type {
+            Type::VaryingChar(_max_length) => {
+                let characters = self.get_varying_characters_pointer(target_ptr);
+                self.build_copy_characters(characters, string.characters, length);
+
+                let length_ptr = self
+                    .builder
+                    .build_struct_gep(target_ptr, LENGTH_FIELD, "varying_length_ptr")
+                    .unwrap();
+                self.builder.build_store(length_ptr, length).unwrap();
+            }
+            _ => {
+                let characters = self
+                    .builder
+                    .build_bitcast(
+                        target_ptr,
+                        self.context.i8_type().ptr_type(AddressSpace::default()),
+                        "char_characters",
+                    )
+                    .unwrap()
+                    .into_pointer_value();
+                self.build_copy_characters(characters, string.characters, length);
+            }
+        }
+    }
+
+    ///Makes a VARYING variable hold the empty string.
+    pub fn initialize_varying_string(&self, string_ptr: PointerValue<'ctx>) {
+        let length_ptr = self
+            .builder
+            .build_struct_gep(string_ptr, LENGTH_FIELD, "varying_length_ptr")
+            .unwrap();
+        self.builder
+            .build_store(length_ptr, self.context.i32_type().const_zero())
+            .unwrap();
+
+        let characters = self.get_varying_characters_pointer(string_ptr);
+        self.builder
+            .build_store(characters, self.context.i8_type().const_zero())
+            .unwrap();
+    }
+
+    ///Makes `storage` hold the empty string, or every element of it hold one
+    ///when it is an array. Storage of any other type is left alone.
+    pub fn initialize_varying_storage(
+        &self,
+        storage: PointerValue<'ctx>,
+        _type: Type,
+        dimension: Option<u32>,
+    ) {
+        match (_type, dimension) {
+            (Type::VaryingChar(_max_length), None) => self.initialize_varying_string(storage),
+            (Type::VaryingChar(max_length), Some(element_count)) => {
+                //all zeros is an empty string with its terminator
+                let empty_strings = self
+                    .get_varying_character_type(max_length)
+                    .array_type(element_count)
+                    .const_zero();
+                self.builder.build_store(storage, empty_strings).unwrap();
+            }
+            _ => (),
+        }
+    }
+
+    ///Joins two strings into a VARYING string that can hold both.
+    pub fn generate_concatenation(
+        &self,
+        left: StringView<'ctx>,
+        right: StringView<'ctx>,
+        max_length: u32,
+    ) -> StructValue<'ctx> {
+        let result_type = self.get_varying_character_type(max_length);
+        let result_ptr = self
+            .builder
+            .build_alloca(result_type, "concatenation")
+            .unwrap();
+        let characters = self.get_varying_characters_pointer(result_ptr);
+
+        self.build_copy_characters(characters, left.characters, left.length);
+        let right_characters = unsafe {
+            self.builder
+                .build_gep(characters, &[left.length], "right_characters")
+                .unwrap()
+        };
+        self.build_copy_characters(right_characters, right.characters, right.length);
+
+        let length = self
+            .builder
+            .build_int_add(left.length, right.length, "concatenated_length")
+            .unwrap();
+        let length_ptr = self
+            .builder
+            .build_struct_gep(result_ptr, LENGTH_FIELD, "varying_length_ptr")
+            .unwrap();
+        self.builder.build_store(length_ptr, length).unwrap();
+
+        self.builder
+            .build_load(result_ptr, "concatenated")
+            .unwrap()
+            .into_struct_value()
+    }
+}
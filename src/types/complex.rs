@@ -0,0 +1,250 @@
+use inkwell::{
+    types::{BasicType, StructType},
+    values::{AnyValue, FloatValue, PointerValue, StructValue},
+    AddressSpace, IntPredicate,
+};
+
+use crate::codegen::codegen::Compiler;
+
+use super::{
+    traits::{get_mathable_type, get_puttable_type, Mathable, Puttable},
+    Type,
+};
+
+///Field 0 of a COMPLEX value is its real part, field 1 its imaginary part.
+pub const REAL_FIELD: u32 = 0;
+pub const IMAGINARY_FIELD: u32 = 1;
+
+///The parts of a FIXED COMPLEX keep this many of their digits after the point.
+pub const FIXED_PART_SCALE: u32 = 15;
+
+///Large enough for both parts printed like real numbers.
+const COMPLEX_STRING_BUFFER_SIZE: u32 = 1024;
+
+///Represents a COMPLEX PL/1 value, a struct of its two parts, each stored
+///like a real number of the type of its parts.
+#[derive(Debug)]
+pub struct ComplexValue<'ctx> {
+    pub value: StructValue<'ctx>,
+    pub _type: Type,
+}
+
+impl<'ctx> ComplexValue<'ctx> {
+    pub fn new(value: StructValue<'ctx>, _type: Type) -> ComplexValue<'ctx> {
+        ComplexValue { value, _type }
+    }
+
+    ///The real and imaginary parts, as doubles.
+    pub fn get_parts<'a>(
+        &self,
+        compiler: &'a Compiler<'a, 'ctx>,
+    ) -> Result<(FloatValue<'ctx>, FloatValue<'ctx>), String> {
+        let real = self.get_part(compiler, REAL_FIELD, "real_part")?;
+        let imaginary = self.get_part(compiler, IMAGINARY_FIELD, "imaginary_part")?;
+        Ok((real, imaginary))
+    }
+
+    fn get_part<'a>(
+        &self,
+        compiler: &'a Compiler<'a, 'ctx>,
+        field: u32,
+        name: &str,
+    ) -> Result<FloatValue<'ctx>, String> {
+        let part = compiler
+            .builder
+            .build_extract_value(self.value, field, name)
+            .map_err(|err| err.to_string())?;
+        let part_value: Box<dyn AnyValue<'ctx> + 'ctx> = Box::new(part);
+        Ok(get_mathable_type(part_value, self._type.get_real_type())?.convert_to_float(compiler))
+    }
+
+    ///A part printed like a real number of the type of the parts.
+    fn get_printable_part<'a>(
+        &self,
+        compiler: &'a Compiler<'a, 'ctx>,
+        field: u32,
+        name: &str,
+    ) -> PointerValue<'ctx> {
+        let part = compiler
+            .builder
+            .build_extract_value(self.value, field, name)
+            .unwrap();
+        let part_value: Box<dyn AnyValue<'ctx> + 'ctx> = Box::new(part);
+        get_puttable_type(part_value, self._type.get_real_type())
+            .unwrap()
+            .get_pointer_to_printable_string(compiler)
+    }
+}
+
+impl<'a, 'ctx> Puttable<'a, 'ctx> for ComplexValue<'ctx> {
+    unsafe fn print_object(&self, compiler: &'a Compiler<'a, 'ctx>) {
+        let string_ptr = self.get_pointer_to_printable_string(compiler);
+
+        compiler
+            .builder
+            .build_call(
+                compiler.get_function("printf").unwrap(),
+                &[string_ptr.into()],
+                "printing_complex_from_puttable",
+            )
+            .unwrap();
+    }
+
+    ///Prints the number as a+bI, each part printed like a real number of the
+    ///type of the parts and the sign of the imaginary part always shown.
+    fn get_pointer_to_printable_string(
+        &self,
+        compiler: &'a Compiler<'a, 'ctx>,
+    ) -> PointerValue<'ctx> {
+        let real = self.get_printable_part(compiler, REAL_FIELD, "real_part");
+        let imaginary = self.get_printable_part(compiler, IMAGINARY_FIELD, "imaginary_part");
+
+        //a part printed without a sign, like a positive FLOAT, gets a "+"
+        let first_character = compiler
+            .builder
+            .build_load(imaginary, "imaginary_first_character")
+            .unwrap()
+            .into_int_value();
+        let i8_type = compiler.context.i8_type();
+        let is_minus = compiler
+            .builder
+            .build_int_compare(
+                IntPredicate::EQ,
+                first_character,
+                i8_type.const_int(b'-' as u64, false),
+                "is_minus",
+            )
+            .unwrap();
+        let is_plus = compiler
+            .builder
+            .build_int_compare(
+                IntPredicate::EQ,
+                first_character,
+                i8_type.const_int(b'+' as u64, false),
+                "is_plus",
+            )
+            .unwrap();
+        let has_sign = compiler
+            .builder
+            .build_or(is_minus, is_plus, "has_sign")
+            .unwrap();
+        let no_sign = compiler
+            .builder
+            .build_global_string_ptr("", "glob_complex_no_sign")
+            .unwrap()
+            .as_pointer_value();
+        let plus_sign = compiler
+            .builder
+            .build_global_string_ptr("+", "glob_complex_plus_sign")
+            .unwrap()
+            .as_pointer_value();
+        let sign = compiler
+            .builder
+            .build_select(has_sign, no_sign, plus_sign, "imaginary_sign")
+            .unwrap();
+
+        let buffer_type = compiler
+            .context
+            .i8_type()
+            .array_type(COMPLEX_STRING_BUFFER_SIZE);
+        let buffer = compiler
+            .builder
+            .build_alloca(buffer_type, "complex_as_string")
+            .unwrap();
+        let buffer = compiler
+            .builder
+            .build_bitcast(
+                buffer,
+                compiler.context.i8_type().ptr_type(AddressSpace::default()),
+                "complex_string_ptr",
+            )
+            .unwrap()
+            .into_pointer_value();
+
+        let format_string = compiler
+            .builder
+            .build_global_string_ptr("%s%s%sI", "glob_complex_format")
+            .unwrap()
+            .as_pointer_value();
+
+        compiler
+            .builder
+            .build_call(
+                compiler.module.get_function("sprintf").unwrap(),
+                &[
+                    buffer.into(),
+                    format_string.into(),
+                    real.into(),
+                    sign.into(),
+                    imaginary.into(),
+                ],
+                "complex_to_string",
+            )
+            .unwrap();
+
+        buffer
+    }
+}
+
+///A complex number taking part in real arithmetic is its real part.
+impl<'a, 'ctx> Mathable<'a, 'ctx> for ComplexValue<'ctx> {
+    fn convert_to_float(&self, compiler: &'a Compiler<'a, 'ctx>) -> FloatValue<'ctx> {
+        self.get_part(compiler, REAL_FIELD, "real_part").unwrap()
+    }
+}
+
+impl<'a, 'ctx> Compiler<'a, 'ctx> {
+    ///The struct the complex type `_type` is stored as.
+    pub fn get_complex_struct_type(&self, _type: Type) -> StructType<'ctx> {
+        let part_type = self.convert_plick_type_to_llvm_basic_type(_type.get_real_type());
+        self.context.struct_type(&[part_type, part_type], false)
+    }
+
+    ///Stores two doubles as the parts of a value of the complex type `_type`.
+    pub unsafe fn create_complex_value(
+        &self,
+        real: FloatValue<'ctx>,
+        imaginary: FloatValue<'ctx>,
+        _type: Type,
+    ) -> Result<StructValue<'ctx>, String> {
+        let part_type = _type.get_real_type();
+        let real = self.create_arithmetic_value(&real, part_type)?;
+        let imaginary = self.create_arithmetic_value(&imaginary, part_type)?;
+
+        let complex_value = self
+            .builder
+            .build_insert_value(
+                self.get_complex_struct_type(_type).get_undef(),
+                self.convert_anyvalue_to_basicvalue(real),
+                REAL_FIELD,
+                "complex_real",
+            )
+            .map_err(|err| err.to_string())?;
+        let complex_value = self
+            .builder
+            .build_insert_value(
+                complex_value,
+                self.convert_anyvalue_to_basicvalue(imaginary),
+                IMAGINARY_FIELD,
+                "complex",
+            )
+            .map_err(|err| err.to_string())?;
+        Ok(complex_value.into_struct_value())
+    }
+
+    ///The real and imaginary parts of a number of type `_type`, as doubles.
+    ///The imaginary part of a real number is zero.
+    pub unsafe fn get_complex_parts(
+        &self,
+        value: Box<dyn AnyValue<'ctx> + 'ctx>,
+        _type: Type,
+    ) -> Result<(FloatValue<'ctx>, FloatValue<'ctx>), String> {
+        if _type.is_complex() {
+            let complex_value =
+                ComplexValue::new(value.as_any_value_enum().into_struct_value(), _type);
+            return complex_value.get_parts(self);
+        }
+        let real = get_mathable_type(value, _type)?.convert_to_float(self);
+        Ok((real, self.context.f64_type().const_zero()))
+    }
+}
@@ -0,0 +1,669 @@
+use inkwell::{
+    values::{AnyValue, PointerValue},
+    IntPredicate,
+};
+
+use crate::{
+    ast::Condition,
+    codegen::{
+        codegen::Compiler,
+        runtime::character::COMPARE_STRINGS_FUNCTION_NAME,
+        utils::{branch_only_if_no_terminator, get_current_function},
+    },
+    lexer::Token,
+    types::{
+        character::StringView,
+        fixed_decimal::MAX_PRECISION,
+        float_decimal::{
+            get_precision_of_binary_digits, resolve_float_precisions, EXTENDED_PRECISION,
+            MAX_BINARY_DIGITS,
+        },
+        Type,
+    },
+};
+
+///The most digits a FIXED DECIMAL can have.
+pub const MAX_FIXED_DECIMAL_PRECISION: u32 = MAX_PRECISION;
+pub const MAX_FIXED_BINARY_PRECISION: u32 = 63;
+///The precision of a FIXED BINARY declared without one.
+pub const DEFAULT_FIXED_BINARY_PRECISION: u32 = 15;
+
+///How many binary digits a decimal digit is worth, times 100.
+const BINARY_DIGITS_PER_DECIMAL_DIGIT: u32 = 332;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Base {
+    Decimal,
+    Binary,
+}
+
+///FIXED(p,q) has p digits, q of them after the point. FLOAT(p) has p digits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scale {
+    Fixed { precision: u32, scale_factor: i32 },
+    Float { precision: u32 },
+}
+
+///The base, scale and precision of an arithmetic operand, which the rules of
+///the language reference work on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Attributes {
+    pub base: Base,
+    pub scale: Scale,
+}
+
+impl Attributes {
+    pub fn fixed(base: Base, precision: u32, scale_factor: i32) -> Attributes {
+        Attributes {
+            base,
+            scale: Scale::Fixed {
+                precision,
+                scale_factor,
+            },
+        }
+    }
+
+    pub fn float(base: Base, precision: u32) -> Attributes {
+        Attributes {
+            base,
+            scale: Scale::Float { precision },
+        }
+    }
+
+    ///The attributes of an operand of `_type` in arithmetic context. A string
+    ///is read as a FIXED DECIMAL(31,0). Other types aren't numbers.
+    pub fn of_type(_type: Type) -> Option<Attributes> {
+        match _type.get_computational_type() {
+            Type::FixedDecimal(precision, scale) => {
+                Some(Attributes::fixed(Base::Decimal, precision, scale as i32))
+            }
+            Type::Float(precision) => Some(Attributes::float(Base::Decimal, precision)),
+            Type::Char(_) | Type::VaryingChar(_) => Some(Attributes::fixed(
+                Base::Decimal,
+                MAX_FIXED_DECIMAL_PRECISION,
+                0,
+            )),
+            _ => None,
+        }
+    }
+
+    ///The type a value of these attributes is held as. Every FIXED is a
+    ///FIXED DECIMAL, with no more digits after the point than it has in all
+    ///and none before it, and FLOAT BINARY(p) is held like the FLOAT DECIMAL
+    ///of its format.
+    pub fn get_type(&self) -> Type {
+        match (self.scale, self.base) {
+            (
+                Scale::Fixed {
+                    precision,
+                    scale_factor,
+                },
+                _base,
+            ) => {
+                let precision = precision.clamp(1, MAX_FIXED_DECIMAL_PRECISION);
+                Type::FixedDecimal(precision, scale_factor.clamp(0, precision as i32) as u32)
+            }
+            (Scale::Float { precision }, Base::Decimal) => Type::Float(precision),
+            (Scale::Float { precision }, Base::Binary) => {
+                Type::Float(get_precision_of_binary_digits(precision))
+            }
+        }
+    }
+
+    ///The same value in `base`, with the precisions the language reference
+    ///gives the converted value.
+    pub fn to_base(&self, base: Base) -> Attributes {
+        if self.base == base {
+            return *self;
+        }
+        match (self.scale, base) {
+            (
+                Scale::Fixed {
+                    precision,
+                    scale_factor,
+                },
+                Base::Binary,
+            ) => Attributes::fixed(
+                base,
+                (decimal_to_binary_digits(precision) + 1).min(MAX_FIXED_BINARY_PRECISION),
+                convert_scale_factor(scale_factor, decimal_to_binary_digits),
+            ),
+            (
+                Scale::Fixed {
+                    precision,
+                    scale_factor,
+                },
+                Base::Decimal,
+            ) => Attributes::fixed(
+                base,
+                (binary_to_decimal_digits(precision) + 1).min(MAX_FIXED_DECIMAL_PRECISION),
+                convert_scale_factor(scale_factor, binary_to_decimal_digits),
+            ),
+            (Scale::Float { precision }, Base::Binary) => Attributes::float(
+                base,
+                decimal_to_binary_digits(precision).min(MAX_BINARY_DIGITS),
+            ),
+            (Scale::Float { precision }, Base::Decimal) => Attributes::float(
+                base,
+                binary_to_decimal_digits(precision).min(EXTENDED_PRECISION),
+            ),
+        }
+    }
+
+    ///The same value as a FLOAT, which keeps the digits of a FIXED.
+    pub fn to_float(&self) -> Attributes {
+        match self.scale {
+            Scale::Fixed { precision, .. } => Attributes::float(self.base, precision),
+            Scale::Float { .. } => *self,
+        }
+    }
+
+    fn get_max_fixed_precision(&self) -> u32 {
+        match self.base {
+            Base::Decimal => MAX_FIXED_DECIMAL_PRECISION,
+            Base::Binary => MAX_FIXED_BINARY_PRECISION,
+        }
+    }
+}
+
+fn decimal_to_binary_digits(digits: u32) -> u32 {
+    (digits * BINARY_DIGITS_PER_DECIMAL_DIGIT).div_ceil(100)
+}
+
+fn binary_to_decimal_digits(digits: u32) -> u32 {
+    (digits * 100).div_ceil(BINARY_DIGITS_PER_DECIMAL_DIGIT)
+}
+
+fn convert_scale_factor(scale_factor: i32, convert_digits: fn(u32) -> u32) -> i32 {
+    convert_digits(scale_factor.unsigned_abs()) as i32 * scale_factor.signum()
+}
+
+///Converts two operands to a common base and scale. An operand with a base
+///or scale different from the other's is converted to BINARY if the other is
+///BINARY, and to FLOAT if the other is FLOAT.
+pub fn get_common_attributes(left: Attributes, right: Attributes) -> (Attributes, Attributes) {
+    let base = match (left.base, right.base) {
+        (Base::Decimal, Base::Decimal) => Base::Decimal,
+        _either_binary => Base::Binary,
+    };
+    let (left, right) = (left.to_base(base), right.to_base(base));
+    match (left.scale, right.scale) {
+        (Scale::Fixed { .. }, Scale::Fixed { .. }) => (left, right),
+        _either_float => (left.to_float(), right.to_float()),
+    }
+}
+
+///The attributes of the result of `operator` on two operands already
+///converted to a common base and scale. A comparison gives 1 or 0, held in
+///the scale the operands were compared in.
+pub fn get_result_attributes(
+    operator: &Token,
+    left: Attributes,
+    right: Attributes,
+) -> Option<Attributes> {
+    if !works_on_numbers(operator) {
+        return None;
+    }
+    let base = left.base;
+    let max_precision = left.get_max_fixed_precision() as i32;
+    let (p1, q1, p2, q2) = match (left.scale, right.scale) {
+        (Scale::Float { precision: p1 }, Scale::Float { precision: p2 }) => {
+            return Some(Attributes::float(base, resolve_float_precisions(p1, p2)));
+        }
+        (
+            Scale::Fixed {
+                precision: p1,
+                scale_factor: q1,
+            },
+            Scale::Fixed {
+                precision: p2,
+                scale_factor: q2,
+            },
+        ) => (p1 as i32, q1, p2 as i32, q2),
+        _mixed_scales => return None,
+    };
+
+    let (precision, scale_factor) = match operator {
+        Token::PLUS | Token::MINUS => {
+            let scale_factor = q1.max(q2);
+            ((p1 - q1).max(p2 - q2) + scale_factor + 1, scale_factor)
+        }
+        Token::MULTIPLY => (p1 + p2 + 1, q1 + q2),
+        Token::DIVIDE => (max_precision, max_precision - p1 + q1 - q2),
+        //PL/I keeps a FIXED raised to a constant integer FIXED, and plick
+        //has room for the most digits any power can have
+        Token::EXPONENT => (max_precision, q1),
+        _comparison => (1, 0),
+    };
+    Some(Attributes::fixed(
+        base,
+        precision.clamp(1, max_precision) as u32,
+        scale_factor,
+    ))
+}
+
+///+, -, *, / and **, which read strings as numbers.
+fn is_arithmetic_operator(operator: &Token) -> bool {
+    matches!(
+        operator,
+        Token::PLUS | Token::MINUS | Token::MULTIPLY | Token::DIVIDE | Token::EXPONENT
+    )
+}
+
+///The arithmetic operators, the comparisons and &.
+fn works_on_numbers(operator: &Token) -> bool {
+    is_arithmetic_operator(operator)
+        || matches!(
+            operator,
+            Token::LESS_THAN | Token::GREATER_THAN | Token::EQ | Token::AND
+        )
+}
+
+///How the operands of a binary expression are converted before the operator
+///works on them, and the type of its result.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Conversion {
+    ///When either operand is FLOAT, both are converted to the FLOAT of the
+    ///one with more digits. FIXED operands keep their precisions, and a
+    ///string is read as a FIXED DECIMAL(31,0).
+    pub left_type: Type,
+    pub right_type: Type,
+    pub result_type: Type,
+}
+
+///The conversions PL/I makes for `left operator right`. None when the
+///operator doesn't work on numbers or an operand isn't one. A string is read
+///as a number when the other operand is one, and when both are given to an
+///arithmetic operator.
+pub fn get_conversion(operator: &Token, left: Type, right: Type) -> Option<Conversion> {
+    //two strings are compared and concatenated as strings
+    if left.is_character() && right.is_character() && !is_arithmetic_operator(operator) {
+        return None;
+    }
+
+    let (left, right) =
+        get_common_attributes(Attributes::of_type(left)?, Attributes::of_type(right)?);
+    let result = get_result_attributes(operator, left, right)?;
+    let (left_type, right_type) = match result.scale {
+        Scale::Fixed { .. } => (left.get_type(), right.get_type()),
+        Scale::Float { .. } => {
+            let common_type = get_result_attributes(&Token::PLUS, left, right)?.get_type();
+            (common_type, common_type)
+        }
+    };
+    Some(Conversion {
+        left_type,
+        right_type,
+        result_type: result.get_type(),
+    })
+}
+
+///The type two numbers are held in when they are worked on together, the
+///type of their sum.
+pub fn get_common_type(first: Type, second: Type) -> Option<Type> {
+    get_conversion(&Token::PLUS, first, second).map(|conversion| conversion.result_type)
+}
+
+///The FIXED DECIMAL a FIXED BINARY(p) is held as, which has room for all its
+///values.
+pub fn get_fixed_binary_type(precision: u32) -> Type {
+    Attributes::fixed(Base::Binary, precision, 0)
+        .to_base(Base::Decimal)
+        .get_type()
+}
+
+impl<'a, 'ctx> Compiler<'a, 'ctx> {
+    ///Converts an operand to the type the conversion rules give it. A string
+    ///is read as the number it holds, blanks around it allowed, and raises
+    ///CONVERSION when it holds anything else. The null string is 0.
+    pub unsafe fn generate_conversion_code(
+        &self,
+        value: Box<dyn AnyValue<'ctx> + 'ctx>,
+        from: Type,
+        to: Type,
+    ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, String> {
+        if !from.is_character() {
+            return self.convert_arithmetic_value(value, from, to);
+        }
+
+        let string_view = self.generate_string_view(value, from)?;
+        let end_pointer = self
+            .builder
+            .build_alloca(self.get_pointer_type(), "number_end")
+            .unwrap();
+        let number = self
+            .builder
+            .build_call(
+                self.module.get_function("strtod").unwrap(),
+                &[string_view.characters.into(), end_pointer.into()],
+                "string_as_number",
+            )
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_float_value();
+        let end = self
+            .builder
+            .build_load(end_pointer, "number_end")
+            .unwrap()
+            .into_pointer_value();
+        self.generate_conversion_check(&string_view, end);
+        self.create_arithmetic_value(&number, to)
+    }
+
+    ///Raises CONVERSION unless strtod read a number from the string,
+    ///stopping at `end`, and there are only blanks after it.
+    unsafe fn generate_conversion_check(
+        &self,
+        string_view: &StringView<'ctx>,
+        end: PointerValue<'ctx>,
+    ) {
+        let builder = self.builder;
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+
+        let start = builder
+            .build_ptr_to_int(string_view.characters, i64_type, "number_start")
+            .unwrap();
+        let end_address = builder
+            .build_ptr_to_int(end, i64_type, "number_end")
+            .unwrap();
+        let read_length = builder
+            .build_int_sub(end_address, start, "read_length")
+            .unwrap();
+        let read_length = builder
+            .build_int_truncate(read_length, i32_type, "read_length")
+            .unwrap();
+
+        let is_null_string = builder
+            .build_int_compare(
+                IntPredicate::EQ,
+                string_view.length,
+                i32_type.const_zero(),
+                "is_null_string",
+            )
+            .unwrap();
+        let has_read = builder
+            .build_int_compare(
+                IntPredicate::SGT,
+                read_length,
+                i32_type.const_zero(),
+                "has_read",
+            )
+            .unwrap();
+        let is_in_string = builder
+            .build_int_compare(
+                IntPredicate::SLE,
+                read_length,
+                string_view.length,
+                "is_in_string",
+            )
+            .unwrap();
+        let rest_length = builder
+            .build_int_sub(string_view.length, read_length, "rest_length")
+            .unwrap();
+        let rest_length = builder
+            .build_select(
+                is_in_string,
+                rest_length,
+                i32_type.const_zero(),
+                "rest_length",
+            )
+            .unwrap()
+            .into_int_value();
+
+        //the rest is compared with the null string, which is padded with blanks
+        let rest_comparison = builder
+            .build_call(
+                self.module
+                    .get_function(COMPARE_STRINGS_FUNCTION_NAME)
+                    .unwrap(),
+                &[
+                    end.into(),
+                    rest_length.into(),
+                    end.into(),
+                    i32_type.const_zero().into(),
+                ],
+                "rest_comparison",
+            )
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_int_value();
+        let is_rest_blank = builder
+            .build_int_compare(
+                IntPredicate::EQ,
+                rest_comparison,
+                i32_type.const_zero(),
+                "is_rest_blank",
+            )
+            .unwrap();
+
+        let holds_number = builder
+            .build_or(has_read, is_null_string, "holds_number")
+            .unwrap();
+        let holds_number = builder
+            .build_and(holds_number, is_in_string, "holds_number")
+            .unwrap();
+        let holds_number = builder
+            .build_and(holds_number, is_rest_blank, "holds_number")
+            .unwrap();
+
+        let current_function = get_current_function(self);
+        let conversion_block = self
+            .context
+            .append_basic_block(current_function, "conversion");
+        let after_check_block = self
+            .context
+            .append_basic_block(current_function, "after_conversion_check");
+        builder
+            .build_conditional_branch(holds_number, after_check_block, conversion_block)
+            .unwrap();
+
+        builder.position_at_end(conversion_block);
+        self.generate_raise_code(Condition::Conversion, None);
+        branch_only_if_no_terminator(self, after_check_block);
+
+        builder.position_at_end(after_check_block);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::resolve_types;
+
+    fn decimal(precision: u32, scale_factor: i32) -> Attributes {
+        Attributes::fixed(Base::Decimal, precision, scale_factor)
+    }
+
+    #[test]
+    fn fixed_and_float_give_float() {
+        assert_eq!(
+            Some(Conversion {
+                left_type: Type::Float(6),
+                right_type: Type::Float(6),
+                result_type: Type::Float(6),
+            }),
+            get_conversion(&Token::PLUS, Type::DEFAULT_FIXED, Type::Float(6))
+        );
+        assert_eq!(
+            Some(Type::Float(33)),
+            get_common_type(Type::Float(33), Type::DEFAULT_FIXED)
+        );
+        assert_eq!(
+            Ok(Type::Float(6)),
+            resolve_types(&Type::DEFAULT_FIXED, &Type::Float(6))
+        );
+
+        //the FIXED keeps its digits as a FLOAT
+        let (fixed, float) =
+            get_common_attributes(decimal(7, 2), Attributes::float(Base::Decimal, 6));
+        assert_eq!(Attributes::float(Base::Decimal, 7), fixed);
+        assert_eq!(
+            Some(Attributes::float(Base::Decimal, 7)),
+            get_result_attributes(&Token::MULTIPLY, fixed, float)
+        );
+        assert_eq!(
+            Some(Type::Float(15)),
+            get_common_type(Type::FixedDecimal(15, 2), Type::Float(6))
+        );
+    }
+
+    #[test]
+    fn fixed_results_follow_the_precision_rules() {
+        //FIXED DECIMAL(5,2) + FIXED DECIMAL(7,1) is FIXED DECIMAL(9,2)
+        assert_eq!(
+            Some(decimal(9, 2)),
+            get_result_attributes(&Token::PLUS, decimal(5, 2), decimal(7, 1))
+        );
+        //FIXED DECIMAL(5,2) * FIXED DECIMAL(7,1) is FIXED DECIMAL(13,3)
+        assert_eq!(
+            Some(decimal(13, 3)),
+            get_result_attributes(&Token::MULTIPLY, decimal(5, 2), decimal(7, 1))
+        );
+        //FIXED DECIMAL(5,2) / FIXED DECIMAL(7,1) is FIXED DECIMAL(31,27)
+        assert_eq!(
+            Some(decimal(31, 27)),
+            get_result_attributes(&Token::DIVIDE, decimal(5, 2), decimal(7, 1))
+        );
+        //results never have more digits than can be held
+        assert_eq!(
+            Some(decimal(31, 10)),
+            get_result_attributes(&Token::MULTIPLY, decimal(20, 5), decimal(20, 5))
+        );
+        assert_eq!(
+            Some(decimal(1, 0)),
+            get_result_attributes(&Token::LESS_THAN, decimal(5, 2), decimal(7, 1))
+        );
+        assert_eq!(
+            None,
+            get_result_attributes(&Token::CONCATENATE, decimal(5, 2), decimal(7, 1))
+        );
+    }
+
+    #[test]
+    fn fixed_operands_keep_their_precisions() {
+        assert_eq!(
+            Some(Conversion {
+                left_type: Type::FixedDecimal(5, 2),
+                right_type: Type::FixedDecimal(5, 2),
+                result_type: Type::FixedDecimal(6, 2),
+            }),
+            get_conversion(
+                &Token::PLUS,
+                Type::FixedDecimal(5, 2),
+                Type::FixedDecimal(5, 2)
+            )
+        );
+        assert_eq!(
+            Some(Type::FixedDecimal(11, 3)),
+            get_conversion(
+                &Token::MULTIPLY,
+                Type::FixedDecimal(5, 2),
+                Type::FixedDecimal(5, 1)
+            )
+            .map(|conversion| conversion.result_type)
+        );
+        //no more digits after the point than can be held
+        assert_eq!(
+            Some(Type::FixedDecimal(31, 31)),
+            get_conversion(
+                &Token::MULTIPLY,
+                Type::FixedDecimal(20, 20),
+                Type::FixedDecimal(20, 20)
+            )
+            .map(|conversion| conversion.result_type)
+        );
+        assert_eq!(
+            Some(Type::TRUTH_VALUE),
+            get_conversion(
+                &Token::LESS_THAN,
+                Type::FixedDecimal(5, 2),
+                Type::FixedDecimal(9, 0)
+            )
+            .map(|conversion| conversion.result_type)
+        );
+    }
+
+    #[test]
+    fn fixed_binary_is_held_with_room_for_its_values() {
+        assert_eq!(Type::FixedDecimal(6, 0), get_fixed_binary_type(15));
+        assert_eq!(Type::FixedDecimal(11, 0), get_fixed_binary_type(31));
+    }
+
+    #[test]
+    fn decimal_is_converted_to_binary() {
+        let (left, right) =
+            get_common_attributes(decimal(5, 2), Attributes::fixed(Base::Binary, 15, 0));
+        assert_eq!(Attributes::fixed(Base::Binary, 18, 7), left);
+        assert_eq!(Attributes::fixed(Base::Binary, 15, 0), right);
+
+        let (left, right) = get_common_attributes(
+            Attributes::float(Base::Decimal, 6),
+            Attributes::fixed(Base::Binary, 31, 0),
+        );
+        assert_eq!(Attributes::float(Base::Binary, 20), left);
+        assert_eq!(Attributes::float(Base::Binary, 31), right);
+        assert_eq!(Type::Float(6), left.get_type());
+
+        //the result has the binary digits of the wider operand
+        let wide = Attributes::float(Base::Binary, 64);
+        assert_eq!(Some(wide), get_result_attributes(&Token::PLUS, left, wide));
+
+        assert_eq!(
+            Attributes::float(Base::Decimal, 16),
+            Attributes::float(Base::Binary, 53).to_base(Base::Decimal)
+        );
+        assert_eq!(
+            decimal(11, 0),
+            Attributes::fixed(Base::Binary, 31, 0).to_base(Base::Decimal)
+        );
+    }
+
+    #[test]
+    fn strings_are_numbers_in_arithmetic_context() {
+        assert_eq!(
+            Some(Conversion {
+                left_type: Type::FixedDecimal(MAX_FIXED_DECIMAL_PRECISION, 0),
+                right_type: Type::DEFAULT_FIXED,
+                result_type: Type::FixedDecimal(MAX_FIXED_DECIMAL_PRECISION, 0),
+            }),
+            get_conversion(&Token::PLUS, Type::Char(3), Type::DEFAULT_FIXED)
+        );
+        //a string has every digit a FIXED DECIMAL can have
+        assert_eq!(
+            Some(Type::Float(MAX_FIXED_DECIMAL_PRECISION)),
+            get_common_type(Type::Float(6), Type::VaryingChar(10))
+        );
+        assert_eq!(
+            Some(Type::FixedDecimal(MAX_FIXED_DECIMAL_PRECISION, 0)),
+            get_conversion(&Token::MINUS, Type::Char(1), Type::Char(2))
+                .map(|conversion| conversion.result_type)
+        );
+
+        //two strings are compared as strings
+        assert_eq!(
+            None,
+            get_conversion(&Token::LESS_THAN, Type::Char(1), Type::Char(2))
+        );
+        assert_eq!(
+            None,
+            get_conversion(&Token::CONCATENATE, Type::Char(1), Type::DEFAULT_FIXED)
+        );
+    }
+
+    #[test]
+    fn only_numbers_are_converted() {
+        assert_eq!(
+            None,
+            get_conversion(&Token::EQ, Type::Pointer, Type::DEFAULT_FIXED)
+        );
+        assert_eq!(
+            None,
+            get_conversion(&Token::PLUS, Type::FixedComplex, Type::DEFAULT_FIXED)
+        );
+    }
+}
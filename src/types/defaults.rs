@@ -0,0 +1,83 @@
+use super::{infer_pli_type_via_name, Type};
+
+///The names a DEFAULT statement applies to: RANGE(A:H) is every name
+///starting with a letter from A to H, RANGE(*) is every name.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DefaultRange {
+    Letters(char, char),
+    All,
+}
+
+impl DefaultRange {
+    pub fn contains(&self, name: &str) -> bool {
+        match self {
+            DefaultRange::All => true,
+            DefaultRange::Letters(first, last) => match name.chars().next() {
+                Some(letter) => {
+                    let letter = letter.to_ascii_uppercase();
+                    first.to_ascii_uppercase() <= letter && letter <= last.to_ascii_uppercase()
+                }
+                None => false,
+            },
+        }
+    }
+}
+
+///DEFAULT RANGE(A:H) FLOAT;
+#[derive(Debug, Clone, PartialEq)]
+pub struct DefaultRule {
+    pub range: DefaultRange,
+    pub _type: Type,
+}
+
+///The DEFAULT statements in effect in a block, including the ones of the
+///blocks containing it. Names no rule applies to get the language defaults.
+#[derive(Debug, Clone, Default)]
+pub struct DefaultRules {
+    rules: Vec<DefaultRule>,
+}
+
+impl DefaultRules {
+    pub fn new() -> Self {
+        DefaultRules { rules: vec![] }
+    }
+
+    pub fn add(&mut self, rule: DefaultRule) {
+        self.rules.push(rule);
+    }
+
+    ///The type a name gets when it isn't declared with one. The rules added
+    ///last, which are the ones of the innermost block, are tried first.
+    pub fn get_type(&self, name: &str) -> Type {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.range.contains(name))
+            .map(|rule| rule._type)
+            .unwrap_or_else(|| infer_pli_type_via_name(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_override_the_language_defaults() {
+        let mut rules = DefaultRules::new();
+        assert_eq!(Type::FixedDecimal(6, 0), rules.get_type("MEAN"));
+        assert_eq!(Type::Float(6), rules.get_type("AVERAGE"));
+
+        rules.add(DefaultRule {
+            range: DefaultRange::All,
+            _type: Type::DEFAULT_FIXED,
+        });
+        rules.add(DefaultRule {
+            range: DefaultRange::Letters('A', 'H'),
+            _type: Type::Float(16),
+        });
+        assert_eq!(Type::Float(16), rules.get_type("AVERAGE"));
+        assert_eq!(Type::DEFAULT_FIXED, rules.get_type("TOTAL"));
+        assert_eq!(Type::DEFAULT_FIXED, rules.get_type("MEAN"));
+    }
+}
@@ -0,0 +1,160 @@
+use std::error::Error;
+
+use inkwell::{
+    types::FunctionType,
+    values::{AnyValue, FunctionValue, PointerValue},
+    AddressSpace,
+};
+
+use crate::{
+    ast,
+    codegen::{
+        codegen::Compiler, named_value::NamedValue, named_value_store::NamedValueStore, utils,
+    },
+    error::get_error,
+    types::Type,
+};
+
+///FIXED BINARY and CHAR(*) are only for OPTIONS(C) entries.
+pub fn get_descriptor_type(
+    name: &str,
+    descriptor: ast::Descriptor,
+) -> Result<Type, Box<dyn Error>> {
+    match descriptor {
+        ast::Descriptor::Data(_type) => Ok(_type),
+        _other => Err(get_error(&["28", name]).into()),
+    }
+}
+
+impl<'a, 'ctx> Compiler<'a, 'ctx> {
+    ///The parameters and result of the procedures declared by an ENTRY.
+    ///Without RETURNS(...), the result has the default type of the name.
+    pub fn get_entry_prototype(
+        &self,
+        name: &str,
+        entry: &ast::Entry,
+    ) -> Result<(Vec<ast::PrototypeArgument>, Type), Box<dyn Error>> {
+        let mut parameters: Vec<ast::PrototypeArgument> = vec![];
+        for (index, descriptor) in entry.parameters.iter().enumerate() {
+            parameters.push(ast::PrototypeArgument {
+                name: format!("PARAMETER{}", index + 1),
+                _type: get_descriptor_type(name, *descriptor)?,
+            });
+        }
+        let return_type = match entry.returns {
+            Some(descriptor) => get_descriptor_type(name, descriptor)?,
+            None => self.get_default_type(name),
+        };
+        Ok((parameters, return_type))
+    }
+
+    ///DCL F ENTRY(FLOAT) RETURNS(FLOAT) VARIABLE; holds no procedure until
+    ///it is given one.
+    pub fn declare_entry_variable(
+        &self,
+        name: &str,
+        entry: ast::Entry,
+        storage_class: ast::StorageClass,
+        dimension: Option<u32>,
+    ) -> Result<PointerValue<'ctx>, Box<dyn Error>> {
+        if storage_class != ast::StorageClass::Automatic {
+            return Err(get_error(&["36", name]).into());
+        }
+        //the parameters have to be ones a procedure can take
+        self.get_entry_prototype(name, &entry)?;
+
+        let storage_type = self.get_storage_type(Type::Entry, dimension);
+        let variable_ptr = self.create_entry_block_alloca_of_type(
+            name,
+            &utils::get_current_function(self),
+            storage_type,
+        );
+        self.builder
+            .build_store(variable_ptr, storage_type.const_zero())
+            .unwrap();
+        self.named_values.insert(
+            NamedValue::new(name.to_string(), Type::Entry, variable_ptr)
+                .with_dimension(dimension)
+                .with_entry(Some(entry)),
+        );
+        Ok(variable_ptr)
+    }
+
+    ///A procedure named where a value is expected, as in F = SQUARE; or
+    ///SORT(COMPARE);, is an ENTRY constant.
+    pub fn get_entry_constant(&self, name: &str) -> Option<FunctionValue<'ctx>> {
+        if self.named_values.try_get(name).is_some() || self.is_label_constant(name) {
+            return None;
+        }
+        self.module.get_function(name)
+    }
+
+    ///The address of the procedure, which is what an ENTRY variable holds.
+    pub fn generate_entry_constant_code(
+        &self,
+        function: FunctionValue<'ctx>,
+    ) -> Box<dyn AnyValue<'ctx> + 'ctx> {
+        let address = self
+            .builder
+            .build_pointer_cast(
+                function.as_global_value().as_pointer_value(),
+                self.get_pointer_type(),
+                "entry",
+            )
+            .unwrap();
+        Box::new(address)
+    }
+
+    ///The types of the parameters and result of the procedures held by an
+    ///ENTRY variable.
+    pub fn get_entry_variable_prototype(
+        &self,
+        named_value: &NamedValue<'ctx>,
+    ) -> Result<(Vec<Type>, Type), String> {
+        let entry = named_value
+            .entry
+            .as_ref()
+            .ok_or(get_error(&["37", &named_value.name]))?;
+        let (parameters, return_type) = self
+            .get_entry_prototype(&named_value.name, entry)
+            .map_err(|err| err.to_string())?;
+        let parameter_types: Vec<Type> = parameters
+            .into_iter()
+            .map(|parameter| parameter._type)
+            .collect();
+        Ok((parameter_types, return_type))
+    }
+
+    ///The type the procedures held by an ENTRY variable are called with.
+    pub fn get_entry_variable_function_type(
+        &self,
+        named_value: &NamedValue<'ctx>,
+    ) -> Result<FunctionType<'ctx>, String> {
+        let (parameter_types, return_type) = self.get_entry_variable_prototype(named_value)?;
+        Ok(self.get_function_type(&parameter_types, return_type))
+    }
+
+    ///The address of the procedure an ENTRY variable holds, as a pointer to
+    ///a function of `function_type`.
+    pub unsafe fn generate_entry_variable_callee_code(
+        &self,
+        named_value: &NamedValue<'ctx>,
+        function_type: FunctionType<'ctx>,
+    ) -> Result<PointerValue<'ctx>, String> {
+        let variable_ptr = self.get_variable_storage(named_value)?;
+        let address = self
+            .builder
+            .build_load(variable_ptr, &named_value.name)
+            .map_err(|err| format!("error loading an ENTRY variable: {}", err))?
+            .into_pointer_value();
+        let callee = self
+            .builder
+            .build_pointer_cast(
+                address,
+                function_type.ptr_type(AddressSpace::default()),
+                "callee",
+            )
+            .map_err(|err| format!("error calling an ENTRY variable: {}", err))?;
+        Ok(callee)
+    }
+}
@@ -1,21 +1,10 @@
 use inkwell::{
-    module::Linkage,
-    types::{ArrayType, BasicMetadataTypeEnum, BasicType, BasicTypeEnum, FunctionType, StructType},
-    values::{
-        AnyValue, ArrayValue, BasicMetadataValueEnum, BasicValue, BasicValueEnum, FloatValue,
-        IntValue, PointerValue, StructValue,
-    },
-    AddressSpace,
+    types::{FloatType, IntType},
+    values::{BasicMetadataValueEnum, FloatValue, IntValue, PointerValue},
+    AddressSpace, IntPredicate,
 };
 
-use crate::codegen::{
-    codegen::Compiler,
-    utils::{
-        self, build_pow, get_current_function, get_nth_digit_of_a_float, print_float_value,
-        print_int_value,
-    },
-};
-use regex;
+use crate::codegen::codegen::Compiler;
 
 use super::{
     traits::{Mathable, Puttable},
@@ -23,184 +12,191 @@ use super::{
 };
 
 mod float_to_fixed;
+pub mod packed;
+pub mod scaled;
+
+///The most digits a FIXED DECIMAL can have, N in the language reference.
+pub const MAX_PRECISION: u32 = 31;
+
+///The precision of a FIXED DECIMAL declared without one.
+pub const DEFAULT_PRECISION: u32 = 5;
+
+///Values are printed with at least this many digits before the point, and
+///this many after it unless they have more.
+const PRINTED_BEFORE_DIGIT_COUNT: u32 = 16;
+const PRINTED_AFTER_DIGIT_COUNT: u32 = 15;
 
-const BEFORE_DIGIT_COUNT: u32 = 16;
-const AFTER_DIGIT_COUNT: u32 = 15;
+///Wide enough for every value with MAX_PRECISION digits, and for the
+///products the arithmetic works out on the way.
+pub const FIXED_BITS: u32 = 128;
 
 const PLUS_ASCII_CODE: u64 = 43;
 const MINUS_ASCII_CODE: u64 = 45;
 
-const CLOSE_PAREN_ASCII_CODE: u64 = 41;
-const OPEN_PAREN_ASCII_CODE: u64 = 40;
-const PERIOD_ASCII_CODE: u64 = 46;
+///A sign, the digits, the point, the parentheses and the NUL terminator.
+const FIXED_STRING_BUFFER_SIZE: u32 = 2 * MAX_PRECISION + 5;
 
-const ASCII_OFFSET: u64 = 48;
-///Represents a Fixed PL/1 value.
-///Currently can only represent a fixed decimal with
-///16 digits before the decimal, 15 after.
+///Represents a FIXED DECIMAL(p,q) PL/1 value, with p digits, q of them
+///after the point.
+///It is stored as an integer counting in units of its last digit after the
+///point, so 1.5 in a FIXED DECIMAL(5,2) is 150. Digits are only worked out
+///to print it.
 #[derive(Debug)]
 pub struct FixedValue<'ctx> {
-    pub value: StructValue<'ctx>,
+    pub value: IntValue<'ctx>,
+    pub precision: u32,
+    pub scale: u32,
 }
 
 impl<'ctx> FixedValue<'ctx> {
-    pub fn new(value: StructValue<'ctx>) -> FixedValue<'ctx> {
-        FixedValue { value }
+    pub fn new(value: IntValue<'ctx>, precision: u32, scale: u32) -> FixedValue<'ctx> {
+        FixedValue {
+            value,
+            precision,
+            scale,
+        }
     }
-}
 
-impl<'ctx> Into<StructValue<'ctx>> for FixedValue<'ctx> {
-    fn into(self) -> StructValue<'ctx> {
-        self.value
+    pub fn get_type(&self) -> Type {
+        Type::FixedDecimal(self.precision, self.scale)
     }
 }
 
-impl<'ctx> From<StructValue<'ctx>> for FixedValue<'ctx> {
-    fn from(value: StructValue<'ctx>) -> Self {
-        let fixed_value = FixedValue { value };
-
-        fixed_value
+impl<'ctx> Into<IntValue<'ctx>> for FixedValue<'ctx> {
+    fn into(self) -> IntValue<'ctx> {
+        self.value
     }
 }
 
 impl<'a, 'ctx> Puttable<'a, 'ctx> for FixedValue<'ctx> {
     unsafe fn print_object(&self, compiler: &'a Compiler<'a, 'ctx>) {
-        let print_func = compiler.get_function("print_fixed_decimal").unwrap();
-
-        let struc = self.value;
-
-        let name = "print_puttable";
+        let string_ptr = self.get_pointer_to_printable_string(compiler);
 
         compiler
             .builder
-            .build_call(print_func, &[struc.into()], name)
+            .build_call(
+                compiler.get_function("printf").unwrap(),
+                &[string_ptr.into()],
+                "print_puttable",
+            )
             .unwrap();
     }
 
+    ///Prints the number like +(0000000000000003.000000000000000), with more
+    ///digits before the point when it has more than 16 of them, and more
+    ///after it when it has more than 15.
     fn get_pointer_to_printable_string(
         &self,
         compiler: &'a Compiler<'a, 'ctx>,
     ) -> PointerValue<'ctx> {
-        let const_int_zero = compiler.context.i8_type().const_zero();
-
-        let mut fd_to_float_converter = FixedDecimalToFloatBuilder::new(compiler, &self.value);
-
-        fd_to_float_converter.alloca_struct_value();
-        let sign_bit_value = fd_to_float_converter.get_sign_bit_ascii_value();
-        let ptr_to_before_array = fd_to_float_converter.get_before_ptr();
-
-        let zero_intval = compiler.context.i8_type().const_zero();
-
-        let mut before_int_values: Vec<IntValue<'ctx>> =
-            vec![zero_intval; BEFORE_DIGIT_COUNT as usize];
+        let builder = compiler.builder;
+        let i64_type = compiler.context.i64_type();
+        let i32_type = compiler.context.i32_type();
+        let fixed_type = self.value.get_type();
 
-        let mut before_int_values: Vec<IntValue<'ctx>> = vec![];
-
-        for mut i in 0..BEFORE_DIGIT_COUNT as usize {
-            let old_i = i;
-            i = (BEFORE_DIGIT_COUNT as usize - 1) - i;
-
-            let current_digit_index = compiler.context.i8_type().const_int(i as u64, false);
-
-            unsafe {
-                let digit_int_val = fd_to_float_converter
-                    .load_digit_from_digit_array(current_digit_index, ptr_to_before_array);
-
-                //now we take the array value, build a GEP for the inner array
-                let ascii_offset = compiler.context.i8_type().const_int(ASCII_OFFSET, false);
+        let is_negative = builder
+            .build_int_compare(
+                IntPredicate::SLT,
+                self.value,
+                fixed_type.const_zero(),
+                "is_negative",
+            )
+            .unwrap();
+        let negated = builder.build_int_neg(self.value, "negated_fixed").unwrap();
+        let magnitude = builder
+            .build_select(is_negative, negated, self.value, "magnitude")
+            .unwrap()
+            .into_int_value();
+        let sign_character = builder
+            .build_select(
+                is_negative,
+                i32_type.const_int(MINUS_ASCII_CODE, false),
+                i32_type.const_int(PLUS_ASCII_CODE, false),
+                "sign_character",
+            )
+            .unwrap();
 
-                before_int_values.push(
-                    compiler
-                        .builder
-                        .build_int_add(digit_int_val, ascii_offset, "ascioffset")
-                        .unwrap(),
-                );
+        //printf prints 64 bits at most, so each part is split into pieces
+        //of at most 16 digits
+        let split = |value: IntValue<'ctx>, low_digit_count: u32, name: &str| {
+            let divisor = const_power_of_ten(fixed_type, low_digit_count);
+            let high = builder
+                .build_int_unsigned_div(value, divisor, name)
+                .unwrap();
+            let low = builder
+                .build_int_unsigned_rem(value, divisor, name)
+                .unwrap();
+            (high, low)
+        };
+        let as_u64 = |value: IntValue<'ctx>| {
+            builder
+                .build_int_truncate(value, i64_type, "printed_digits")
+                .unwrap()
+        };
+
+        let (before_point, after_point) = split(magnitude, self.scale, "point");
+        let (before_high, before_low) =
+            split(before_point, PRINTED_BEFORE_DIGIT_COUNT, "before_point");
+        let mut arguments: Vec<BasicMetadataValueEnum<'ctx>> = vec![
+            sign_character.into(),
+            as_u64(before_high).into(),
+            as_u64(before_low).into(),
+        ];
+        let format_string = match self.scale.checked_sub(PRINTED_AFTER_DIGIT_COUNT) {
+            Some(extra_digit_count @ 1..) => {
+                let (after_high, after_low) = split(after_point, extra_digit_count, "after_point");
+                arguments.push(as_u64(after_high).into());
+                arguments.push(as_u64(after_low).into());
+                format!(
+                    "%c(%.0llu%0{}llu.%0{}llu%0{}llu)",
+                    PRINTED_BEFORE_DIGIT_COUNT, PRINTED_AFTER_DIGIT_COUNT, extra_digit_count
+                )
             }
-        }
-
-        before_int_values.splice(
-            0..0,
-            [
-                sign_bit_value,
-                compiler
-                    .context
-                    .i8_type()
-                    .const_int(OPEN_PAREN_ASCII_CODE, false),
-            ],
-        );
-
-        before_int_values.push(
-            compiler
-                .context
-                .i8_type()
-                .const_int(PERIOD_ASCII_CODE, false),
-        );
-
-        let ptr_to_after_array = fd_to_float_converter.get_after_ptr();
-
-        for i in 0..AFTER_DIGIT_COUNT as usize {
-            let current_digit_index = compiler.context.i8_type().const_int(i as u64, false);
-            unsafe {
-                let digit_int_val = fd_to_float_converter
-                    .load_digit_from_digit_array(current_digit_index, ptr_to_after_array);
-
-                //now we take the array value, build a GEP for the inner array
-                let digit_as_ascii_offset =
-                    compiler.context.i8_type().const_int(ASCII_OFFSET, false);
-                before_int_values.push(
-                    compiler
-                        .builder
-                        .build_int_add(digit_int_val, digit_as_ascii_offset, "ascioffset")
-                        .unwrap(),
-                );
+            _ => {
+                let padding =
+                    const_power_of_ten(fixed_type, PRINTED_AFTER_DIGIT_COUNT - self.scale);
+                let after_point = builder
+                    .build_int_mul(after_point, padding, "after_point")
+                    .unwrap();
+                arguments.push(as_u64(after_point).into());
+                format!(
+                    "%c(%.0llu%0{}llu.%0{}llu)",
+                    PRINTED_BEFORE_DIGIT_COUNT, PRINTED_AFTER_DIGIT_COUNT
+                )
             }
-        }
+        };
 
-        before_int_values.push(
-            compiler
-                .context
-                .i8_type()
-                .const_int(CLOSE_PAREN_ASCII_CODE, false),
-        );
-        before_int_values.push(compiler.context.i8_type().const_zero());
-        //HARD DECK
-        let _typ = compiler
+        let buffer_type = compiler
             .context
             .i8_type()
-            .array_type(before_int_values.len() as u32);
-        let aloca = compiler
-            .builder
-            .build_alloca(_typ, "fd_before_as_string")
-            .unwrap();
-
-        //let aloca = compiler.builder.build_alloca(compiler.context.i8_type().array_type(3), "fd_before_as_string").unwrap();
-        compiler
-            .builder
-            .build_store(aloca, _typ.const_zero())
-            .unwrap();
+            .array_type(FIXED_STRING_BUFFER_SIZE);
+        let buffer = builder.build_alloca(buffer_type, "fd_as_string").unwrap();
+        let buffer = builder
+            .build_bitcast(
+                buffer,
+                compiler.context.i8_type().ptr_type(AddressSpace::default()),
+                "fd_string_ptr",
+            )
+            .unwrap()
+            .into_pointer_value();
 
-        unsafe {
-            for (index, intval) in before_int_values.iter().enumerate() {
-                let current_index_as_intval =
-                    compiler.context.i8_type().const_int(index as u64, false);
-                let elemptr = compiler
-                    .builder
-                    .build_gep(aloca, &[const_int_zero, current_index_as_intval], "lol")
-                    .unwrap();
+        let format_string = builder
+            .build_global_string_ptr(&format_string, "glob_fixed_format")
+            .unwrap()
+            .as_pointer_value();
 
-                compiler
-                    .builder
-                    .build_store(elemptr, *intval)
-                    .expect("Intval wasnt an intval!");
-            }
-        }
+        let mut sprintf_arguments: Vec<BasicMetadataValueEnum<'ctx>> =
+            vec![buffer.into(), format_string.into()];
+        sprintf_arguments.append(&mut arguments);
+        builder
+            .build_call(
+                compiler.module.get_function("sprintf").unwrap(),
+                &sprintf_arguments,
+                "fixed_to_string",
+            )
+            .unwrap();
 
-        let aloca = compiler.builder.build_bitcast(
-            aloca,
-            compiler.context.i8_type().ptr_type(AddressSpace::default()),
-            "mybitcast",
-        );
-        aloca.unwrap().into_pointer_value()
+        buffer
     }
 }
 
@@ -208,505 +204,203 @@ impl<'a, 'ctx> Mathable<'a, 'ctx> for FixedValue<'ctx> {
     fn convert_to_float(&self, compiler: &'a Compiler<'a, 'ctx>) -> FloatValue<'ctx> {
         unsafe { compiler.fixed_decimal_to_float(self) }
     }
-}
 
-pub fn get_fixed_type<'ctx>(ctx: &'ctx inkwell::context::Context) -> StructType<'ctx> {
-    let mut field_types: Vec<BasicTypeEnum> = vec![];
-    //previously before decimal array
-    let left_of_decimal_array = ctx.i8_type().array_type(BEFORE_DIGIT_COUNT);
-    //previously after decimal array
-    let right_of_decimal_array = ctx.i8_type().array_type(AFTER_DIGIT_COUNT);
-    let is_negative_type = ctx.bool_type();
-    field_types.push(is_negative_type.as_basic_type_enum());
-    field_types.push(left_of_decimal_array.as_basic_type_enum());
-    field_types.push(right_of_decimal_array.as_basic_type_enum());
-
-    let packed = false;
-    ctx.struct_type(&field_types, packed)
+    fn convert_to_float_of_type(
+        &self,
+        compiler: &'a Compiler<'a, 'ctx>,
+        float_type: FloatType<'ctx>,
+    ) -> FloatValue<'ctx> {
+        unsafe { compiler.fixed_decimal_to_float_of_type(self, float_type) }
+    }
 }
 
-pub fn create_empty_fixed<'a, 'ctx>(
-    ctx: &'ctx inkwell::context::Context,
-    _type: &'a StructType<'ctx>,
-) -> StructValue<'ctx> {
-    let mut values: Vec<BasicValueEnum> = vec![];
-
-    let is_negative_value = ctx.bool_type().const_int(0, false);
-    let left_of_decimal_value = ctx.i8_type().array_type(BEFORE_DIGIT_COUNT).const_zero();
-    let right_of_decimal_value = ctx.i8_type().array_type(AFTER_DIGIT_COUNT).const_zero();
-    values.push(is_negative_value.into());
-    values.push(left_of_decimal_value.into());
-    values.push(right_of_decimal_value.into());
-
-    let struc = _type.const_named_struct(&values);
-
-    struc
+pub fn get_fixed_type<'ctx>(ctx: &'ctx inkwell::context::Context) -> IntType<'ctx> {
+    ctx.custom_width_int_type(FIXED_BITS)
 }
 
-///Coverts a f64 into a FixedValue
-pub fn generate_fixed_decimal_code<'ctx>(
-    ctx: &'ctx inkwell::context::Context,
-    _type: StructType<'ctx>,
-    value: f64,
-) -> FixedValue<'ctx> {
-    let mut values: Vec<BasicValueEnum> = vec![];
-    let is_float_negative = match value < 0.0 {
-        true => 1,
-        false => 0,
-    };
-
-    let is_negative_value = ctx.bool_type().const_int(is_float_negative, false);
-
-    //now we gotta extract the number before the decimal as a positive integer
-    let before_decimal_side = value as u64;
-
-    let mut before_decimal_digits: Vec<IntValue> = convert_num_to_arr(before_decimal_side as i64)
-        .iter()
-        .map(|w| -> IntValue<'ctx> { ctx.i8_type().const_int(*w as u64, false) })
-        .collect();
-
-    before_decimal_digits.resize(
-        BEFORE_DIGIT_COUNT as usize,
-        ctx.i8_type().const_int(0, false),
-    );
-
-    let before_dec_side = before_decimal_side as f64;
-
-    let length_of_after_decimal_side = num_digits_after_point_in_f64(value);
-
-    let after_decimal_side =
-        (value - before_decimal_side as f64) * 10_f64.powf(length_of_after_decimal_side as f64);
-
-    let after_decimal_side = after_decimal_side.round();
-    let mut after_decimal_digits: Vec<IntValue> = convert_num_to_arr(after_decimal_side as i64)
-        .iter()
-        .map(|w| -> IntValue<'ctx> { ctx.i8_type().const_int(*w as u64, false) })
-        .collect();
-
-    after_decimal_digits.reverse();
-    after_decimal_digits.resize(
-        AFTER_DIGIT_COUNT as usize,
-        ctx.i8_type().const_int(0, false),
-    );
-
-    let before_decimal_value = ctx.i8_type().const_array(&before_decimal_digits[..]);
-    let after_decimal_value = ctx.i8_type().const_array(&after_decimal_digits[..]);
-    values.push(is_negative_value.into());
-    values.push(before_decimal_value.into());
-    values.push(after_decimal_value.into());
-
-    let new_fixed_value = FixedValue::new(_type.const_named_struct(&values));
-
-    new_fixed_value
+///10 to the power of `exponent`, the stored value of 1 in a FIXED DECIMAL
+///with `exponent` digits after the point.
+pub fn power_of_ten(exponent: u32) -> u128 {
+    10_u128.pow(exponent)
 }
 
-struct FixedDecimalToFloatBuilder<'a, 'b, 'ctx> {
-    compiler: &'a Compiler<'a, 'ctx>,
-    fd: &'b StructValue<'ctx>,
-    pointer_to_struct_value: Option<PointerValue<'ctx>>,
+pub fn const_power_of_ten<'ctx>(fixed_type: IntType<'ctx>, exponent: u32) -> IntValue<'ctx> {
+    const_fixed(fixed_type, power_of_ten(exponent) as i128)
 }
 
-impl<'a, 'b, 'ctx> FixedDecimalToFloatBuilder<'a, 'b, 'ctx> {
-    fn new(compiler: &'a Compiler<'a, 'ctx>, fd: &'b StructValue<'ctx>) -> Self {
-        FixedDecimalToFloatBuilder {
-            compiler,
-            fd,
-            pointer_to_struct_value: None,
-        }
-    }
-    fn alloca_struct_value(&mut self) -> PointerValue<'ctx> {
-        let pointer_to_struct_value: PointerValue<'ctx> = self
-            .compiler
-            .builder
-            .build_alloca(self.fd.get_type(), "tmpalloca")
-            .unwrap();
-        let my_fd: StructValue<'ctx> = self.fd.clone();
-        self.compiler
-            .builder
-            .build_store(pointer_to_struct_value, my_fd)
-            .unwrap();
-        self.pointer_to_struct_value = Some(pointer_to_struct_value);
-
-        pointer_to_struct_value
-    }
-    fn get_sign_bit_ascii_value(&self) -> IntValue<'ctx> {
-        let sign_bit_value = self.get_sign_bit_value();
-        let builder = self.compiler.builder;
-        let function = get_current_function(self.compiler);
-
-        let then_block = self
-            .compiler
-            .context
-            .append_basic_block(function, "if_negative");
-        let else_block = self
-            .compiler
-            .context
-            .append_basic_block(function, "if_positive");
-        let merge_block = self.compiler.context.append_basic_block(function, "merge");
-
-        let rhs = self.compiler.context.bool_type().const_int(1, false);
-        let comparison = builder
-            .build_int_compare(
-                inkwell::IntPredicate::EQ,
-                sign_bit_value,
-                rhs,
-                "is_negative",
-            )
-            .unwrap();
-
-        builder
-            .build_conditional_branch(comparison, then_block, else_block)
-            .unwrap();
-
-        builder.position_at_end(then_block);
-        let negative_value = self
-            .compiler
-            .context
-            .i8_type()
-            .const_int(MINUS_ASCII_CODE, false);
-        builder.build_unconditional_branch(merge_block).unwrap();
-
-        builder.position_at_end(else_block);
-        let positive_value = self
-            .compiler
-            .context
-            .i8_type()
-            .const_int(PLUS_ASCII_CODE, false);
-        builder.build_unconditional_branch(merge_block).unwrap();
-
-        builder.position_at_end(merge_block);
-
-        let x = builder
-            .build_phi(self.compiler.context.i8_type(), "sign_character")
-            .unwrap();
-
-        x.add_incoming(&[(&negative_value.as_basic_value_enum(), then_block)]);
-        x.add_incoming(&[(&positive_value.as_basic_value_enum(), else_block)]);
-
-        x.as_basic_value().into_int_value()
-    }
-    fn get_sign_bit_value(&self) -> IntValue<'ctx> {
-        let sign_bit_ptr = self
-            .compiler
-            .builder
-            .build_struct_gep(self.pointer_to_struct_value.unwrap(), 0, "get_sign_bit")
-            .unwrap();
-
-        let sign_bit_val = self
-            .compiler
-            .builder
-            .build_load(sign_bit_ptr, "sign_bit")
-            .unwrap()
-            .into_int_value();
-
-        sign_bit_val
-    }
-    fn get_before_ptr(&self) -> PointerValue<'ctx> {
-        self.compiler
-            .builder
-            .build_struct_gep(self.pointer_to_struct_value.unwrap(), 1, "get_before")
-            .unwrap()
-    }
-    fn get_after_ptr(&self) -> PointerValue<'ctx> {
-        self.compiler
-            .builder
-            .build_struct_gep(self.pointer_to_struct_value.unwrap(), 2, "get_after")
-            .unwrap()
-    }
-    fn get_before_array(&self) -> ArrayValue<'ctx> {
-        let before_ptr = self.get_before_ptr();
-
-        self.compiler
-            .builder
-            .build_load(before_ptr, "load_before_arr")
-            .unwrap()
-            .into_array_value()
-    }
-
-    unsafe fn load_digit_from_digit_array(
-        &self,
-        index: IntValue<'ctx>,
-        ptr_to_array: PointerValue<'ctx>,
-    ) -> IntValue<'ctx> {
-        let zero_intval = self.compiler.context.i8_type().const_zero();
-        let pointer_to_digit_array_value = self
-            .compiler
-            .builder
-            .build_gep(ptr_to_array, &[zero_intval, index], "load_digit")
-            .unwrap();
-
-        let digit_int_val = self
-            .compiler
-            .builder
-            .build_load(pointer_to_digit_array_value, "diggit")
-            .unwrap()
-            .into_int_value();
-
-        digit_int_val
-    }
-
-    unsafe fn sum_up_before_digits_into_a_float(
-        &self,
-        before_int_values: Vec<IntValue<'ctx>>,
-        after_int_values: Vec<IntValue<'ctx>>,
-    ) -> FloatValue<'ctx> {
-        let f64_type = self.compiler.context.f64_type();
-        let result_float: FloatValue<'ctx>;
-        let result_float_alloca = self
-            .compiler
-            .builder
-            .build_alloca(self.compiler.context.f64_type(), "store_result")
-            .unwrap();
-
-        self.compiler
-            .builder
-            .build_store(result_float_alloca, f64_type.const_zero())
-            .unwrap();
-
-        let ten_float = self.compiler.context.f64_type().const_float(10.0);
-        for (index, digit_value) in before_int_values.iter().enumerate() {
-            let my_float = index as f64;
-
-            let rhs = f64_type.const_float(my_float);
-            let digit_log = build_pow(self.compiler, ten_float, rhs);
-
-            let digit_scoped: IntValue<'ctx> = *digit_value;
-
-            let digit_value_as_float = self
-                .compiler
-                .builder
-                .build_unsigned_int_to_float(digit_scoped, f64_type, "lol")
-                .unwrap();
-
-            let digit_pure_value = self
-                .compiler
-                .builder
-                .build_float_mul(digit_value_as_float, digit_log, "calc_digit_value")
-                .unwrap();
-
-            let old_value = self
-                .compiler
-                .builder
-                .build_load(result_float_alloca, "load_old_val")
-                .unwrap()
-                .into_float_value();
-            let new_value = self
-                .compiler
-                .builder
-                .build_float_add(old_value, digit_pure_value, "lol")
-                .unwrap();
-
-            self.compiler
-                .builder
-                .build_store(result_float_alloca, new_value)
-                .unwrap();
-        }
-
-        for (index, digit_value) in after_int_values.iter().enumerate() {
-            let index_as_float = (index as f64 + 1.0) * -1.0;
-
-            let index = f64_type.const_float(index_as_float);
-            let digit_log = build_pow(self.compiler, ten_float, index);
-
-            let digit_scoped: IntValue<'ctx> = *digit_value;
-
-            let digit_value_as_float = self
-                .compiler
-                .builder
-                .build_unsigned_int_to_float(digit_scoped, f64_type, "decimal_digit_as_float")
-                .unwrap();
-
-            let digit_pure_value = self
-                .compiler
-                .builder
-                .build_float_mul(digit_value_as_float, digit_log, "calc_digit_value")
-                .unwrap();
+///A constant of the fixed type, which can be wider than a u64.
+pub fn const_fixed<'ctx>(fixed_type: IntType<'ctx>, scaled: i128) -> IntValue<'ctx> {
+    let bits = scaled as u128;
+    fixed_type.const_int_arbitrary_precision(&[bits as u64, (bits >> 64) as u64])
+}
 
-            let old_value = self
-                .compiler
-                .builder
-                .build_load(result_float_alloca, "load_old_val")
-                .unwrap()
-                .into_float_value();
-            let new_value = self
-                .compiler
-                .builder
-                .build_float_add(old_value, digit_pure_value, "lol")
-                .unwrap();
+///Whether `written` is a constant like 123.45, of digits and at most one
+///point.
+pub fn is_decimal_constant(written: &str) -> bool {
+    let mut parts = written.split('.');
+    let before_point = parts.next().unwrap_or_default();
+    let after_point = parts.next().unwrap_or_default();
+    parts.next().is_none()
+        && !(before_point.is_empty() && after_point.is_empty())
+        && before_point
+            .chars()
+            .chain(after_point.chars())
+            .all(|character| character.is_ascii_digit())
+}
 
-            self.compiler
-                .builder
-                .build_store(result_float_alloca, new_value)
-                .unwrap();
-        }
+///The FIXED DECIMAL(p,q) of a constant like 123.45, whose q is the number of
+///digits written after its point and p the number written in all.
+pub fn get_constant_type(digits: &str) -> Type {
+    let digits = digits.trim_start_matches('-');
+    let (before_point, after_point) = digits.split_once('.').unwrap_or((digits, ""));
 
-        result_float = self
-            .compiler
-            .builder
-            .build_load(result_float_alloca, "load_result")
-            .unwrap()
-            .into_float_value();
-
-        result_float
-    }
+    let scale = (after_point.len() as u32).min(MAX_PRECISION);
+    let before_digit_count = before_point.trim_start_matches('0').len() as u32;
+    let precision = (before_digit_count + scale).clamp(1, MAX_PRECISION);
+    Type::FixedDecimal(precision, scale)
 }
 
-///Helper function
-fn convert_num_to_arr(value: i64) -> Vec<u8> {
-    let mut value = value;
-    let mut before_decimal: Vec<u8> = vec![];
-
-    if value < 0 {
-        value *= -1;
+///The digits of a constant with the other sign.
+pub fn negate_digits(digits: &str) -> String {
+    match digits.strip_prefix('-') {
+        Some(positive) => positive.to_string(),
+        None => format!("-{}", digits),
     }
-
-    loop {
-        let current_digit: u8 = (value % 10) as u8;
-        before_decimal.push(current_digit);
-
-        value = value / 10;
-
-        if value == 0 {
-            break;
-        }
-    }
-
-    before_decimal
 }
 
-pub fn add_fd_print_function<'a, 'ctx>(compiler: &mut Compiler<'a, 'ctx>) {
-    let mut param_types: Vec<BasicMetadataTypeEnum> = vec![];
-
-    let fd_type = compiler.type_module.fixed_type;
-
-    param_types.push(fd_type.into());
-
-    let ty: FunctionType<'ctx> = compiler
-        .context
-        .void_type()
-        .fn_type(&param_types[..], false);
-
-    let print_fd_func =
-        compiler
-            .module
-            .add_function("print_fixed_decimal", ty, Some(Linkage::Internal));
-
-    let current_bb = compiler.builder.get_insert_block().unwrap();
-
-    let func_bb = compiler.context.append_basic_block(print_fd_func, "entry");
-
-    compiler.builder.position_at_end(func_bb);
-
-    let fixedvalue_param = func_bb.get_parent().unwrap().get_first_param().unwrap();
-
-    let alloca_value = compiler
-        .builder
-        .build_alloca(fixedvalue_param.get_type(), "num_to_print")
-        .unwrap();
-
-    compiler
-        .builder
-        .build_store(alloca_value, fixedvalue_param.into_struct_value())
-        .unwrap();
-
-    let mut args: Vec<BasicMetadataValueEnum> = vec![];
-    let fd = FixedValue::from(fixedvalue_param.into_struct_value());
-    let ptr = fd.get_pointer_to_printable_string(compiler);
-
-    args.push(ptr.into());
-
-    let func = compiler.module.get_function("printf").unwrap();
-    compiler.builder.build_call(func, &args[..], "lol").unwrap();
-    compiler.builder.build_return(None).unwrap();
-
-    compiler.builder.position_at_end(current_bb);
+///Coverts a constant written like -123.45 into a FixedValue with `scale`
+///digits after the point, or None when it has more digits before the point
+///than the FixedValue holds.
+pub fn generate_fixed_decimal_code<'ctx>(
+    _type: IntType<'ctx>,
+    digits: &str,
+    precision: u32,
+    scale: u32,
+) -> Option<FixedValue<'ctx>> {
+    let scaled = scale_digits(digits, precision, scale)?;
+    Some(FixedValue::new(
+        const_fixed(_type, scaled),
+        precision,
+        scale,
+    ))
 }
 
-fn num_digits_after_point_in_f64(num: f64) -> usize {
-    let as_str = num.to_string();
-    let parts: Vec<&str> = as_str.split(".").collect();
+///The scaled value of the constant `digits` with `scale` digits after the
+///point, worked out from its decimal digits so that it is exact. The digits
+///after the last one that can be held are cut off, and None is given when
+///there are more than `precision - scale` before the point.
+fn scale_digits(digits: &str, precision: u32, scale: u32) -> Option<i128> {
+    let (is_negative, magnitude) = match digits.strip_prefix('-') {
+        Some(magnitude) => (true, magnitude),
+        None => (false, digits),
+    };
+    let (before_point, after_point) = magnitude.split_once('.').unwrap_or((magnitude, ""));
+
+    let before_point = before_point.trim_start_matches('0');
+    if before_point.len() > (precision - scale) as usize {
+        return None;
+    }
+    let digits = before_point.chars().chain(
+        after_point
+            .chars()
+            .chain(std::iter::repeat('0'))
+            .take(scale as usize),
+    );
+    let magnitude = digits
+        .filter_map(|digit| digit.to_digit(10))
+        .fold(0_i128, |scaled, digit| scaled * 10 + digit as i128);
 
-    if parts.len() == 2 {
-        parts[1].len()
-    } else {
-        0
+    match is_negative {
+        true => Some(-magnitude),
+        false => Some(magnitude),
     }
 }
 
+#[cfg(test)]
 mod tests {
-    use inkwell::types::{BasicType, BasicTypeEnum};
-    use regex::Regex;
-
-    use crate::types::fixed_decimal::create_empty_fixed;
-
-    use super::{generate_fixed_decimal_code, get_fixed_type};
+    use super::{
+        get_constant_type, get_fixed_type, is_decimal_constant, negate_digits, scale_digits,
+        FIXED_BITS,
+    };
+    use crate::types::Type;
 
     #[test]
-    fn test_structy() {
+    fn fixed_type_is_scaled_integer() {
         let ctx = inkwell::context::Context::create();
 
-        let fixed_decimal_type = get_fixed_type(&ctx);
-
-        let fixed_decimal = create_empty_fixed(&ctx, &fixed_decimal_type);
-
-        dbg!(fixed_decimal);
+        assert_eq!(FIXED_BITS, get_fixed_type(&ctx).get_bit_width());
     }
 
-    #[test]
-    fn test_full_structy() {
-        let ctx = inkwell::context::Context::create();
-
-        let _type = get_fixed_type(&ctx);
-        let myval = generate_fixed_decimal_code(&ctx, _type, 421.88888);
-
-        dbg!(myval);
-    }
     #[test]
     fn fixed_decimal_point_num() {
-        let ctx = inkwell::context::Context::create();
-
-        let _type = get_fixed_type(&ctx);
-        let const_fixedvalue = generate_fixed_decimal_code(&ctx, _type, 421.52);
-
-        let debug_message = format!("{:?}", dbg!(const_fixedvalue.value));
-
-        let reg = Regex::new(r"c\\.*?,").unwrap();
-
-        let iter = reg.find_iter(&debug_message);
-
-        let mut digits_as_strings: Vec<&str> = vec![];
-
-        for mat in iter {
-            digits_as_strings.push(mat.as_str());
-        }
-
-        assert_eq!(digits_as_strings[0],"c\\\"\\\\01\\\\02\\\\04\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\",",
-                   "Before decimal digits are not the expected value!");
-        assert_eq!(digits_as_strings[1],"c\\\"\\\\05\\\\02\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\" }\",",
-                   "After decimal digits are not the expected value!");
+        assert_eq!(Some(42_152), scale_digits("421.52", 5, 2));
+        assert_eq!(
+            Some(421_520_000_000_000_000),
+            scale_digits("421.52", 31, 15)
+        );
     }
 
     #[test]
     fn fixed_decimal_point_num_small() {
-        let ctx = inkwell::context::Context::create();
-
-        let _type = get_fixed_type(&ctx);
-        let const_fixedvalue = generate_fixed_decimal_code(&ctx, _type, 0.3);
-
-        let debug_message = format!("{:?}", dbg!(const_fixedvalue.value));
+        assert_eq!(Some(3), scale_digits("0.3", 1, 1));
+        assert_eq!(Some(50_000_000_000_000), scale_digits("0.05", 15, 15));
+        assert_eq!(Some(-15), scale_digits("-1.5", 2, 1));
+    }
 
-        let reg = Regex::new(r"c\\.*?,").unwrap();
+    #[test]
+    fn fixed_decimal_cuts_digits_after_the_scale() {
+        assert_eq!(Some(1234), scale_digits("12.345", 4, 2));
+        assert_eq!(Some(12), scale_digits("12.9", 2, 0));
+        assert_eq!(Some(1), scale_digits("0.0000000000000019", 15, 15));
+    }
 
-        let iter = reg.find_iter(&debug_message);
+    #[test]
+    fn fixed_decimal_overflows_when_digits_before_the_point_cant_be_held() {
+        assert_eq!(None, scale_digits("123.45", 4, 2));
+        assert_eq!(Some(-9999), scale_digits("-99.99", 4, 2));
+        //2^60 is 1152921504606846976, 19 digits
+        assert_eq!(None, scale_digits(&2_f64.powi(60).to_string(), 31, 15));
+        assert_eq!(
+            Some(1_152_921_504_606_846_976),
+            scale_digits(&2_f64.powi(60).to_string(), 19, 0)
+        );
+    }
 
-        let mut digits_as_strings: Vec<&str> = vec![];
+    #[test]
+    fn constants_have_the_digits_they_are_written_with() {
+        assert_eq!(Type::FixedDecimal(5, 2), get_constant_type("123.45"));
+        assert_eq!(Type::FixedDecimal(2, 2), get_constant_type("0.05"));
+        assert_eq!(Type::FixedDecimal(1, 0), get_constant_type("0"));
+        assert_eq!(Type::FixedDecimal(3, 0), get_constant_type("-100"));
+        assert_eq!(Type::FixedDecimal(3, 2), get_constant_type("2.50"));
+    }
 
-        for mat in iter {
-            digits_as_strings.push(mat.as_str());
-        }
+    #[test]
+    fn constants_keep_all_their_digits() {
+        //more digits than a f64 holds
+        assert_eq!(
+            Some(1_234_567_890_123_456_789_012_345_678_901),
+            scale_digits("1234567890123456789012345678901", 31, 0)
+        );
+        assert_eq!(
+            Some(-123_456_789_012_345_678_901),
+            scale_digits("-123456.789012345678901", 21, 15)
+        );
+        assert_eq!(
+            Type::FixedDecimal(31, 15),
+            get_constant_type("1234567890123456.789012345678901")
+        );
+    }
 
-        //only one element in the vec because of zeroinitializer
-        assert_eq!(digits_as_strings[0],"c\\\"\\\\03\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\\00\\\" }\",",
-                   "After decimal digits are not the expected value!");
+    #[test]
+    fn only_digits_with_a_point_are_decimal_constants() {
+        assert!(is_decimal_constant("123.45"));
+        assert!(is_decimal_constant(".5"));
+        assert!(is_decimal_constant("7"));
+        assert!(!is_decimal_constant("1E5"));
+        assert!(!is_decimal_constant("1.2.3"));
+        assert!(!is_decimal_constant("."));
+        assert_eq!("5.1", negate_digits("-5.1"));
+        assert_eq!("-5.1", negate_digits("5.1"));
     }
 }
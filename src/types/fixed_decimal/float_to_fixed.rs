@@ -1,319 +1,107 @@
-use inkwell::{
-    basic_block::BasicBlock,
-    values::{BasicValue, FloatValue, IntValue, PointerValue, StructValue},
-};
+use inkwell::{types::FloatType, values::FloatValue, FloatPredicate};
 
-use crate::{
-    codegen::{
-        codegen::Compiler,
-        utils::{self, get_nth_digit_of_a_float, get_nth_digit_of_a_float_neg, print_float_value},
-    },
-    types::fixed_decimal::{FixedDecimalToFloatBuilder, BEFORE_DIGIT_COUNT},
-};
+use super::{power_of_ten, FixedValue};
+use crate::codegen::codegen::Compiler;
 
-use super::{create_empty_fixed, FixedValue, AFTER_DIGIT_COUNT};
+///How far from its value a float is moved, away from zero, before it is cut
+///to a FIXED DECIMAL, as a part of the value.
+const FLOAT_ERROR_ALLOWANCE: f64 = 4.0 * f64::EPSILON;
 
 impl<'a, 'ctx> Compiler<'a, 'ctx> {
     pub unsafe fn fixed_decimal_to_float(
         &self,
         fixed_value: &FixedValue<'ctx>,
     ) -> FloatValue<'ctx> {
-        log::info!("Converting fixed value {:?} into a decimal!", fixed_value);
-
-        let fixed_value_as_struct_value: StructValue<'ctx> = fixed_value.value;
-
-        let mut fd_to_float_converter =
-            FixedDecimalToFloatBuilder::new(self, &fixed_value_as_struct_value);
-
-        fd_to_float_converter.alloca_struct_value();
-
-        let sign_bit_value = fd_to_float_converter.get_sign_bit_value();
-
-        let before_int_values = self.get_before_digits_as_vec(&fd_to_float_converter);
-        let after_int_values = self.get_after_digits_as_vec(&fd_to_float_converter);
-
-        let result_floatval = fd_to_float_converter
-            .sum_up_before_digits_into_a_float(before_int_values, after_int_values);
-        let conditional = sign_bit_value;
+        self.fixed_decimal_to_float_of_type(fixed_value, self.context.f64_type())
+    }
 
-        let mut negative_float = result_floatval.get_type().const_float(0.0);
-        let mut positive_float = result_floatval.get_type().const_float(0.0);
-        let then_block = || {
-            let rhs = result_floatval.get_type().const_float(-1.0);
-            negative_float = self
-                .builder
-                .build_float_mul(result_floatval, rhs, "make_float_neg")
-                .unwrap();
-        };
-        let else_block = || {
-            let rhs = result_floatval.get_type().const_float(1.0);
-            positive_float = self
-                .builder
-                .build_float_mul(result_floatval, rhs, "keep_float_positive")
-                .unwrap();
-        };
-        let blocks = self.build_if_else_control_flow(conditional, then_block, else_block);
+    pub unsafe fn fixed_decimal_to_float_of_type(
+        &self,
+        fixed_value: &FixedValue<'ctx>,
+        float_type: FloatType<'ctx>,
+    ) -> FloatValue<'ctx> {
+        log::info!("Converting fixed value {:?} into a decimal!", fixed_value);
 
-        let phi = self
+        let scaled_float = self
             .builder
-            .build_phi(result_floatval.get_type(), "result_of_negation")
+            .build_signed_int_to_float(fixed_value.value, float_type, "fixed_as_float")
             .unwrap();
-        phi.add_incoming(&[(&negative_float.as_basic_value_enum(), blocks.then_block)]);
-        phi.add_incoming(&[(&positive_float.as_basic_value_enum(), blocks.else_block)]);
-
-        let result = phi.as_basic_value().into_float_value();
-
-        result
+        self.builder
+            .build_float_div(
+                scaled_float,
+                float_type.const_float(power_of_ten(fixed_value.scale) as f64),
+                "unscaled_float",
+            )
+            .unwrap()
     }
 
+    ///The FIXED DECIMAL(precision, scale) of a float. The digits after the
+    ///last one that can be held are cut off, once the float is made up for
+    ///the last bits it can be off by, so a double just below a decimal, like
+    ///0.1 + 0.2, is held as that decimal. FIXEDOVERFLOW is raised when there
+    ///are too many digits before the point, and for NaN.
     pub unsafe fn float_value_to_fixed_decimal(
         &'a self,
         float_value: FloatValue<'ctx>,
+        precision: u32,
+        scale: u32,
     ) -> FixedValue<'ctx> {
-        let zero_intval = self.context.i8_type().const_zero();
-        let fixed_value: StructValue<'ctx> =
-            create_empty_fixed(self.context, &self.type_module.fixed_type);
-        let allocd_fd: PointerValue<'ctx> = self
-            .builder
-            .build_alloca(fixed_value.get_type(), "allocate_fd_for_fixed_conv")
-            .unwrap();
-
-        self.builder.build_store(allocd_fd, fixed_value).unwrap();
-        self.builder
-            .build_alloca(float_value.get_type(), "allocate_float_for_fixed_conv")
-            .unwrap();
-
-        let rhs: FloatValue<'ctx> = float_value.get_type().const_zero();
-
-        let conditional = self
+        let float_type = float_value.get_type();
+        let scaled_float = self
             .builder
-            .build_float_compare(
-                inkwell::FloatPredicate::OLT,
+            .build_float_mul(
                 float_value,
-                rhs,
-                "is_float_neg",
+                float_type.const_float(power_of_ten(scale) as f64),
+                "scaled_float",
             )
             .unwrap();
-
-        //start of sign bit loading
-        let current_func = utils::get_current_function(self);
-        let then_block = self.context.append_basic_block(current_func, "then_block");
-        let else_block = self.context.append_basic_block(current_func, "else_block");
-        let cont_block = self.context.append_basic_block(current_func, "cont_block");
-        self.builder
-            .build_conditional_branch(conditional, then_block, else_block)
-            .expect("This should be fine!");
-
-        self.builder.position_at_end(then_block);
-
-        let sign_ptr = self
-            .builder
-            .build_struct_gep(allocd_fd, 0, "get_sign_bit_ptr")
-            .unwrap();
-        self.builder
-            .build_store(sign_ptr, self.context.bool_type().const_int(1, false))
-            .unwrap();
-        let rhs = float_value.get_type().const_float(-1.0);
-        let float_value_neg_to_pos = self
+        let nudged_float = self
             .builder
-            .build_float_mul(float_value, rhs, "turn_negative_float_into_positive")
-            .unwrap();
-        self.builder.build_unconditional_branch(cont_block).unwrap();
-
-        self.builder.position_at_end(else_block);
-        let sign_ptr = self
-            .builder
-            .build_struct_gep(allocd_fd, 0, "get_sign_bit_ptr")
-            .unwrap();
-        self.builder
-            .build_store(sign_ptr, self.context.bool_type().const_int(0, false))
-            .unwrap();
-        let rhs = float_value.get_type().const_float(1.0);
-        let float_value_pos_to_pos = self
-            .builder
-            .build_float_mul(float_value, rhs, "keep_float_positive")
+            .build_float_mul(
+                scaled_float,
+                float_type.const_float(1.0 + FLOAT_ERROR_ALLOWANCE),
+                "nudged_float",
+            )
             .unwrap();
-        self.builder.build_unconditional_branch(cont_block).unwrap();
 
-        self.builder.position_at_end(cont_block);
-        //end of sign bit loading
-        let phi = self
+        //the float is compared before it is converted, since converting a
+        //float the integer can't hold gives no value at all
+        let limit = float_type.const_float(power_of_ten(precision) as f64);
+        let negative_limit = float_type.const_float(-(power_of_ten(precision) as f64));
+        let is_too_large = self
             .builder
-            .build_phi(float_value_pos_to_pos.get_type(), "float_after_maybe_neg")
+            .build_float_compare(FloatPredicate::UGE, nudged_float, limit, "is_too_large")
             .unwrap();
-        phi.add_incoming(&[(&float_value_neg_to_pos, then_block)]);
-        phi.add_incoming(&[(&float_value_pos_to_pos, else_block)]);
-
-        let float_value = phi.as_basic_value().into_float_value();
-        //start of calculating fv digits
-        let bdcl = self
-            .context
-            .append_basic_block(current_func, "before_digits_calculation_loop");
-        self.builder.build_unconditional_branch(bdcl).unwrap();
-        self.builder.position_at_end(bdcl);
-
-        //counter
-
-        let before_array_ptr = self
+        let is_too_small = self
             .builder
-            .build_struct_gep(allocd_fd, 1, "get_before_array_ptr")
+            .build_float_compare(
+                FloatPredicate::ULE,
+                nudged_float,
+                negative_limit,
+                "is_too_small",
+            )
             .unwrap();
-        let after_array_ptr = self
+        let is_overflow = self
             .builder
-            .build_struct_gep(allocd_fd, 2, "get_after_array_ptr")
-            .unwrap();
-        for i in 0..BEFORE_DIGIT_COUNT {
-            let index_as_intval = self.context.i8_type().const_int(i as u64, false);
-            let digit_to_load_up = get_nth_digit_of_a_float(self, &float_value, index_as_intval);
-            let digi_as_i8 = self
-                .builder
-                .build_int_cast(digit_to_load_up, self.context.i8_type(), "turn_i64_into_i8")
-                .unwrap();
-
-            let current_digit_ptr = self
-                .builder
-                .build_gep(
-                    before_array_ptr,
-                    &[zero_intval, index_as_intval],
-                    "getdigiforconv",
-                )
-                .unwrap();
-
-            self.builder
-                .build_store(current_digit_ptr, digi_as_i8)
-                .unwrap();
-        }
-
-        for i in 0..AFTER_DIGIT_COUNT {
-            let index_as_intval = self.context.i8_type().const_int(i as u64, false);
-
-            let negative_index = self.context.i8_type().const_int(i as u64 + 1, true);
-
-            let digit_to_load_up = get_nth_digit_of_a_float_neg(self, &float_value, negative_index);
-            let digi_as_i8 = self
-                .builder
-                .build_int_cast(digit_to_load_up, self.context.i8_type(), "turn_i64_into_i8")
-                .unwrap();
-
-            let current_digit_ptr = self
-                .builder
-                .build_gep(
-                    after_array_ptr,
-                    &[zero_intval, index_as_intval],
-                    "getdigiforconv",
-                )
-                .unwrap();
-
-            self.builder
-                .build_store(current_digit_ptr, digi_as_i8)
-                .unwrap();
-        }
-
-        let after_before_loop = self
-            .context
-            .append_basic_block(current_func, "after_digits_loop");
-        self.builder
-            .build_unconditional_branch(after_before_loop)
+            .build_or(is_too_large, is_too_small, "is_overflow")
             .unwrap();
-        self.builder.position_at_end(after_before_loop);
-        //end of calculating fv digits
-
-        let fd_struct: StructValue<'ctx> = self
+        self.generate_fixedoverflow_raise(is_overflow);
+        let held_float = self
             .builder
-            .build_load(allocd_fd, "loading_final_fd")
+            .build_select(
+                is_overflow,
+                float_type.const_zero(),
+                nudged_float,
+                "held_float",
+            )
             .unwrap()
-            .into_struct_value();
-        FixedValue::new(fd_struct)
-    }
-
-    unsafe fn get_after_digits_as_vec<'b>(
-        &'a self,
-        fd_to_float_converter: &FixedDecimalToFloatBuilder<'a, 'b, 'ctx>,
-    ) -> Vec<IntValue<'ctx>> {
-        let ptr_to_after_array = fd_to_float_converter.get_after_ptr();
-        let zero_intval = self.context.i8_type().const_zero();
-
-        let mut after_int_values: Vec<IntValue<'ctx>> =
-            vec![zero_intval; AFTER_DIGIT_COUNT as usize];
-
-        for i in 0..AFTER_DIGIT_COUNT as usize {
-            let current_digit_index = self.context.i8_type().const_int(i as u64, false);
-
-            let digit_int_val = fd_to_float_converter
-                .load_digit_from_digit_array(current_digit_index, ptr_to_after_array);
-
-            after_int_values[i] = digit_int_val;
-        }
-
-        after_int_values
-    }
-
-    unsafe fn get_before_digits_as_vec<'b>(
-        &'a self,
-        fd_to_float_converter: &FixedDecimalToFloatBuilder<'a, 'b, 'ctx>,
-    ) -> Vec<IntValue<'ctx>> {
-        let ptr_to_before_array = fd_to_float_converter.get_before_ptr();
-        let zero_intval = self.context.i8_type().const_zero();
-
-        let mut before_int_values: Vec<IntValue<'ctx>> =
-            vec![zero_intval; BEFORE_DIGIT_COUNT as usize];
-
-        for i in 0..BEFORE_DIGIT_COUNT as usize {
-            let current_digit_index = self.context.i8_type().const_int(i as u64, false);
-
-            let digit_int_val = fd_to_float_converter
-                .load_digit_from_digit_array(current_digit_index, ptr_to_before_array);
-
-            before_int_values[i] = digit_int_val;
-        }
-
-        before_int_values
-    }
-
-    fn build_if_else_control_flow<T, Y>(
-        &self,
-        conditional: IntValue<'ctx>,
-        mut then_block_gen: T,
-        mut else_block_gen: Y,
-    ) -> ThenElseBlocks<'ctx>
-    where
-        T: FnMut(),
-        Y: FnMut(),
-    {
-        let current_func = utils::get_current_function(self);
-        let then_block = self.context.append_basic_block(current_func, "then_block");
-        let else_block = self.context.append_basic_block(current_func, "else_block");
-        let merge_block = self.context.append_basic_block(current_func, "merge_block");
+            .into_float_value();
 
-        self.builder
-            .build_conditional_branch(conditional, then_block, else_block)
-            .expect("This should be fine!");
-
-        self.builder.position_at_end(then_block);
-        then_block_gen();
-        self.builder
-            .build_unconditional_branch(merge_block)
-            .unwrap();
-
-        self.builder.position_at_end(else_block);
-        else_block_gen();
-        self.builder
-            .build_unconditional_branch(merge_block)
+        //converting to an integer cuts toward zero
+        let scaled = self
+            .builder
+            .build_float_to_signed_int(held_float, self.type_module.fixed_type, "float_as_fixed")
             .unwrap();
-
-        self.builder.position_at_end(merge_block);
-
-        let x: ThenElseBlocks<'ctx> = ThenElseBlocks {
-            then_block,
-            else_block,
-            merge_block,
-        };
-        x
+        FixedValue::new(scaled, precision, scale)
     }
 }
-
-struct ThenElseBlocks<'ctx> {
-    then_block: BasicBlock<'ctx>,
-    else_block: BasicBlock<'ctx>,
-    merge_block: BasicBlock<'ctx>,
-}
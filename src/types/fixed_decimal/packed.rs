@@ -0,0 +1,119 @@
+use super::MAX_PRECISION;
+
+///The most digits a packed value holds, those of the largest FIXED DECIMAL.
+pub const PACKED_DIGIT_COUNT: u32 = MAX_PRECISION;
+
+///Two digits to a byte, with the sign in the low half of the last one.
+pub const PACKED_LENGTH: u32 = get_packed_length(PACKED_DIGIT_COUNT);
+
+///The sign written for positive values and zero.
+pub const PACKED_PLUS_SIGN: u8 = 0xC;
+///The sign written for negative values.
+pub const PACKED_MINUS_SIGN: u8 = 0xD;
+///Also read as negative. Every other sign is read as positive.
+pub const PACKED_ALTERNATE_MINUS_SIGN: u8 = 0xB;
+
+///The bytes a FIXED DECIMAL(p,q) takes packed, whatever q is. An even p
+///leaves the first half byte 0.
+pub const fn get_packed_length(precision: u32) -> u32 {
+    (precision + 2) / 2
+}
+
+///Packed decimal, also known as COMP-3, is the form mainframes store
+///FIXED DECIMAL in: a digit in each half of a byte, most significant first,
+///and a sign in the last half byte. 123.45 in a FIXED DECIMAL(7,2) is
+///00 12 34 5C. None is given when the value has more than `precision` digits.
+///
+///The runtime's plick_fixed_to_packed and plick_packed_to_fixed work these
+///out for PACKED_LENGTH bytes in record I/O buffers, and a value with fewer
+///digits is the last bytes of those.
+pub fn scaled_to_packed(scaled: i128, precision: u32) -> Option<Vec<u8>> {
+    let mut magnitude = scaled.unsigned_abs();
+    if magnitude >= 10_u128.pow(precision) {
+        return None;
+    }
+    let mut packed = vec![0; get_packed_length(precision) as usize];
+
+    let sign = match scaled < 0 {
+        true => PACKED_MINUS_SIGN,
+        false => PACKED_PLUS_SIGN,
+    };
+    let mut low_half = sign;
+    for byte in packed.iter_mut().rev() {
+        let high_half = (magnitude % 10) as u8;
+        magnitude /= 10;
+        *byte = (high_half << 4) | low_half;
+        low_half = (magnitude % 10) as u8;
+        magnitude /= 10;
+    }
+
+    Some(packed)
+}
+
+///The scaled value of a packed one. Digits above 9 aren't checked for.
+pub fn packed_to_scaled(packed: &[u8]) -> i128 {
+    let (last_byte, digit_bytes) = packed.split_last().unwrap();
+
+    let magnitude = digit_bytes
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0xF])
+        .chain(std::iter::once(last_byte >> 4))
+        .fold(0_i128, |scaled, digit| scaled * 10 + digit as i128);
+
+    match last_byte & 0xF {
+        PACKED_MINUS_SIGN | PACKED_ALTERNATE_MINUS_SIGN => -magnitude,
+        _ => magnitude,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_packed_length, packed_to_scaled, scaled_to_packed, PACKED_LENGTH};
+
+    #[test]
+    fn packed_holds_the_declared_digits_and_the_sign() {
+        assert_eq!(16, PACKED_LENGTH);
+        assert_eq!(4, get_packed_length(7));
+        assert_eq!(4, get_packed_length(6));
+        assert_eq!(
+            Some(vec![0x00, 0x12, 0x34, 0x5C]),
+            scaled_to_packed(12_345, 7)
+        );
+        assert_eq!(
+            Some(vec![0x00, 0x00, 0x72, 0x5D]),
+            scaled_to_packed(-725, 7)
+        );
+        assert_eq!(
+            Some(vec![
+                0, 0, 0, 0, 0, 0, 0x01, 0x23, 0x45, 0, 0, 0, 0, 0, 0, 0x0C
+            ]),
+            scaled_to_packed(123_450_000_000_000_000, 31)
+        );
+    }
+
+    #[test]
+    fn packed_overflows_past_the_declared_digits() {
+        assert_eq!(None, scaled_to_packed(12_345, 4));
+        assert_eq!(None, scaled_to_packed(-10_000_000, 7));
+        assert_eq!(Some(vec![0x99, 0x99, 0x9D]), scaled_to_packed(-99_999, 5));
+    }
+
+    #[test]
+    fn packed_round_trips() {
+        let largest = 10_i128.pow(31) - 1;
+        for scaled in [0, 1, -1, 300_000_000_000_000, largest, -largest] {
+            let packed = scaled_to_packed(scaled, 31).unwrap();
+            assert_eq!(scaled, packed_to_scaled(&packed));
+        }
+        assert_eq!(-725, packed_to_scaled(&[0x00, 0x00, 0x72, 0x5D]));
+    }
+
+    #[test]
+    fn packed_reads_the_other_signs() {
+        let mut packed = scaled_to_packed(5, 1).unwrap();
+        packed[0] = 0x5B;
+        assert_eq!(-5, packed_to_scaled(&packed));
+        packed[0] = 0x5F;
+        assert_eq!(5, packed_to_scaled(&packed));
+    }
+}
@@ -0,0 +1,406 @@
+use inkwell::{
+    values::{AnyValue, BasicMetadataValueEnum, IntValue},
+    IntPredicate,
+};
+
+use crate::{
+    ast::Condition,
+    codegen::{
+        codegen::Compiler,
+        runtime::decimal,
+        utils::{branch_only_if_no_terminator, get_current_function},
+    },
+    lexer::Token,
+    types::{conversion::get_conversion, Type},
+};
+
+use super::{const_fixed, const_power_of_ten, power_of_ten, FixedValue};
+
+///Operands aligned to the same scale are kept below 10 to the power of this,
+///so adding two can't wrap. It is far above the largest value of
+///MAX_PRECISION digits, so an operand cut down to the limit still gives a sum
+///too large to be held.
+pub const ALIGNMENT_DIGIT_COUNT: u32 = 37;
+
+///Whether two FIXED DECIMAL operands of `operator` are worked on exactly,
+///instead of as doubles.
+pub fn is_exact_operator(operator: &Token) -> bool {
+    matches!(
+        operator,
+        Token::PLUS
+            | Token::MINUS
+            | Token::MULTIPLY
+            | Token::DIVIDE
+            | Token::LESS_THAN
+            | Token::GREATER_THAN
+            | Token::EQ
+    )
+}
+
+///FIXED DECIMAL arithmetic works on the stored integers, which count in units
+///of the last digit after the point.
+///
+///Each result has the precision and scale the language reference gives it
+///from those of the operands. Digits after the point that can't be held are
+///cut off, and results with too many digits before it raise FIXEDOVERFLOW.
+impl<'a, 'ctx> Compiler<'a, 'ctx> {
+    ///Works out `lhs operator rhs` exactly. Comparisons give a FIXED DECIMAL(1)
+    ///1 or 0.
+    pub unsafe fn generate_exact_fixed_decimal_code(
+        &'a self,
+        operator: Token,
+        lhs: FixedValue<'ctx>,
+        rhs: FixedValue<'ctx>,
+    ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, String> {
+        let (precision, scale) = match get_conversion(&operator, lhs.get_type(), rhs.get_type()) {
+            Some(conversion) => match conversion.result_type {
+                Type::FixedDecimal(precision, scale) => (precision, scale),
+                other => return Err(format!("Expected a FIXED DECIMAL result, found {}", other)),
+            },
+            None => {
+                return Err(format!(
+                    "Can't work out operator {:?} on FIXED DECIMAL values exactly",
+                    operator
+                ))
+            }
+        };
+        let limit = const_power_of_ten(lhs.value.get_type(), precision);
+
+        let scaled = match operator {
+            Token::PLUS => {
+                let lhs = self.generate_fixed_alignment(&lhs, scale);
+                let rhs = self.generate_fixed_alignment(&rhs, scale);
+                self.generate_fixed_arithmetic_call(
+                    decimal::FIXED_ADD_FUNCTION_NAME,
+                    &[lhs.into(), rhs.into(), limit.into()],
+                )
+            }
+            Token::MINUS => {
+                let lhs = self.generate_fixed_alignment(&lhs, scale);
+                let rhs = self.generate_fixed_alignment(&rhs, scale);
+                let negated_rhs = self
+                    .builder
+                    .build_int_neg(rhs, "negated_rhs")
+                    .map_err(|err| err.to_string())?;
+                self.generate_fixed_arithmetic_call(
+                    decimal::FIXED_ADD_FUNCTION_NAME,
+                    &[lhs.into(), negated_rhs.into(), limit.into()],
+                )
+            }
+            Token::MULTIPLY => {
+                //the product has the digits after the point of both operands,
+                //so the ones past the most that can be held are cut off the
+                //operand with more of them first
+                let (mut lhs_scale, mut rhs_scale) = (lhs.scale, rhs.scale);
+                while lhs_scale + rhs_scale > scale {
+                    match lhs_scale >= rhs_scale {
+                        true => lhs_scale -= 1,
+                        false => rhs_scale -= 1,
+                    }
+                }
+                let lhs = self.generate_fixed_truncation(&lhs, lhs_scale);
+                let rhs = self.generate_fixed_truncation(&rhs, rhs_scale);
+                self.generate_fixed_arithmetic_call(
+                    decimal::FIXED_MULTIPLY_FUNCTION_NAME,
+                    &[lhs.into(), rhs.into(), limit.into()],
+                )
+            }
+            Token::DIVIDE => {
+                //a over b counts in units of the digit lhs.scale - rhs.scale
+                //places after the point, and the quotient needs `digits` more
+                let digits = scale as i32 - lhs.scale as i32 + rhs.scale as i32;
+                let lhs = match digits < 0 {
+                    true => self.generate_fixed_truncation(&lhs, scale + rhs.scale),
+                    false => lhs.value,
+                };
+                let digits = digits.max(0) as u32;
+                let whole_limit =
+                    const_power_of_ten(lhs.get_type(), precision.saturating_sub(digits));
+                self.generate_fixed_arithmetic_call(
+                    decimal::FIXED_DIVIDE_FUNCTION_NAME,
+                    &[
+                        lhs.into(),
+                        rhs.value.into(),
+                        self.context
+                            .i32_type()
+                            .const_int(digits as u64, false)
+                            .into(),
+                        whole_limit.into(),
+                    ],
+                )
+            }
+            Token::LESS_THAN => self.generate_fixed_comparison(IntPredicate::SLT, &lhs, &rhs),
+            Token::GREATER_THAN => self.generate_fixed_comparison(IntPredicate::SGT, &lhs, &rhs),
+            Token::EQ => self.generate_fixed_comparison(IntPredicate::EQ, &lhs, &rhs),
+            other => {
+                return Err(format!(
+                    "Can't work out operator {:?} on FIXED DECIMAL values exactly",
+                    other
+                ))
+            }
+        };
+
+        Ok(Box::new(scaled))
+    }
+
+    ///-value, which always fits.
+    pub unsafe fn generate_exact_fixed_decimal_negation(
+        &self,
+        value: FixedValue<'ctx>,
+    ) -> Box<dyn AnyValue<'ctx> + 'ctx> {
+        let negated = self.builder.build_int_neg(value.value, "negated").unwrap();
+        Box::new(negated)
+    }
+
+    ///Assigns a FIXED DECIMAL to one of another precision and scale. Digits
+    ///after the point the target can't hold are cut off, and FIXEDOVERFLOW is
+    ///raised when there are too many before it.
+    pub unsafe fn generate_fixed_conversion(
+        &self,
+        value: &FixedValue<'ctx>,
+        precision: u32,
+        scale: u32,
+    ) -> FixedValue<'ctx> {
+        let fixed_type = value.value.get_type();
+        let loses_whole_digits = precision - scale < value.precision - value.scale;
+
+        let scaled = match scale >= value.scale {
+            true => {
+                let shift = scale - value.scale;
+                if loses_whole_digits {
+                    let limit = const_power_of_ten(fixed_type, precision - shift);
+                    self.generate_fixedoverflow_check(value.value, limit);
+                }
+                self.builder
+                    .build_int_mul(
+                        value.value,
+                        const_power_of_ten(fixed_type, shift),
+                        "rescaled",
+                    )
+                    .unwrap()
+            }
+            false => {
+                let truncated = self.generate_fixed_truncation(value, scale);
+                if loses_whole_digits {
+                    let limit = const_power_of_ten(fixed_type, precision);
+                    self.generate_fixedoverflow_check(truncated, limit);
+                }
+                truncated
+            }
+        };
+        FixedValue::new(scaled, precision, scale)
+    }
+
+    ///The FIXED DECIMAL(1) 1 or 0 of a comparison.
+    pub fn generate_fixed_truth_value(&self, is_true: IntValue<'ctx>) -> IntValue<'ctx> {
+        self.builder
+            .build_int_z_extend(is_true, self.type_module.fixed_type, "truth_value")
+            .unwrap()
+    }
+
+    ///Raises FIXEDOVERFLOW when `scaled` is not between -limit and limit.
+    pub unsafe fn generate_fixedoverflow_check(
+        &self,
+        scaled: IntValue<'ctx>,
+        limit: IntValue<'ctx>,
+    ) {
+        let negative_limit = self.builder.build_int_neg(limit, "negative_limit").unwrap();
+        let is_too_large = self
+            .builder
+            .build_int_compare(IntPredicate::SGE, scaled, limit, "is_too_large")
+            .unwrap();
+        let is_too_small = self
+            .builder
+            .build_int_compare(IntPredicate::SLE, scaled, negative_limit, "is_too_small")
+            .unwrap();
+        let is_overflow = self
+            .builder
+            .build_or(is_too_large, is_too_small, "is_overflow")
+            .unwrap();
+        self.generate_fixedoverflow_raise(is_overflow);
+    }
+
+    ///Raises FIXEDOVERFLOW when `is_overflow` is true.
+    pub unsafe fn generate_fixedoverflow_raise(&self, is_overflow: IntValue<'ctx>) {
+        let current_function = get_current_function(self);
+        let fixedoverflow_block = self
+            .context
+            .append_basic_block(current_function, "fixedoverflow");
+        let after_check_block = self
+            .context
+            .append_basic_block(current_function, "after_overflow_check");
+        self.builder
+            .build_conditional_branch(is_overflow, fixedoverflow_block, after_check_block)
+            .unwrap();
+
+        self.builder.position_at_end(fixedoverflow_block);
+        self.generate_raise_code(Condition::FixedOverflow, None);
+        branch_only_if_no_terminator(self, after_check_block);
+
+        self.builder.position_at_end(after_check_block);
+    }
+
+    ///`value` counting in units of the digit `scale` places after the point,
+    ///at least as many as it has. Values too large to be held that way are
+    ///cut down to 10^ALIGNMENT_DIGIT_COUNT, keeping their sign.
+    unsafe fn generate_fixed_alignment(
+        &self,
+        value: &FixedValue<'ctx>,
+        scale: u32,
+    ) -> IntValue<'ctx> {
+        let shift = scale - value.scale;
+        if shift == 0 {
+            return value.value;
+        }
+        let builder = self.builder;
+        let fixed_type = value.value.get_type();
+        let aligned = builder
+            .build_int_mul(
+                value.value,
+                const_power_of_ten(fixed_type, shift),
+                "aligned",
+            )
+            .unwrap();
+        if value.precision + shift <= ALIGNMENT_DIGIT_COUNT {
+            return aligned;
+        }
+
+        let alignment_limit = power_of_ten(ALIGNMENT_DIGIT_COUNT) as i128;
+        let largest = const_fixed(fixed_type, alignment_limit / power_of_ten(shift) as i128);
+        let smallest = const_fixed(fixed_type, -(alignment_limit / power_of_ten(shift) as i128));
+        let is_too_large = builder
+            .build_int_compare(IntPredicate::SGT, value.value, largest, "is_too_large")
+            .unwrap();
+        let is_too_small = builder
+            .build_int_compare(IntPredicate::SLT, value.value, smallest, "is_too_small")
+            .unwrap();
+        let aligned = builder
+            .build_select(
+                is_too_large,
+                const_fixed(fixed_type, alignment_limit),
+                aligned,
+                "aligned",
+            )
+            .unwrap()
+            .into_int_value();
+        builder
+            .build_select(
+                is_too_small,
+                const_fixed(fixed_type, -alignment_limit),
+                aligned,
+                "aligned",
+            )
+            .unwrap()
+            .into_int_value()
+    }
+
+    ///`value` with the digits after the `scale`th one after the point cut
+    ///off, at most as many as it has.
+    unsafe fn generate_fixed_truncation(
+        &self,
+        value: &FixedValue<'ctx>,
+        scale: u32,
+    ) -> IntValue<'ctx> {
+        let cut_digit_count = value.scale - scale;
+        if cut_digit_count == 0 {
+            return value.value;
+        }
+        //signed division rounds toward zero
+        self.builder
+            .build_int_signed_div(
+                value.value,
+                const_power_of_ten(value.value.get_type(), cut_digit_count),
+                "truncated",
+            )
+            .unwrap()
+    }
+
+    ///Calls one of the runtime's arithmetic functions, raising the condition
+    ///it reports, if any.
+    unsafe fn generate_fixed_arithmetic_call(
+        &'a self,
+        function_name: &str,
+        arguments: &[BasicMetadataValueEnum<'ctx>],
+    ) -> IntValue<'ctx> {
+        let result = self
+            .builder
+            .build_call(
+                self.get_function(function_name).unwrap(),
+                arguments,
+                "fixed_arithmetic",
+            )
+            .unwrap()
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_struct_value();
+        let scaled = self
+            .builder
+            .build_extract_value(result, decimal::SCALED_FIELD, "scaled_result")
+            .unwrap()
+            .into_int_value();
+        let status = self
+            .builder
+            .build_extract_value(result, decimal::STATUS_FIELD, "arithmetic_status")
+            .unwrap()
+            .into_int_value();
+
+        let current_function = get_current_function(self);
+        let fixedoverflow_block = self
+            .context
+            .append_basic_block(current_function, "fixedoverflow");
+        let zerodivide_block = self
+            .context
+            .append_basic_block(current_function, "zerodivide");
+        let after_arithmetic_block = self
+            .context
+            .append_basic_block(current_function, "after_arithmetic");
+
+        let i8_type = self.context.i8_type();
+        self.builder
+            .build_switch(
+                status,
+                after_arithmetic_block,
+                &[
+                    (
+                        i8_type.const_int(decimal::DECIMAL_FIXEDOVERFLOW, false),
+                        fixedoverflow_block,
+                    ),
+                    (
+                        i8_type.const_int(decimal::DECIMAL_ZERODIVIDE, false),
+                        zerodivide_block,
+                    ),
+                ],
+            )
+            .unwrap();
+
+        self.builder.position_at_end(fixedoverflow_block);
+        self.generate_raise_code(Condition::FixedOverflow, None);
+        branch_only_if_no_terminator(self, after_arithmetic_block);
+
+        self.builder.position_at_end(zerodivide_block);
+        self.generate_raise_code(Condition::ZeroDivide, None);
+        branch_only_if_no_terminator(self, after_arithmetic_block);
+
+        self.builder.position_at_end(after_arithmetic_block);
+        scaled
+    }
+
+    ///The FIXED DECIMAL(1) 1 or 0 for whether `lhs predicate rhs` holds,
+    ///once both count in units of the same digit.
+    unsafe fn generate_fixed_comparison(
+        &self,
+        predicate: IntPredicate,
+        lhs: &FixedValue<'ctx>,
+        rhs: &FixedValue<'ctx>,
+    ) -> IntValue<'ctx> {
+        let scale = lhs.scale.max(rhs.scale);
+        let lhs = self.generate_fixed_alignment(lhs, scale);
+        let rhs = self.generate_fixed_alignment(rhs, scale);
+        let comparison = self
+            .builder
+            .build_int_compare(predicate, lhs, rhs, "fixed_comparison")
+            .unwrap();
+        self.generate_fixed_truth_value(comparison)
+    }
+}
@@ -1,172 +1,264 @@
 use super::traits::{Mathable, MathableFactory, Puttable};
-use crate::codegen::codegen;
-use crate::codegen::{codegen::Compiler, utils::print_float_value};
+use crate::codegen::{codegen::Compiler, runtime::float::get_extended_to_string_function};
 use inkwell::{
-    types::{BasicType, BasicTypeEnum, StructType},
-    values::{BasicValue, BasicValueEnum, FloatValue, IntValue, StructValue},
+    context::Context,
+    types::{BasicType, BasicTypeEnum, FloatType},
+    values::{BasicMetadataValueEnum, FloatValue, PointerValue},
+    AddressSpace,
 };
 
+///Large enough for any FLOAT printed with all its digits.
+const FLOAT_STRING_BUFFER_SIZE: u32 = 512;
+
+///The most digits each format holds. FLOAT DECIMAL(p) is held in the
+///smallest format holding p digits.
+pub const SINGLE_PRECISION: u32 = 6;
+pub const DOUBLE_PRECISION: u32 = 16;
+pub const EXTENDED_PRECISION: u32 = 33;
+
+///The precision of a FLOAT declared without one, and of FLOAT constants.
+pub const DEFAULT_FLOAT_PRECISION: u32 = SINGLE_PRECISION;
+
+///The most binary digits FLOAT BINARY(p) can have, the significand of a
+///128 bit float.
+pub const MAX_BINARY_DIGITS: u32 = 113;
+
+///The floating point format a FLOAT DECIMAL is held in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FloatFormat {
+    ///f32, for precisions up to 6.
+    Single,
+    ///f64, for precisions up to 16.
+    Double,
+    ///A 128 bit float, for precisions up to 33.
+    Extended,
+}
+
+impl FloatFormat {
+    pub fn from_precision(precision: u32) -> FloatFormat {
+        match precision {
+            digits if digits <= SINGLE_PRECISION => FloatFormat::Single,
+            digits if digits <= DOUBLE_PRECISION => FloatFormat::Double,
+            _ => FloatFormat::Extended,
+        }
+    }
+
+    pub fn get_float_type<'ctx>(&self, context: &'ctx Context) -> FloatType<'ctx> {
+        match self {
+            FloatFormat::Single => context.f32_type(),
+            FloatFormat::Double => context.f64_type(),
+            FloatFormat::Extended => context.f128_type(),
+        }
+    }
+}
+
+///FLOAT BINARY(p) counts binary digits. It is held in the format that many
+///bits fit in, so it gets the decimal precision of that format.
+pub fn get_precision_of_binary_digits(binary_digits: u32) -> u32 {
+    match binary_digits {
+        0..=24 => SINGLE_PRECISION,
+        25..=53 => DOUBLE_PRECISION,
+        _ => EXTENDED_PRECISION,
+    }
+}
+
+///The precision of the result of mixing two FLOAT values, the larger of the
+///two.
+pub fn resolve_float_precisions(first: u32, second: u32) -> u32 {
+    first.max(second)
+}
+
+///A FLOAT DECIMAL, held as a float of the format of its precision.
 #[derive(Debug)]
 pub struct PLIFloatDecimalValue<'ctx> {
-    pub value: StructValue<'ctx>,
+    pub value: FloatValue<'ctx>,
+    pub precision: u32,
 }
 
 impl<'ctx> PLIFloatDecimalValue<'ctx> {
-    pub fn new(value: StructValue<'ctx>) -> PLIFloatDecimalValue<'ctx> {
-        PLIFloatDecimalValue { value }
+    pub fn new(value: FloatValue<'ctx>, precision: u32) -> PLIFloatDecimalValue<'ctx> {
+        PLIFloatDecimalValue { value, precision }
     }
-    pub fn get_llvm_basic_type<'a>(compiler: &Compiler<'a, 'ctx>) -> BasicTypeEnum<'ctx> {
-        get_float_decimal_type(compiler).as_basic_type_enum()
+
+    pub fn get_llvm_basic_type<'a>(
+        compiler: &Compiler<'a, 'ctx>,
+        precision: u32,
+    ) -> BasicTypeEnum<'ctx> {
+        FloatFormat::from_precision(precision)
+            .get_float_type(compiler.context)
+            .as_basic_type_enum()
     }
 
-    pub fn set_precision<'a>(
-        &self,
-        _value: IntValue<'ctx>,
-        _compiler: &Compiler<'a, 'ctx>,
+    ///Holds a float of any format as a FLOAT DECIMAL of `precision`.
+    pub fn from_float<'a>(
+        float: FloatValue<'ctx>,
+        precision: u32,
+        compiler: &Compiler<'a, 'ctx>,
     ) -> Self {
-        todo!("Make this actually work and return a self value!");
-        let malloc_ptr = _compiler
+        let float_type = FloatFormat::from_precision(precision).get_float_type(compiler.context);
+        let float = compiler
             .builder
-            .build_malloc(self.value.get_type(), "malloc_to_get_float")
-            .unwrap();
-
-        _compiler
-            .builder
-            .build_store(malloc_ptr, self.value)
-            .unwrap();
-
-        let precision_ptr = _compiler
-            .builder
-            .build_struct_gep(malloc_ptr, 1, "gepping_to_precision")
-            .unwrap();
-
-        _compiler
-            .builder
-            .build_store(precision_ptr, _value)
+            .build_float_cast(float, float_type, "float_in_format")
             .unwrap();
+        PLIFloatDecimalValue::new(float, precision)
+    }
 
-        _compiler.builder.build_free(malloc_ptr).unwrap();
+    ///The value held with another precision, rounded when it has fewer digits.
+    pub fn set_precision<'a>(&self, precision: u32, compiler: &Compiler<'a, 'ctx>) -> Self {
+        PLIFloatDecimalValue::from_float(self.value, precision, compiler)
     }
 }
 
 impl<'a, 'ctx> Puttable<'a, 'ctx> for PLIFloatDecimalValue<'ctx> {
     unsafe fn print_object(&self, compiler: &'a Compiler<'a, 'ctx>) {
-        let malloc_ptr = compiler
-            .builder
-            .build_malloc(self.value.get_type(), "malloc_to_get_float")
-            .unwrap();
+        let string_ptr = self.get_pointer_to_printable_string(compiler);
 
         compiler
             .builder
-            .build_store(malloc_ptr, self.value)
+            .build_call(
+                compiler.module.get_function("printf").unwrap(),
+                &[string_ptr.into()],
+                "printf",
+            )
             .unwrap();
+    }
+
+    ///Prints as many digits as the precision, like 3.33333E-01 for
+    ///FLOAT DECIMAL(6). printf can't print all the digits of an extended
+    ///value, so the runtime does.
+    fn get_pointer_to_printable_string(
+        &self,
+        compiler: &'a Compiler<'a, 'ctx>,
+    ) -> PointerValue<'ctx> {
+        let digits = self.precision;
 
-        let precision_ptr = compiler
+        let buffer_type = compiler
+            .context
+            .i8_type()
+            .array_type(FLOAT_STRING_BUFFER_SIZE);
+        let buffer = compiler
             .builder
-            .build_struct_gep(malloc_ptr, 0, "gepping_to_float")
+            .build_alloca(buffer_type, "float_as_string")
             .unwrap();
-
-        let struct_float = compiler
+        let buffer = compiler
             .builder
-            .build_load(precision_ptr, "loading_float")
+            .build_bitcast(
+                buffer,
+                compiler.context.i8_type().ptr_type(AddressSpace::default()),
+                "float_string_ptr",
+            )
             .unwrap()
-            .into_float_value();
-
-        print_float_value(compiler, struct_float);
+            .into_pointer_value();
 
-        compiler.builder.build_free(malloc_ptr).unwrap();
-    }
-    fn get_pointer_to_printable_string(
-        &self,
-        compiler: &'a Compiler<'a, 'ctx>,
-    ) -> inkwell::values::PointerValue<'ctx> {
-        todo!("Implement way to get pointer!");
-    }
-}
-
-impl<'a, 'ctx> Mathable<'a, 'ctx> for PLIFloatDecimalValue<'ctx> {
-    fn convert_to_float(&self, compiler: &'a Compiler<'a, 'ctx>) -> FloatValue<'ctx> {
-        let struc: StructValue<'ctx> = self.value;
+        if FloatFormat::from_precision(self.precision) == FloatFormat::Extended {
+            compiler
+                .builder
+                .build_call(
+                    get_extended_to_string_function(compiler),
+                    &[
+                        self.value.into(),
+                        compiler
+                            .context
+                            .i32_type()
+                            .const_int(digits as u64, false)
+                            .into(),
+                        buffer.into(),
+                    ],
+                    "extended_to_string",
+                )
+                .unwrap();
+            return buffer;
+        }
 
-        let malloc_ptr = compiler
+        let format_string = compiler
             .builder
-            .build_malloc(struc.get_type(), "malloc_for_floatie")
-            .unwrap();
+            .build_global_string_ptr(&format!("%.{}E", digits - 1), "glob_float_format")
+            .unwrap()
+            .as_pointer_value();
+        let float_value: BasicMetadataValueEnum<'ctx> = self.convert_to_float(compiler).into();
 
         compiler
             .builder
-            .build_store(malloc_ptr, self.value)
+            .build_call(
+                compiler.module.get_function("sprintf").unwrap(),
+                &[buffer.into(), format_string.into(), float_value],
+                "float_to_string",
+            )
             .unwrap();
 
-        let float_val: FloatValue<'ctx>;
+        buffer
+    }
+}
 
-        let float_ptr = compiler
-            .builder
-            .build_struct_gep(malloc_ptr, 0, "gepping_to_float")
-            .unwrap();
+impl<'a, 'ctx> Mathable<'a, 'ctx> for PLIFloatDecimalValue<'ctx> {
+    fn convert_to_float(&self, compiler: &'a Compiler<'a, 'ctx>) -> FloatValue<'ctx> {
+        self.convert_to_float_of_type(compiler, compiler.context.f64_type())
+    }
 
-        float_val = compiler
+    fn convert_to_float_of_type(
+        &self,
+        compiler: &'a Compiler<'a, 'ctx>,
+        float_type: FloatType<'ctx>,
+    ) -> FloatValue<'ctx> {
+        compiler
             .builder
-            .build_load(float_ptr, "getf64_from_float_decimal")
+            .build_float_cast(self.value, float_type, "float_decimal")
             .unwrap()
-            .into_float_value();
-
-        compiler.builder.build_free(malloc_ptr).unwrap();
-        float_val
     }
 }
 
 impl<'a, 'ctx> MathableFactory<'a, 'ctx, PLIFloatDecimalValue<'ctx>>
     for PLIFloatDecimalValue<'ctx>
 {
+    ///A FLOAT of the default precision.
     unsafe fn create_mathable(
         float: &FloatValue<'ctx>,
         compiler: &Compiler<'a, 'ctx>,
     ) -> Box<PLIFloatDecimalValue<'ctx>> {
-        let mut values: Vec<BasicValueEnum> = vec![];
-        values.push(
-            compiler
-                .context
-                .f64_type()
-                .const_zero()
-                .as_basic_value_enum(),
-        );
-        values.push(
-            compiler
-                .context
-                .i8_type()
-                .const_zero()
-                .as_basic_value_enum(),
-        );
-
-        let newstruc = get_float_decimal_type(compiler).const_named_struct(&values);
-        let ptr = compiler
-            .builder
-            .build_malloc(newstruc.get_type(), "float_type")
-            .unwrap();
+        Box::new(PLIFloatDecimalValue::from_float(
+            *float,
+            DEFAULT_FLOAT_PRECISION,
+            compiler,
+        ))
+    }
+}
 
-        let float_ptr = compiler
-            .builder
-            .build_struct_gep(ptr, 0, "storing float in pli float value")
-            .unwrap();
-        compiler
-            .builder
-            .build_store(float_ptr, float.as_basic_value_enum())
-            .unwrap();
-        let newstruc = compiler
-            .builder
-            .build_load(ptr, "loading new float")
-            .unwrap()
-            .into_struct_value();
+///A constant FLOAT DECIMAL.
+pub fn generate_constant_float_decimal<'a, 'ctx>(
+    compiler: &Compiler<'a, 'ctx>,
+    value: f64,
+    precision: u32,
+) -> FloatValue<'ctx> {
+    FloatFormat::from_precision(precision)
+        .get_float_type(compiler.context)
+        .const_float(value)
+}
 
-        compiler.builder.build_free(ptr).unwrap();
-        Box::new(PLIFloatDecimalValue::new(newstruc))
+#[cfg(test)]
+mod tests {
+    use super::{
+        get_precision_of_binary_digits, resolve_float_precisions, FloatFormat, DOUBLE_PRECISION,
+        EXTENDED_PRECISION, SINGLE_PRECISION,
+    };
+
+    #[test]
+    fn precision_selects_the_format() {
+        assert_eq!(FloatFormat::Single, FloatFormat::from_precision(6));
+        assert_eq!(FloatFormat::Double, FloatFormat::from_precision(7));
+        assert_eq!(FloatFormat::Double, FloatFormat::from_precision(16));
+        assert_eq!(FloatFormat::Extended, FloatFormat::from_precision(33));
     }
-}
 
-fn get_float_decimal_type<'a, 'ctx>(compiler: &Compiler<'a, 'ctx>) -> StructType<'ctx> {
-    let mut field_types: Vec<BasicTypeEnum> = vec![];
-    field_types.push(compiler.context.f64_type().as_basic_type_enum());
-    field_types.push(compiler.context.i8_type().as_basic_type_enum());
-    compiler.context.struct_type(&field_types, false)
+    #[test]
+    fn binary_digits_get_the_precision_of_their_format() {
+        assert_eq!(SINGLE_PRECISION, get_precision_of_binary_digits(21));
+        assert_eq!(DOUBLE_PRECISION, get_precision_of_binary_digits(53));
+        assert_eq!(EXTENDED_PRECISION, get_precision_of_binary_digits(109));
+    }
+
+    #[test]
+    fn mixed_precisions_take_the_larger() {
+        assert_eq!(16, resolve_float_precisions(6, 16));
+        assert_eq!(33, resolve_float_precisions(33, 6));
+    }
 }
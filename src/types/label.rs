@@ -0,0 +1,237 @@
+use inkwell::{
+    basic_block::BasicBlock,
+    context::Context,
+    module::Linkage,
+    types::{BasicType, StructType},
+    values::{AnyValue, PointerValue, StructValue},
+    AddressSpace,
+};
+
+use crate::{
+    ast::{Command, Statement},
+    codegen::{codegen::Compiler, utils::get_current_function},
+};
+
+///The field of a LABEL value holding the blockaddress of the statement.
+pub const ADDRESS_FIELD: u32 = 0;
+///The field of a LABEL value holding the number of the procedure invocation
+///the statement is in, which GO TO checks is the one running.
+pub const ACTIVATION_FIELD: u32 = 1;
+
+///Counts the invocations of every procedure that takes a LABEL value, so
+///each of them gets its own number. It is shared by all units.
+const ACTIVATION_COUNT_NAME: &str = "plick_activation_count";
+
+///A LABEL value is the address of the block of the statement it names,
+///with the number of the invocation it was taken in. Both are zero until it
+///is assigned, and no invocation is numbered zero.
+pub fn get_label_type<'ctx>(context: &'ctx Context) -> StructType<'ctx> {
+    let i8_ptr_type = context.i8_type().ptr_type(AddressSpace::default());
+    context.struct_type(&[i8_ptr_type.into(), context.i64_type().into()], false)
+}
+
+///The labels of `statements` and the groups in them, which are the LABEL
+///constants of the procedure. The labels of procedures are not.
+pub fn get_statement_labels(statements: &[Statement]) -> Vec<String> {
+    let mut labels = vec![];
+    for statement in statements {
+        if let Command::FunctionDec(_) = statement.command {
+            continue;
+        }
+        if let Some(ref label) = statement.label {
+            labels.push(label.clone());
+        }
+        match statement.command {
+            Command::IF(ref if_statement) => {
+                labels.append(&mut get_statement_labels(&if_statement.then_statements));
+                if let Some(ref else_statements) = if_statement.else_statements {
+                    labels.append(&mut get_statement_labels(else_statements));
+                }
+            }
+            Command::DO(ref do_group) => {
+                labels.append(&mut get_statement_labels(&do_group.statements));
+            }
+            _ => (),
+        }
+    }
+    labels
+}
+
+impl<'a, 'ctx> Compiler<'a, 'ctx> {
+    ///Records the labels of the statements of a block before it is compiled,
+    ///so a LABEL variable can be given a label that comes later.
+    pub fn declare_label_constants(&self, statements: &[Statement]) {
+        let mut function_properties = self.function_properties.borrow_mut();
+        for label in get_statement_labels(statements) {
+            function_properties.add_label_constant(label);
+        }
+    }
+
+    pub fn is_label_constant(&self, name: &str) -> bool {
+        self.function_properties.borrow().is_label_constant(name)
+    }
+
+    ///The value of the label constant `name`: the address of its block, and
+    ///the number of the running invocation.
+    pub unsafe fn generate_label_constant_code(
+        &self,
+        name: &str,
+    ) -> Result<Box<dyn AnyValue<'ctx> + 'ctx>, String> {
+        let block = self.get_label_block(name);
+        let address = block
+            .get_address()
+            .ok_or(format!("Can't take the address of label {}", name))?;
+        self.function_properties
+            .borrow_mut()
+            .add_label_target(name.to_string());
+
+        let label_type = get_label_type(self.context);
+        let label_value = self
+            .builder
+            .build_insert_value(label_type.get_undef(), address, ADDRESS_FIELD, "label")
+            .unwrap();
+        let activation = self
+            .builder
+            .build_load(self.get_label_activation(), "label_activation")
+            .unwrap();
+        let label_value = self
+            .builder
+            .build_insert_value(label_value, activation, ACTIVATION_FIELD, "label")
+            .unwrap();
+        let label_struct: StructValue<'ctx> = label_value.into_struct_value();
+        Ok(Box::new(label_struct))
+    }
+
+    ///The block of the label `name` in the procedure being compiled. A label
+    ///that comes later gets a placeholder, replaced once it is reached.
+    unsafe fn get_label_block(&self, name: &str) -> BasicBlock<'ctx> {
+        let function = get_current_function(self);
+        let labeled_block = self.function_properties.borrow().get_labeled_block(name);
+        if let Some(block) = labeled_block {
+            if block.get_parent() == Some(function) {
+                return block;
+            }
+        }
+        let future_block = self
+            .function_properties
+            .borrow()
+            .get_future_labeled_block(name);
+        if let Some(block) = future_block {
+            return block;
+        }
+
+        let placeholder_block = self.context.append_basic_block(function, "PLACEHOLDER");
+        self.function_properties
+            .borrow_mut()
+            .store_placeholder_block(name, placeholder_block);
+        placeholder_block
+    }
+
+    ///The storage holding the number of the running invocation of the
+    ///procedure. The invocation takes the next number when it starts, so a
+    ///label of one that has ended never matches a later one, even when it
+    ///had the same stack frame.
+    pub fn get_label_activation(&self) -> PointerValue<'ctx> {
+        if let Some(activation) = self.function_properties.borrow().get_label_activation() {
+            return activation;
+        }
+        let i64_type = self.context.i64_type();
+        let entry = get_current_function(self).get_first_basic_block().unwrap();
+        let builder = self.context.create_builder();
+        match entry.get_first_instruction() {
+            Some(first_instruction) => builder.position_before(&first_instruction),
+            None => builder.position_at_end(entry),
+        }
+
+        let activation = builder.build_alloca(i64_type, "label_activation").unwrap();
+        let count = self.get_activation_count();
+        let previous_count = builder
+            .build_load(count, "activation_count")
+            .unwrap()
+            .into_int_value();
+        let number = builder
+            .build_int_add(previous_count, i64_type.const_int(1, false), "activation")
+            .unwrap();
+        builder.build_store(count, number).unwrap();
+        builder.build_store(activation, number).unwrap();
+
+        self.function_properties
+            .borrow_mut()
+            .set_label_activation(activation);
+        activation
+    }
+
+    ///The global counting the invocations, added the first time. It is
+    ///common, so the units linked together count with the same one.
+    fn get_activation_count(&self) -> PointerValue<'ctx> {
+        if let Some(count) = self.module.get_global(ACTIVATION_COUNT_NAME) {
+            return count.as_pointer_value();
+        }
+        let i64_type = self.context.i64_type();
+        let count = self.module.add_global(
+            i64_type,
+            Some(AddressSpace::default()),
+            ACTIVATION_COUNT_NAME,
+        );
+        count.set_initializer(&i64_type.const_zero());
+        count.set_linkage(Linkage::Common);
+        count.as_pointer_value()
+    }
+
+    ///The block that goes to the address GO TO stores in the returned
+    ///storage. Its indirectbr is only built once the procedure is done, see
+    ///finish_label_dispatch.
+    pub fn get_label_dispatch(&self) -> (BasicBlock<'ctx>, PointerValue<'ctx>) {
+        if let Some(dispatch) = self.function_properties.borrow().get_label_dispatch() {
+            return dispatch;
+        }
+        let function = get_current_function(self);
+        let dispatch_block = self.context.append_basic_block(function, "labeldispatch");
+        let address_ptr = self.create_entry_block_alloca_of_type(
+            "label_address",
+            &function,
+            self.get_pointer_type().as_basic_type_enum(),
+        );
+        let dispatch = (dispatch_block, address_ptr);
+        self.function_properties
+            .borrow_mut()
+            .set_label_dispatch(dispatch);
+        dispatch
+    }
+
+    ///Builds the indirectbr of the procedure, which can go to every label a
+    ///LABEL variable was given in it.
+    pub fn finish_label_dispatch(&self) {
+        let (dispatch_block, address_ptr) =
+            match self.function_properties.borrow().get_label_dispatch() {
+                Some(dispatch) => dispatch,
+                None => return,
+            };
+        let function = dispatch_block.get_parent();
+        let mut destinations: Vec<BasicBlock<'ctx>> = vec![];
+        for name in self.function_properties.borrow().get_label_targets() {
+            match self.function_properties.borrow().get_labeled_block(&name) {
+                Some(block) if block.get_parent() == function => {
+                    if !destinations.contains(&block) {
+                        destinations.push(block);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let current_block = self.builder.get_insert_block();
+        self.builder.position_at_end(dispatch_block);
+        let address = self
+            .builder
+            .build_load(address_ptr, "label_address")
+            .unwrap()
+            .into_pointer_value();
+        self.builder
+            .build_indirect_branch(address, &destinations)
+            .unwrap();
+        if let Some(current_block) = current_block {
+            self.builder.position_at_end(current_block);
+        }
+    }
+}
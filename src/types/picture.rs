@@ -0,0 +1,695 @@
+use std::fmt::{Debug, Display};
+
+use inkwell::{
+    values::{ArrayValue, FloatValue, IntValue, PointerValue},
+    FloatPredicate, IntPredicate,
+};
+
+use crate::codegen::codegen::Compiler;
+
+use super::{
+    character::CharValue,
+    traits::{Mathable, Puttable},
+};
+
+///The longest picture, counting its V.
+pub const MAX_PICTURE_LENGTH: usize = 32;
+///The most digit positions a picture can have, so its digits fit in an i64.
+pub const MAX_PICTURE_DIGITS: u32 = 18;
+
+const BLANK: u8 = b' ';
+const ASTERISK: u8 = b'*';
+const PLUS: u8 = b'+';
+const MINUS: u8 = b'-';
+
+///The pattern of a PICTURE, as in PIC '$$$,$$9V.99'. Repetition factors like
+///(5)9 are expanded when it is parsed.
+///
+///A pictured value is stored as the characters the number is edited into,
+///so it is kept like a CHAR of get_length() characters.
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub struct Picture {
+    characters: [u8; MAX_PICTURE_LENGTH],
+    length: u8,
+}
+
+///What one character of an edited value holds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PositionKind {
+    ///9, Z or *, or a drifting character after the first one. Holds the
+    ///digit with this index, counting from the left.
+    Digit(u32),
+    ///The first character of a drifting field, which can only hold its symbol.
+    DriftingStart,
+    ///One of . , / or B, inserted between the digits.
+    Insertion,
+    ///A static S, + or -.
+    Sign,
+    ///A static $.
+    Currency,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Position {
+    character: u8,
+    kind: PositionKind,
+    after_point: bool,
+}
+
+///Whether a position shows what it holds, or is replaced by the fill
+///character because every digit to its left is a suppressed zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Significance {
+    Always,
+    Never,
+    ///Only when the value isn't zero.
+    ValueNotZero,
+    ///Only when the digit with this index, or one to its left, isn't zero.
+    DigitsFrom(u32),
+}
+
+impl Picture {
+    pub fn parse(pattern: &str) -> Result<Picture, String> {
+        let characters = expand_repetitions(&pattern.to_ascii_uppercase())?;
+        if characters.is_empty() || characters.len() > MAX_PICTURE_LENGTH {
+            return Err(format!(
+                "a picture has between 1 and {} characters",
+                MAX_PICTURE_LENGTH
+            ));
+        }
+
+        let mut picture = Picture {
+            characters: [0; MAX_PICTURE_LENGTH],
+            length: characters.len() as u8,
+        };
+        picture.characters[..characters.len()].copy_from_slice(&characters);
+        picture.validate()?;
+        Ok(picture)
+    }
+
+    fn get_pattern(&self) -> &[u8] {
+        &self.characters[..self.length as usize]
+    }
+
+    ///The number of characters of an edited value, every one but the V.
+    pub fn get_length(&self) -> u32 {
+        self.get_pattern()
+            .iter()
+            .filter(|character| **character != b'V')
+            .count() as u32
+    }
+
+    ///The number of digit positions, the precision of the FIXED DECIMAL the
+    ///picture edits.
+    pub fn get_digit_count(&self) -> u32 {
+        self.get_positions()
+            .iter()
+            .filter(|position| matches!(position.kind, PositionKind::Digit(_)))
+            .count() as u32
+    }
+
+    ///The number of digit positions after the V.
+    pub fn get_scale(&self) -> u32 {
+        self.get_positions()
+            .iter()
+            .filter(|position| {
+                position.after_point && matches!(position.kind, PositionKind::Digit(_))
+            })
+            .count() as u32
+    }
+
+    ///The $, S, + or - that is written more than once, and so drifts to the
+    ///left of the first significant digit.
+    fn get_drifting_character(&self) -> Option<u8> {
+        [b'$', b'S', PLUS, MINUS].into_iter().find(|symbol| {
+            self.get_pattern()
+                .iter()
+                .filter(|character| *character == symbol)
+                .count()
+                > 1
+        })
+    }
+
+    ///Every position but the fractional ones is suppressed with * instead of
+    ///blanks when the picture uses it.
+    fn get_fill_character(&self) -> u8 {
+        match self.get_pattern().contains(&ASTERISK) {
+            true => ASTERISK,
+            false => BLANK,
+        }
+    }
+
+    fn get_positions(&self) -> Vec<Position> {
+        let drifting_character = self.get_drifting_character();
+        let mut positions = vec![];
+        let mut digit_index = 0;
+        let mut after_point = false;
+        let mut drifting_start_found = false;
+
+        for character in self.get_pattern().iter().copied() {
+            let kind = match character {
+                b'V' => {
+                    after_point = true;
+                    continue;
+                }
+                _ if Some(character) == drifting_character && !drifting_start_found => {
+                    drifting_start_found = true;
+                    PositionKind::DriftingStart
+                }
+                _ if Some(character) == drifting_character => PositionKind::Digit(digit_index),
+                b'9' | b'Z' | ASTERISK => PositionKind::Digit(digit_index),
+                b'S' | PLUS | MINUS => PositionKind::Sign,
+                b'$' => PositionKind::Currency,
+                _ => PositionKind::Insertion,
+            };
+            if let PositionKind::Digit(_) = kind {
+                digit_index += 1;
+            }
+            positions.push(Position {
+                character,
+                kind,
+                after_point,
+            });
+        }
+        positions
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let pattern = self.get_pattern();
+        if let Some(character) = pattern
+            .iter()
+            .find(|character| !b"9ZV*$S+-.,/B".contains(character))
+        {
+            return Err(format!(
+                "'{}' can't be used in a picture",
+                *character as char
+            ));
+        }
+        if pattern
+            .iter()
+            .filter(|character| **character == b'V')
+            .count()
+            > 1
+        {
+            return Err("a picture can only have one V".to_string());
+        }
+        if pattern.contains(&b'Z') && pattern.contains(&ASTERISK) {
+            return Err("Z and * can't both be used".to_string());
+        }
+        let sign_kinds = [b'S', PLUS, MINUS]
+            .iter()
+            .filter(|sign| pattern.contains(sign))
+            .count();
+        if sign_kinds > 1 {
+            return Err("a picture can only have one kind of sign".to_string());
+        }
+
+        let positions = self.get_positions();
+        let digit_count = self.get_digit_count();
+        if digit_count == 0 || digit_count > MAX_PICTURE_DIGITS {
+            return Err(format!(
+                "a picture has between 1 and {} digit positions",
+                MAX_PICTURE_DIGITS
+            ));
+        }
+
+        if let Some(drifting_character) = self.get_drifting_character() {
+            let is_drifting = |position: &Position| position.character == drifting_character;
+            let first = positions.iter().position(is_drifting).unwrap();
+            let last = positions.iter().rposition(is_drifting).unwrap();
+            //the drifting field can only hold insertion characters besides its own
+            let is_broken = positions[first..=last]
+                .iter()
+                .any(|position| !is_drifting(position) && position.kind != PositionKind::Insertion);
+            if is_broken || positions[last].after_point {
+                return Err(format!(
+                    "the drifting '{}' characters must come together, before the V",
+                    drifting_character as char
+                ));
+            }
+            let has_digits_before = positions[..first]
+                .iter()
+                .any(|position| matches!(position.kind, PositionKind::Digit(_)));
+            let suppresses = pattern.contains(&b'Z') || pattern.contains(&ASTERISK);
+            if has_digits_before || suppresses {
+                return Err(format!(
+                    "the drifting '{}' characters must be the first digit positions",
+                    drifting_character as char
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn get_significance(&self, positions: &[Position], index: usize) -> Significance {
+        let position = positions[index];
+        //past the V only a value of zero is suppressed, and only when
+        //every digit position can be
+        if position.after_point {
+            return match self.get_pattern().contains(&b'9') {
+                true => Significance::Always,
+                false => Significance::ValueNotZero,
+            };
+        }
+        //everything after a 9 is shown
+        let is_forced = positions[..=index]
+            .iter()
+            .any(|position| position.character == b'9');
+        match position.kind {
+            _ if is_forced => Significance::Always,
+            PositionKind::Digit(digit_index) => Significance::DigitsFrom(digit_index),
+            PositionKind::DriftingStart => Significance::Never,
+            //an insertion character is suppressed along with the digit before it
+            _ => match positions[..index].iter().rposition(|position| {
+                matches!(
+                    position.kind,
+                    PositionKind::Digit(_) | PositionKind::DriftingStart
+                )
+            }) {
+                Some(previous) => self.get_significance(positions, previous),
+                None => Significance::Always,
+            },
+        }
+    }
+
+    ///The drifting position after the one at `index`, if it isn't the last.
+    fn get_next_drifting_position(&self, positions: &[Position], index: usize) -> Option<usize> {
+        let drifting_character = self.get_drifting_character()?;
+        positions[index + 1..]
+            .iter()
+            .position(|position| position.character == drifting_character)
+            .map(|offset| index + 1 + offset)
+    }
+
+    ///Edits a number into the characters the picture describes. Digits that
+    ///don't fit are lost.
+    pub fn edit(&self, value: f64) -> String {
+        let digit_count = self.get_digit_count();
+        let scaled = (value.abs() * 10_f64.powi(self.get_scale() as i32)).round() as u64;
+        let is_negative = value < 0.0 && scaled != 0;
+        let is_significant = |significance: Significance| match significance {
+            Significance::Always => true,
+            Significance::Never => false,
+            Significance::ValueNotZero => scaled != 0,
+            Significance::DigitsFrom(digit_index) => {
+                scaled >= 10_u64.pow(digit_count - 1 - digit_index)
+            }
+        };
+
+        let positions = self.get_positions();
+        let mut edited = String::new();
+        for (index, position) in positions.iter().enumerate() {
+            let significant = is_significant(self.get_significance(&positions, index));
+            let holds_symbol = !significant
+                && self
+                    .get_next_drifting_position(&positions, index)
+                    .map_or(true, |next| {
+                        is_significant(self.get_significance(&positions, next))
+                    });
+            let character = match position.kind {
+                PositionKind::Digit(digit_index) => {
+                    let digit = scaled / 10_u64.pow(digit_count - 1 - digit_index) % 10;
+                    match position.character {
+                        _ if significant => b'0' + digit as u8,
+                        character if Some(character) == self.get_drifting_character() => {
+                            match holds_symbol {
+                                true => get_symbol(character, is_negative),
+                                false => BLANK,
+                            }
+                        }
+                        b'Z' => BLANK,
+                        _ => self.get_fill_character(),
+                    }
+                }
+                PositionKind::DriftingStart => match holds_symbol {
+                    true => get_symbol(position.character, is_negative),
+                    false => BLANK,
+                },
+                PositionKind::Insertion if position.character == b'B' => BLANK,
+                PositionKind::Insertion if significant => position.character,
+                PositionKind::Insertion => self.get_fill_character(),
+                PositionKind::Sign | PositionKind::Currency => {
+                    get_symbol(position.character, is_negative)
+                }
+            };
+            edited.push(character as char);
+        }
+        edited
+    }
+}
+
+///The character a $, S, + or - position holds for a value of this sign.
+fn get_symbol(character: u8, is_negative: bool) -> u8 {
+    match (character, is_negative) {
+        (b'S', false) | (PLUS, false) => PLUS,
+        (b'S', true) | (MINUS, true) => MINUS,
+        (PLUS, true) | (MINUS, false) => BLANK,
+        (other, _) => other,
+    }
+}
+
+///Turns '(3)9V99' into '999V99'.
+fn expand_repetitions(pattern: &str) -> Result<Vec<u8>, String> {
+    let mut expanded = vec![];
+    let mut characters = pattern.bytes();
+    while let Some(character) = characters.next() {
+        if character != b'(' {
+            expanded.push(character);
+            continue;
+        }
+        let factor: String = characters
+            .by_ref()
+            .take_while(|character| *character != b')')
+            .map(|character| character as char)
+            .collect();
+        let factor: usize = factor
+            .trim()
+            .parse()
+            .map_err(|_| format!("'({})' is not a repetition factor", factor))?;
+        let repeated = characters
+            .next()
+            .ok_or("a repetition factor has to be followed by a character")?;
+        expanded.extend(std::iter::repeat(repeated).take(factor));
+    }
+    Ok(expanded)
+}
+
+impl Display for Picture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(self.get_pattern()))
+    }
+}
+
+impl Debug for Picture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "'{}'", self)
+    }
+}
+
+///Represents a pictured PL/1 value, the characters a number was edited into.
+#[derive(Debug)]
+pub struct PictureValue<'ctx> {
+    pub value: ArrayValue<'ctx>,
+    pub picture: Picture,
+}
+
+impl<'ctx> PictureValue<'ctx> {
+    pub fn new(value: ArrayValue<'ctx>, picture: Picture) -> PictureValue<'ctx> {
+        PictureValue { value, picture }
+    }
+}
+
+impl<'a, 'ctx> Puttable<'a, 'ctx> for PictureValue<'ctx> {
+    unsafe fn print_object(&self, compiler: &'a Compiler<'a, 'ctx>) {
+        CharValue::new(self.value).print_object(compiler)
+    }
+
+    fn get_pointer_to_printable_string(
+        &self,
+        compiler: &'a Compiler<'a, 'ctx>,
+    ) -> PointerValue<'ctx> {
+        CharValue::new(self.value).get_pointer_to_printable_string(compiler)
+    }
+}
+
+impl<'a, 'ctx> Mathable<'a, 'ctx> for PictureValue<'ctx> {
+    fn convert_to_float(&self, compiler: &'a Compiler<'a, 'ctx>) -> FloatValue<'ctx> {
+        compiler.de_edit_picture(self)
+    }
+}
+
+impl<'a, 'ctx> Compiler<'a, 'ctx> {
+    ///Edits `value` into the characters of `picture`. The picture is known
+    ///when compiling, so the code for each character is laid out in turn.
+    pub fn edit_into_picture(&self, value: FloatValue<'ctx>, picture: Picture) -> ArrayValue<'ctx> {
+        let builder = self.builder;
+        let f64_type = self.context.f64_type();
+        let i64_type = self.context.i64_type();
+        let i8_type = self.context.i8_type();
+        let digit_count = picture.get_digit_count();
+
+        let is_below_zero = builder
+            .build_float_compare(
+                FloatPredicate::OLT,
+                value,
+                f64_type.const_zero(),
+                "is_below_zero",
+            )
+            .unwrap();
+        let negated = builder.build_float_neg(value, "negated").unwrap();
+        let magnitude = builder
+            .build_select(is_below_zero, negated, value, "magnitude")
+            .unwrap()
+            .into_float_value();
+        let scale = f64_type.const_float(10_f64.powi(picture.get_scale() as i32));
+        let scaled = builder.build_float_mul(magnitude, scale, "scaled").unwrap();
+        let rounded = builder
+            .build_float_add(scaled, f64_type.const_float(0.5), "rounded")
+            .unwrap();
+        let digits = builder
+            .build_float_to_unsigned_int(rounded, i64_type, "digits")
+            .unwrap();
+        let is_zero = builder
+            .build_int_compare(IntPredicate::EQ, digits, i64_type.const_zero(), "is_zero")
+            .unwrap();
+        let is_not_zero = builder.build_not(is_zero, "is_not_zero").unwrap();
+        let is_negative = builder
+            .build_and(is_below_zero, is_not_zero, "is_negative")
+            .unwrap();
+
+        let get_power =
+            |digit_index: u32| i64_type.const_int(10_u64.pow(digit_count - 1 - digit_index), false);
+        let is_significant = |significance: Significance| match significance {
+            Significance::Always => self.context.bool_type().const_all_ones(),
+            Significance::Never => self.context.bool_type().const_zero(),
+            Significance::ValueNotZero => is_not_zero,
+            Significance::DigitsFrom(digit_index) => builder
+                .build_int_compare(
+                    IntPredicate::UGE,
+                    digits,
+                    get_power(digit_index),
+                    "is_significant",
+                )
+                .unwrap(),
+        };
+        let constant = |character: u8| i8_type.const_int(character as u64, false);
+        let select = |condition: IntValue<'ctx>, then: IntValue<'ctx>, other: IntValue<'ctx>| {
+            builder
+                .build_select(condition, then, other, "picture_character")
+                .unwrap()
+                .into_int_value()
+        };
+        let symbol = |character: u8| {
+            select(
+                is_negative,
+                constant(get_symbol(character, true)),
+                constant(get_symbol(character, false)),
+            )
+        };
+
+        let drifting_character = picture.get_drifting_character();
+        let fill = constant(picture.get_fill_character());
+        let positions = picture.get_positions();
+        //the terminator stays zero
+        let mut edited = self.get_character_type(picture.get_length()).const_zero();
+
+        for (index, position) in positions.iter().enumerate() {
+            let significant = is_significant(picture.get_significance(&positions, index));
+            let holds_symbol = || {
+                let next_is_significant =
+                    match picture.get_next_drifting_position(&positions, index) {
+                        Some(next) => is_significant(picture.get_significance(&positions, next)),
+                        None => self.context.bool_type().const_all_ones(),
+                    };
+                let is_suppressed = builder.build_not(significant, "is_suppressed").unwrap();
+                builder
+                    .build_and(is_suppressed, next_is_significant, "holds_symbol")
+                    .unwrap()
+            };
+            let character = match position.kind {
+                PositionKind::Digit(digit_index) => {
+                    let shifted = builder
+                        .build_int_unsigned_div(digits, get_power(digit_index), "shifted")
+                        .unwrap();
+                    let digit = builder
+                        .build_int_unsigned_rem(shifted, i64_type.const_int(10, false), "digit")
+                        .unwrap();
+                    let digit = builder.build_int_truncate(digit, i8_type, "digit").unwrap();
+                    let digit = builder
+                        .build_int_add(digit, constant(b'0'), "digit_character")
+                        .unwrap();
+                    let suppressed = match position.character {
+                        character if Some(character) == drifting_character => {
+                            select(holds_symbol(), symbol(character), constant(BLANK))
+                        }
+                        b'Z' => constant(BLANK),
+                        _ => fill,
+                    };
+                    select(significant, digit, suppressed)
+                }
+                PositionKind::DriftingStart => {
+                    select(holds_symbol(), symbol(position.character), constant(BLANK))
+                }
+                PositionKind::Insertion if position.character == b'B' => constant(BLANK),
+                PositionKind::Insertion => select(significant, constant(position.character), fill),
+                PositionKind::Sign | PositionKind::Currency => symbol(position.character),
+            };
+            edited = builder
+                .build_insert_value(edited, character, index as u32, "edited")
+                .unwrap()
+                .into_array_value();
+        }
+        edited
+    }
+
+    ///Reads the number back out of an edited value. Positions that don't
+    ///hold a digit count as zeros, and a - in any sign position makes the
+    ///number negative.
+    pub fn de_edit_picture(&self, pictured: &PictureValue<'ctx>) -> FloatValue<'ctx> {
+        let builder = self.builder;
+        let i64_type = self.context.i64_type();
+        let i8_type = self.context.i8_type();
+        let f64_type = self.context.f64_type();
+        let picture = pictured.picture;
+        let drifting_character = picture.get_drifting_character();
+
+        let mut digits = i64_type.const_zero();
+        let mut is_negative = self.context.bool_type().const_zero();
+        for (index, position) in picture.get_positions().iter().enumerate() {
+            let character = builder
+                .build_extract_value(pictured.value, index as u32, "picture_character")
+                .unwrap()
+                .into_int_value();
+
+            let is_sign_position = position.kind == PositionKind::Sign
+                || (matches!(position.character, b'S' | PLUS | MINUS)
+                    && Some(position.character) == drifting_character);
+            if is_sign_position {
+                let is_minus = builder
+                    .build_int_compare(
+                        IntPredicate::EQ,
+                        character,
+                        i8_type.const_int(MINUS as u64, false),
+                        "is_minus",
+                    )
+                    .unwrap();
+                is_negative = builder
+                    .build_or(is_negative, is_minus, "is_negative")
+                    .unwrap();
+            }
+
+            if let PositionKind::Digit(_) = position.kind {
+                let digit = builder
+                    .build_int_sub(character, i8_type.const_int(b'0' as u64, false), "digit")
+                    .unwrap();
+                //blanks, * and symbols are below '0' once it is subtracted
+                let is_digit = builder
+                    .build_int_compare(
+                        IntPredicate::ULE,
+                        digit,
+                        i8_type.const_int(9, false),
+                        "is_digit",
+                    )
+                    .unwrap();
+                let digit = builder
+                    .build_select(is_digit, digit, i8_type.const_zero(), "digit")
+                    .unwrap()
+                    .into_int_value();
+                let digit = builder
+                    .build_int_z_extend(digit, i64_type, "digit")
+                    .unwrap();
+                let shifted = builder
+                    .build_int_mul(digits, i64_type.const_int(10, false), "shifted")
+                    .unwrap();
+                digits = builder.build_int_add(shifted, digit, "digits").unwrap();
+            }
+        }
+
+        let magnitude = builder
+            .build_unsigned_int_to_float(digits, f64_type, "magnitude")
+            .unwrap();
+        let magnitude = builder
+            .build_float_div(
+                magnitude,
+                f64_type.const_float(10_f64.powi(picture.get_scale() as i32)),
+                "magnitude",
+            )
+            .unwrap();
+        let negated = builder.build_float_neg(magnitude, "negated").unwrap();
+        builder
+            .build_select(is_negative, negated, magnitude, "de_edited")
+            .unwrap()
+            .into_float_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Picture;
+
+    fn edit(pattern: &str, value: f64) -> String {
+        Picture::parse(pattern).unwrap().edit(value)
+    }
+
+    #[test]
+    fn zero_suppression() {
+        assert_eq!(edit("ZZZ9", 42.0), "  42");
+        assert_eq!(edit("ZZZ9", 0.0), "   0");
+        assert_eq!(edit("ZZZZ", 0.0), "    ");
+        assert_eq!(edit("9999", 42.0), "0042");
+        assert_eq!(edit("***9", 42.0), "**42");
+        assert_eq!(edit("ZZZVZZ", 0.0), "     ");
+        assert_eq!(edit("ZZZVZZ", 0.05), "   05");
+    }
+
+    #[test]
+    fn insertion_characters() {
+        assert_eq!(edit("ZZ,ZZ9V.99", 1234.5), " 1,234.50");
+        assert_eq!(edit("ZZ,ZZ9V.99", 12.5), "    12.50");
+        assert_eq!(edit("ZZZV.99", 0.05), "   .05");
+        assert_eq!(edit("99/99/99", 123199.0), "12/31/99");
+        assert_eq!(edit("99B99", 1234.0), "12 34");
+        assert_eq!(edit("**,**9", 12.0), "****12");
+    }
+
+    #[test]
+    fn drifting_characters() {
+        assert_eq!(edit("$$$,$$9V.99", 1234.5), " $1,234.50");
+        assert_eq!(edit("$$$,$$9V.99", 7.25), "     $7.25");
+        assert_eq!(edit("$$$9", 5.0), "  $5");
+        assert_eq!(edit("---9", -42.0), " -42");
+        assert_eq!(edit("---9", 42.0), "  42");
+        assert_eq!(edit("+++9", 42.0), " +42");
+        assert_eq!(edit("SSS9", -7.0), "  -7");
+    }
+
+    #[test]
+    fn static_characters() {
+        assert_eq!(edit("$999", 42.0), "$042");
+        assert_eq!(edit("S999", -42.0), "-042");
+        assert_eq!(edit("999S", 42.0), "042+");
+        assert_eq!(edit("-999", 42.0), " 042");
+    }
+
+    #[test]
+    fn repetition_factors_and_lengths() {
+        let picture = Picture::parse("(3)Z9V(2)9").unwrap();
+        assert_eq!(picture.to_string(), "ZZZ9V99");
+        assert_eq!(picture.get_length(), 6);
+        assert_eq!(picture.get_digit_count(), 6);
+        assert_eq!(picture.get_scale(), 2);
+        assert_eq!(picture.edit(3.14159), "   314");
+    }
+
+    #[test]
+    fn invalid_pictures() {
+        assert!(Picture::parse("99X").is_err());
+        assert!(Picture::parse("9V9V9").is_err());
+        assert!(Picture::parse("ZZ**").is_err());
+        assert!(Picture::parse("S99-").is_err());
+        assert!(Picture::parse("9$$$").is_err());
+        assert!(Picture::parse("$$V$$").is_err());
+        assert!(Picture::parse("(19)9").is_err());
+        assert!(Picture::parse("$,,").is_err());
+    }
+}
@@ -1,9 +1,17 @@
-use inkwell::values::{AnyValue, ArrayValue, FloatValue, PointerValue, StructValue};
+use inkwell::{
+    types::FloatType,
+    values::{AnyValue, ArrayValue, FloatValue, IntValue, PointerValue, StructValue},
+};
 
 use crate::codegen::codegen::Compiler;
 
 use super::{
-    character::CharValue, fixed_decimal::FixedValue, float_decimal::PLIFloatDecimalValue, Type,
+    character::{CharValue, VaryingCharValue},
+    complex::ComplexValue,
+    fixed_decimal::FixedValue,
+    float_decimal::PLIFloatDecimalValue,
+    picture::PictureValue,
+    Type,
 };
 
 pub trait Puttable<'a, 'ctx> {
@@ -19,14 +27,14 @@ pub fn get_puttable_type<'a, 'ctx>(
     _type: Type,
 ) -> Result<Box<dyn Puttable<'a, 'ctx> + 'ctx>, String> {
     let result: Box<dyn Puttable> = match _type {
-        Type::FixedDecimal => {
-            let struc: StructValue<'ctx> = value.as_any_value_enum().into_struct_value();
-            let fd: FixedValue<'ctx> = FixedValue::new(struc);
+        Type::FixedDecimal(precision, scale) => {
+            let scaled: IntValue<'ctx> = value.as_any_value_enum().into_int_value();
+            let fd: FixedValue<'ctx> = FixedValue::new(scaled, precision, scale);
             Box::new(fd)
         }
-        Type::Float => {
-            let struc: StructValue<'ctx> = value.as_any_value_enum().into_struct_value();
-            let fd: PLIFloatDecimalValue<'ctx> = PLIFloatDecimalValue::new(struc);
+        Type::Float(precision) => {
+            let float: FloatValue<'ctx> = value.as_any_value_enum().into_float_value();
+            let fd: PLIFloatDecimalValue<'ctx> = PLIFloatDecimalValue::new(float, precision);
             Box::new(fd)
         }
         Type::Char(_size) => {
@@ -34,6 +42,18 @@ pub fn get_puttable_type<'a, 'ctx>(
             let char_value: CharValue<'ctx> = CharValue::new(char_array);
             Box::new(char_value)
         }
+        Type::VaryingChar(_max_length) => {
+            let varying_struct: StructValue<'ctx> = value.as_any_value_enum().into_struct_value();
+            Box::new(VaryingCharValue::new(varying_struct))
+        }
+        Type::Picture(picture) => {
+            let char_array: ArrayValue<'ctx> = value.as_any_value_enum().into_array_value();
+            Box::new(PictureValue::new(char_array, picture))
+        }
+        Type::FixedComplex | Type::FloatComplex => {
+            let struc: StructValue<'ctx> = value.as_any_value_enum().into_struct_value();
+            Box::new(ComplexValue::new(struc, _type))
+        }
         other => panic!("Cant make puttable type {}", other),
     };
 
@@ -42,6 +62,19 @@ pub fn get_puttable_type<'a, 'ctx>(
 
 pub trait Mathable<'a, 'ctx> {
     fn convert_to_float(&self, compiler: &'a Compiler<'a, 'ctx>) -> FloatValue<'ctx>;
+    ///The value as a float of `float_type`, for arithmetic done in a format
+    ///wider or narrower than a double.
+    fn convert_to_float_of_type(
+        &self,
+        compiler: &'a Compiler<'a, 'ctx>,
+        float_type: FloatType<'ctx>,
+    ) -> FloatValue<'ctx> {
+        let float = self.convert_to_float(compiler);
+        compiler
+            .builder
+            .build_float_cast(float, float_type, "float_of_type")
+            .unwrap()
+    }
     //fn convert_from_float(float_value: &FloatValue<'ctx>, compiler: &'a Compiler<'a,'ctx>) -> Box<dyn Mathable<'a,'ctx>>;
 }
 
@@ -52,32 +85,31 @@ where
     unsafe fn create_mathable(float: &FloatValue<'ctx>, compiler: &Compiler<'a, 'ctx>) -> Box<T>;
 }
 
-impl<'a, 'ctx> MathableFactory<'a, 'ctx, FixedValue<'ctx>> for FixedValue<'ctx> {
-    unsafe fn create_mathable(
-        float: &FloatValue<'ctx>,
-        compiler: &Compiler<'a, 'ctx>,
-    ) -> Box<FixedValue<'ctx>> {
-        Box::new(compiler.float_value_to_fixed_decimal(float.clone()))
-    }
-}
-
 pub fn get_mathable_type<'a, 'ctx>(
     value: Box<dyn AnyValue<'ctx> + 'ctx>,
     _type: Type,
 ) -> Result<Box<dyn Mathable<'a, 'ctx> + 'ctx>, String> {
     match _type {
-        Type::FixedDecimal => {
-            let struct_value: StructValue<'ctx> = value.as_any_value_enum().into_struct_value();
+        Type::FixedDecimal(precision, scale) => {
+            let scaled: IntValue<'ctx> = value.as_any_value_enum().into_int_value();
 
-            let fixed_value = FixedValue::new(struct_value);
+            let fixed_value = FixedValue::new(scaled, precision, scale);
             Ok(Box::new(fixed_value))
         }
-        Type::Float => {
-            let struct_value: StructValue<'ctx> = value.as_any_value_enum().into_struct_value();
+        Type::Float(precision) => {
+            let float_value: FloatValue<'ctx> = value.as_any_value_enum().into_float_value();
 
-            let pli_float_value = PLIFloatDecimalValue::new(struct_value);
+            let pli_float_value = PLIFloatDecimalValue::new(float_value, precision);
             Ok(Box::new(pli_float_value))
         }
+        Type::Picture(picture) => {
+            let char_array: ArrayValue<'ctx> = value.as_any_value_enum().into_array_value();
+            Ok(Box::new(PictureValue::new(char_array, picture)))
+        }
+        Type::FixedComplex | Type::FloatComplex => {
+            let struct_value: StructValue<'ctx> = value.as_any_value_enum().into_struct_value();
+            Ok(Box::new(ComplexValue::new(struct_value, _type)))
+        }
         other => Err(format!("Type {:#?} is not mathable", other)),
     }
 }
@@ -5,7 +5,7 @@ use std::{
     process::{Command, Output},
 };
 
-use plick::{compile_input, Config};
+use plick::{compile_input, compile_units, Config};
 use uuid::Uuid;
 const RUST_LOG_CONFIG_STRING: &str = "trace";
 pub fn initialize_test_logger() {
@@ -23,9 +23,83 @@ pub fn test_normal_compile(input: &str) -> Result<(), Box<dyn Error>> {
 }
 
 pub fn run_new_test(input: &str) -> Result<RunTestResult, Box<dyn Error>> {
+    run_test_with(|conf| {
+        compile_input(input, conf);
+    })
+}
+
+///Like run_new_test, with directories for %INCLUDE to look in.
+pub fn run_new_test_with_includes(
+    input: &str,
+    include_directories: &[&str],
+) -> Result<RunTestResult, Box<dyn Error>> {
+    run_test_with(|mut conf| {
+        conf.include_directories = include_directories
+            .iter()
+            .map(|directory| directory.to_string())
+            .collect();
+        compile_input(input, conf);
+    })
+}
+
+///Like run_new_test, compiled with --packed-decimal.
+pub fn run_new_packed_decimal_test(input: &str) -> Result<RunTestResult, Box<dyn Error>> {
+    run_test_with(|mut conf| {
+        conf.packed_decimal = true;
+        compile_input(input, conf);
+    })
+}
+
+///Like run_new_test, for a program split across several units.
+pub fn run_new_units_test(units: &[(&str, &str)]) -> Result<RunTestResult, Box<dyn Error>> {
+    run_test_with(|conf| {
+        compile_units(units, conf);
+    })
+}
+
+///Compiles the units with --emit=header, returning the text of the header.
+pub fn generate_header(units: &[(&str, &str)]) -> Result<String, Box<dyn Error>> {
+    initialize_test_logger();
+    let mut conf = generate_test_config();
+    let mystr: String = Uuid::new_v4().into();
+    let path_to_header = "TEST_".to_string() + &mystr + ".h";
+    conf.filename = path_to_header.clone();
+    conf.emit_header = true;
+
+    compile_units(units, conf);
+
+    let header = std::fs::read_to_string(&path_to_header)?;
+    std::fs::remove_file(&path_to_header)?;
+    Ok(header)
+}
+
+///Compiles the input with debug info, returning the text of its IR.
+pub fn generate_ir_with_includes(
+    input: &str,
+    include_directories: &[&str],
+) -> Result<String, Box<dyn Error>> {
+    initialize_test_logger();
+    let mut conf = generate_test_config();
+    let mystr: String = Uuid::new_v4().into();
+    let path_to_ir = "TEST_".to_string() + &mystr + ".ll";
+    conf.filename = path_to_ir.clone();
+    conf.write_ir_to_file = true;
+    conf.include_directories = include_directories
+        .iter()
+        .map(|directory| directory.to_string())
+        .collect();
+
+    compile_input(input, conf);
+
+    let ir = std::fs::read_to_string(&path_to_ir)?;
+    std::fs::remove_file(&path_to_ir)?;
+    Ok(ir)
+}
+
+fn run_test_with(compile: impl FnOnce(Config)) -> Result<RunTestResult, Box<dyn Error>> {
     initialize_test_logger();
 
-    let output = full_compile_test_and_run(input)?;
+    let output = full_compile_test_and_run(compile)?;
 
     let output_string: String;
     let stderr_string: String;
@@ -39,7 +113,7 @@ pub fn run_new_test(input: &str) -> Result<RunTestResult, Box<dyn Error>> {
     Ok(RunTestResult::new(output_string, stderr_string, exit_code))
 }
 
-pub fn full_compile_test_and_run(input: &str) -> Result<Output, Box<dyn Error>> {
+pub fn full_compile_test_and_run(compile: impl FnOnce(Config)) -> Result<Output, Box<dyn Error>> {
     let mut conf = generate_test_config();
 
     let mystr: String = Uuid::new_v4().into();
@@ -47,7 +121,7 @@ pub fn full_compile_test_and_run(input: &str) -> Result<Output, Box<dyn Error>>
     let path_to_exe = "EXE_".to_string() + &mystr + ".exe";
     conf.filename = path_to_object_file.clone();
 
-    compile_input(input, conf);
+    compile(conf);
 
     let path_to_exe = "./".to_string() + &path_to_exe;
 
@@ -1,4 +1,4 @@
-use plick::compile_input;
+use plick::{compile_input, compile_units};
 
 use crate::common::{generate_error_test_config, initialize_test_logger};
 
@@ -32,6 +32,21 @@ fn run_error_test(input: &str, expected_error: &str) {
     }
 }
 
+fn run_units_error_test(units: &[(&str, &str)], expected_error: &str) {
+    initialize_test_logger();
+    let config = generate_error_test_config();
+    let results = compile_units(units, config);
+
+    for error in results.errors {
+        if error.message.contains(expected_error) {
+            panic!(
+                "Found error {:?}, as was expected by this test!",
+                expected_error
+            );
+        }
+    }
+}
+
 #[test]
 #[should_panic]
 fn wrong_type_assignment() {
@@ -102,3 +117,237 @@ fn top_level_list() {
 
     any_error_test(input);
 }
+
+#[test]
+#[should_panic]
+fn put_to_undeclared_file() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        PUT FILE(NOPE) LIST('A');
+        END;";
+
+    run_error_test(input, "E013");
+}
+
+#[test]
+#[should_panic]
+fn keyto_into_fixed_variable() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL ACCOUNTS FILE RECORD KEYED INPUT;
+        DCL BALANCE FIXED;
+        READ FILE(ACCOUNTS) INTO(BALANCE) KEYTO(BALANCE);
+        END;";
+
+    run_error_test(input, "E014");
+}
+
+#[test]
+#[should_panic]
+fn based_variable_without_locator() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL NAME CHAR(10) BASED;
+        NAME = 'BOB';
+        END;";
+
+    run_error_test(input, "E015");
+}
+
+#[test]
+#[should_panic]
+fn qualify_automatic_variable() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL P POINTER;
+        DCL X FIXED;
+        P = ADDR(X);
+        P->X = 1;
+        END;";
+
+    run_error_test(input, "E017");
+}
+
+#[test]
+#[should_panic]
+fn allocate_controlled_with_set() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL P POINTER;
+        DCL X FIXED CONTROLLED;
+        ALLOCATE X SET(P);
+        END;";
+
+    run_error_test(input, "E019");
+}
+
+#[test]
+#[should_panic]
+fn static_initial_is_not_constant() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+DCL X FIXED INIT(2);
+DCL Y FIXED STATIC INIT(X + 1);
+END;";
+
+    run_error_test(input, "E020");
+}
+
+#[test]
+#[should_panic]
+fn external_declared_differently_across_units() {
+    let main_unit = "HELLO:   PROCEDURE OPTIONS (MAIN);
+DCL COUNTER FIXED EXTERNAL;
+PUT LIST(COUNTER);
+END;";
+    let other_unit = "OTHER:   PROCEDURE;
+DCL COUNTER CHAR(5) EXTERNAL;
+END;";
+
+    run_units_error_test(&[("MAIN_UNIT", main_unit), ("OTHER", other_unit)], "E024");
+}
+
+#[test]
+#[should_panic]
+fn number_passed_to_c_string_parameter() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+DCL STRLEN ENTRY(CHAR(*)) RETURNS(FIXED BIN(31)) OPTIONS(C);
+X = STRLEN(5);
+END;";
+
+    run_error_test(input, "E027");
+}
+
+#[test]
+#[should_panic]
+fn exported_procedure_named_like_a_c_function() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+STRLEN: PROCEDURE(S) OPTIONS(C);
+RETURN S;
+END;
+END;";
+
+    run_error_test(input, "E029");
+}
+
+#[test]
+#[should_panic]
+fn unknown_preprocessor_statement() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+%FOO;
+END;";
+
+    run_error_test(input, "E032");
+}
+
+#[test]
+#[should_panic]
+fn leave_names_a_group_not_containing_it() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+LOOP: DO I = 1 TO 3;
+END;
+DO J = 1 TO 3;
+LEAVE LOOP;
+END;
+END;";
+
+    run_error_test(input, "E034");
+}
+
+#[test]
+#[should_panic]
+fn iterate_outside_of_a_do_group() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+ITERATE;
+END;";
+
+    run_error_test(input, "E035");
+}
+
+#[test]
+#[should_panic]
+fn static_entry_variable() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+DCL F STATIC ENTRY(FIXED) VARIABLE;
+END;";
+
+    run_error_test(input, "E036");
+}
+
+#[test]
+#[should_panic]
+fn no_generic_alternative_matches() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+DCL AREA GENERIC (CIRCLE WHEN (FLOAT), SQUARE WHEN (FIXED));
+X = AREA('WIDE');
+END;";
+
+    run_error_test(input, "E038");
+}
+
+#[test]
+#[should_panic]
+fn invalid_picture() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+DCL AMT PIC '$$9V9V9';
+END;";
+
+    run_error_test(input, "E039");
+}
+
+#[test]
+#[should_panic]
+fn float_precision_too_large() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+DCL X FLOAT DECIMAL(40);
+END;";
+
+    run_error_test(input, "E040");
+}
+
+#[test]
+#[should_panic]
+fn fixed_scale_beyond_its_precision() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+DCL X FIXED DECIMAL(5,7);
+END;";
+
+    run_error_test(input, "E041");
+}
+
+#[test]
+#[should_panic]
+fn static_initial_too_large_for_its_precision() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+DCL X FIXED DECIMAL(3) STATIC INIT(1000);
+END;";
+
+    run_error_test(input, "E042");
+}
+
+#[test]
+#[should_panic]
+fn string_conditional() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+IF 'HELLO' THEN PUT LIST('YES');
+END;";
+
+    run_error_test(input, "E043");
+}
+
+#[test]
+#[should_panic]
+fn pointer_while_condition() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+DCL P POINTER;
+DO WHILE (P);
+END;
+END;";
+
+    run_error_test(input, "E043");
+}
+
+#[test]
+#[should_panic]
+fn label_mixed_with_a_number() {
+    let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+DCL L LABEL;
+X = L + 1;
+END;";
+
+    run_error_test(input, "E005");
+}
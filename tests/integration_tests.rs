@@ -8,7 +8,10 @@ const LINE_ENDING: &'static str = "\r\n";
 #[cfg(not(windows))]
 const LINE_ENDING: &'static str = "\n";
 mod full_compile_tests {
-    use crate::common::run_new_test;
+    use crate::common::{
+        generate_header, generate_ir_with_includes, run_new_packed_decimal_test, run_new_test,
+        run_new_test_with_includes, run_new_units_test,
+    };
 
     use super::*;
     #[test]
@@ -60,7 +63,10 @@ mod full_compile_tests {
                 END;";
 
         let output = run_new_test(input)?;
-        assert_eq!("HELLO BOL  BOL HELLOHELLO", output.stdout);
+        assert_eq!(
+            "HELLO                    BOL                     BOL                    HELLO                   HELLO",
+            output.stdout
+        );
         Ok(())
     }
     #[test]
@@ -93,7 +99,13 @@ mod full_compile_tests {
                 END;";
 
         let output = run_new_test(input)?;
-        assert_eq!("-(0000000000000023.000000000000000)+(0000000000000023.000000000000000)-(0000000000000023.000000000000000)+(0000000000000000.000000000000000)+(0000000000000000.000000000000000)-(0000000000000001.000000000000000)-(0000000000000001.000000000000000)+(0000000000000000.000000000000000)+(0000000000000000.000000000000000)+(0000000000000002.000000000000000)+(0000000000000001.000000000000000)+(0000000000000000.000000000000000)", output.stdout);
+        //VARIABLE is FLOAT DECIMAL(6) by default
+        let expected_lines = [
+            "-2.30000E+01            2.30000E+01             -2.30000E+01            0.00000E+00             0.00000E+00",
+            "-1.00000E+00            -1.00000E+00            0.00000E+00             0.00000E+00             2.00000E+00",
+            "1.00000E+00             0.00000E+00",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
         Ok(())
     }
     #[test]
@@ -104,7 +116,7 @@ mod full_compile_tests {
                 END;";
 
         let output = run_new_test(input)?;
-        assert_eq!("+(0000000000000001.000000000000000)", output.stdout);
+        assert_eq!("1.00000E+00", output.stdout);
         Ok(())
     }
     #[test]
@@ -120,7 +132,10 @@ mod full_compile_tests {
                 END;";
 
         let output = run_new_test(input)?;
-        assert_eq!("+(0000000000000001.000000000000000)+(0000000000000000.000000000000000)+(0000000000000001.000000000000000)", output.stdout);
+        assert_eq!(
+            "1.00000E+00             0.00000E+00             1.00000E+00",
+            output.stdout
+        );
         Ok(())
     }
     #[test]
@@ -131,7 +146,7 @@ mod full_compile_tests {
                 END;";
 
         let output = run_new_test(input)?;
-        assert_eq!("+(0000000000000001.000000000000000)", output.stdout);
+        assert_eq!("1.00000E+00", output.stdout);
         Ok(())
     }
     #[test]
@@ -144,7 +159,7 @@ mod full_compile_tests {
                 END;";
 
         let output = run_new_test(input)?;
-        assert_eq!("5.000000", output.stdout);
+        assert_eq!("5.00000E+00", output.stdout);
         Ok(())
     }
     #[test]
@@ -165,7 +180,10 @@ END;";
 
         let output = run_new_test(input)?;
 
-        assert_eq!("+(0000000000000005.500000000000000)+(0000000000000004.990000000000000)+(0000000000000004.690000000000000)", output.stdout);
+        assert_eq!(
+            "5.50000E+00             4.99000E+00             4.69000E+00",
+            output.stdout
+        );
 
         Ok(())
     }
@@ -183,7 +201,7 @@ END;";
                 END;";
 
         let output = run_new_test(input)?;
-        assert_eq!("HELLOHELLO", output.stdout);
+        assert_eq!("HELLO                   HELLO", output.stdout);
         Ok(())
     }
     #[test]
@@ -224,7 +242,10 @@ END;";
         ";
 
         let output = run_new_test(input)?;
-        assert_eq!("Hello!Hello!Hello!End!", output.stdout);
+        assert_eq!(
+            "Hello!                  Hello!                  Hello!                  End!",
+            output.stdout
+        );
         Ok(())
     }
 
@@ -249,7 +270,12 @@ end;
             ";
 
         let output = run_new_test(input)?;
-        assert_eq!("+(0000000000000003.000000000000000)+(0000000000000000.000000000000000)+(0000000000000003.000000000000000)+(0000000000000004.000000000000000)+(0000000000000005.000000000000000)FINAL VALUETesty", output.stdout);
+        let expected_lines = [
+            "+(0000000000000003.000000000000000)             +(0000000000000000.000000000000000)             +(0000000000000003.000000000000000)",
+            "+(0000000000000004.000000000000000)             +(0000000000000005.000000000000000)             FINAL VALUE",
+            "Testy",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
         Ok(())
     }
 
@@ -291,7 +317,10 @@ end;
                 END;";
 
         let output = run_new_test(input)?;
-        assert_eq!("GOODGOODGOOD".to_owned(), output.stdout);
+        assert_eq!(
+            "GOOD                    GOOD                    GOOD".to_owned(),
+            output.stdout
+        );
         Ok(())
     }
     #[test]
@@ -318,10 +347,7 @@ end;
                 PUT LIST(A); END;";
 
         let output = run_new_test(input)?;
-        assert_eq!(
-            "+(0000000000000001.000000000000000)".to_owned(),
-            output.stdout
-        );
+        assert_eq!("1.00000E+00".to_owned(), output.stdout);
         Ok(())
     }
 
@@ -364,7 +390,9 @@ end;
 
         let output = run_new_test(input)?;
         assert_eq!(
-            "HELLOHELLOHELLOHELLO".to_owned() + LINE_ENDING,
+            "HELLO                   HELLO                   HELLO                   HELLO"
+                .to_owned()
+                + LINE_ENDING,
             output.stdout
         );
         Ok(())
@@ -375,7 +403,10 @@ end;
                 IF 1 THEN DO; PUT LIST ('HELLO', 'HELLO', 'HELLO\n'); END; ELSE DO; PUT LIST('HELLO', 'HELLO', 'HELLO', 'HELLO\n'); END; END;";
 
         let output = run_new_test(input)?;
-        assert_eq!("HELLOHELLOHELLO".to_owned() + LINE_ENDING, output.stdout);
+        assert_eq!(
+            "HELLO                   HELLO                   HELLO".to_owned() + LINE_ENDING,
+            output.stdout
+        );
         Ok(())
     }
 
@@ -417,6 +448,1321 @@ end;
         assert_eq!("BEEP", output.stdout);
         Ok(())
     }
+
+    #[test]
+    fn put_list_tab_stops() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        PUT LIST('A', 'TWENTY FOUR CHARACTERS!!', 'C'); END;";
+
+        let output = run_new_test(input)?;
+        assert_eq!(
+            "A                       TWENTY FOUR CHARACTERS!!                        C",
+            output.stdout
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn put_skip() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        PUT LIST('A');
+        PUT SKIP LIST('B');
+        PUT SKIP(2) LIST('C');
+        PUT SKIP;
+        END;";
+
+        let output = run_new_test(input)?;
+        let expected_lines = ["A", "B", "", "C", ""];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn put_line_and_page() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        PUT LINE(3) LIST('THREE');
+        PUT LIST('PAGE TWO') LINE(3);
+        PUT PAGE LIST('NEW PAGE');
+        END;";
+
+        let output = run_new_test(input)?;
+        let expected_lines = ["", "", "THREE\x0C", "", "PAGE TWO\x0CNEW PAGE"];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn put_to_named_file() -> Result<(), Box<dyn Error>> {
+        let path = format!("OUT_{}.txt", uuid::Uuid::new_v4());
+        let input = format!(
+            "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL OUTF FILE STREAM OUTPUT;
+        OPEN FILE(OUTF) TITLE('{}');
+        PUT FILE(OUTF) LIST('FIRST', 'SECOND');
+        PUT FILE(OUTF) SKIP LIST('THIRD');
+        CLOSE FILE(OUTF);
+        PUT LIST('DONE');
+        END;",
+            path
+        );
+
+        let output = run_new_test(&input)?;
+        let written = std::fs::read_to_string(&path)?;
+        std::fs::remove_file(&path)?;
+
+        assert_eq!("DONE", output.stdout);
+        let expected_lines = ["FIRST                   SECOND", "THIRD"];
+        assert_eq!(expected_lines.join(LINE_ENDING), written);
+        Ok(())
+    }
+
+    #[test]
+    fn get_from_named_file_until_endfile() -> Result<(), Box<dyn Error>> {
+        let path = format!("IN_{}.txt", uuid::Uuid::new_v4());
+        std::fs::write(&path, "1 2")?;
+        let input = format!(
+            "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL INF FILE INPUT;
+        DCL A FIXED;
+        ON ENDFILE(INF) GO DONE;
+        OPEN FILE(INF) TITLE('{}');
+        LOOP: GET FILE(INF) LIST(A);
+        PUT LIST(A);
+        GO LOOP;
+        DONE: CLOSE FILE(INF);
+        PUT LIST('END');
+        END;",
+            path
+        );
+
+        let output = run_new_test(&input)?;
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(
+            "+(0000000000000001.000000000000000)             \
+             +(0000000000000002.000000000000000)             \
+             END",
+            output.stdout
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn endfile_on_unit_for_sysin() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL A FIXED;
+        ON ENDFILE(SYSIN) PUT LIST('NO INPUT');
+        GET LIST(A);
+        PUT LIST('AFTER');
+        END;";
+
+        let output = run_new_test(input)?;
+        assert_eq!("NO INPUT                AFTER", output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn endfile_without_on_unit_ends_program() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL A FIXED;
+        GET LIST(A);
+        PUT LIST('NOT REACHED');
+        END;";
+
+        let output = run_new_test(input)?;
+        assert_eq!(1, output.error_code);
+        assert!(output
+            .stdout
+            .contains("ENDFILE condition raised on file SYSIN"));
+        assert!(!output.stdout.contains("NOT REACHED"));
+        Ok(())
+    }
+
+    #[test]
+    fn write_and_read_records_sequentially() -> Result<(), Box<dyn Error>> {
+        let path = format!("RECORDS_{}.dat", uuid::Uuid::new_v4());
+        let input = format!(
+            "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL RECS FILE RECORD SEQUENTIAL;
+        DCL A FIXED;
+        OPEN FILE(RECS) OUTPUT TITLE('{0}');
+        A = 1;
+        WRITE FILE(RECS) FROM(A);
+        A = 2;
+        WRITE FILE(RECS) FROM(A);
+        CLOSE FILE(RECS);
+        ON ENDFILE(RECS) GO DONE;
+        OPEN FILE(RECS) INPUT TITLE('{0}');
+        LOOP: READ FILE(RECS) INTO(A);
+        PUT LIST(A);
+        GO LOOP;
+        DONE: CLOSE FILE(RECS);
+        PUT LIST('END');
+        END;",
+            path
+        );
+
+        let output = run_new_test(&input)?;
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(
+            "+(0000000000000001.000000000000000)             \
+             +(0000000000000002.000000000000000)             \
+             END",
+            output.stdout
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn keyed_records_can_be_rewritten_and_deleted() -> Result<(), Box<dyn Error>> {
+        let path = format!("ACCOUNTS_{}.dat", uuid::Uuid::new_v4());
+        let input = format!(
+            "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL ACCOUNTS FILE RECORD KEYED;
+        DCL BALANCE FIXED;
+        DCL NAME CHAR(5);
+        ON KEY(ACCOUNTS) PUT SKIP LIST('NO KEY');
+        OPEN FILE(ACCOUNTS) OUTPUT TITLE('{0}');
+        BALANCE = 10;
+        WRITE FILE(ACCOUNTS) FROM(BALANCE) KEYFROM('ALICE');
+        BALANCE = 20;
+        WRITE FILE(ACCOUNTS) FROM(BALANCE) KEYFROM('BOB');
+        WRITE FILE(ACCOUNTS) FROM(BALANCE) KEYFROM('BOB');
+        CLOSE FILE(ACCOUNTS);
+        OPEN FILE(ACCOUNTS) UPDATE TITLE('{0}');
+        READ FILE(ACCOUNTS) INTO(BALANCE) KEY('BOB');
+        PUT SKIP LIST(BALANCE);
+        BALANCE = 30;
+        REWRITE FILE(ACCOUNTS) FROM(BALANCE) KEY('ALICE');
+        DELETE FILE(ACCOUNTS) KEY('BOB');
+        READ FILE(ACCOUNTS) INTO(BALANCE) KEY('BOB');
+        CLOSE FILE(ACCOUNTS);
+        ON ENDFILE(ACCOUNTS) GO DONE;
+        OPEN FILE(ACCOUNTS) INPUT TITLE('{0}');
+        LOOP: READ FILE(ACCOUNTS) INTO(BALANCE) KEYTO(NAME);
+        PUT SKIP LIST(NAME, BALANCE);
+        GO LOOP;
+        DONE: CLOSE FILE(ACCOUNTS);
+        PUT SKIP LIST('END');
+        END;",
+            path
+        );
+
+        let output = run_new_test(&input)?;
+        std::fs::remove_file(&path)?;
+
+        let expected_lines = [
+            "",
+            "NO KEY",
+            "+(0000000000000020.000000000000000)",
+            "NO KEY",
+            "ALICE                   +(0000000000000030.000000000000000)",
+            "END",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn key_without_on_unit_ends_program() -> Result<(), Box<dyn Error>> {
+        let path = format!("ACCOUNTS_{}.dat", uuid::Uuid::new_v4());
+        let input = format!(
+            "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL ACCOUNTS FILE RECORD KEYED OUTPUT;
+        DCL BALANCE FIXED;
+        OPEN FILE(ACCOUNTS) TITLE('{}');
+        WRITE FILE(ACCOUNTS) FROM(BALANCE) KEYFROM('ALICE');
+        WRITE FILE(ACCOUNTS) FROM(BALANCE) KEYFROM('ALICE');
+        PUT LIST('NOT REACHED');
+        END;",
+            path
+        );
+
+        let output = run_new_test(&input)?;
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(1, output.error_code);
+        assert!(output
+            .stdout
+            .contains("KEY condition raised on file ACCOUNTS"));
+        assert!(!output.stdout.contains("NOT REACHED"));
+        Ok(())
+    }
+
+    #[test]
+    fn varying_strings_keep_their_current_length() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL NAME CHAR(10) VARYING;
+        DCL GREETING CHAR(20) VARYING;
+        NAME = 'BOB';
+        GREETING = 'HELLO ' || NAME || '!';
+        PUT LIST(GREETING);
+        PUT SKIP LIST(LENGTH(GREETING));
+        NAME = 'A VERY LONG NAME';
+        PUT SKIP LIST(NAME, LENGTH(NAME));
+        END;";
+
+        let output = run_new_test(input)?;
+        let expected_lines = [
+            "HELLO BOB!",
+            "+(0000000000000010.000000000000000)",
+            "A VERY LON              +(0000000000000010.000000000000000)",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn compare_strings() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL NAME CHAR(10) VARYING;
+        NAME = 'AB';
+        IF NAME = 'AB   ' THEN PUT LIST('PADDED');
+        IF NAME < 'AC' THEN PUT SKIP LIST('LESS');
+        IF NAME > 'A' THEN PUT SKIP LIST('GREATER');
+        IF NAME = 'ABC' THEN PUT SKIP LIST('WRONG');
+        END;";
+
+        let output = run_new_test(input)?;
+        let expected_lines = ["PADDED", "LESS", "GREATER"];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn get_into_varying_string() -> Result<(), Box<dyn Error>> {
+        let path = format!("IN_{}.txt", uuid::Uuid::new_v4());
+        std::fs::write(&path, "'HELLO'")?;
+        let input = format!(
+            "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL INF FILE INPUT;
+        DCL S CHAR(30) VARYING;
+        OPEN FILE(INF) TITLE('{}');
+        GET FILE(INF) LIST(S);
+        PUT LIST(S, LENGTH(S));
+        END;",
+            path
+        );
+
+        let output = run_new_test(&input)?;
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(
+            "HELLO                   +(0000000000000005.000000000000000)",
+            output.stdout
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn based_variables_live_where_their_pointer_points() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL P POINTER;
+        DCL Q POINTER;
+        DCL COUNT FIXED BASED(P);
+        DCL NAME CHAR(10) VARYING BASED;
+        ALLOCATE COUNT;
+        COUNT = 5;
+        Q = P;
+        Q->COUNT = Q->COUNT + 1;
+        PUT LIST(COUNT);
+        FREE COUNT;
+        ALLOCATE NAME SET(Q);
+        Q->NAME = 'PLICK';
+        PUT SKIP LIST(Q->NAME);
+        FREE Q->NAME;
+        END;";
+
+        let output = run_new_test(input)?;
+        let expected_lines = ["+(0000000000000006.000000000000000)", "PLICK"];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn addr_and_null_pointers() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL X FIXED;
+        DCL Y FIXED BASED(P);
+        DCL P POINTER;
+        X = 3;
+        P = ADDR(X);
+        Y = 7;
+        PUT LIST(X);
+        IF P = ADDR(X) THEN PUT SKIP LIST('SAME');
+        P = NULL();
+        IF P = NULL() THEN PUT SKIP LIST('EMPTY');
+        END;";
+
+        let output = run_new_test(input)?;
+        let expected_lines = ["+(0000000000000007.000000000000000)", "SAME", "EMPTY"];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn controlled_variables_stack_generations() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL X FIXED CONTROLLED;
+        ALLOCATE X;
+        X = 1;
+        ALLOCATE X;
+        X = 2;
+        PUT LIST(X, ALLOCATION(X));
+        FREE X;
+        PUT SKIP LIST(X, ALLOCATION(X));
+        FREE X;
+        PUT SKIP LIST(ALLOCATION(X));
+        END;";
+
+        let output = run_new_test(input)?;
+        let expected_lines = [
+            "+(0000000000000002.000000000000000)             +(0000000000000002.000000000000000)",
+            "+(0000000000000001.000000000000000)             +(0000000000000001.000000000000000)",
+            "+(0000000000000000.000000000000000)",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn controlled_variable_without_generation_ends_program() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL NAME CHAR(10) VARYING CTL;
+        ALLOCATE NAME;
+        NAME = 'FIRST';
+        FREE NAME;
+        PUT LIST(NAME);
+        PUT LIST('NOT REACHED');
+        END;";
+
+        let output = run_new_test(input)?;
+        assert_eq!(1, output.error_code);
+        assert!(output
+            .stdout
+            .contains("CONTROLLED variable NAME was referenced, but it has no generation"));
+        assert!(!output.stdout.contains("NOT REACHED"));
+        Ok(())
+    }
+    #[test]
+    fn static_variables_keep_their_values() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        COUNTER: PROCEDURE();
+            DCL CALLS FIXED STATIC INITIAL(0);
+            DCL TIMES FIXED AUTOMATIC INITIAL(0);
+            CALLS = CALLS + 1;
+            TIMES = TIMES + 1;
+            PUT SKIP LIST(CALLS, TIMES);
+            RETURN 0;
+        END;
+        PUT LIST('CALLS');
+        COUNTER();
+        COUNTER();
+        END;";
+
+        let output = run_new_test(input)?;
+        let expected_lines = [
+            "CALLS",
+            "+(0000000000000001.000000000000000)             +(0000000000000001.000000000000000)",
+            "+(0000000000000002.000000000000000)             +(0000000000000001.000000000000000)",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn initial_values_of_scalars_and_arrays() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL LIMIT FIXED INITIAL(-3);
+        DCL NAME CHAR(2) INIT('AB');
+        DCL TABLE(4) FIXED INIT((3)7);
+        DCL NAMES(3) CHAR(3) VARYING STATIC INIT('X', (2)'YZ');
+        TABLE(4) = TABLE(1) + LIMIT;
+        PUT LIST(LIMIT);
+        PUT SKIP LIST(TABLE(3), TABLE(4));
+        PUT SKIP LIST(NAME);
+        PUT SKIP LIST(NAMES(1) || NAMES(3));
+        END;";
+
+        let output = run_new_test(input)?;
+        let expected_lines = [
+            "-(0000000000000003.000000000000000)",
+            "+(0000000000000007.000000000000000)             +(0000000000000004.000000000000000)",
+            "AB",
+            "XYZ",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+    #[test]
+    fn external_variables_are_shared_across_units() -> Result<(), Box<dyn Error>> {
+        let main_unit = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL COUNTER FIXED EXTERNAL INITIAL(5);
+        DCL BUMP ENTRY(FIXED) EXTERNAL;
+        BUMP(2);
+        BUMP(3);
+        PUT LIST(COUNTER);
+        END;";
+        let counting_unit = "COUNTING:   PROCEDURE;
+        BUMP: PROCEDURE(AMOUNT) EXTERNAL;
+            DCL AMOUNT FIXED;
+            DCL COUNTER FIXED EXTERNAL;
+            COUNTER = COUNTER + AMOUNT;
+            RETURN 0;
+        END;
+        END;";
+
+        let output = run_new_units_test(&[("MAIN_UNIT", main_unit), ("COUNTING", counting_unit)])?;
+        assert_eq!("+(0000000000000010.000000000000000)", output.stdout);
+        Ok(())
+    }
+    #[test]
+    fn call_c_functions_through_options_c_entries() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL STRLEN ENTRY(CHAR(*)) RETURNS(FIXED BIN(31)) OPTIONS(C);
+        DCL ABS ENTRY(FIXED BINARY(31)) RETURNS(FIXED BINARY(31)) OPTIONS(C);
+        DCL NAME CHAR(10) VARYING;
+        NAME = 'HELLO';
+        LENGTH_OF_NAME = STRLEN(NAME);
+        PUT LIST(LENGTH_OF_NAME, STRLEN('AB'));
+        PUT SKIP LIST(ABS(-7));
+        END;";
+
+        let output = run_new_test(input)?;
+        let expected_lines = [
+            "+(0000000000000005.000000000000000)             +(0000000000000002.000000000000000)",
+            "+(0000000000000007.000000000000000)",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+    #[test]
+    fn exported_procedures_are_declared_in_the_header() -> Result<(), Box<dyn Error>> {
+        let input = "ADDING:   PROCEDURE;
+        ADD_ONE: PROCEDURE(AMOUNT) OPTIONS(C);
+            RETURN AMOUNT + 1;
+        END;
+        SCALE: PROCEDURE(AMOUNT, FACTOR) EXTERNAL;
+            DCL FACTOR FIXED;
+            RETURN AMOUNT * FACTOR;
+        END;
+        HIDDEN: PROCEDURE(AMOUNT);
+            RETURN AMOUNT;
+        END;
+        END;";
+
+        let header = generate_header(&[("ADDING", input)])?;
+        assert!(header.contains("typedef __int128 plick_fixed_decimal_5_0;"));
+        //the results and undeclared parameters are FLOAT DECIMAL(6), the
+        //default for names starting with A and S
+        assert!(header.contains(
+            "void add_one(plick_float_decimal_single *result, const plick_float_decimal_single *amount);"
+        ));
+        assert!(header.contains("void scale(plick_float_decimal_single *result, const plick_float_decimal_single *amount, const plick_fixed_decimal_5_0 *factor);"));
+        assert!(!header.contains("hidden"));
+        Ok(())
+    }
+    #[test]
+    fn include_copybooks_from_include_directories() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        %INCLUDE TOTALS;
+        PUT LIST(TOTAL);
+        END;";
+        let output = run_new_test_with_includes(input, &["./test_pli_files/copybooks"])?;
+        assert_eq!("+(0000000000000042.000000000000000)", output.stdout);
+        Ok(())
+    }
+    #[test]
+    fn included_statements_are_described_in_their_file() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        SHOW: PROCEDURE();
+        %INCLUDE TOTALS;
+        PUT LIST(TOTAL);
+        RETURN 0;
+        END;
+        SHOW();
+        END;";
+        let ir = generate_ir_with_includes(input, &["./test_pli_files/copybooks"])?;
+        let included_file = ir
+            .lines()
+            .find(|line| line.contains("DIFile") && line.to_lowercase().contains("totals.pli"))
+            .expect("the included file has no DIFile");
+        let included_file_id = included_file.split(' ').next().unwrap();
+        assert!(ir.lines().any(|line| line.contains("DILexicalBlock")
+            && line.contains(&format!("file: {},", included_file_id))));
+        Ok(())
+    }
+    #[test]
+    fn macros_select_the_variant_to_build() -> Result<(), Box<dyn Error>> {
+        let variant = |debug: u32| {
+            format!(
+                "HELLO:   PROCEDURE OPTIONS (MAIN);
+        %DCL (DEBUG, I) FIXED;
+        %DEBUG = {};
+        %REPLACE FACTOR BY 3;
+        %TRIPLE: PROCEDURE(X) RETURNS(CHAR);
+            RETURN(X || ' * FACTOR');
+        %END;
+        TOTAL = 0;
+        %DO I = 1 TO 3;
+        TOTAL = TOTAL + TRIPLE(I);
+        %END;
+        %IF DEBUG = 1 %THEN %DO;
+        PUT LIST(TOTAL + 100);
+        %END;
+        %ELSE %DO;
+        PUT LIST(TOTAL);
+        %END;
+        END;",
+                debug
+            )
+        };
+
+        let output = run_new_test(&variant(1))?;
+        assert_eq!("1.18000E+02", output.stdout);
+        let output = run_new_test(&variant(0))?;
+        assert_eq!("1.80000E+01", output.stdout);
+        Ok(())
+    }
+    #[test]
+    fn undeclared_names_get_the_default_types() -> Result<(), Box<dyn Error>> {
+        let path = format!("IN_{}.txt", uuid::Uuid::new_v4());
+        std::fs::write(&path, "1 2 3 4 5")?;
+        let input = format!(
+            "AVER:   PROCEDURE OPTIONS (MAIN);
+        DCL INF FILE INPUT;
+        OPEN FILE(INF) TITLE('{}');
+        GET FILE(INF) LIST(A, B, C, D, E);
+        MEAN = (A + B + C + D + E) / 5;
+        AVERAGE = (A + B + C + D + E) / 5;
+        PUT LIST(MEAN, AVERAGE);
+        END;",
+            path
+        );
+
+        let output = run_new_test(&input)?;
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(
+            "+(0000000000000003.000000000000000)             3.00000E+00",
+            output.stdout
+        );
+        Ok(())
+    }
+    #[test]
+    fn default_statements_apply_to_their_block() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DEFAULT RANGE(*) FIXED BIN;
+        DFT RANGE(A:H) FLOAT;
+        INNER: PROCEDURE();
+            DEFAULT RANGE(T) FLOAT;
+            TOTAL = 5;
+            PUT SKIP LIST(TOTAL);
+            RETURN 0;
+        END;
+        AMOUNT = 5;
+        TOTAL = 5;
+        PUT LIST(AMOUNT, TOTAL);
+        INNER();
+        END;";
+
+        let output = run_new_test(input)?;
+        let expected_lines = [
+            "5.00000E+00             +(0000000000000005.000000000000000)",
+            "5.00000E+00",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+    #[test]
+    fn default_fixed_binary_changes_undeclared_names() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DEFAULT RANGE(*) FIXED BIN;
+        TOTAL = 2.5;
+        PUT LIST(TOTAL);
+        END;";
+
+        let output = run_new_test(input)?;
+        //TOTAL is a FIXED BINARY(15) instead of a FLOAT, so the fraction is
+        //dropped
+        let expected_lines = ["+(0000000000000002.000000000000000)"];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+    #[test]
+    fn do_loops_with_leave_and_iterate() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        TOTAL = 0;
+        OUTER: DO I = 1 TO 10;
+            DO J = 1 TO 10;
+                IF J > I THEN ITERATE OUTER;
+                IF I > 4 THEN LEAVE OUTER;
+                TOTAL = TOTAL + J;
+            END;
+        END OUTER;
+        COUNT = 0;
+        DO K = 10 TO 1 BY -2;
+            COUNT = COUNT + 1;
+        END;
+        DO WHILE (COUNT < 8);
+            COUNT = COUNT + 1;
+        END;
+        DO;
+            LEAVE;
+            COUNT = 100;
+        END;
+        PUT LIST(TOTAL, COUNT);
+        END;";
+
+        let output = run_new_test(input)?;
+
+        assert_eq!("2.00000E+01             8.00000E+00", output.stdout);
+        Ok(())
+    }
+    #[test]
+    fn pliretc_sets_the_return_code() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        PLIRETC(4);
+        PLIRETC(PLIRETV() + 4);
+        END;";
+
+        let output = run_new_test(input)?;
+
+        assert_eq!("", output.stdout);
+        assert_eq!(8, output.error_code);
+        Ok(())
+    }
+    #[test]
+    fn stop_ends_the_program_from_a_procedure() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        CHECK: PROCEDURE(N);
+            IF N > 2 THEN DO;
+                PLIRETC(12);
+                STOP;
+            END;
+            RETURN N;
+        END;
+        TOTAL = 1;
+        PUT LIST(TOTAL);
+        CHECK(3);
+        PUT LIST(TOTAL);
+        END;";
+
+        let output = run_new_test(input)?;
+
+        assert_eq!("1.00000E+00", output.stdout);
+        assert_eq!(12, output.error_code);
+        Ok(())
+    }
+    #[test]
+    fn exit_without_a_return_code_succeeds() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        COUNT = 0;
+        DO I = 1 TO 10;
+            COUNT = COUNT + 1;
+            IF COUNT > 2 THEN DO;
+                PUT LIST(COUNT);
+                EXIT;
+            END;
+        END;
+        PUT LIST(COUNT);
+        END;";
+
+        let output = run_new_test(input)?;
+
+        assert_eq!("3.00000E+00", output.stdout);
+        assert_eq!(0, output.error_code);
+        Ok(())
+    }
+    #[test]
+    fn go_to_through_label_variables() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL CASE(3) LABEL;
+        DCL NEXT LABEL;
+        CASE(1) = ONE;
+        CASE(2) = TWO;
+        CASE(3) = THREE;
+        NEXT = DONE;
+        TOTAL = 0;
+        DO I = 1 TO 3;
+            GO TO CASE(I);
+        ONE: TOTAL = TOTAL + 1;
+            ITERATE;
+        TWO: TOTAL = TOTAL + 20;
+            ITERATE;
+        THREE: TOTAL = TOTAL + 300;
+        END;
+        GO TO NEXT;
+        TOTAL = 0;
+        DONE: PUT LIST(TOTAL);
+        END;";
+
+        let output = run_new_test(input)?;
+
+        assert_eq!("3.21000E+02", output.stdout);
+        Ok(())
+    }
+    #[test]
+    fn go_to_an_unassigned_label_variable_fails() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL NEXT LABEL;
+        GO TO NEXT;
+        PUT LIST(1);
+        END;";
+
+        let output = run_new_test(input)?;
+
+        assert_eq!(1, output.error_code);
+        Ok(())
+    }
+    #[test]
+    fn go_to_a_label_of_an_ended_invocation_fails() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        TAKE: PROCEDURE(N);
+            DCL KEPT LABEL STATIC;
+            IF N = 2 THEN GO TO KEPT;
+            KEPT = HERE;
+        HERE: PUT LIST(N);
+            RETURN 0;
+        END;
+        TAKE(1);
+        TAKE(2);
+        END;";
+
+        let output = run_new_test(input)?;
+
+        //the second call likely has the same stack frame as the first, but
+        //is another invocation
+        assert!(output
+            .stdout
+            .starts_with("+(0000000000000001.000000000000000)"));
+        assert!(output.stdout.contains("GO TO KEPT"));
+        assert_eq!(1, output.error_code);
+        Ok(())
+    }
+    #[test]
+    fn call_procedures_through_entry_variables() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        SQUARE: PROCEDURE(N);
+            RETURN N * N;
+        END;
+        TWICE: PROCEDURE(N);
+            RETURN N + N;
+        END;
+        APPLY: PROCEDURE(G, N);
+            DCL G ENTRY(FIXED) RETURNS(FLOAT DECIMAL(6));
+            RETURN G(N) + 1;
+        END;
+        DCL F ENTRY(FIXED) RETURNS(FLOAT DECIMAL(6)) VARIABLE;
+        F = SQUARE;
+        PUT LIST(F(3));
+        F = TWICE;
+        PUT LIST(F(3));
+        PUT LIST(APPLY(SQUARE, 4));
+        END;";
+
+        let output = run_new_test(input)?;
+
+        assert_eq!(
+            "9.00000E+00             6.00000E+00             1.70000E+01",
+            output.stdout
+        );
+        Ok(())
+    }
+    #[test]
+    fn generic_names_call_the_procedure_for_their_arguments() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL AREA GENERIC (CIRCLE WHEN (FLOAT), SQUARE WHEN (FIXED));
+        CIRCLE: PROCEDURE(R);
+            RETURN 3 * R * R;
+        END;
+        SQUARE: PROCEDURE(S);
+            RETURN S * S;
+        END;
+        DCL RADIUS FLOAT;
+        DCL SIDE FIXED;
+        RADIUS = 2;
+        SIDE = 5;
+        PUT LIST(AREA(RADIUS), AREA(SIDE));
+        END;";
+
+        let output = run_new_test(input)?;
+
+        assert_eq!("1.20000E+01             2.50000E+01", output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn pictures_edit_and_de_edit_numbers() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL AMT PIC '$$$,$$9V.99';
+        DCL N PIC 'ZZZ9';
+        DCL TOTAL FIXED DECIMAL(7,1);
+        AMT = 1234.5;
+        N = 42;
+        TOTAL = AMT + N;
+        PUT LIST(AMT, N);
+        PUT SKIP LIST(TOTAL);
+        END;";
+
+        let output = run_new_test(input)?;
+        let expected_lines = [
+            " $1,234.50                42",
+            "+(0000000000001276.500000000000000)",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn runtime_editing_matches_picture_edit() -> Result<(), Box<dyn Error>> {
+        //the same pictures and values as the Picture::edit unit tests, edited
+        //by the generated code instead of at compile time
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL A PIC 'ZZZ9';
+        DCL B PIC 'ZZZZ';
+        DCL C PIC '***9';
+        DCL D PIC 'ZZZVZZ';
+        DCL E PIC 'ZZ,ZZ9V.99';
+        DCL F PIC 'ZZZV.99';
+        DCL G PIC '99/99/99';
+        DCL H PIC '99B99';
+        DCL U PIC '**,**9';
+        DCL J PIC '$$$,$$9V.99';
+        DCL K PIC '---9';
+        DCL L PIC '+++9';
+        DCL M PIC 'SSS9';
+        DCL N PIC '$999';
+        DCL O PIC 'S999';
+        DCL P PIC '999S';
+        DCL Q PIC '-999';
+        DCL R PIC '(3)Z9V99';
+        A = 42;
+        B = 0;
+        C = 42;
+        D = 0.05;
+        E = 12.5;
+        F = 0.05;
+        G = 123199;
+        H = 1234;
+        U = 12;
+        J = 7.25;
+        K = -42;
+        L = 42;
+        M = -7;
+        N = 42;
+        O = -42;
+        P = 42;
+        Q = 42;
+        R = 3.14159;
+        PUT LIST(A);
+        PUT SKIP LIST(B);
+        PUT SKIP LIST(C);
+        PUT SKIP LIST(D);
+        PUT SKIP LIST(E);
+        PUT SKIP LIST(F);
+        PUT SKIP LIST(G);
+        PUT SKIP LIST(H);
+        PUT SKIP LIST(U);
+        PUT SKIP LIST(J);
+        PUT SKIP LIST(K);
+        PUT SKIP LIST(L);
+        PUT SKIP LIST(M);
+        PUT SKIP LIST(N);
+        PUT SKIP LIST(O);
+        PUT SKIP LIST(P);
+        PUT SKIP LIST(Q);
+        PUT SKIP LIST(R);
+        END;";
+
+        let output = run_new_test(input)?;
+        let expected_lines = [
+            "  42",
+            "    ",
+            "**42",
+            "   05",
+            "    12.50",
+            "   .05",
+            "12/31/99",
+            "12 34",
+            "****12",
+            "     $7.25",
+            " -42",
+            " +42",
+            "  -7",
+            "$042",
+            "-042",
+            "042+",
+            " 042",
+            "   314",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn initial_pictures_match_runtime_editing() -> Result<(), Box<dyn Error>> {
+        //INITIAL values are edited at compile time; assigning the same value
+        //edits it at run time, and both must print the same characters
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL A PIC '$$$,$$9V.99' INITIAL(1234.5);
+        DCL B PIC '$$$,$$9V.99';
+        DCL C PIC '---9' INITIAL(-42);
+        DCL D PIC '---9';
+        B = 1234.5;
+        D = -42;
+        PUT LIST(A);
+        PUT SKIP LIST(B);
+        PUT SKIP LIST(C);
+        PUT SKIP LIST(D);
+        END;";
+
+        let output = run_new_test(input)?;
+        let expected_lines = [" $1,234.50", " $1,234.50", " -42", " -42"];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn complex_arithmetic_and_builtins() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL Z FLOAT COMPLEX;
+        DCL W FIXED COMPLEX;
+        Z = 3 + 4I;
+        W = COMPLEX(1, 2);
+        PUT LIST(Z * W);
+        PUT SKIP LIST(REAL(Z), IMAG(CONJG(Z)));
+        PUT SKIP LIST(Z = CONJG(CONJG(Z)));
+        END;";
+
+        let output = run_new_test(input)?;
+        let expected_lines = [
+            "-5.00000E+00+1.00000E+01I",
+            "3.00000E+00             -4.00000E+00",
+            "+(0000000000000001.000000000000000)",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn exact_fixed_decimal_arithmetic() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL A FIXED DECIMAL(5,1);
+        DCL B FIXED DECIMAL(5,1);
+        DCL C FIXED DECIMAL(17,2);
+        DCL X FIXED DECIMAL(5,2), Y FIXED DECIMAL(5,2);
+        DCL L FIXED DECIMAL(31);
+        A = 0.1;
+        B = 0.2;
+        IF A + B = 0.3 THEN PUT LIST('EXACT');
+        C = 1234567890123.45 * 100;
+        PUT SKIP LIST(C);
+        PUT SKIP LIST(1 / 3);
+        ON FIXEDOVERFLOW PUT SKIP LIST('OVERFLOW');
+        C = 999999999999999 * 100;
+        X = 999.99;
+        Y = 999.99;
+        PUT SKIP LIST(X + Y);
+        X = X + Y;
+        L = 1000000000000000;
+        L = L * L;
+        PUT SKIP LIST(L);
+        L = L * 10;
+        END;";
+
+        let output = run_new_test(input)?;
+        //(5,2) + (5,2) is a FIXED DECIMAL(6,2), which only overflows when
+        //assigned back to a (5,2); 1 / 3 is worked out to 30 digits
+        let expected_lines = [
+            "EXACT",
+            "+(0123456789012345.000000000000000)",
+            "+(0000000000000000.333333333333333333333333333333)",
+            "OVERFLOW",
+            "+(0000000000001999.980000000000000)",
+            "OVERFLOW",
+            "+(1000000000000000000000000000000.000000000000000)",
+            "OVERFLOW",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn fixed_decimal_constants_keep_all_their_digits() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL L FIXED DECIMAL(31);
+        DCL M FIXED DECIMAL(31,15);
+        DCL S FIXED DECIMAL(31) STATIC INIT(-1234567890123456789012345678901);
+        L = 1234567890123456789012345678901;
+        M = 1234567890123456.789012345678901;
+        PUT LIST(L);
+        PUT SKIP LIST(M);
+        PUT SKIP LIST(S);
+        END;";
+
+        let output = run_new_test(input)?;
+        //each of them has more digits than a double holds
+        let expected_lines = [
+            "+(1234567890123456789012345678901.000000000000000)",
+            "+(1234567890123456.789012345678901)",
+            "-(1234567890123456789012345678901.000000000000000)",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn floats_too_large_for_a_fixed_decimal_raise_fixedoverflow() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL F FLOAT;
+        DCL N FIXED DECIMAL(3);
+        ON FIXEDOVERFLOW PUT SKIP LIST('OVERFLOW');
+        F = 999.5;
+        N = F;
+        PUT SKIP LIST(N);
+        F = -1000;
+        N = F;
+        END;";
+
+        let output = run_new_test(input)?;
+        let expected_lines = ["", "+(0000000000000999.000000000000000)", "OVERFLOW"];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn records_hold_packed_decimal() -> Result<(), Box<dyn Error>> {
+        let path = format!("PACKED_{}.dat", uuid::Uuid::new_v4());
+        let input = format!(
+            "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL RECS FILE RECORD SEQUENTIAL;
+        DCL A FIXED DECIMAL(7,2);
+        OPEN FILE(RECS) OUTPUT TITLE('{0}');
+        A = 123.45;
+        WRITE FILE(RECS) FROM(A);
+        A = -7.25;
+        WRITE FILE(RECS) FROM(A);
+        CLOSE FILE(RECS);
+        ON ENDFILE(RECS) GO DONE;
+        OPEN FILE(RECS) INPUT TITLE('{0}');
+        LOOP: READ FILE(RECS) INTO(A);
+        PUT SKIP LIST(A + 0.1);
+        GO LOOP;
+        DONE: CLOSE FILE(RECS);
+        PUT SKIP LIST(A);
+        END;",
+            path
+        );
+
+        let output = run_new_packed_decimal_test(&input)?;
+        let records = std::fs::read(&path)?;
+        std::fs::remove_file(&path)?;
+
+        //a FIXED DECIMAL(7,2) takes (7 + 2) / 2 bytes
+        let expected_records = [[0x00, 0x12, 0x34, 0x5C], [0x00, 0x00, 0x72, 0x5D]].concat();
+        assert_eq!(expected_records, records);
+
+        let expected_lines = [
+            "",
+            "+(0000000000000123.550000000000000)",
+            "-(0000000000000007.150000000000000)",
+            "-(0000000000000007.250000000000000)",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn packed_records_hold_each_array_element() -> Result<(), Box<dyn Error>> {
+        let path = format!("PACKED_{}.dat", uuid::Uuid::new_v4());
+        let input = format!(
+            "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL RECS FILE RECORD SEQUENTIAL;
+        DCL A(3) FIXED DECIMAL(5,2);
+        OPEN FILE(RECS) OUTPUT TITLE('{0}');
+        A(1) = 1.5;
+        A(2) = -2.25;
+        A(3) = 300;
+        WRITE FILE(RECS) FROM(A);
+        CLOSE FILE(RECS);
+        A(1) = 0;
+        A(2) = 0;
+        A(3) = 0;
+        OPEN FILE(RECS) INPUT TITLE('{0}');
+        READ FILE(RECS) INTO(A);
+        CLOSE FILE(RECS);
+        PUT SKIP LIST(A(1));
+        PUT SKIP LIST(A(2));
+        PUT SKIP LIST(A(3));
+        END;",
+            path
+        );
+
+        let output = run_new_packed_decimal_test(&input)?;
+        let records = std::fs::read(&path)?;
+        std::fs::remove_file(&path)?;
+
+        //each element takes (5 + 2) / 2 bytes
+        let expected_records =
+            [[0x00, 0x15, 0x0C], [0x00, 0x22, 0x5D], [0x30, 0x00, 0x0C]].concat();
+        assert_eq!(expected_records, records);
+
+        let expected_lines = [
+            "",
+            "+(0000000000000001.500000000000000)",
+            "-(0000000000000002.250000000000000)",
+            "+(0000000000000300.000000000000000)",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn packed_records_with_too_many_digits_raise_fixedoverflow() -> Result<(), Box<dyn Error>> {
+        let path = format!("PACKED_{}.dat", uuid::Uuid::new_v4());
+        let input = format!(
+            "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL RECS FILE RECORD SEQUENTIAL;
+        DCL A FIXED DECIMAL(5);
+        DCL B FIXED DECIMAL(4);
+        ON FIXEDOVERFLOW PUT SKIP LIST('OVERFLOW');
+        OPEN FILE(RECS) OUTPUT TITLE('{0}');
+        A = 12345;
+        WRITE FILE(RECS) FROM(A);
+        CLOSE FILE(RECS);
+        OPEN FILE(RECS) INPUT TITLE('{0}');
+        READ FILE(RECS) INTO(B);
+        CLOSE FILE(RECS);
+        END;",
+            path
+        );
+
+        let output = run_new_packed_decimal_test(&input)?;
+        let records = std::fs::read(&path)?;
+        std::fs::remove_file(&path)?;
+
+        //both take 3 bytes, but B only holds 4 digits
+        assert_eq!(vec![0x12, 0x34, 0x5C], records);
+        assert_eq!(["", "OVERFLOW"].join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn float_decimal_precision_selects_the_format() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL S FLOAT DECIMAL(6), D FLOAT DEC(16), E FLOAT DECIMAL(33);
+        S = 1 / 3;
+        D = 1;
+        D = D / 3E0;
+        E = 1;
+        E = E / 3E0;
+        PUT SKIP LIST(S);
+        PUT SKIP LIST(D);
+        PUT SKIP LIST(E);
+        PUT SKIP LIST(S + D);
+        END;";
+
+        let output = run_new_test(input)?;
+        let expected_lines = [
+            "",
+            "3.33333E-01",
+            "3.333333333333333E-01",
+            "3.33333333333333333333333333333333E-01",
+            "6.666666766007741E-01",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn mixed_operands_are_converted() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL F FLOAT DECIMAL(6), N FIXED, C CHAR(4);
+        N = 2;
+        F = 1.5;
+        C = '12.5';
+        D = 0.25;
+        PUT SKIP LIST(N + F);
+        PUT SKIP LIST(C + N);
+        PUT SKIP LIST(D + N / 4);
+        IF C > 11 THEN PUT SKIP LIST('GREATER');
+        END;";
+
+        let output = run_new_test(input)?;
+        //FIXED and FLOAT give a FLOAT, and a string is read as a
+        //FIXED DECIMAL(31,0). N / 4 has 31 digits, so D + N / 4 is a
+        //FLOAT DECIMAL(31)
+        let expected_lines = [
+            "",
+            "3.50000E+00",
+            "+(0000000000000014.000000000000000)",
+            "7.500000000000000000000000000000E-01",
+            "GREATER",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn strings_that_are_not_numbers_raise_conversion() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL C CHAR(5), N FIXED;
+        ON CONVERSION PUT SKIP LIST('NOT A NUMBER');
+        C = ' 7';
+        N = 1;
+        PUT SKIP LIST(C + N);
+        C = '7X';
+        PUT SKIP LIST(C + N);
+        C = 'ABC';
+        PUT SKIP LIST(C + N);
+        END;";
+
+        let output = run_new_test(input)?;
+        //blanks around the number are allowed
+        let expected_lines = [
+            "",
+            "+(0000000000000008.000000000000000)",
+            "NOT A NUMBER",
+            "+(0000000000000008.000000000000000)",
+            "NOT A NUMBER",
+            "+(0000000000000001.000000000000000)",
+        ];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
+
+    #[test]
+    fn conversion_without_an_on_unit_ends_the_program() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL C CHAR(3);
+        C = 'ABC';
+        PUT LIST(C + 1);
+        END;";
+
+        let output = run_new_test(input)?;
+
+        assert_eq!(1, output.error_code);
+        assert!(output.stdout.contains("CONVERSION condition raised"));
+        Ok(())
+    }
+
+    #[test]
+    fn large_fixed_keeps_its_digits_with_float() -> Result<(), Box<dyn Error>> {
+        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
+        DCL A FIXED DECIMAL(15,2), F FLOAT DECIMAL(6);
+        A = 1234567890123.45;
+        F = 0.25;
+        PUT SKIP LIST(A + F);
+        END;";
+
+        let output = run_new_test(input)?;
+        //A + F is a FLOAT DECIMAL(15), so none of A's digits are lost
+        let expected_lines = ["", "1.23456789012370E+12"];
+        assert_eq!(expected_lines.join(LINE_ENDING), output.stdout);
+        Ok(())
+    }
 }
 
 mod should_fails {
@@ -425,19 +1771,6 @@ mod should_fails {
     use super::common::initialize_test_logger;
     use super::*;
 
-    #[test]
-    #[should_panic(expected = "support type Char")]
-    fn string_conditional_panic() -> () {
-        initialize_test_logger();
-        let input = "HELLO:   PROCEDURE OPTIONS (MAIN);
-                    LOL: PROCEDURE (A);  RETURN A-4;
-                END;
-                LOL(2);
-                IF 'HELLO' THEN LOL;
-                END;";
-
-        test_normal_compile(input).unwrap();
-    }
     #[test]
     #[should_panic]
     fn test_unknown_function_panic_test() {
@@ -474,14 +1807,14 @@ mod lexer_and_parser_integration_tests {
 
             let left_expr: ast::Expr = *left;
 
-            if let ast::Expr::NumVal { value, _type } = left_expr {
+            if let ast::Expr::NumVal { value, _type, .. } = left_expr {
                 assert_eq!(value, 2.0);
             } else {
                 panic!("not numval");
             }
 
             let right_expr: ast::Expr = *right;
-            if let ast::Expr::NumVal { value, _type } = right_expr {
+            if let ast::Expr::NumVal { value, _type, .. } = right_expr {
                 assert_eq!(value, 2.0);
             } else {
                 panic!("not numval");
@@ -506,7 +1839,7 @@ mod lexer_and_parser_integration_tests {
 
             let left_expr: ast::Expr = *left;
 
-            if let ast::Expr::NumVal { value, _type } = left_expr {
+            if let ast::Expr::NumVal { value, _type, .. } = left_expr {
                 assert_eq!(value, 2.0);
             } else {
                 panic!("not numval");
@@ -519,7 +1852,7 @@ mod lexer_and_parser_integration_tests {
                 right,
             } = right_expr
             {
-                if let ast::Expr::NumVal { value, _type } = *left {
+                if let ast::Expr::NumVal { value, _type, .. } = *left {
                     assert_eq!(3.0, value);
                 } else {
                     panic!("not a numval!")
@@ -530,7 +1863,7 @@ mod lexer_and_parser_integration_tests {
                     panic!("not a multiply!")
                 }
 
-                if let ast::Expr::NumVal { value, _type } = *right {
+                if let ast::Expr::NumVal { value, _type, .. } = *right {
                     assert_eq!(5.0, value);
 
                     Ok(())
@@ -553,7 +1886,7 @@ mod lexer_and_parser_integration_tests {
         let result = parser::parse_constant_numeric(&mut tok_man)?;
         let resulting_num;
         match result {
-            Expr::NumVal { value, _type } => {
+            Expr::NumVal { value, _type, .. } => {
                 resulting_num = value;
             }
             other => {
@@ -573,7 +1906,7 @@ mod lexer_and_parser_integration_tests {
         let result = parser::parse_constant_numeric(&mut tok_man)?;
         let resulting_num;
         match result {
-            Expr::NumVal { value, _type } => {
+            Expr::NumVal { value, _type, .. } => {
                 resulting_num = value;
             }
             other => {